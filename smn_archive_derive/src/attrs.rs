@@ -0,0 +1,163 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, Field, GenericArgument, Ident, LitInt, LitStr, Path, PathArguments, Type};
+
+/// Options set on the form struct.
+pub struct FormAttrs {
+    pub krate: Path,
+    pub manual_references: bool,
+}
+
+/// Integer type of a `Vec` field's length prefix.
+#[derive(Clone, Copy)]
+pub enum CountType {
+    U8,
+    U16,
+    U32,
+}
+
+impl CountType {
+    fn parse(lit: &LitStr) -> syn::Result<Self> {
+        match lit.value().as_str() {
+            "u8" => Ok(CountType::U8),
+            "u16" => Ok(CountType::U16),
+            "u32" => Ok(CountType::U32),
+            _ => Err(syn::Error::new_spanned(lit, "count must be \"u8\", \"u16\" or \"u32\"")),
+        }
+    }
+
+    pub fn to_type(self) -> TokenStream {
+        match self {
+            CountType::U8 => quote!(u8),
+            CountType::U16 => quote!(u16),
+            CountType::U32 => quote!(u32),
+        }
+    }
+}
+
+/// How a field is laid out in the form's bytes.
+pub enum FieldLayout {
+    /// A single value.
+    Single,
+    /// A `Vec` with its own length prefix.
+    Counted(CountType),
+    /// A `Vec` of a fixed length, without a prefix.
+    Fixed(usize),
+    /// A `Vec` using the length prefix of another field.
+    CountOf(Ident),
+}
+
+/// A form field after `base` with its options.
+pub struct FormFieldAttrs {
+    pub ident: Ident,
+    pub ty: Type,
+    pub element: Option<Type>,
+    pub layout: FieldLayout,
+    pub key: String,
+    pub reference: bool,
+}
+
+/// Reads the `#[form(...)]` options of the struct.
+pub fn parse_form_attrs(attrs: &[Attribute]) -> syn::Result<FormAttrs> {
+    let mut form_attrs = FormAttrs {
+        krate: syn::parse_quote!(::smn_archive),
+        manual_references: false,
+    };
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("form")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                let lit: LitStr = meta.value()?.parse()?;
+                form_attrs.krate = lit.parse()?;
+                Ok(())
+            } else if meta.path.is_ident("manual_references") {
+                form_attrs.manual_references = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown form attribute, expected `crate` or `manual_references`"))
+            }
+        })?;
+    }
+
+    Ok(form_attrs)
+}
+
+/// Reads a field and its `#[form(...)]` options.
+pub fn parse_field(field: &Field) -> syn::Result<FormFieldAttrs> {
+    let ident = field.ident.clone().expect("named field");
+    let mut layout = FieldLayout::Single;
+    let mut layout_set = false;
+    let mut key = ident.to_string();
+    let mut reference = false;
+
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("form")) {
+        attr.parse_nested_meta(|meta| {
+            let mut set_layout = |new_layout: FieldLayout| {
+                if layout_set {
+                    return Err(meta.error("only one of `count`, `fixed` and `count_of` can be set"));
+                }
+                layout = new_layout;
+                layout_set = true;
+                Ok(())
+            };
+
+            if meta.path.is_ident("count") {
+                let lit: LitStr = meta.value()?.parse()?;
+                set_layout(FieldLayout::Counted(CountType::parse(&lit)?))
+            } else if meta.path.is_ident("fixed") {
+                let lit: LitInt = meta.value()?.parse()?;
+                set_layout(FieldLayout::Fixed(lit.base10_parse()?))
+            } else if meta.path.is_ident("count_of") {
+                let lit: LitStr = meta.value()?.parse()?;
+                set_layout(FieldLayout::CountOf(lit.parse()?))
+            } else if meta.path.is_ident("rename") {
+                let lit: LitStr = meta.value()?.parse()?;
+                key = lit.value();
+                Ok(())
+            } else if meta.path.is_ident("reference") {
+                reference = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown form attribute, expected `count`, `fixed`, `count_of`, `rename` or `reference`"))
+            }
+        })?;
+    }
+
+    let element = vec_element(&field.ty).cloned();
+    match (&layout, &element) {
+        (FieldLayout::Single, Some(_)) => {
+            return Err(syn::Error::new_spanned(&field.ty, "Vec fields need `count`, `fixed` or `count_of` to set how their length is stored"));
+        }
+        (FieldLayout::Counted(_) | FieldLayout::Fixed(_) | FieldLayout::CountOf(_), None) => {
+            return Err(syn::Error::new_spanned(&field.ty, "`count`, `fixed` and `count_of` can only be set on Vec fields"));
+        }
+        _ => {}
+    }
+
+    Ok(FormFieldAttrs {
+        ident,
+        ty: field.ty.clone(),
+        element,
+        layout,
+        key,
+        reference,
+    })
+}
+
+/// Returns `T` when `ty` is `Vec<T>`.
+fn vec_element(ty: &Type) -> Option<&Type> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment = type_path.path.segments.last()?;
+    if segment.ident != "Vec" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.first()? {
+        GenericArgument::Type(element) => Some(element),
+        _ => None,
+    }
+}
@@ -0,0 +1,448 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DataStruct, DeriveInput, Fields, Ident};
+
+use crate::attrs::{parse_field, parse_form_attrs, CountType, FieldLayout, FormFieldAttrs};
+
+/// Expands `#[derive(Form)]` for a struct.
+pub fn expand_form(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let form_attrs = parse_form_attrs(&input.attrs)?;
+    let krate = &form_attrs.krate;
+    let forms = quote!(#krate::core::structs::forms);
+    let types = quote!(#krate::core::structs::types);
+
+    let fields = match &input.data {
+        Data::Struct(DataStruct { fields: Fields::Named(named), .. }) => &named.named,
+        _ => return Err(syn::Error::new_spanned(&input.ident, "#[derive(Form)] needs a struct with named fields")),
+    };
+
+    // The first field holds the FormID, FormType and FormName
+    let mut field_iter = fields.iter();
+    match field_iter.next() {
+        Some(field) if field.ident.as_ref().is_some_and(|ident| ident == "base") => {}
+        _ => return Err(syn::Error::new_spanned(&input.ident, "the first field of a form must be `base: FormBase`")),
+    }
+    let form_fields = field_iter.map(parse_field).collect::<syn::Result<Vec<_>>>()?;
+    validate_count_of(&form_fields)?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let byte_counts = form_fields.iter().map(|field| expand_byte_count(field, &forms));
+    let writes = form_fields.iter().map(|field| expand_write(field, &forms));
+    let dict_inserts = form_fields.iter().map(|field| expand_dict_insert(field, &forms));
+    let file_reads = form_fields.iter().map(|field| expand_file_read(field, &forms));
+    let buffer_reads = form_fields.iter().map(|field| expand_buffer_read(field, &forms));
+    let schema_fields = form_fields.iter().map(|field| expand_schema_field(field, &form_fields, &forms, &types)).collect::<syn::Result<Vec<_>>>()?;
+    let field_idents: Vec<&Ident> = form_fields.iter().map(|field| &field.ident).collect();
+    let local_idents: Vec<Ident> = form_fields.iter().map(|field| local_ident(&field.ident)).collect();
+
+    let reference_methods = if form_attrs.manual_references {
+        TokenStream::new()
+    } else {
+        expand_reference_methods(&form_fields, &types)
+    };
+
+    Ok(quote! {
+        #[allow(unused)]
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Calculates the byte count needed for serialization.
+            pub fn get_byte_count(&self) -> usize {
+                self.base.get_byte_count() #( + #byte_counts )*
+            }
+
+            /// Serializes the form to a byte array.
+            pub fn to_bytes(&self) -> ::std::vec::Vec<u8> {
+                let mut bytes = self.base.to_bytes();
+                #( #writes )*
+                bytes
+            }
+
+            /// Converts the form into a dictionary-like JSON object.
+            pub fn to_dict(&self) -> #forms::derive_support::Value {
+                let mut dict = #forms::derive_support::Map::new();
+                dict.insert("form_id".to_string(), #forms::derive_support::Value::from(self.base.form_id.to_string()));
+                dict.insert("form_type".to_string(), #forms::derive_support::Value::from(self.base.form_type.to_string()));
+                dict.insert("form_name".to_string(), #forms::derive_support::Value::from(self.base.form_name.to_string()));
+                #( #dict_inserts )*
+                if !self.base.metadata.is_empty() {
+                    dict.insert("metadata".to_string(), self.base.metadata.to_dict());
+                }
+                #forms::derive_support::Value::Object(dict)
+            }
+
+            /// Reads the form from a binary file.
+            pub fn read_from_bytes(file: &mut ::std::fs::File) -> ::std::io::Result<Self> {
+                let form_id = <#types::FormID as #forms::FormField>::field_read_from_bytes(file)?;
+                let form_type = <#types::FormType as #forms::FormField>::field_read_from_bytes(file)?;
+                let form_name = <#types::StrSml as #forms::FormField>::field_read_from_bytes(file)?;
+                #( #file_reads )*
+
+                Ok(Self {
+                    base: #forms::FormBase::new(form_id, form_type, form_name),
+                    #( #field_idents: #local_idents, )*
+                })
+            }
+
+            /// Reads the form from a byte buffer, returning the bytes consumed.
+            pub fn read_from_byte_buffer(bytes: &[u8]) -> ::std::io::Result<(Self, usize)> {
+                let mut offset = 0;
+                let (form_id, consumed) = <#types::FormID as #forms::FormField>::field_read_from_byte_buffer(&bytes[offset..])?;
+                offset += consumed;
+                let (form_type, consumed) = <#types::FormType as #forms::FormField>::field_read_from_byte_buffer(&bytes[offset..])?;
+                offset += consumed;
+                let (form_name, consumed) = <#types::StrSml as #forms::FormField>::field_read_from_byte_buffer(&bytes[offset..])?;
+                offset += consumed;
+                #( #buffer_reads )*
+
+                Ok((
+                    Self {
+                        base: #forms::FormBase::new(form_id, form_type, form_name),
+                        #( #field_idents: #local_idents, )*
+                    },
+                    offset,
+                ))
+            }
+
+            /// Returns the fields of the form's `FormSchema`, keyed like `to_dict`.
+            pub fn schema_fields() -> ::std::vec::Vec<#forms::FormSchemaField> {
+                ::std::vec![ #( #schema_fields ),* ]
+            }
+
+            #reference_methods
+        }
+
+        #[automatically_derived]
+        impl #impl_generics #forms::FormTrait for #name #ty_generics #where_clause {
+            fn form_id(&self) -> #types::FormID {
+                self.base.form_id
+            }
+
+            fn form_type(&self) -> #types::FormType {
+                self.base.form_type
+            }
+
+            fn form_name(&self) -> #types::StrSml {
+                self.base.form_name.clone()
+            }
+
+            fn to_dict(&self) -> #forms::derive_support::Value {
+                Self::to_dict(self)
+            }
+
+            fn to_bytes(&self) -> ::std::vec::Vec<u8> {
+                Self::to_bytes(self)
+            }
+
+            fn get_byte_count(&self) -> usize {
+                Self::get_byte_count(self)
+            }
+
+            fn get_references(&self) -> ::std::vec::Vec<#types::GlobalID> {
+                Self::get_references(self)
+            }
+
+            fn remove_references(&mut self, target: #types::GlobalID) -> usize {
+                Self::remove_references(self, target)
+            }
+
+            fn remap_references(&mut self, remap: &dyn Fn(#types::GlobalID) -> Option<#types::GlobalID>) -> usize {
+                Self::remap_references(self, remap)
+            }
+
+            fn set_form_id(&mut self, form_id: #types::FormID) {
+                self.base.form_id = form_id;
+            }
+
+            fn form_metadata(&self) -> #forms::FormMetadata {
+                self.base.metadata.clone()
+            }
+
+            fn set_form_metadata(&mut self, metadata: #forms::FormMetadata) {
+                self.base.metadata = metadata;
+            }
+        }
+    })
+}
+
+/// Checks every `count_of` names an earlier `Vec` field with its own length.
+fn validate_count_of(form_fields: &[FormFieldAttrs]) -> syn::Result<()> {
+    for (i, field) in form_fields.iter().enumerate() {
+        let FieldLayout::CountOf(source) = &field.layout else {
+            continue;
+        };
+        let source_field = form_fields[..i].iter().find(|earlier| earlier.ident == *source);
+        match source_field.map(|earlier| &earlier.layout) {
+            Some(FieldLayout::Counted(_) | FieldLayout::Fixed(_)) => {}
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    source,
+                    "`count_of` must name an earlier field declared with `count` or `fixed`",
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Describes a field in the form's schema, an array sharing a length naming the other field by its key.
+fn expand_schema_field(field: &FormFieldAttrs, form_fields: &[FormFieldAttrs], forms: &TokenStream, types: &TokenStream) -> syn::Result<TokenStream> {
+    let key = &field.key;
+    let element = field.element.as_ref().unwrap_or(&field.ty);
+    let element_type = quote!(<#element as #forms::FormField>::schema_type());
+    let length = match &field.layout {
+        FieldLayout::Single => return Ok(quote!(#forms::FormSchemaField::new(#key, #element_type))),
+        FieldLayout::Counted(CountType::U8) => quote!(#forms::SchemaArrayLength::U8),
+        FieldLayout::Counted(CountType::U16) => quote!(#forms::SchemaArrayLength::U16),
+        FieldLayout::Counted(CountType::U32) => quote!(#forms::SchemaArrayLength::U32),
+        FieldLayout::Fixed(fixed) => {
+            let Ok(fixed) = u16::try_from(*fixed) else {
+                return Err(syn::Error::new_spanned(&field.ident, "`fixed` lengths above 65535 can't be described in a form schema"));
+            };
+            quote!(#forms::SchemaArrayLength::Fixed(#fixed))
+        }
+        FieldLayout::CountOf(source) => {
+            let source_key = &form_fields.iter().find(|earlier| earlier.ident == *source).expect("count_of checked").key;
+            quote!(#forms::SchemaArrayLength::CountOf(#types::StrSml::from(#source_key)))
+        }
+    };
+    Ok(quote! {
+        #forms::FormSchemaField::new(#key, #forms::SchemaFieldType::Array(#length, ::std::boxed::Box::new(#element_type)))
+    })
+}
+
+/// Name of the local a field is read into, kept apart from the reader's own locals.
+fn local_ident(ident: &Ident) -> Ident {
+    format_ident!("field_{}", ident)
+}
+
+fn expand_byte_count(field: &FormFieldAttrs, forms: &TokenStream) -> TokenStream {
+    let ident = &field.ident;
+    let items = quote! {
+        self.#ident.iter().map(|item| #forms::FormField::field_byte_count(item)).sum::<usize>()
+    };
+    match &field.layout {
+        FieldLayout::Single => quote!(#forms::FormField::field_byte_count(&self.#ident)),
+        FieldLayout::Counted(count_type) => {
+            let count_type = count_type.to_type();
+            quote!(::std::mem::size_of::<#count_type>() + #items)
+        }
+        FieldLayout::Fixed(_) | FieldLayout::CountOf(_) => items,
+    }
+}
+
+fn expand_write(field: &FormFieldAttrs, forms: &TokenStream) -> TokenStream {
+    let ident = &field.ident;
+    let items = quote! {
+        for item in &self.#ident {
+            #forms::FormField::field_to_bytes(item, &mut bytes);
+        }
+    };
+    match &field.layout {
+        FieldLayout::Single => quote!(#forms::FormField::field_to_bytes(&self.#ident, &mut bytes);),
+        FieldLayout::Counted(count_type) => {
+            let count_type = count_type.to_type();
+            quote! {
+                #forms::FormField::field_to_bytes(&(self.#ident.len() as #count_type), &mut bytes);
+                #items
+            }
+        }
+        FieldLayout::Fixed(_) | FieldLayout::CountOf(_) => items,
+    }
+}
+
+fn expand_dict_insert(field: &FormFieldAttrs, forms: &TokenStream) -> TokenStream {
+    let ident = &field.ident;
+    let key = &field.key;
+    let value = match &field.layout {
+        FieldLayout::Single => quote!(#forms::FormField::field_to_dict(&self.#ident)),
+        _ => quote! {
+            #forms::derive_support::Value::Array(self.#ident.iter().map(|item| #forms::FormField::field_to_dict(item)).collect())
+        },
+    };
+    quote!(dict.insert(#key.to_string(), #value);)
+}
+
+fn expand_file_read(field: &FormFieldAttrs, forms: &TokenStream) -> TokenStream {
+    let local = local_ident(&field.ident);
+    let ty = &field.ty;
+    let count = match &field.layout {
+        FieldLayout::Single => {
+            return quote!(let #local = <#ty as #forms::FormField>::field_read_from_bytes(file)?;);
+        }
+        FieldLayout::Counted(count_type) => {
+            let count_type = count_type.to_type();
+            quote!(<#count_type as #forms::FormField>::field_read_from_bytes(file)? as usize)
+        }
+        FieldLayout::Fixed(length) => quote!(#length),
+        FieldLayout::CountOf(source) => {
+            let source = local_ident(source);
+            quote!(#source.len())
+        }
+    };
+    let element = &field.element;
+    quote! {
+        let #local = {
+            let count = #count;
+            let mut items = ::std::vec::Vec::with_capacity(count);
+            for _ in 0..count {
+                items.push(<#element as #forms::FormField>::field_read_from_bytes(file)?);
+            }
+            items
+        };
+    }
+}
+
+fn expand_buffer_read(field: &FormFieldAttrs, forms: &TokenStream) -> TokenStream {
+    let local = local_ident(&field.ident);
+    let ty = &field.ty;
+    let count = match &field.layout {
+        FieldLayout::Single => {
+            return quote! {
+                let (#local, consumed) = <#ty as #forms::FormField>::field_read_from_byte_buffer(&bytes[offset..])?;
+                offset += consumed;
+            };
+        }
+        FieldLayout::Counted(count_type) => {
+            let count_type = count_type.to_type();
+            quote! {{
+                let (count, consumed) = <#count_type as #forms::FormField>::field_read_from_byte_buffer(&bytes[offset..])?;
+                offset += consumed;
+                count as usize
+            }}
+        }
+        FieldLayout::Fixed(length) => quote!(#length),
+        FieldLayout::CountOf(source) => {
+            let source = local_ident(source);
+            quote!(#source.len())
+        }
+    };
+    let element = &field.element;
+    quote! {
+        let #local = {
+            let count = #count;
+            let mut items = ::std::vec::Vec::with_capacity(count);
+            for _ in 0..count {
+                let (item, consumed) = <#element as #forms::FormField>::field_read_from_byte_buffer(&bytes[offset..])?;
+                offset += consumed;
+                items.push(item);
+            }
+            items
+        };
+    }
+}
+
+/// Generates `get_references`, `remove_references` and `remap_references` from the `reference` fields.
+fn expand_reference_methods(form_fields: &[FormFieldAttrs], types: &TokenStream) -> TokenStream {
+    let reference_fields: Vec<&FormFieldAttrs> = form_fields.iter().filter(|field| field.reference).collect();
+
+    let collects = reference_fields.iter().map(|field| {
+        let ident = &field.ident;
+        match &field.layout {
+            FieldLayout::Single => quote!(references.push(self.#ident);),
+            _ => quote!(references.extend_from_slice(&self.#ident);),
+        }
+    });
+
+    let removals = reference_fields.iter().map(|field| {
+        let ident = &field.ident;
+        match &field.layout {
+            FieldLayout::Single => quote! {
+                if self.#ident == target {
+                    self.#ident = #types::GlobalID::NULL;
+                    removed += 1;
+                }
+            },
+            // Items with their own length are dropped together with the items sharing it
+            FieldLayout::Counted(_) => {
+                let dependents = form_fields.iter()
+                    .filter(|other| matches!(&other.layout, FieldLayout::CountOf(source) if source == ident))
+                    .map(|other| &other.ident);
+                quote! {
+                    let mut i = 0;
+                    while i < self.#ident.len() {
+                        if self.#ident[i] == target {
+                            self.#ident.remove(i);
+                            #( self.#dependents.remove(i); )*
+                            removed += 1;
+                        } else {
+                            i += 1;
+                        }
+                    }
+                }
+            }
+            // Fixed slots cannot be dropped, so they are nulled
+            FieldLayout::Fixed(_) | FieldLayout::CountOf(_) => quote! {
+                for reference in self.#ident.iter_mut() {
+                    if *reference == target {
+                        *reference = #types::GlobalID::NULL;
+                        removed += 1;
+                    }
+                }
+            },
+        }
+    });
+
+    let remaps = reference_fields.iter().map(|field| {
+        let ident = &field.ident;
+        match &field.layout {
+            FieldLayout::Single => quote! {
+                if let Some(new_reference) = remap(self.#ident) {
+                    self.#ident = new_reference;
+                    remapped += 1;
+                }
+            },
+            _ => quote! {
+                for reference in self.#ident.iter_mut() {
+                    if let Some(new_reference) = remap(*reference) {
+                        *reference = new_reference;
+                        remapped += 1;
+                    }
+                }
+            },
+        }
+    });
+
+    // Forms without references get empty methods, keeping the generated code free of unused bindings
+    if reference_fields.is_empty() {
+        return quote! {
+            /// Returns the `GlobalID`s this form references (none).
+            pub fn get_references(&self) -> ::std::vec::Vec<#types::GlobalID> {
+                ::std::vec::Vec::new()
+            }
+
+            /// Removes every reference to `target`, returning how many were removed.
+            pub fn remove_references(&mut self, _target: #types::GlobalID) -> usize {
+                0
+            }
+
+            /// Replaces every reference `remap` returns a new `GlobalID` for, returning how many were replaced.
+            pub fn remap_references(&mut self, _remap: &dyn Fn(#types::GlobalID) -> Option<#types::GlobalID>) -> usize {
+                0
+            }
+        };
+    }
+
+    quote! {
+        /// Returns the `GlobalID`s this form references.
+        pub fn get_references(&self) -> ::std::vec::Vec<#types::GlobalID> {
+            let mut references = ::std::vec::Vec::new();
+            #( #collects )*
+            references
+        }
+
+        /// Removes every reference to `target`, returning how many were removed.
+        pub fn remove_references(&mut self, target: #types::GlobalID) -> usize {
+            let mut removed = 0;
+            #( #removals )*
+            removed
+        }
+
+        /// Replaces every reference `remap` returns a new `GlobalID` for, returning how many were replaced.
+        pub fn remap_references(&mut self, remap: &dyn Fn(#types::GlobalID) -> Option<#types::GlobalID>) -> usize {
+            let mut remapped = 0;
+            #( #remaps )*
+            remapped
+        }
+    }
+}
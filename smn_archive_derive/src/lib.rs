@@ -0,0 +1,39 @@
+/*
+    Derive macro for smn_archive forms.
+
+    `#[derive(Form)]` generates `get_byte_count`, `to_bytes`, `to_dict`, `read_from_bytes`,
+    `read_from_byte_buffer`, `schema_fields`, the reference methods and the `FormTrait` impl of a
+    form struct from its field declarations. Every field after `base` is serialized in declaration order
+    through the `FormField` trait of smn_archive.
+*/
+
+mod attrs;
+mod expand;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+/// Derives the serialization, schema description and `FormTrait` impl of a form struct.
+///
+/// The struct must have named fields, the first being `base: FormBase`.
+///
+/// Struct attributes:
+/// - `#[form(crate = "path")]`: Path of the smn_archive crate, `::smn_archive` by default.
+/// - `#[form(manual_references)]`: The struct writes its own inherent `get_references`,
+///   `remove_references` and `remap_references` instead of having them generated.
+///
+/// Field attributes:
+/// - `#[form(count = "u8" | "u16" | "u32")]`: `Vec` field stored with a length prefix of that type.
+/// - `#[form(fixed = N)]`: `Vec` field stored as exactly `N` items without a length prefix.
+/// - `#[form(count_of = "field")]`: `Vec` field sharing the length prefix of an earlier `Vec` field.
+/// - `#[form(rename = "key")]`: Key of the field in `to_dict`, the field name by default.
+/// - `#[form(reference)]`: `GlobalID` or `Vec<GlobalID>` field holding references to other forms.
+///   Removed references are nulled, except in `count` fields where the item is dropped along
+///   with the matching item of every `count_of` field sharing its length.
+#[proc_macro_derive(Form, attributes(form))]
+pub fn derive_form(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand::expand_form(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -1,8 +1,11 @@
-use std::{ffi::{c_void, CStr}, ptr, slice};
+use std::{ffi::{c_void, CStr}, ptr, slice, sync::Mutex, time::Duration};
 
-use crate::core::io::{delete_form, get_form_exists, read_archive_info, read_form, read_forms, read_lite_archive, write_archive_info, write_archive_skeleton, write_form};
+use crate::core::io::{allocate_form_id, allocate_form_id_in_range, compact_archive, delete_form, delete_form_checked, get_form_exists, insert_form, read_archive_info, read_archive_metadata, read_form, read_form_by_name, read_form_metadata, read_forms, read_lite_archive, read_lite_archive_page, query_world_part_entities, query_world_parts, reserve_form_id_range, set_archive_lock_wait, set_archive_write_mode, write_archive_info, write_archive_metadata, write_archive_skeleton, write_form, write_form_metadata, ArchiveLockWait, ArchiveWriteMode, DeleteMode, FormIDRange, LiteArchiveQuery, ReferenceIndex};
 use crate::core::structs::*;
 
+/// Reference index kept between calls, keyed by the path list it was built from.
+static REFERENCE_INDEX_CACHE: Mutex<Option<(String, ReferenceIndex)>> = Mutex::new(None);
+
 /// Writes the skeleton of an archive, initializing the archive structure.
 /// Parameters are passed as C-style strings and integers, and memory is allocated for the result.
 #[no_mangle]
@@ -103,8 +106,9 @@ pub extern "C" fn smn_read_archive_info(path: *const i8) -> *const u8 {
         Err(_) => return ptr::null(),
     };
 
-    // Convert the archive info to bytes
-    let archive_bytes = archive_info.header_to_bytes();
+    // Convert the archive info to bytes, followed by the archive metadata
+    let mut archive_bytes = archive_info.header_to_bytes();
+    archive_bytes.extend_from_slice(&archive_info.metadata.to_bytes());
     let len = archive_bytes.len() as u32;
 
     // Allocate memory for the length and archive bytes
@@ -125,6 +129,84 @@ pub extern "C" fn smn_read_archive_info(path: *const i8) -> *const u8 {
     ptr as *const u8
 }
 
+/// Reads the key/value metadata of an archive and returns a pointer to the serialized metadata.
+///
+/// # Safety
+///
+/// `path` must point to a NUL-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn smn_read_archive_metadata(path: *const i8) -> *const u8 {
+    let c_str = unsafe { CStr::from_ptr(path) };
+    let path_str = c_str.to_str().unwrap_or("Invalid UTF-8");
+
+    // Attempt to read the metadata and handle errors
+    let metadata = match read_archive_metadata(path_str) {
+        Ok(metadata) => metadata,
+        Err(_) => return ptr::null(),
+    };
+
+    // Convert the metadata to bytes
+    let metadata_bytes = metadata.to_bytes();
+    let len = metadata_bytes.len() as u32;
+
+    // Allocate memory for the length and metadata bytes
+    let total_len = std::mem::size_of::<u32>() + len as usize;
+    let ptr = unsafe { libc::malloc(total_len) as *mut u8 };
+    if ptr.is_null() {
+        return ptr::null();
+    }
+
+    // Write length and metadata bytes to the allocated memory
+    unsafe {
+        let len_ptr = ptr as *mut u32;
+        *len_ptr = len;
+        let data_ptr = ptr.add(std::mem::size_of::<u32>());
+        data_ptr.copy_from_nonoverlapping(metadata_bytes.as_ptr(), len as usize);
+    }
+
+    ptr as *const u8
+}
+
+/// Replaces the key/value metadata of an archive with serialized metadata and returns a success flag.
+///
+/// # Safety
+///
+/// `path` must point to a NUL-terminated C string and `metadata_data` to `metadata_size` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn smn_write_archive_metadata(path: *const i8, metadata_data: *const u8, metadata_size: usize) -> *const u8 {
+    let c_str = unsafe { CStr::from_ptr(path) };
+    let path_str = c_str.to_str().unwrap_or("Invalid UTF-8");
+
+    // Parse the metadata, it must use every byte given
+    let metadata_slice = unsafe { std::slice::from_raw_parts(metadata_data, metadata_size) };
+    let metadata = match ArchiveMetadata::read_from_byte_buffer(metadata_slice) {
+        Ok((metadata, consumed)) if consumed == metadata_slice.len() => metadata,
+        _ => return ptr::null(),
+    };
+
+    // Write the metadata and check success
+    let was_successful = match write_archive_metadata(path_str, &metadata) {
+        Ok(_) => 1u8,
+        Err(_) => 0u8,
+    };
+
+    // Prepare a response with the success flag
+    let response_len = 5;
+    let ptr = unsafe { libc::malloc(response_len) as *mut u8 };
+    if ptr.is_null() {
+        return ptr::null();
+    }
+
+    // Write length and success flag to the allocated memory
+    unsafe {
+        let len_ptr = ptr as *mut u32;
+        *len_ptr = 1u32;
+        *ptr.add(4) = was_successful;
+    }
+
+    ptr as *const u8
+}
+
 /// Reads a lightweight version of the archive and returns a pointer to the data.
 #[no_mangle]
 pub extern "C" fn smn_read_lite_archive(path: *const i8) -> *const u8 {
@@ -159,6 +241,51 @@ pub extern "C" fn smn_read_lite_archive(path: *const i8) -> *const u8 {
     ptr as *const u8
 }
 
+/// Reads one page of the lightweight archive listing and returns a pointer to the data.
+/// A negative `form_type` lists forms of every type, a `limit` of 0 lists every form past `offset`.
+///
+/// # Safety
+///
+/// `path` must point to a NUL-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn smn_read_lite_archive_page(path: *const i8, form_type: i16, offset: u32, limit: u32) -> *const u8 {
+    let c_str = unsafe { CStr::from_ptr(path) };
+    let path_str = c_str.to_str().unwrap_or("Invalid UTF-8");
+
+    let query = LiteArchiveQuery {
+        form_type: u8::try_from(form_type).ok().map(FormType::from),
+        offset: offset as usize,
+        limit: (limit > 0).then_some(limit as usize),
+    };
+
+    // Read the page and handle errors
+    let lite_archive = match read_lite_archive_page(path_str, &query) {
+        Ok(archive) => archive,
+        Err(_) => return ptr::null(),
+    };
+
+    // Convert the page to bytes
+    let archive_bytes = lite_archive.to_page_bytes();
+    let len = archive_bytes.len() as u32;
+
+    // Allocate memory for the length and archive bytes
+    let total_len = std::mem::size_of::<u32>() + archive_bytes.len();
+    let ptr = unsafe { libc::malloc(total_len) as *mut u8 };
+    if ptr.is_null() {
+        return ptr::null();
+    }
+
+    // Write length and archive bytes to the allocated memory
+    unsafe {
+        let len_ptr = ptr as *mut u32;
+        *len_ptr = len;
+        let data_ptr = ptr.add(std::mem::size_of::<u32>());
+        data_ptr.copy_from_nonoverlapping(archive_bytes.as_ptr(), archive_bytes.len());
+    }
+
+    ptr as *const u8
+}
+
 /// Writes form data to a file and returns a success flag.
 #[no_mangle]
 pub extern "C" fn smn_write_form(path: *const i8, form_data: *const u8, form_size: usize) -> *const u8 {
@@ -198,6 +325,128 @@ pub extern "C" fn smn_write_form(path: *const i8, form_data: *const u8, form_siz
     ptr as *const u8
 }
 
+/// Writes a new form to a specified file and returns a success flag.
+/// Fails instead of overwriting when a form with the same FormID already exists.
+///
+/// # Safety
+///
+/// `path` must point to a NUL-terminated C string and `form_data` to `form_size` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn smn_insert_form(path: *const i8, form_data: *const u8, form_size: usize) -> *const u8 {
+    let c_str = unsafe { CStr::from_ptr(path) };
+    let path_str = c_str.to_str().unwrap_or("Invalid UTF-8");
+
+    // Convert form data to a byte slice
+    let form_slice = unsafe { std::slice::from_raw_parts(form_data, form_size) };
+
+    // Parse the form and handle errors
+    let read_form = match FormBase::read_from_byte_buffer(form_slice) {
+        Ok(f) => f.0,
+        Err(_) => return ptr::null(),
+    };
+
+    // Insert the form into the file and check success
+    let was_successful = match insert_form(path_str, &*read_form) {
+        Ok(_) => 1u8,
+        Err(_) => 0u8,
+    };
+
+    // Prepare a response with the success flag
+    let response_len = 5;
+    let ptr = unsafe { libc::malloc(response_len) as *mut u8 };
+    if ptr.is_null() {
+        return ptr::null();
+    }
+
+    // Write length and success flag to the allocated memory
+    unsafe {
+        let len_ptr = ptr as *mut u32;
+        *len_ptr = 1u32;
+        *ptr.add(4) = was_successful;
+    }
+
+    ptr as *const u8
+}
+
+/// Returns the next free FormID of an archive, inside the named reserved range when `range_name` is not null.
+///
+/// # Safety
+///
+/// `path` must point to a NUL-terminated C string, and `range_name` must be null or point to one.
+#[no_mangle]
+pub unsafe extern "C" fn smn_allocate_form_id(path: *const i8, range_name: *const i8) -> *const u8 {
+    let c_str = unsafe { CStr::from_ptr(path) };
+    let path_str = c_str.to_str().unwrap_or("Invalid UTF-8");
+
+    // Allocate outside the reserved ranges unless a range is named
+    let result = if range_name.is_null() {
+        allocate_form_id(path_str)
+    } else {
+        let range_str = unsafe { CStr::from_ptr(range_name) }.to_str().unwrap_or("Invalid UTF-8");
+        allocate_form_id_in_range(path_str, range_str)
+    };
+    let form_id = match result {
+        Ok(form_id) => form_id,
+        Err(_) => return ptr::null(),
+    };
+
+    // Allocate memory for the length and FormID bytes
+    let total_len = std::mem::size_of::<u32>() + FormID::BYTE_COUNT;
+    let ptr = unsafe { libc::malloc(total_len) as *mut u8 };
+    if ptr.is_null() {
+        return ptr::null();
+    }
+
+    // Write length and FormID to the allocated memory
+    unsafe {
+        let len_ptr = ptr as *mut u32;
+        *len_ptr = FormID::BYTE_COUNT as u32;
+        let data_ptr = ptr.add(std::mem::size_of::<u32>());
+        data_ptr.copy_from_nonoverlapping(form_id.to_bytes().as_ptr(), FormID::BYTE_COUNT);
+    }
+
+    ptr as *const u8
+}
+
+/// Reserves a named FormID range in an archive's metadata and returns a success flag.
+///
+/// # Safety
+///
+/// `path` and `range_name` must point to NUL-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn smn_reserve_form_id_range(path: *const i8, range_name: *const i8, start: u16, end: u16) -> *const u8 {
+    let c_str = unsafe { CStr::from_ptr(path) };
+    let path_str = c_str.to_str().unwrap_or("Invalid UTF-8");
+
+    let range_str = unsafe { CStr::from_ptr(range_name) }.to_str().unwrap_or("Invalid UTF-8");
+    if range_str.len() > u8::MAX as usize || !range_str.is_ascii() {
+        return ptr::null();
+    }
+    let range = FormIDRange::new(StrSml::from(range_str), FormID::from(start), FormID::from(end));
+
+    // Reserve the range and check success
+    let was_successful = match reserve_form_id_range(path_str, range) {
+        Ok(_) => 1u8,
+        Err(_) => 0u8,
+    };
+
+    // Prepare a response with the success flag
+    let response_len = 5;
+    let ptr = unsafe { libc::malloc(response_len) as *mut u8 };
+    if ptr.is_null() {
+        return ptr::null();
+    }
+
+    // Write length and success flag to the allocated memory
+    unsafe {
+        let len_ptr = ptr as *mut u32;
+        *len_ptr = 1u32;
+        *ptr.add(4) = was_successful;
+    }
+
+    ptr as *const u8
+}
+
 /// Deletes a form by its form ID from a specified file and returns a success flag.
 #[no_mangle]
 pub extern "C" fn smn_delete_form(path: *const i8, form_id: u16) -> *const u8 {
@@ -231,6 +480,61 @@ pub extern "C" fn smn_delete_form(path: *const i8, form_id: u16) -> *const u8 {
     ptr as *const u8
 }
 
+/// Deletes a form after checking references to it across a `;`-separated list of archive paths.
+/// Mode 0 deletes unconditionally, 1 refuses while referenced, 2 cascades to the referrers.
+///
+/// # Safety
+///
+/// `paths` and `path` must point to NUL-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn smn_delete_form_checked(paths: *const i8, path: *const i8, form_id: u16, mode: u8) -> *const u8 {
+    let paths_c_str = unsafe { CStr::from_ptr(paths) };
+    let paths_str = paths_c_str.to_str().unwrap_or("Invalid UTF-8");
+
+    let c_str = unsafe { CStr::from_ptr(path) };
+    let path_str = c_str.to_str().unwrap_or("Invalid UTF-8");
+
+    let mode = match mode {
+        0 => DeleteMode::Unchecked,
+        1 => DeleteMode::Restrict,
+        2 => DeleteMode::Cascade,
+        _ => return ptr::null(),
+    };
+
+    // Attempt the delete and capture the outcome
+    let archive_paths: Vec<&str> = paths_str.split(';').filter(|p| !p.is_empty()).collect();
+    let report = match delete_form_checked(&archive_paths, path_str, FormID::from(form_id), mode) {
+        Ok(report) => report,
+        Err(_) => return ptr::null(),
+    };
+
+    // Serialize the deleted flag followed by the referrers
+    let mut report_bytes = Vec::with_capacity(1 + report.referrers.len() * GlobalID::BYTE_COUNT);
+    report_bytes.push(report.deleted as u8);
+    for referrer in report.referrers {
+        report_bytes.extend_from_slice(&referrer.to_bytes());
+    }
+
+    let len = report_bytes.len() as u32;
+
+    // Allocate memory for the length and report bytes
+    let total_len = std::mem::size_of::<u32>() + len as usize;
+    let ptr = unsafe { libc::malloc(total_len) as *mut u8 };
+    if ptr.is_null() {
+        return ptr::null();
+    }
+
+    // Write length and report bytes to the allocated memory
+    unsafe {
+        let len_ptr = ptr as *mut u32;
+        *len_ptr = len;
+        let data_ptr = ptr.add(std::mem::size_of::<u32>());
+        data_ptr.copy_from_nonoverlapping(report_bytes.as_ptr(), len as usize);
+    }
+
+    ptr as *const u8
+}
+
 /// Checks if a form exists in the specified file by its form ID.
 #[no_mangle]
 pub extern "C" fn smn_get_form_exists(path: *const i8, form_id: u16) -> *const u8 {
@@ -299,6 +603,128 @@ pub extern "C" fn smn_read_form(path: *const i8, form_id: u16) -> *const u8 {
     ptr as *const u8
 }
 
+/// Reads a form by its name from a file and returns a pointer to the serialized form data.
+///
+/// # Safety
+///
+/// `path` and `form_name` must point to NUL-terminated C strings.
+#[no_mangle]
+pub unsafe extern "C" fn smn_read_form_by_name(path: *const i8, form_name: *const i8) -> *const u8 {
+    let c_str = unsafe { CStr::from_ptr(path) };
+    let path_str = c_str.to_str().unwrap_or("Invalid UTF-8");
+
+    let name_c_str = unsafe { CStr::from_ptr(form_name) };
+    let form_name_str = match name_c_str.to_str() {
+        Ok(name) => name,
+        Err(_) => return ptr::null(),
+    };
+
+    // Attempt to read the form and handle errors
+    let form = match read_form_by_name(path_str, form_name_str) {
+        Ok(f) => f,
+        Err(_) => return ptr::null(),
+    };
+
+    // Convert the form to bytes
+    let form_bytes = form.to_bytes();
+    let len = form_bytes.len() as u32;
+
+    // Allocate memory for the length and form bytes
+    let total_len = std::mem::size_of::<u32>() + len as usize;
+    let ptr = unsafe { libc::malloc(total_len) as *mut u8 };
+    if ptr.is_null() {
+        return ptr::null();
+    }
+
+    // Write length and form bytes to the allocated memory
+    unsafe {
+        let len_ptr = ptr as *mut u32;
+        *len_ptr = len;
+        let data_ptr = ptr.add(std::mem::size_of::<u32>());
+        data_ptr.copy_from_nonoverlapping(form_bytes.as_ptr(), len as usize);
+    }
+
+    ptr as *const u8
+}
+
+/// Reads the metadata of a form and returns a pointer to its serialized metadata section.
+///
+/// # Safety
+///
+/// `path` must point to a NUL-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn smn_read_form_metadata(path: *const i8, form_id: u16) -> *const u8 {
+    let c_str = unsafe { CStr::from_ptr(path) };
+    let path_str = c_str.to_str().unwrap_or("Invalid UTF-8");
+
+    // Attempt to read the metadata and handle errors
+    let metadata = match read_form_metadata(path_str, FormID::from(form_id)) {
+        Ok(metadata) => metadata,
+        Err(_) => return ptr::null(),
+    };
+
+    // Convert the metadata to bytes
+    let metadata_bytes = metadata.to_bytes();
+    let len = metadata_bytes.len() as u32;
+
+    // Allocate memory for the length and metadata bytes
+    let total_len = std::mem::size_of::<u32>() + len as usize;
+    let ptr = unsafe { libc::malloc(total_len) as *mut u8 };
+    if ptr.is_null() {
+        return ptr::null();
+    }
+
+    // Write length and metadata bytes to the allocated memory
+    unsafe {
+        let len_ptr = ptr as *mut u32;
+        *len_ptr = len;
+        let data_ptr = ptr.add(std::mem::size_of::<u32>());
+        data_ptr.copy_from_nonoverlapping(metadata_bytes.as_ptr(), len as usize);
+    }
+
+    ptr as *const u8
+}
+
+/// Replaces the metadata of a form with a serialized metadata section and returns a success flag.
+///
+/// # Safety
+///
+/// `path` must point to a NUL-terminated C string and `metadata_data` to `metadata_size` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn smn_write_form_metadata(path: *const i8, form_id: u16, metadata_data: *const u8, metadata_size: usize) -> *const u8 {
+    let c_str = unsafe { CStr::from_ptr(path) };
+    let path_str = c_str.to_str().unwrap_or("Invalid UTF-8");
+
+    // Parse the metadata section, it must use every byte given
+    let metadata_slice = unsafe { std::slice::from_raw_parts(metadata_data, metadata_size) };
+    let metadata = match FormMetadata::read_from_byte_buffer(metadata_slice) {
+        Ok((metadata, consumed)) if consumed == metadata_slice.len() => metadata,
+        _ => return ptr::null(),
+    };
+
+    // Write the metadata and check success
+    let was_successful = match write_form_metadata(path_str, FormID::from(form_id), metadata) {
+        Ok(_) => 1u8,
+        Err(_) => 0u8,
+    };
+
+    // Prepare a response with the success flag
+    let response_len = 5;
+    let ptr = unsafe { libc::malloc(response_len) as *mut u8 };
+    if ptr.is_null() {
+        return ptr::null();
+    }
+
+    // Write length and success flag to the allocated memory
+    unsafe {
+        let len_ptr = ptr as *mut u32;
+        *len_ptr = 1u32;
+        *ptr.add(4) = was_successful;
+    }
+
+    ptr as *const u8
+}
+
 /// Reads multiple forms by their IDs from a file and returns a pointer to the serialized form data.
 #[no_mangle]
 pub extern "C" fn smn_read_forms(path: *const i8, form_ids: *const u8) -> *const u8 {
@@ -349,6 +775,220 @@ pub extern "C" fn smn_read_forms(path: *const i8, form_ids: *const u8) -> *const
     ptr as *const u8
 }
 
+/// Lists every form referencing a `GlobalID` across a `;`-separated list of archive paths.
+/// The index is cached between calls and rescanned only for archives that changed on disk.
+///
+/// # Safety
+///
+/// `paths` must point to a NUL-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn smn_read_form_referrers(paths: *const i8, archive_id: u8, form_id: u16) -> *const u8 {
+    let c_str = unsafe { CStr::from_ptr(paths) };
+    let paths_str = c_str.to_str().unwrap_or("Invalid UTF-8");
+    let target = GlobalID::from((ArchiveID::from(archive_id), FormID::from(form_id)));
+
+    // Reuse the cached index when the path list matches, otherwise build a new one
+    let mut cache = match REFERENCE_INDEX_CACHE.lock() {
+        Ok(cache) => cache,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let cache_valid = match cache.as_mut() {
+        Some((cached_paths, index)) if cached_paths == paths_str => index.refresh().is_ok(),
+        _ => false,
+    };
+    if !cache_valid {
+        let file_paths: Vec<&str> = paths_str.split(';').filter(|path| !path.is_empty()).collect();
+        match ReferenceIndex::build(&file_paths) {
+            Ok(index) => *cache = Some((paths_str.to_string(), index)),
+            Err(_) => {
+                *cache = None;
+                return ptr::null();
+            }
+        }
+    }
+
+    // Serialize the referrers as consecutive GlobalIDs
+    let referrers = match cache.as_ref() {
+        Some((_, index)) => index.get_referrers(target),
+        None => return ptr::null(),
+    };
+    let mut referrer_bytes = Vec::with_capacity(referrers.len() * GlobalID::BYTE_COUNT);
+    for referrer in referrers {
+        referrer_bytes.extend_from_slice(&referrer.to_bytes());
+    }
+
+    let len = referrer_bytes.len() as u32;
+
+    // Allocate memory for the length and referrer bytes
+    let total_len = std::mem::size_of::<u32>() + len as usize;
+    let ptr = unsafe { libc::malloc(total_len) as *mut u8 };
+    if ptr.is_null() {
+        return ptr::null();
+    }
+
+    // Write length and referrer bytes to the allocated memory
+    unsafe {
+        let len_ptr = ptr as *mut u32;
+        *len_ptr = len;
+        let data_ptr = ptr.add(std::mem::size_of::<u32>());
+        data_ptr.copy_from_nonoverlapping(referrer_bytes.as_ptr(), len as usize);
+    }
+
+    ptr as *const u8
+}
+
+/// Finds the world parts of a world whose anchors match a query and returns their GlobalIDs.
+/// `kind` 0 finds the part nearest to `(x, y, z)`, 1 the parts inside the box from `(x, y, z)` to
+/// `(x2, y2, z2)`, 2 the parts within a radius of `x2` around `(x, y, z)`.
+///
+/// # Safety
+///
+/// `path` must point to a NUL-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn smn_query_world_parts(path: *const i8, world_id: u16, kind: u8, x: i32, y: i32, z: i32, x2: i32, y2: i32, z2: i32) -> *const u8 {
+    let c_str = unsafe { CStr::from_ptr(path) };
+    let path_str = c_str.to_str().unwrap_or("Invalid UTF-8");
+    let point = Vec3Int::from((x, y, z));
+    let query = match kind {
+        0 => WorldPartQuery::Nearest(point),
+        1 => WorldPartQuery::Box { min: point, max: Vec3Int::from((x2, y2, z2)) },
+        2 if x2 >= 0 => WorldPartQuery::Radius { center: point, radius: x2 as u32 },
+        _ => return ptr::null(),
+    };
+
+    let world_parts = match query_world_parts(path_str, FormID::from(world_id), &query) {
+        Ok(world_parts) => world_parts,
+        Err(_) => return ptr::null(),
+    };
+    let mut world_part_bytes = Vec::with_capacity(world_parts.len() * GlobalID::BYTE_COUNT);
+    for world_part in world_parts {
+        world_part_bytes.extend_from_slice(&world_part.to_bytes());
+    }
+
+    let len = world_part_bytes.len() as u32;
+
+    // Allocate memory for the length and world part bytes
+    let total_len = std::mem::size_of::<u32>() + len as usize;
+    let ptr = unsafe { libc::malloc(total_len) as *mut u8 };
+    if ptr.is_null() {
+        return ptr::null();
+    }
+
+    // Write length and world part bytes to the allocated memory
+    unsafe {
+        let len_ptr = ptr as *mut u32;
+        *len_ptr = len;
+        let data_ptr = ptr.add(std::mem::size_of::<u32>());
+        data_ptr.copy_from_nonoverlapping(world_part_bytes.as_ptr(), len as usize);
+    }
+
+    ptr as *const u8
+}
+
+/// Finds the entity instances of a world part whose positions match a query and returns them.
+/// `kind` 0 finds the entities inside the box from `(x, y, z)` to `(x2, y2, z2)`, 1 the entities
+/// within a radius of `x2` around `(x, y, z)`.
+///
+/// # Safety
+///
+/// `path` must point to a NUL-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn smn_query_world_part_entities(path: *const i8, form_id: u16, kind: u8, x: f32, y: f32, z: f32, x2: f32, y2: f32, z2: f32) -> *const u8 {
+    let c_str = unsafe { CStr::from_ptr(path) };
+    let path_str = c_str.to_str().unwrap_or("Invalid UTF-8");
+    let point = Vec3Float::from((x, y, z));
+    let query = match kind {
+        0 => EntityQuery::Box { min: point, max: Vec3Float::from((x2, y2, z2)) },
+        1 => EntityQuery::Radius { center: point, radius: x2 },
+        _ => return ptr::null(),
+    };
+
+    let entities = match query_world_part_entities(path_str, FormID::from(form_id), &query) {
+        Ok(entities) => entities,
+        Err(_) => return ptr::null(),
+    };
+    let mut entity_bytes = Vec::with_capacity(entities.len() * EntInstance::BYTE_COUNT);
+    for entity in entities {
+        entity_bytes.extend_from_slice(&entity.to_bytes());
+    }
+
+    let len = entity_bytes.len() as u32;
+
+    // Allocate memory for the length and entity bytes
+    let total_len = std::mem::size_of::<u32>() + len as usize;
+    let ptr = unsafe { libc::malloc(total_len) as *mut u8 };
+    if ptr.is_null() {
+        return ptr::null();
+    }
+
+    // Write length and entity bytes to the allocated memory
+    unsafe {
+        let len_ptr = ptr as *mut u32;
+        *len_ptr = len;
+        let data_ptr = ptr.add(std::mem::size_of::<u32>());
+        data_ptr.copy_from_nonoverlapping(entity_bytes.as_ptr(), len as usize);
+    }
+
+    ptr as *const u8
+}
+
+/// Sets how long every call waits for an archive another process or thread has locked, in
+/// milliseconds: 0 fails at once, a negative timeout waits until the lock is released.
+#[no_mangle]
+pub extern "C" fn smn_set_archive_lock_wait(timeout_ms: i64) {
+    let wait = match timeout_ms {
+        0 => ArchiveLockWait::NoWait,
+        ms if ms < 0 => ArchiveLockWait::Forever,
+        ms => ArchiveLockWait::Timeout(Duration::from_millis(ms as u64)),
+    };
+    set_archive_lock_wait(wait);
+}
+
+/// Sets how every call writes and deletes forms: 0 keeps records in order, 1 appends new versions
+/// and leaves dead space for `smn_compact_archive`. Other modes are ignored.
+#[no_mangle]
+pub extern "C" fn smn_set_archive_write_mode(mode: u8) {
+    match mode {
+        0 => set_archive_write_mode(ArchiveWriteMode::Rewrite),
+        1 => set_archive_write_mode(ArchiveWriteMode::Append),
+        _ => {},
+    }
+}
+
+/// Reclaims the dead space left by append mode writes and returns the number of bytes freed.
+///
+/// # Safety
+///
+/// `path` must point to a NUL-terminated C string.
+#[no_mangle]
+pub unsafe extern "C" fn smn_compact_archive(path: *const i8) -> *const u8 {
+    let c_str = unsafe { CStr::from_ptr(path) };
+    let path_str = c_str.to_str().unwrap_or("Invalid UTF-8");
+
+    let reclaimed = match compact_archive(path_str) {
+        Ok(reclaimed) => reclaimed,
+        Err(_) => return ptr::null(),
+    };
+
+    // Allocate memory for the length and byte count
+    let result_len = std::mem::size_of::<u32>();
+    let total_len = std::mem::size_of::<u32>() + result_len;
+    let ptr = unsafe { libc::malloc(total_len) as *mut u8 };
+    if ptr.is_null() {
+        return ptr::null();
+    }
+
+    // Write length and byte count to the allocated memory
+    unsafe {
+        let len_ptr = ptr as *mut u32;
+        *len_ptr = result_len as u32;
+        let data_ptr = ptr.add(std::mem::size_of::<u32>());
+        data_ptr.copy_from_nonoverlapping(reclaimed.to_be_bytes().as_ptr(), result_len);
+    }
+
+    ptr as *const u8
+}
+
 /// Frees a pointer allocated by the FFI.
 #[no_mangle]
 pub extern "C" fn free_ptr(ptr: *mut c_void) {
@@ -0,0 +1,185 @@
+use std::collections::BTreeSet;
+use std::io;
+
+use serde_json::{json, Value};
+
+use crate::core::io::{read_ext_block, read_form_ids, remove_ext_block, write_ext_block, ArchiveLock};
+use crate::core::structs::*;
+
+/// Extension block tag holding the archive's reserved FormID ranges.
+pub const EXT_TAG_RESERVED_RANGES: [u8; 4] = *b"RSRV";
+
+/// A named, inclusive span of FormIDs set aside for a team or form type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FormIDRange {
+    pub name: StrSml,
+    pub start: FormID,
+    pub end: FormID,
+}
+
+#[allow(unused)]
+impl FormIDRange {
+    pub fn new(name: StrSml, start: FormID, end: FormID) -> Self {
+        Self { name, start, end }
+    }
+
+    /// Returns whether `form_id` lies inside the range.
+    pub fn contains(&self, form_id: FormID) -> bool {
+        self.start <= form_id && form_id <= self.end
+    }
+
+    /// Returns whether the two ranges share any FormID.
+    pub fn overlaps(&self, other: &FormIDRange) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub fn get_byte_count(&self) -> usize {
+        self.name.get_byte_count() + FormID::BYTE_COUNT * 2
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.name.to_bytes();
+        bytes.extend_from_slice(&self.start.to_bytes());
+        bytes.extend_from_slice(&self.end.to_bytes());
+        bytes
+    }
+
+    pub fn read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        let (name, mut offset) = StrSml::read_from_byte_buffer(bytes)?;
+
+        if bytes.len() < offset + FormID::BYTE_COUNT * 2 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for FormIDRange bounds"));
+        }
+        let start = FormID::from([bytes[offset], bytes[offset + 1]]);
+        let end = FormID::from([bytes[offset + 2], bytes[offset + 3]]);
+        offset += FormID::BYTE_COUNT * 2;
+
+        Ok((Self { name, start, end }, offset))
+    }
+
+    pub fn to_dict(&self) -> Value {
+        json!({
+            "name": self.name.to_string(),
+            "start": self.start.to_string(),
+            "end": self.end.to_string(),
+        })
+    }
+}
+
+/// Reads the reserved FormID ranges of an archive.
+pub fn read_reserved_ranges(file_path: &str) -> io::Result<Vec<FormIDRange>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    let bytes = match read_ext_block(file_path, EXT_TAG_RESERVED_RANGES)? {
+        Some(bytes) => bytes,
+        None => return Ok(Vec::new()),
+    };
+
+    if bytes.len() < 2 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for reserved range count"));
+    }
+    let range_count = u16::from_be_bytes([bytes[0], bytes[1]]);
+    let mut offset = 2;
+
+    let mut ranges = Vec::with_capacity(range_count as usize);
+    for _ in 0..range_count {
+        let (range, consumed) = FormIDRange::read_from_byte_buffer(&bytes[offset..])?;
+        ranges.push(range);
+        offset += consumed;
+    }
+
+    Ok(ranges)
+}
+
+/// Reserves a FormID range in the archive's metadata.
+///
+/// Fails if the name is taken or the range overlaps another reserved range. Forms already inside the range are kept.
+pub fn reserve_form_id_range(file_path: &str, range: FormIDRange) -> io::Result<()> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    if range.start.to_u16() == 0 || range.start > range.end {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "A reserved range must start above 00000 and not end before it starts."));
+    }
+
+    let mut ranges = read_reserved_ranges(file_path)?;
+    for existing in &ranges {
+        if existing.name == range.name {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("Range {} is already reserved.", range.name.to_string())));
+        }
+        if existing.overlaps(&range) {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("Range overlaps reserved range {}.", existing.name.to_string())));
+        }
+    }
+
+    ranges.push(range);
+    ranges.sort_by_key(|range| range.start);
+    write_reserved_ranges(file_path, &ranges)
+}
+
+/// Releases a reserved FormID range, returning whether it existed.
+pub fn release_form_id_range(file_path: &str, name: &str) -> io::Result<bool> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut ranges = read_reserved_ranges(file_path)?;
+    let range_count = ranges.len();
+    ranges.retain(|range| range.name.to_string() != name);
+    if ranges.len() == range_count {
+        return Ok(false);
+    }
+
+    write_reserved_ranges(file_path, &ranges)?;
+    Ok(true)
+}
+
+/// Returns the next free FormID outside every reserved range.
+///
+/// IDs above the highest one in use are preferred so deleted IDs, which may still be referenced, are only reused once the top is full.
+pub fn allocate_form_id(file_path: &str) -> io::Result<FormID> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let ranges = read_reserved_ranges(file_path)?;
+    let used: BTreeSet<FormID> = read_form_ids(file_path)?.into_iter().collect();
+
+    find_free_form_id(&used, FormID::from(1), FormID::from(u16::MAX), &ranges)
+        .ok_or_else(|| io::Error::other("No free FormID left outside the reserved ranges."))
+}
+
+/// Returns the next free FormID inside the named reserved range.
+pub fn allocate_form_id_in_range(file_path: &str, name: &str) -> io::Result<FormID> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let ranges = read_reserved_ranges(file_path)?;
+    let range = match ranges.iter().find(|range| range.name.to_string() == name) {
+        Some(range) => range,
+        None => return Err(io::Error::new(io::ErrorKind::NotFound, format!("Range {} is not reserved.", name))),
+    };
+    let used: BTreeSet<FormID> = read_form_ids(file_path)?.into_iter().collect();
+
+    find_free_form_id(&used, range.start, range.end, &[])
+        .ok_or_else(|| io::Error::other(format!("Range {} is full.", name)))
+}
+
+/// Writes the reserved ranges block, removing it when no ranges are left.
+fn write_reserved_ranges(file_path: &str, ranges: &[FormIDRange]) -> io::Result<()> {
+    if ranges.is_empty() {
+        remove_ext_block(file_path, EXT_TAG_RESERVED_RANGES)?;
+        return Ok(());
+    }
+
+    let mut bytes = (ranges.len() as u16).to_be_bytes().to_vec();
+    for range in ranges {
+        bytes.extend_from_slice(&range.to_bytes());
+    }
+    write_ext_block(file_path, EXT_TAG_RESERVED_RANGES, bytes)
+}
+
+/// Finds a FormID in `start..=end` that is unused and outside `excluded`, preferring IDs above the highest used one.
+fn find_free_form_id(used: &BTreeSet<FormID>, start: FormID, end: FormID, excluded: &[FormIDRange]) -> Option<FormID> {
+    let is_excluded = |form_id: FormID| excluded.iter().any(|range| range.contains(form_id));
+    let is_free = |id: &u16| !used.contains(&FormID::from(*id)) && !is_excluded(FormID::from(*id));
+
+    let highest_used = used.range(start..=end).rev().find(|form_id| !is_excluded(**form_id));
+    let above_highest = match highest_used {
+        Some(form_id) if *form_id < end => (form_id.to_u16() + 1..=end.to_u16()).find(is_free),
+        _ => None,
+    };
+
+    above_highest
+        .or_else(|| (start.to_u16()..=end.to_u16()).find(is_free))
+        .map(FormID::from)
+}
@@ -0,0 +1,5 @@
+// -- Modules for choosing FormIDs for new forms --
+
+// FormID allocation and reserved ranges
+pub mod io_alloc_form_id;
+pub use io_alloc_form_id::*;
@@ -0,0 +1,93 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::core::io::{read_block_bytestarts, read_block_ext, read_block_header, write_block_ext, IOStructExt, ArchiveLock};
+
+/// Marks the start of the extension area that follows the index block.
+pub const EXT_AREA_MAGIC: [u8; 4] = *b"SMNX";
+
+/// Reads every extension block of an archive.
+pub fn read_ext_blocks(file_path: &str) -> io::Result<IOStructExt> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    let mut file = File::open(file_path)?;
+    let index_end = get_index_end(&mut file)?;
+    file.seek(SeekFrom::Start(index_end))?;
+    read_block_ext(&mut file)
+}
+
+/// Reads the data of the extension block with the given tag, if the archive has one.
+pub fn read_ext_block(file_path: &str, tag: [u8; 4]) -> io::Result<Option<Vec<u8>>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    let ext = read_ext_blocks(file_path)?;
+    Ok(ext.blocks.into_iter().find(|block| block.tag == tag).map(|block| block.data))
+}
+
+/// Writes an extension block, replacing any block with the same tag.
+pub fn write_ext_block(file_path: &str, tag: [u8; 4], data: Vec<u8>) -> io::Result<()> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut ext = read_ext_blocks(file_path)?;
+    ext.set_block(tag, data);
+    write_ext_blocks(file_path, &ext)
+}
+
+/// Removes the extension block with the given tag, returning whether it existed.
+pub fn remove_ext_block(file_path: &str, tag: [u8; 4]) -> io::Result<bool> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut ext = read_ext_blocks(file_path)?;
+    let block_count = ext.blocks.len();
+    ext.blocks.retain(|block| block.tag != tag);
+    if ext.blocks.len() == block_count {
+        return Ok(false);
+    }
+    write_ext_blocks(file_path, &ext)?;
+    Ok(true)
+}
+
+/// Replaces the whole extension area of an archive, writing it in place after the index block.
+pub fn write_ext_blocks(file_path: &str, ext: &IOStructExt) -> io::Result<()> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut file = File::options().read(true).write(true).open(file_path)?;
+    let index_end = get_index_end(&mut file)?;
+    file.seek(SeekFrom::Start(index_end))?;
+    write_block_ext(&mut file, ext)?;
+
+    // Trim whatever was left after the end of the new area
+    let area_end = file.stream_position()?;
+    file.set_len(area_end)
+}
+
+/// Moves the file to the data of the extension block with the given tag and returns its length.
+///
+/// Only the block headers are read, so single entries of a large block can be read in place.
+pub(crate) fn seek_ext_block(file: &mut File, tag: [u8; 4]) -> io::Result<Option<u32>> {
+    let index_end = get_index_end(file)?;
+    file.seek(SeekFrom::Start(index_end))?;
+
+    let mut area_header = [0u8; 6];
+    match file.read_exact(&mut area_header) {
+        Ok(()) if area_header[0..4] == EXT_AREA_MAGIC => {},
+        Ok(()) => return Ok(None),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let block_count = u16::from_be_bytes([area_header[4], area_header[5]]);
+    for _ in 0..block_count {
+        let mut block_header = [0u8; 8];
+        file.read_exact(&mut block_header)?;
+        let length = u32::from_be_bytes(block_header[4..8].try_into().unwrap());
+        if block_header[0..4] == tag {
+            return Ok(Some(length));
+        }
+        file.seek(SeekFrom::Current(length as i64))?;
+    }
+    Ok(None)
+}
+
+/// Returns the byte offset where the index block ends and the extension area starts.
+fn get_index_end(file: &mut File) -> io::Result<u64> {
+    file.seek(SeekFrom::Start(0))?;
+    let header = read_block_header(file)?;
+    let bytestarts = read_block_bytestarts(file)?;
+    Ok(bytestarts.bytestart_index as u64 + header.form_count as u64 * 7)
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Seek, SeekFrom};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::core::io::{read_archive_info, read_archive_layout_versions, read_ext_block, read_form_record, remove_ext_block, write_ext_block, write_form, IOStructExt, IOStructExtBlock, ArchiveLock};
+use crate::core::structs::*;
+
+/// Extension block tag holding the prior revisions an archive keeps of its forms.
+///
+/// The revisions' records stay in the data block where they were written, so an archive keeping
+/// history is always written in append mode, see `ArchiveWriteMode`.
+pub const EXT_TAG_FORM_HISTORY: [u8; 4] = *b"HIST";
+
+/// Byte size of a revision in the history block: its number, replacement time, data offset and length.
+const REVISION_BYTE_COUNT: usize = 4 + 8 + 4 + 4;
+
+/// A prior version of a form, kept in the archive's history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormRevision {
+    /// Numbers the revisions of a form in the order they were replaced, from 1.
+    pub revision: u32,
+    /// Unix seconds at which this version was replaced or deleted.
+    pub replaced_at: u64,
+    /// Byte length of the stored record.
+    pub data_length: u32,
+    pub(crate) data_start_offset: u32,
+}
+
+/// The revisions an archive keeps, oldest first for each form.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FormHistory {
+    pub depth: u16,
+    pub revisions: BTreeMap<FormID, Vec<FormRevision>>,
+}
+
+#[allow(unused)]
+impl FormHistory {
+    /// Keeps a form's replaced record as its newest revision, dropping the oldest beyond the depth.
+    pub fn push(&mut self, form_id: FormID, data_start_offset: u32, data_length: u32) {
+        let revisions = self.revisions.entry(form_id).or_default();
+        revisions.push(FormRevision {
+            revision: revisions.last().map_or(1, |newest| newest.revision + 1),
+            replaced_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0),
+            data_length,
+            data_start_offset,
+        });
+        self.trim();
+    }
+
+    /// Drops the oldest revisions of every form beyond the depth, their records become dead space.
+    pub fn trim(&mut self) {
+        let depth = self.depth as usize;
+        for revisions in self.revisions.values_mut() {
+            if revisions.len() > depth {
+                revisions.drain(..revisions.len() - depth);
+            }
+        }
+        self.revisions.retain(|_, revisions| !revisions.is_empty());
+    }
+
+    /// Returns the bytes of the data block taken by kept revisions.
+    pub fn get_data_length(&self) -> u32 {
+        self.revisions.values().flatten().map(|revision| revision.data_length).sum()
+    }
+}
+
+/// Sets how many prior revisions of each form the archive keeps, dropping the oldest beyond it.
+///
+/// A depth of 0 turns the history off; the records of dropped revisions are left as dead space until
+/// `compact_archive`.
+pub fn set_form_history_depth(file_path: &str, depth: u16) -> io::Result<()> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    if depth == 0 {
+        remove_ext_block(file_path, EXT_TAG_FORM_HISTORY)?;
+        return Ok(());
+    }
+    let mut history = read_form_history(file_path)?.unwrap_or_default();
+    history.depth = depth;
+    history.trim();
+    write_ext_block(file_path, EXT_TAG_FORM_HISTORY, get_form_history_bytes(&history))
+}
+
+/// Returns how many prior revisions of each form the archive keeps, 0 when it keeps no history.
+pub fn get_form_history_depth(file_path: &str) -> io::Result<u16> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    Ok(read_form_history(file_path)?.map_or(0, |history| history.depth))
+}
+
+/// Lists the kept revisions of a form, newest first. Deleted forms keep theirs as well.
+pub fn list_form_revisions(file_path: &str, form_id: FormID) -> io::Result<Vec<FormRevision>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    let mut history = read_form_history(file_path)?.unwrap_or_default();
+    let mut revisions = history.revisions.remove(&form_id).unwrap_or_default();
+    revisions.reverse();
+    Ok(revisions)
+}
+
+/// Reads a kept revision of a form.
+pub fn read_form_revision(file_path: &str, form_id: FormID, revision: u32) -> io::Result<Box<dyn FormTrait>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    let form_revision = list_form_revisions(file_path, form_id)?
+        .into_iter()
+        .find(|form_revision| form_revision.revision == revision)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("Revision {} of form {} not found in archive.", revision, form_id.to_string())))?;
+
+    let archive = read_archive_info(file_path)?;
+    let layout_versions = read_archive_layout_versions(file_path)?;
+    let mut file = File::open(file_path)?;
+    file.seek(SeekFrom::Start((archive.bytestart_data + form_revision.data_start_offset) as u64))?;
+    read_form_record(&mut file, file_path, &layout_versions, form_revision.data_length)
+}
+
+/// Writes a kept revision of a form back as its current version, restoring the form if it was deleted.
+/// The version it replaces is kept as a new revision, so a restore can itself be rolled back.
+pub fn restore_form_revision(file_path: &str, form_id: FormID, revision: u32) -> io::Result<()> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let form = read_form_revision(file_path, form_id, revision)?;
+    write_form(file_path, form.as_ref())
+}
+
+/// Reads the form history from an already read extension area, `None` when the archive keeps none.
+pub(crate) fn get_ext_form_history(ext: &IOStructExt) -> io::Result<Option<FormHistory>> {
+    match ext.blocks.iter().find(|block| block.tag == EXT_TAG_FORM_HISTORY) {
+        Some(block) => read_form_history_block(&block.data).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Stores the form history in an extension area.
+pub(crate) fn set_ext_form_history(ext: &mut IOStructExt, history: &FormHistory) {
+    let data = get_form_history_bytes(history);
+    match ext.blocks.iter_mut().find(|block| block.tag == EXT_TAG_FORM_HISTORY) {
+        Some(block) => block.data = data,
+        None => ext.blocks.push(IOStructExtBlock { tag: EXT_TAG_FORM_HISTORY, data }),
+    }
+}
+
+fn read_form_history(file_path: &str) -> io::Result<Option<FormHistory>> {
+    match read_ext_block(file_path, EXT_TAG_FORM_HISTORY)? {
+        Some(bytes) => read_form_history_block(&bytes).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Converts the history into the block: a `u16` depth and form count, then per form its FormID, a
+/// `u16` revision count and its revisions oldest first, each a `u32` number, `u64` replacement time,
+/// `u32` data offset and `u32` length.
+fn get_form_history_bytes(history: &FormHistory) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&history.depth.to_be_bytes());
+    bytes.extend_from_slice(&(history.revisions.len() as u16).to_be_bytes());
+    for (form_id, revisions) in &history.revisions {
+        bytes.extend_from_slice(&form_id.to_bytes());
+        bytes.extend_from_slice(&(revisions.len() as u16).to_be_bytes());
+        for revision in revisions {
+            bytes.extend_from_slice(&revision.revision.to_be_bytes());
+            bytes.extend_from_slice(&revision.replaced_at.to_be_bytes());
+            bytes.extend_from_slice(&revision.data_start_offset.to_be_bytes());
+            bytes.extend_from_slice(&revision.data_length.to_be_bytes());
+        }
+    }
+    bytes
+}
+
+fn read_form_history_block(bytes: &[u8]) -> io::Result<FormHistory> {
+    let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for form history");
+    let header = bytes.get(0..4).ok_or_else(truncated)?;
+    let mut history = FormHistory { depth: u16::from_be_bytes([header[0], header[1]]), revisions: BTreeMap::new() };
+    let form_count = u16::from_be_bytes([header[2], header[3]]);
+
+    let mut position = 4;
+    for _ in 0..form_count {
+        let form_header = bytes.get(position..position + FormID::BYTE_COUNT + 2).ok_or_else(truncated)?;
+        let form_id = FormID::from([form_header[0], form_header[1]]);
+        let revision_count = u16::from_be_bytes([form_header[2], form_header[3]]) as usize;
+        position += form_header.len();
+
+        let revision_bytes = bytes.get(position..position + revision_count * REVISION_BYTE_COUNT).ok_or_else(truncated)?;
+        let revisions = revision_bytes
+            .chunks_exact(REVISION_BYTE_COUNT)
+            .map(|revision| FormRevision {
+                revision: u32::from_be_bytes(revision[0..4].try_into().unwrap()),
+                replaced_at: u64::from_be_bytes(revision[4..12].try_into().unwrap()),
+                data_start_offset: u32::from_be_bytes(revision[12..16].try_into().unwrap()),
+                data_length: u32::from_be_bytes(revision[16..20].try_into().unwrap()),
+            })
+            .collect();
+        history.revisions.insert(form_id, revisions);
+        position += revision_bytes.len();
+    }
+    Ok(history)
+}
+
@@ -0,0 +1,36 @@
+use std::fs::File;
+use std::io::{self, Read};
+
+use crate::core::io::{seek_ext_block, IOStructExt, ArchiveLock};
+
+/// Extension block tag holding the archive's content generation, counted up by every write that adds,
+/// replaces or removes forms.
+pub const EXT_TAG_GENERATION: [u8; 4] = *b"GENR";
+
+/// Reads the content generation of an archive, 0 when no form was written since generations are kept.
+///
+/// Two reads of the same generation saw the same forms, so caches of an archive's forms can tell
+/// whether they are current without rereading them.
+pub fn read_archive_generation(file_path: &str) -> io::Result<u64> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    let mut file = File::open(file_path)?;
+    let Some(length) = seek_ext_block(&mut file, EXT_TAG_GENERATION)? else {
+        return Ok(0);
+    };
+    if length != 8 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Generation block is not 8 bytes"));
+    }
+    let mut bytes = [0u8; 8];
+    file.read_exact(&mut bytes)?;
+    Ok(u64::from_be_bytes(bytes))
+}
+
+/// Counts up the content generation in an already read extension area.
+pub(crate) fn bump_ext_generation(ext: &mut IOStructExt) -> io::Result<()> {
+    let generation = match ext.get_block(EXT_TAG_GENERATION) {
+        Some(bytes) => u64::from_be_bytes(bytes.try_into().map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Generation block is not 8 bytes"))?),
+        None => 0,
+    };
+    ext.set_block(EXT_TAG_GENERATION, generation.wrapping_add(1).to_be_bytes().to_vec());
+    Ok(())
+}
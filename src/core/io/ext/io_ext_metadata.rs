@@ -0,0 +1,61 @@
+use std::io;
+
+use crate::core::io::{read_ext_block, remove_ext_block, write_ext_block, IOStructExt, ArchiveLock};
+use crate::core::structs::*;
+
+/// Extension block tag holding the archive's key/value metadata.
+pub const EXT_TAG_ARCHIVE_METADATA: [u8; 4] = *b"META";
+
+/// Reads the key/value metadata of an archive, empty when it has none.
+pub fn read_archive_metadata(file_path: &str) -> io::Result<ArchiveMetadata> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    match read_ext_block(file_path, EXT_TAG_ARCHIVE_METADATA)? {
+        Some(bytes) => read_metadata_block(&bytes),
+        None => Ok(ArchiveMetadata::default()),
+    }
+}
+
+/// Replaces the key/value metadata of an archive, removing the block when the metadata is empty.
+pub fn write_archive_metadata(file_path: &str, metadata: &ArchiveMetadata) -> io::Result<()> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    if metadata.is_empty() {
+        remove_ext_block(file_path, EXT_TAG_ARCHIVE_METADATA)?;
+        return Ok(());
+    }
+    write_ext_block(file_path, EXT_TAG_ARCHIVE_METADATA, metadata.to_bytes())
+}
+
+/// Sets one metadata value of an archive, keeping the others.
+pub fn set_archive_metadata_value(file_path: &str, key: &str, value: ArchiveMetadataValue) -> io::Result<()> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut metadata = read_archive_metadata(file_path)?;
+    metadata.set(key, value)?;
+    write_archive_metadata(file_path, &metadata)
+}
+
+/// Removes one metadata value of an archive, returning whether it was set.
+pub fn remove_archive_metadata_value(file_path: &str, key: &str) -> io::Result<bool> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut metadata = read_archive_metadata(file_path)?;
+    if metadata.remove(key).is_none() {
+        return Ok(false);
+    }
+    write_archive_metadata(file_path, &metadata)?;
+    Ok(true)
+}
+
+/// Reads the archive metadata from an already read extension area.
+pub(crate) fn get_ext_archive_metadata(ext: &IOStructExt) -> io::Result<ArchiveMetadata> {
+    match ext.blocks.iter().find(|block| block.tag == EXT_TAG_ARCHIVE_METADATA) {
+        Some(block) => read_metadata_block(&block.data),
+        None => Ok(ArchiveMetadata::default()),
+    }
+}
+
+fn read_metadata_block(bytes: &[u8]) -> io::Result<ArchiveMetadata> {
+    let (metadata, consumed) = ArchiveMetadata::read_from_byte_buffer(bytes)?;
+    if consumed != bytes.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Archive metadata block has trailing bytes"));
+    }
+    Ok(metadata)
+}
@@ -0,0 +1,230 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::core::io::{read_ext_block, read_form, read_forms_all, remove_ext_block, seek_ext_block, write_ext_block, IOStructExt, IOStructExtBlock, ArchiveLock};
+use crate::core::structs::*;
+
+/// Extension block tag holding the archive's index of form names.
+pub const EXT_TAG_NAME_INDEX: [u8; 4] = *b"NAME";
+
+/// Builds the name index of an archive from its forms, replacing any existing one.
+///
+/// Once an archive has a name index, writes keep it current and refuse a form whose name another
+/// form already uses. Fails without writing it if form names are not unique. Returns the entry count.
+pub fn build_name_index(file_path: &str) -> io::Result<usize> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut names: BTreeMap<String, FormID> = BTreeMap::new();
+    for form in read_forms_all(file_path)? {
+        let form_name = form.form_name().to_string();
+        if let Some(other_id) = names.insert(form_name.clone(), form.form_id()) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("Form name {} is used by forms {} and {}.", form_name, other_id.to_string(), form.form_id().to_string()),
+            ));
+        }
+    }
+    write_name_index(file_path, &names)?;
+    Ok(names.len())
+}
+
+/// Removes the name index of an archive, returning whether it had one.
+pub fn remove_name_index(file_path: &str) -> io::Result<bool> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    remove_ext_block(file_path, EXT_TAG_NAME_INDEX)
+}
+
+/// Reads the name index of an archive, keyed by form name. `None` if the archive has no name index.
+pub fn read_name_index(file_path: &str) -> io::Result<Option<BTreeMap<String, FormID>>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    match read_ext_block(file_path, EXT_TAG_NAME_INDEX)? {
+        Some(bytes) => read_name_index_block(&bytes).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Returns the FormID of the form with the given name, if any.
+///
+/// Uses a binary search over the name index, or reads every form if the archive has none.
+pub fn find_form_id_by_name(file_path: &str, form_name: &str) -> io::Result<Option<FormID>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    if let Some(found) = search_name_index(file_path, form_name)? {
+        return Ok(found);
+    }
+    let forms = read_forms_all(file_path)?;
+    Ok(forms.iter().find(|form| form.form_name().to_string() == form_name).map(|form| form.form_id()))
+}
+
+/// Reads the form with the given name from an archive.
+pub fn read_form_by_name(file_path: &str, form_name: &str) -> io::Result<Box<dyn FormTrait>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    match find_form_id_by_name(file_path, form_name)? {
+        Some(form_id) => read_form(file_path, form_id),
+        None => Err(io::Error::new(io::ErrorKind::NotFound, format!("Form {} not found in archive.", form_name))),
+    }
+}
+
+/// Fails if an archive's extension area has a name index and another form already uses the form's name.
+pub(crate) fn check_form_name_unique(ext: &IOStructExt, form: &dyn FormTrait) -> io::Result<()> {
+    let Some(bytes) = ext.get_block(EXT_TAG_NAME_INDEX) else {
+        return Ok(());
+    };
+    let form_name = form.form_name().to_string();
+    match search_name_index_block(bytes, &form_name)? {
+        Some(form_id) if form_id != form.form_id() => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("Form name {} is already used by form {}.", form_name, form_id.to_string()),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Records a written form's name in the name index held in an extension area, if it has one.
+pub(crate) fn update_name_index(ext: &mut IOStructExt, form: &dyn FormTrait) -> io::Result<()> {
+    let Some(bytes) = ext.get_block(EXT_TAG_NAME_INDEX) else {
+        return Ok(());
+    };
+    let form_name = form.form_name().to_string();
+    if search_name_index_block(bytes, &form_name)? == Some(form.form_id()) {
+        return Ok(());
+    }
+
+    // The form is new or renamed, its old name goes
+    let mut names = read_name_index_block(bytes)?;
+    names.retain(|_, form_id| *form_id != form.form_id());
+    names.insert(form_name, form.form_id());
+    ext.set_block(EXT_TAG_NAME_INDEX, get_name_index_bytes(&names));
+    Ok(())
+}
+
+/// Returns the name of every form in the name index held in an extension area, keyed by FormID.
+/// `None` if the area has no name index.
+pub(crate) fn get_ext_form_names(ext: &IOStructExt) -> io::Result<Option<HashMap<FormID, StrSml>>> {
+    let Some(bytes) = ext.get_block(EXT_TAG_NAME_INDEX) else {
+        return Ok(None);
+    };
+    let names = read_name_index_block(bytes)?;
+    Ok(Some(names.into_iter().map(|(form_name, form_id)| (form_id, StrSml::from(form_name.as_str()))).collect()))
+}
+
+/// Removes deleted forms from the name index held in an extension area, if it has one.
+pub(crate) fn remove_from_name_index(ext: &mut IOStructExt, form_ids: &[FormID]) -> io::Result<()> {
+    let Some(block) = ext.blocks.iter_mut().find(|block| block.tag == EXT_TAG_NAME_INDEX) else {
+        return Ok(());
+    };
+    let mut names = read_name_index_block(&block.data)?;
+    names.retain(|_, form_id| !form_ids.contains(form_id));
+    *block = IOStructExtBlock { tag: EXT_TAG_NAME_INDEX, data: get_name_index_bytes(&names) };
+    Ok(())
+}
+
+/// Looks a name up in the name index without reading the whole block.
+///
+/// Returns `None` if the archive has no name index, `Some(None)` if the name is not in it.
+fn search_name_index(file_path: &str, form_name: &str) -> io::Result<Option<Option<FormID>>> {
+    let mut file = File::open(file_path)?;
+    if seek_ext_block(&mut file, EXT_TAG_NAME_INDEX)?.is_none() {
+        return Ok(None);
+    }
+    let block_start = file.stream_position()?;
+
+    let mut count_buf = [0u8; 2];
+    file.read_exact(&mut count_buf)?;
+    let entry_count = u16::from_be_bytes(count_buf) as u64;
+    let entries_start = block_start + 2 + entry_count * 4;
+
+    // Entries are sorted by name, the offset table gives the position of each
+    let target = form_name.as_bytes();
+    let (mut left, mut right) = (0u64, entry_count);
+    while left < right {
+        let mid = left + (right - left) / 2;
+        file.seek(SeekFrom::Start(block_start + 2 + mid * 4))?;
+        let mut offset_buf = [0u8; 4];
+        file.read_exact(&mut offset_buf)?;
+        file.seek(SeekFrom::Start(entries_start + u32::from_be_bytes(offset_buf) as u64))?;
+
+        let mut length_buf = [0u8; 1];
+        file.read_exact(&mut length_buf)?;
+        let mut entry = vec![0u8; length_buf[0] as usize + FormID::BYTE_COUNT];
+        file.read_exact(&mut entry)?;
+        let (name, form_id) = entry.split_at(length_buf[0] as usize);
+
+        match name.cmp(target) {
+            std::cmp::Ordering::Equal => return Ok(Some(Some(FormID::from([form_id[0], form_id[1]])))),
+            std::cmp::Ordering::Less => left = mid + 1,
+            std::cmp::Ordering::Greater => right = mid,
+        }
+    }
+    Ok(Some(None))
+}
+
+/// Looks a name up in a name index block held in memory, see `search_name_index`.
+fn search_name_index_block(bytes: &[u8], form_name: &str) -> io::Result<Option<FormID>> {
+    let eof = || io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for name index entries");
+    let entry_count = u16::from_be_bytes(bytes.get(0..2).ok_or_else(eof)?.try_into().unwrap()) as usize;
+    let entries_start = 2 + entry_count * 4;
+
+    let target = form_name.as_bytes();
+    let (mut left, mut right) = (0usize, entry_count);
+    while left < right {
+        let mid = left + (right - left) / 2;
+        let offset = u32::from_be_bytes(bytes.get(2 + mid * 4..6 + mid * 4).ok_or_else(eof)?.try_into().unwrap()) as usize;
+        let name_start = entries_start + offset + 1;
+        let name_length = *bytes.get(name_start - 1).ok_or_else(eof)? as usize;
+        let name = bytes.get(name_start..name_start + name_length).ok_or_else(eof)?;
+
+        match name.cmp(target) {
+            std::cmp::Ordering::Equal => {
+                let form_id = bytes.get(name_start + name_length..name_start + name_length + FormID::BYTE_COUNT).ok_or_else(eof)?;
+                return Ok(Some(FormID::from([form_id[0], form_id[1]])));
+            },
+            std::cmp::Ordering::Less => left = mid + 1,
+            std::cmp::Ordering::Greater => right = mid,
+        }
+    }
+    Ok(None)
+}
+
+fn write_name_index(file_path: &str, names: &BTreeMap<String, FormID>) -> io::Result<()> {
+    write_ext_block(file_path, EXT_TAG_NAME_INDEX, get_name_index_bytes(names))
+}
+
+/// Converts names into the block: a `u16` count, a `u32` offset per entry, then the entries sorted by
+/// name, each a `StrSml` name and a `FormID`.
+fn get_name_index_bytes(names: &BTreeMap<String, FormID>) -> Vec<u8> {
+    let mut offsets = Vec::with_capacity(names.len() * 4);
+    let mut entries = Vec::new();
+    for (form_name, form_id) in names {
+        offsets.extend_from_slice(&(entries.len() as u32).to_be_bytes());
+        entries.extend_from_slice(&StrSml::from(form_name.as_str()).to_bytes());
+        entries.extend_from_slice(&form_id.to_bytes());
+    }
+
+    let mut bytes = (names.len() as u16).to_be_bytes().to_vec();
+    bytes.extend_from_slice(&offsets);
+    bytes.extend_from_slice(&entries);
+    bytes
+}
+
+fn read_name_index_block(bytes: &[u8]) -> io::Result<BTreeMap<String, FormID>> {
+    if bytes.len() < 2 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for name index count"));
+    }
+    let entry_count = u16::from_be_bytes([bytes[0], bytes[1]]) as usize;
+    let mut offset = 2 + entry_count * 4;
+
+    let mut names = BTreeMap::new();
+    for _ in 0..entry_count {
+        if bytes.len() < offset {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for name index entries"));
+        }
+        let (form_name, consumed) = StrSml::read_from_byte_buffer(&bytes[offset..])?;
+        offset += consumed;
+        if bytes.len() < offset + FormID::BYTE_COUNT {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for name index FormID"));
+        }
+        names.insert(form_name.to_string(), FormID::from([bytes[offset], bytes[offset + 1]]));
+        offset += FormID::BYTE_COUNT;
+    }
+    Ok(names)
+}
@@ -0,0 +1,35 @@
+use std::io;
+
+use crate::core::io::{IOStructExt, IOStructExtBlock};
+
+/// Extension block tag holding the stored length of every form record, in index order.
+///
+/// Archives written in place keep their records in index order, each running up to the next, and
+/// have no such block. Appending a new version of a form leaves its records out of order with dead
+/// space between them, so their lengths are stored until `compact_archive` puts them back in order.
+pub const EXT_TAG_RECORD_LENGTHS: [u8; 4] = *b"RLEN";
+
+/// Reads the record lengths from an already read extension area, `None` when the records are in
+/// index order.
+pub(crate) fn get_ext_record_lengths(ext: &IOStructExt) -> io::Result<Option<Vec<u32>>> {
+    let Some(block) = ext.blocks.iter().find(|block| block.tag == EXT_TAG_RECORD_LENGTHS) else {
+        return Ok(None);
+    };
+    if block.data.len() % 4 != 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Record length block is truncated"));
+    }
+    Ok(Some(block.data.chunks_exact(4).map(|length| u32::from_be_bytes([length[0], length[1], length[2], length[3]])).collect()))
+}
+
+/// Stores the record lengths in an extension area, or removes them when `None`.
+pub(crate) fn set_ext_record_lengths(ext: &mut IOStructExt, record_lengths: Option<&[u32]>) {
+    let Some(record_lengths) = record_lengths else {
+        ext.blocks.retain(|block| block.tag != EXT_TAG_RECORD_LENGTHS);
+        return;
+    };
+    let data: Vec<u8> = record_lengths.iter().flat_map(|length| length.to_be_bytes()).collect();
+    match ext.blocks.iter_mut().find(|block| block.tag == EXT_TAG_RECORD_LENGTHS) {
+        Some(block) => block.data = data,
+        None => ext.blocks.push(IOStructExtBlock { tag: EXT_TAG_RECORD_LENGTHS, data }),
+    }
+}
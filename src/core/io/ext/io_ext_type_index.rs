@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::core::io::{read_archive_index, read_ext_block, read_forms, remove_ext_block, seek_ext_block, write_ext_block, IOStructExt, IOStructExtBlock, ArchiveLock};
+use crate::core::structs::*;
+
+/// Extension block tag holding the archive's FormIDs partitioned by form type.
+pub const EXT_TAG_TYPE_INDEX: [u8; 4] = *b"TYPE";
+
+/// Byte size of a type's entry in the range table: the type, its FormID count and their offset.
+const TYPE_RANGE_BYTE_COUNT: usize = FormType::BYTE_COUNT + 2 + 4;
+
+/// Builds the type index of an archive from its index, replacing any existing one.
+///
+/// Once an archive has a type index, writes keep it current. Returns the number of form types in it.
+pub fn build_type_index(file_path: &str) -> io::Result<usize> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut types: BTreeMap<FormType, Vec<FormID>> = BTreeMap::new();
+    for index_item in read_archive_index(file_path)?.indexes {
+        types.entry(index_item.form_type).or_default().push(index_item.form_id);
+    }
+    write_type_index(file_path, &types)?;
+    Ok(types.len())
+}
+
+/// Removes the type index of an archive, returning whether it had one.
+pub fn remove_type_index(file_path: &str) -> io::Result<bool> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    remove_ext_block(file_path, EXT_TAG_TYPE_INDEX)
+}
+
+/// Reads the type index of an archive, the FormIDs of each type in ascending order. `None` if the
+/// archive has no type index.
+pub fn read_type_index(file_path: &str) -> io::Result<Option<BTreeMap<FormType, Vec<FormID>>>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    match read_ext_block(file_path, EXT_TAG_TYPE_INDEX)? {
+        Some(bytes) => read_type_index_block(&bytes).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Lists the FormIDs of every form of a type, in ascending order.
+///
+/// Reads only the type's range of the type index, or filters the archive's index if it has none.
+pub fn list_forms_of_type(file_path: &str, form_type: FormType) -> io::Result<Vec<FormID>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    if let Some(form_ids) = search_type_index(file_path, form_type)? {
+        return Ok(form_ids);
+    }
+    let index = read_archive_index(file_path)?;
+    Ok(index.indexes.iter().filter(|index_item| index_item.form_type == form_type).map(|index_item| index_item.form_id).collect())
+}
+
+/// Reads every form of a type, in ascending FormID order.
+pub fn read_forms_of_type(file_path: &str, form_type: FormType) -> io::Result<Vec<Box<dyn FormTrait>>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    let form_ids = list_forms_of_type(file_path, form_type)?;
+    if form_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    read_forms(file_path, form_ids)
+}
+
+/// Records a written form under its type in the type index held in an extension area, if it has one.
+pub(crate) fn update_type_index(ext: &mut IOStructExt, form_id: FormID, form_type: FormType) -> io::Result<()> {
+    let Some(bytes) = ext.get_block(EXT_TAG_TYPE_INDEX) else {
+        return Ok(());
+    };
+    let mut types = read_type_index_block(bytes)?;
+    if types.get(&form_type).is_some_and(|form_ids| form_ids.binary_search(&form_id).is_ok()) {
+        return Ok(());
+    }
+
+    // The form is new or changed type, it leaves its old type's range
+    for form_ids in types.values_mut() {
+        form_ids.retain(|listed_id| *listed_id != form_id);
+    }
+    let form_ids = types.entry(form_type).or_default();
+    let position = form_ids.partition_point(|listed_id| *listed_id < form_id);
+    form_ids.insert(position, form_id);
+    ext.set_block(EXT_TAG_TYPE_INDEX, get_type_index_bytes(&types));
+    Ok(())
+}
+
+/// Removes deleted forms from the type index held in an extension area, if it has one.
+pub(crate) fn remove_from_type_index(ext: &mut IOStructExt, form_ids: &[FormID]) -> io::Result<()> {
+    let Some(block) = ext.blocks.iter_mut().find(|block| block.tag == EXT_TAG_TYPE_INDEX) else {
+        return Ok(());
+    };
+    let mut types = read_type_index_block(&block.data)?;
+    for listed_ids in types.values_mut() {
+        listed_ids.retain(|form_id| !form_ids.contains(form_id));
+    }
+    *block = IOStructExtBlock { tag: EXT_TAG_TYPE_INDEX, data: get_type_index_bytes(&types) };
+    Ok(())
+}
+
+/// Reads the FormIDs of one type from the type index without reading the other types.
+///
+/// Returns `None` if the archive has no type index.
+fn search_type_index(file_path: &str, form_type: FormType) -> io::Result<Option<Vec<FormID>>> {
+    let mut file = File::open(file_path)?;
+    if seek_ext_block(&mut file, EXT_TAG_TYPE_INDEX)?.is_none() {
+        return Ok(None);
+    }
+    let block_start = file.stream_position()?;
+
+    let mut count_buf = [0u8; 1];
+    file.read_exact(&mut count_buf)?;
+    let mut range_table = vec![0u8; count_buf[0] as usize * TYPE_RANGE_BYTE_COUNT];
+    file.read_exact(&mut range_table)?;
+    let ids_start = block_start + 1 + range_table.len() as u64;
+
+    let Some(range) = range_table.chunks_exact(TYPE_RANGE_BYTE_COUNT).find(|range| range[0] == form_type.to_u8()) else {
+        return Ok(Some(Vec::new()));
+    };
+    let form_count = u16::from_be_bytes([range[1], range[2]]) as usize;
+    let offset = u32::from_be_bytes([range[3], range[4], range[5], range[6]]) as u64;
+
+    let mut ids = vec![0u8; form_count * FormID::BYTE_COUNT];
+    file.seek(SeekFrom::Start(ids_start + offset))?;
+    file.read_exact(&mut ids)?;
+    Ok(Some(ids.chunks_exact(FormID::BYTE_COUNT).map(|id| FormID::from([id[0], id[1]])).collect()))
+}
+
+fn write_type_index(file_path: &str, types: &BTreeMap<FormType, Vec<FormID>>) -> io::Result<()> {
+    write_ext_block(file_path, EXT_TAG_TYPE_INDEX, get_type_index_bytes(types))
+}
+
+/// Converts the types into the block: a `u8` type count, a range per type (the type, a `u16` FormID
+/// count and a `u32` offset into the FormIDs), then the FormIDs of each type in ascending order.
+fn get_type_index_bytes(types: &BTreeMap<FormType, Vec<FormID>>) -> Vec<u8> {
+    let types: Vec<(&FormType, &Vec<FormID>)> = types.iter().filter(|(_, form_ids)| !form_ids.is_empty()).collect();
+    let mut ranges = Vec::with_capacity(types.len() * TYPE_RANGE_BYTE_COUNT);
+    let mut ids = Vec::new();
+    for (form_type, form_ids) in &types {
+        ranges.push(form_type.to_u8());
+        ranges.extend_from_slice(&(form_ids.len() as u16).to_be_bytes());
+        ranges.extend_from_slice(&(ids.len() as u32).to_be_bytes());
+        for form_id in form_ids.iter() {
+            ids.extend_from_slice(&form_id.to_bytes());
+        }
+    }
+
+    let mut bytes = vec![types.len() as u8];
+    bytes.extend_from_slice(&ranges);
+    bytes.extend_from_slice(&ids);
+    bytes
+}
+
+fn read_type_index_block(bytes: &[u8]) -> io::Result<BTreeMap<FormType, Vec<FormID>>> {
+    if bytes.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for type index count"));
+    }
+    let ids_start = 1 + bytes[0] as usize * TYPE_RANGE_BYTE_COUNT;
+    if bytes.len() < ids_start {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for type index ranges"));
+    }
+
+    let mut types = BTreeMap::new();
+    for range in bytes[1..ids_start].chunks_exact(TYPE_RANGE_BYTE_COUNT) {
+        let form_count = u16::from_be_bytes([range[1], range[2]]) as usize;
+        let offset = ids_start + u32::from_be_bytes([range[3], range[4], range[5], range[6]]) as usize;
+        let Some(ids) = bytes.get(offset..offset + form_count * FormID::BYTE_COUNT) else {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for type index FormIDs"));
+        };
+        let form_ids = ids.chunks_exact(FormID::BYTE_COUNT).map(|id| FormID::from([id[0], id[1]])).collect();
+        types.insert(FormType::from(range[0]), form_ids);
+    }
+    Ok(types)
+}
@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+use std::io;
+
+use crate::core::io::{read_ext_block, read_form, read_forms_of_type, remove_ext_block, write_ext_block, IOStructExt, IOStructExtBlock, ArchiveLock};
+use crate::core::structs::*;
+
+/// Extension block tag holding a grid of every world's parts, cell by cell of their anchors.
+pub const EXT_TAG_WORLD_GRID: [u8; 4] = *b"WGRD";
+
+/// Byte size of a cell header in the grid block: its three coordinates and part count.
+const CELL_HEADER_BYTE_COUNT: usize = 4 * 3 + 2;
+
+/// Byte size of a world part in the grid block: its position in the world, GlobalID and anchor.
+const GRID_PART_BYTE_COUNT: usize = 2 + GlobalID::BYTE_COUNT + Vec3Int::BYTE_COUNT;
+
+/// A world part listed in a grid cell.
+#[derive(Debug, Clone, Copy)]
+struct GridPart {
+    position: u16,
+    world_part: GlobalID,
+    anchor: Vec3Int,
+}
+
+/// The grid of every world in an archive, the parts of each world by the cell their anchor is in.
+#[derive(Debug, Default)]
+struct WorldGrid {
+    cell_size: u32,
+    worlds: BTreeMap<FormID, BTreeMap<[i32; 3], Vec<GridPart>>>,
+}
+
+#[allow(unused)]
+impl WorldGrid {
+    /// Lists a world's parts in the grid, replacing what was listed for it.
+    fn insert_world(&mut self, world: &FormWorld) {
+        let mut cells: BTreeMap<[i32; 3], Vec<GridPart>> = BTreeMap::new();
+        for (position, (world_part, anchor)) in world.world_parts.iter().zip(&world.world_part_anchors).enumerate() {
+            cells.entry(self.get_cell(*anchor)).or_default().push(GridPart { position: position as u16, world_part: *world_part, anchor: *anchor });
+        }
+        self.worlds.insert(world.base.form_id, cells);
+    }
+
+    fn get_cell(&self, anchor: Vec3Int) -> [i32; 3] {
+        let cell_size = self.cell_size as i64;
+        [anchor.x, anchor.y, anchor.z].map(|axis| (axis as i64).div_euclid(cell_size) as i32)
+    }
+
+    /// Answers a query over a world's parts, `None` if the world is not in the grid.
+    fn query(&self, world_id: FormID, query: &WorldPartQuery) -> Option<Vec<GlobalID>> {
+        let cells = self.worlds.get(&world_id)?;
+        let cell_size = self.cell_size as i64;
+        let bounds = query.get_bounds().map(|(min, max)| (min.map(|axis| axis.div_euclid(cell_size)), max.map(|axis| axis.div_euclid(cell_size))));
+
+        // Only parts in cells overlapping the query's bounds can match
+        let mut candidates: Vec<&GridPart> = cells
+            .iter()
+            .filter(|(cell, _)| bounds.is_none_or(|(min, max)| (0..3).all(|axis| (min[axis]..=max[axis]).contains(&(cell[axis] as i64)))))
+            .flat_map(|(_, parts)| parts)
+            .collect();
+        candidates.sort_by_key(|part| part.position);
+
+        let anchors: Vec<Vec3Int> = candidates.iter().map(|part| part.anchor).collect();
+        Some(query.get_matches(&anchors).into_iter().map(|position| candidates[position].world_part).collect())
+    }
+}
+
+/// Builds the world part grid of an archive from its worlds, replacing any existing one.
+///
+/// Each world's parts are listed by the cell of `cell_size` units their anchor is in, so queries only
+/// look at the parts in cells they overlap. Once an archive has a grid, writes keep it current.
+/// Returns the number of worlds in it.
+pub fn build_world_part_grid(file_path: &str, cell_size: u32) -> io::Result<usize> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    if cell_size == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "World part grid cells must be at least one unit wide."));
+    }
+    let mut grid = WorldGrid { cell_size, worlds: BTreeMap::new() };
+    for form in read_forms_of_type(file_path, FormType::WORLD)? {
+        grid.insert_world(&get_world(form.as_ref())?);
+    }
+    write_ext_block(file_path, EXT_TAG_WORLD_GRID, get_world_grid_bytes(&grid))?;
+    Ok(grid.worlds.len())
+}
+
+/// Removes the world part grid of an archive, returning whether it had one.
+pub fn remove_world_part_grid(file_path: &str) -> io::Result<bool> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    remove_ext_block(file_path, EXT_TAG_WORLD_GRID)
+}
+
+/// Returns the cell size of the archive's world part grid, `None` if it has none.
+pub fn get_world_part_grid_cell_size(file_path: &str) -> io::Result<Option<u32>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    Ok(read_world_grid(file_path)?.map(|grid| grid.cell_size))
+}
+
+/// Finds the world parts of a world whose anchors match the query, in the order the world lists them.
+///
+/// Answered from the world part grid when the archive has one, without reading the world, otherwise
+/// from the world itself.
+pub fn query_world_parts(file_path: &str, world_id: FormID, query: &WorldPartQuery) -> io::Result<Vec<GlobalID>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    if let Some(world_parts) = read_world_grid(file_path)?.and_then(|grid| grid.query(world_id, query)) {
+        return Ok(world_parts);
+    }
+    Ok(get_world(read_form(file_path, world_id)?.as_ref())?.query_parts(query))
+}
+
+/// Lists a written world's parts in the world part grid held in an extension area, if it has one.
+pub(crate) fn update_world_part_grid(ext: &mut IOStructExt, form: &dyn FormTrait) -> io::Result<()> {
+    if form.form_type() != FormType::WORLD {
+        return Ok(());
+    }
+    let Some(bytes) = ext.get_block(EXT_TAG_WORLD_GRID) else {
+        return Ok(());
+    };
+    let mut grid = read_world_grid_block(bytes)?;
+    grid.insert_world(&get_world(form)?);
+    ext.set_block(EXT_TAG_WORLD_GRID, get_world_grid_bytes(&grid));
+    Ok(())
+}
+
+/// Removes deleted worlds from the world part grid held in an extension area, if it has one.
+pub(crate) fn remove_from_world_part_grid(ext: &mut IOStructExt, form_ids: &[FormID]) -> io::Result<()> {
+    let Some(block) = ext.blocks.iter_mut().find(|block| block.tag == EXT_TAG_WORLD_GRID) else {
+        return Ok(());
+    };
+    let mut grid = read_world_grid_block(&block.data)?;
+    grid.worlds.retain(|world_id, _| !form_ids.contains(world_id));
+    *block = IOStructExtBlock { tag: EXT_TAG_WORLD_GRID, data: get_world_grid_bytes(&grid) };
+    Ok(())
+}
+
+/// Returns a world form as a `FormWorld`.
+fn get_world(form: &dyn FormTrait) -> io::Result<FormWorld> {
+    if form.form_type() != FormType::WORLD {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Form {} is not a world.", form.form_id().to_string())));
+    }
+    Ok(FormWorld::read_from_byte_buffer(&form.to_bytes())?.0)
+}
+
+fn read_world_grid(file_path: &str) -> io::Result<Option<WorldGrid>> {
+    match read_ext_block(file_path, EXT_TAG_WORLD_GRID)? {
+        Some(bytes) => read_world_grid_block(&bytes).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Converts the grid into the block: a `u32` cell size and `u16` world count, then per world its
+/// FormID, a `u32` cell count and its cells, each three `i32` coordinates, a `u16` part count and the
+/// parts, each a `u16` position in the world, its GlobalID and its anchor.
+fn get_world_grid_bytes(grid: &WorldGrid) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&grid.cell_size.to_be_bytes());
+    bytes.extend_from_slice(&(grid.worlds.len() as u16).to_be_bytes());
+    for (world_id, cells) in &grid.worlds {
+        bytes.extend_from_slice(&world_id.to_bytes());
+        bytes.extend_from_slice(&(cells.len() as u32).to_be_bytes());
+        for (cell, parts) in cells {
+            for axis in cell {
+                bytes.extend_from_slice(&axis.to_be_bytes());
+            }
+            bytes.extend_from_slice(&(parts.len() as u16).to_be_bytes());
+            for part in parts {
+                bytes.extend_from_slice(&part.position.to_be_bytes());
+                bytes.extend_from_slice(&part.world_part.to_bytes());
+                bytes.extend_from_slice(&part.anchor.to_bytes());
+            }
+        }
+    }
+    bytes
+}
+
+fn read_world_grid_block(bytes: &[u8]) -> io::Result<WorldGrid> {
+    let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for world part grid");
+    let header = bytes.get(0..6).ok_or_else(truncated)?;
+    let mut grid = WorldGrid { cell_size: u32::from_be_bytes(header[0..4].try_into().unwrap()), worlds: BTreeMap::new() };
+    if grid.cell_size == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "World part grid has a cell size of 0"));
+    }
+    let world_count = u16::from_be_bytes([header[4], header[5]]);
+
+    let mut position = header.len();
+    for _ in 0..world_count {
+        let world_header = bytes.get(position..position + FormID::BYTE_COUNT + 4).ok_or_else(truncated)?;
+        let world_id = FormID::from([world_header[0], world_header[1]]);
+        let cell_count = u32::from_be_bytes(world_header[2..6].try_into().unwrap());
+        position += world_header.len();
+
+        let mut cells = BTreeMap::new();
+        for _ in 0..cell_count {
+            let cell_header = bytes.get(position..position + CELL_HEADER_BYTE_COUNT).ok_or_else(truncated)?;
+            let cell = [0, 4, 8].map(|start| i32::from_be_bytes(cell_header[start..start + 4].try_into().unwrap()));
+            let part_count = u16::from_be_bytes([cell_header[12], cell_header[13]]) as usize;
+            position += cell_header.len();
+
+            let part_bytes = bytes.get(position..position + part_count * GRID_PART_BYTE_COUNT).ok_or_else(truncated)?;
+            let parts = part_bytes
+                .chunks_exact(GRID_PART_BYTE_COUNT)
+                .map(|part| GridPart {
+                    position: u16::from_be_bytes([part[0], part[1]]),
+                    world_part: GlobalID::from([part[2], part[3], part[4]]),
+                    anchor: Vec3Int::from(<[u8; Vec3Int::BYTE_COUNT]>::try_from(&part[5..]).unwrap()),
+                })
+                .collect();
+            cells.insert(cell, parts);
+            position += part_bytes.len();
+        }
+        grid.worlds.insert(world_id, cells);
+    }
+    Ok(grid)
+}
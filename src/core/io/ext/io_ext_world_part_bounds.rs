@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use std::io;
+
+use crate::core::io::{read_ext_block, read_form, read_forms_of_type, remove_ext_block, write_ext_block, IOStructExt, IOStructExtBlock, ArchiveLock};
+use crate::core::structs::*;
+
+/// Extension block tag holding the bounds of every world part's entity positions.
+pub const EXT_TAG_WORLD_PART_BOUNDS: [u8; 4] = *b"PBND";
+
+/// Byte size of a world part in the bounds block: its FormID, whether it has bounds and the bounds.
+const PART_BOUNDS_BYTE_COUNT: usize = FormID::BYTE_COUNT + 1 + BoundingBox::BYTE_COUNT;
+
+/// Computes and stores the bounds of every world part in an archive, replacing any stored ones.
+///
+/// With the bounds stored, `find_world_parts_in` and `query_world_part_entities` skip world parts
+/// outside a query without reading them. Once an archive has them, writes keep them current.
+/// Returns the number of world parts.
+pub fn build_world_part_bounds(file_path: &str) -> io::Result<usize> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut part_bounds = BTreeMap::new();
+    for form in read_forms_of_type(file_path, FormType::WORLDPART)? {
+        part_bounds.insert(form.form_id(), get_world_part(form.as_ref())?.get_bounds());
+    }
+    write_ext_block(file_path, EXT_TAG_WORLD_PART_BOUNDS, get_part_bounds_bytes(&part_bounds))?;
+    Ok(part_bounds.len())
+}
+
+/// Removes the stored world part bounds of an archive, returning whether it had them.
+pub fn remove_world_part_bounds(file_path: &str) -> io::Result<bool> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    remove_ext_block(file_path, EXT_TAG_WORLD_PART_BOUNDS)
+}
+
+/// Returns the stored bounds of every world part, `None` if the archive has none stored.
+pub fn read_world_part_bounds_all(file_path: &str) -> io::Result<Option<BTreeMap<FormID, Option<BoundingBox>>>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    read_part_bounds(file_path)
+}
+
+/// Returns the bounds of a world part's entity positions, `None` without a positioned entity.
+/// Read from the stored bounds when the archive has them, otherwise computed from the world part.
+pub fn read_world_part_bounds(file_path: &str, form_id: FormID) -> io::Result<Option<BoundingBox>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    if let Some(bounds) = read_part_bounds(file_path)?.and_then(|part_bounds| part_bounds.get(&form_id).copied()) {
+        return Ok(bounds);
+    }
+    Ok(get_world_part(read_form(file_path, form_id)?.as_ref())?.get_bounds())
+}
+
+/// Finds the world parts whose bounds the query overlaps, such as the parts inside a view frustum,
+/// in FormID order. World parts without a positioned entity are never found.
+///
+/// Answered from the stored bounds when the archive has them, otherwise from the world parts.
+pub fn find_world_parts_in(file_path: &str, query: &EntityQuery) -> io::Result<Vec<FormID>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    let part_bounds = match read_part_bounds(file_path)? {
+        Some(part_bounds) => part_bounds,
+        None => {
+            let mut part_bounds = BTreeMap::new();
+            for form in read_forms_of_type(file_path, FormType::WORLDPART)? {
+                part_bounds.insert(form.form_id(), get_world_part(form.as_ref())?.get_bounds());
+            }
+            part_bounds
+        },
+    };
+    Ok(part_bounds.into_iter().filter(|(_, bounds)| bounds.is_some_and(|bounds| query.overlaps(&bounds))).map(|(form_id, _)| form_id).collect())
+}
+
+/// Finds the entity instances of a world part whose positions match the query, in the order the
+/// world part lists them. A world part whose stored bounds the query does not overlap is not read.
+pub fn query_world_part_entities(file_path: &str, form_id: FormID, query: &EntityQuery) -> io::Result<Vec<EntInstance>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    if let Some(bounds) = read_part_bounds(file_path)?.and_then(|part_bounds| part_bounds.get(&form_id).copied()) {
+        if !bounds.is_some_and(|bounds| query.overlaps(&bounds)) {
+            return Ok(Vec::new());
+        }
+    }
+    let world_part = get_world_part(read_form(file_path, form_id)?.as_ref())?;
+    Ok(world_part.query_entities(query).into_iter().filter_map(|position| world_part.entities.get(position).copied()).collect())
+}
+
+/// Stores the bounds of a written world part in an extension area, if it has stored bounds.
+pub(crate) fn update_world_part_bounds(ext: &mut IOStructExt, form: &dyn FormTrait) -> io::Result<()> {
+    if form.form_type() != FormType::WORLDPART {
+        return Ok(());
+    }
+    let Some(bytes) = ext.get_block(EXT_TAG_WORLD_PART_BOUNDS) else {
+        return Ok(());
+    };
+    let mut part_bounds = read_part_bounds_block(bytes)?;
+    part_bounds.insert(form.form_id(), get_world_part(form)?.get_bounds());
+    ext.set_block(EXT_TAG_WORLD_PART_BOUNDS, get_part_bounds_bytes(&part_bounds));
+    Ok(())
+}
+
+/// Removes deleted world parts from the bounds held in an extension area, if it has them.
+pub(crate) fn remove_from_world_part_bounds(ext: &mut IOStructExt, form_ids: &[FormID]) -> io::Result<()> {
+    let Some(block) = ext.blocks.iter_mut().find(|block| block.tag == EXT_TAG_WORLD_PART_BOUNDS) else {
+        return Ok(());
+    };
+    let mut part_bounds = read_part_bounds_block(&block.data)?;
+    part_bounds.retain(|form_id, _| !form_ids.contains(form_id));
+    *block = IOStructExtBlock { tag: EXT_TAG_WORLD_PART_BOUNDS, data: get_part_bounds_bytes(&part_bounds) };
+    Ok(())
+}
+
+/// Returns a world part form as a `FormWorldPart`.
+fn get_world_part(form: &dyn FormTrait) -> io::Result<FormWorldPart> {
+    if form.form_type() != FormType::WORLDPART {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Form {} is not a world part.", form.form_id().to_string())));
+    }
+    Ok(FormWorldPart::read_from_byte_buffer(&form.to_bytes())?.0)
+}
+
+fn read_part_bounds(file_path: &str) -> io::Result<Option<BTreeMap<FormID, Option<BoundingBox>>>> {
+    match read_ext_block(file_path, EXT_TAG_WORLD_PART_BOUNDS)? {
+        Some(bytes) => read_part_bounds_block(&bytes).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Converts the bounds into the block: a `u32` world part count, then per world part its FormID, a
+/// `u8` of 1 if it has bounds or 0 if it has no positioned entity, and the bounds, zeroed without.
+fn get_part_bounds_bytes(part_bounds: &BTreeMap<FormID, Option<BoundingBox>>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(4 + part_bounds.len() * PART_BOUNDS_BYTE_COUNT);
+    bytes.extend_from_slice(&(part_bounds.len() as u32).to_be_bytes());
+    for (form_id, bounds) in part_bounds {
+        bytes.extend_from_slice(&form_id.to_bytes());
+        bytes.push(bounds.is_some() as u8);
+        bytes.extend_from_slice(&bounds.map(|bounds| bounds.to_bytes()).unwrap_or([0u8; BoundingBox::BYTE_COUNT]));
+    }
+    bytes
+}
+
+fn read_part_bounds_block(bytes: &[u8]) -> io::Result<BTreeMap<FormID, Option<BoundingBox>>> {
+    let count = u32::from_be_bytes(bytes.get(0..4).ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for world part bounds count"))?.try_into().unwrap()) as usize;
+    let part_bytes = bytes.get(4..4 + count * PART_BOUNDS_BYTE_COUNT).ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for world part bounds"))?;
+    Ok(part_bytes
+        .chunks_exact(PART_BOUNDS_BYTE_COUNT)
+        .map(|part| {
+            let form_id = FormID::from([part[0], part[1]]);
+            let bounds = (part[2] != 0).then(|| BoundingBox::from(<[u8; BoundingBox::BYTE_COUNT]>::try_from(&part[3..]).unwrap()));
+            (form_id, bounds)
+        })
+        .collect())
+}
@@ -0,0 +1,37 @@
+// -- Modules for the extension blocks stored after the index --
+
+// Extension block storage
+pub mod io_ext_block;
+pub use io_ext_block::*;
+
+// Archive key/value metadata
+pub mod io_ext_metadata;
+pub use io_ext_metadata::*;
+
+// Form name index
+pub mod io_ext_name_index;
+pub use io_ext_name_index::*;
+
+// Form type index
+pub mod io_ext_type_index;
+pub use io_ext_type_index::*;
+
+// Stored lengths of form records written out of order
+pub mod io_ext_record_lengths;
+pub use io_ext_record_lengths::*;
+
+// Prior revisions of forms
+pub mod io_ext_form_history;
+pub use io_ext_form_history::*;
+
+// Grid of world parts by their anchors
+pub mod io_ext_world_grid;
+pub use io_ext_world_grid::*;
+
+// Bounds of world parts' entity positions
+pub mod io_ext_world_part_bounds;
+pub use io_ext_world_part_bounds::*;
+
+// Content generation of an archive's forms
+pub mod io_ext_generation;
+pub use io_ext_generation::*;
@@ -0,0 +1,397 @@
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Seek, SeekFrom, Write};
+
+use crate::core::io::{delete_form, get_form_exists, get_form_record_bytes, load_archive_schemas, read_archive_info, read_form, read_form_record_current, write_archive_info, write_form, ArchiveLock, ArchiveLockMode, ArchiveLockedError};
+use crate::core::structs::*;
+
+/// Marks the start of a journal file.
+const JOURNAL_MAGIC: &[u8; 4] = b"SMNJ";
+
+// Journal record kinds. Edits, undos and redos are recorded before they are applied to the archive
+// and followed by a commit once applied, or an abort if they failed.
+const RECORD_EDIT: u8 = 1;
+const RECORD_UNDO: u8 = 2;
+const RECORD_REDO: u8 = 3;
+const RECORD_COMMIT: u8 = 4;
+const RECORD_ABORT: u8 = 5;
+
+/// Byte size of a record's header: its kind and payload length.
+const RECORD_HEADER_BYTE_COUNT: usize = 5;
+
+/// What a journal step changed in the archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalTarget {
+    Form(FormID),
+    ArchiveInfo,
+}
+
+/// One journaled edit, with the state of its target before and after it.
+///
+/// A form's state is its data record, the form's bytes followed by its metadata section, or `None`
+/// when the form did not exist. The archive info's state is its ID, version and description.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalStep {
+    pub target: JournalTarget,
+    pub before: Option<Vec<u8>>,
+    pub after: Option<Vec<u8>>,
+}
+
+/// How opening a journal dealt with an operation a crash left unfinished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalRecovery {
+    /// The journal was left after a finished operation.
+    Clean,
+    /// The unfinished operation was applied to the archive again and is now part of the history.
+    Replayed,
+    /// The unfinished operation could not be applied again and was dropped.
+    Abandoned(io::ErrorKind),
+}
+
+/// An operation recorded in the journal but not yet committed.
+enum PendingRecord {
+    Edit(JournalStep),
+    Undo,
+    Redo,
+}
+
+/// Undo/redo history of an archive's edits, kept in a `.journal` sidecar so it survives the
+/// session and the archive can be brought back in line after a crash.
+///
+/// Edits made through the journal are recorded, with the state of what they change, before they
+/// are applied to the archive. Opening the journal replays an edit, undo or redo a crash left
+/// unfinished. `checkpoint` drops the history once the archive holds the edits for good. Edits
+/// made without the journal are not recorded, undoing across them restores the journaled state.
+///
+/// Only one journal of an archive can be open at a time.
+pub struct ArchiveJournal {
+    file_path: String,
+    journal: File,
+    steps: Vec<JournalStep>,
+    position: usize,  // Number of steps applied, the rest can be redone
+    recovery: JournalRecovery,
+}
+
+#[allow(unused)]
+impl ArchiveJournal {
+    /// Opens an archive's journal, creating it if needed, and replays an operation a crash left
+    /// unfinished.
+    pub fn open(file_path: &str) -> io::Result<Self> {
+        let _lock = ArchiveLock::exclusive(file_path)?;
+        read_archive_info(file_path)?;
+        load_archive_schemas(file_path)?;
+
+        let journal_path = Self::get_journal_path(file_path);
+        let mut journal = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&journal_path)?;
+        match journal.try_lock() {
+            Ok(()) => {},
+            Err(TryLockError::WouldBlock) => return Err(io::Error::new(io::ErrorKind::WouldBlock, ArchiveLockedError { file_path: journal_path, mode: ArchiveLockMode::Exclusive })),
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+
+        let bytes = fs::read(&journal_path)?;
+        if bytes.is_empty() {
+            journal.write_all(JOURNAL_MAGIC)?;
+            journal.sync_data()?;
+        } else if !bytes.starts_with(JOURNAL_MAGIC) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} is not an archive journal.", journal_path)));
+        }
+
+        let mut this = Self { file_path: file_path.to_string(), journal, steps: Vec::new(), position: 0, recovery: JournalRecovery::Clean };
+        let mut pending = None;
+        let mut offset = JOURNAL_MAGIC.len();
+        while let Some((kind, payload)) = read_record(&bytes, offset) {
+            offset += RECORD_HEADER_BYTE_COUNT + payload.len();
+            pending = match kind {
+                RECORD_EDIT => Some(PendingRecord::Edit(JournalStep::read_from_byte_buffer(payload)?)),
+                RECORD_UNDO => Some(PendingRecord::Undo),
+                RECORD_REDO => Some(PendingRecord::Redo),
+                RECORD_COMMIT => {
+                    if let Some(pending) = pending {
+                        this.commit(pending);
+                    }
+                    None
+                },
+                RECORD_ABORT => None,
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown journal record kind {}.", kind))),
+            };
+        }
+
+        // Drop a record a crash left half written, then finish the operation it left pending
+        this.journal.set_len(offset as u64)?;
+        this.journal.seek(SeekFrom::End(0))?;
+        if let Some(pending) = pending {
+            this.recovery = match this.finish(pending) {
+                Ok(()) => JournalRecovery::Replayed,
+                Err(e) => JournalRecovery::Abandoned(e.kind()),
+            };
+        }
+        Ok(this)
+    }
+
+    pub fn file_path(&self) -> &str {
+        &self.file_path
+    }
+
+    /// How opening the journal dealt with an operation a crash left unfinished.
+    pub fn recovery(&self) -> JournalRecovery {
+        self.recovery
+    }
+
+    /// Every recorded step, oldest first. The first `undo_count()` are applied.
+    pub fn history(&self) -> &[JournalStep] {
+        &self.steps
+    }
+
+    pub fn undo_count(&self) -> usize {
+        self.position
+    }
+
+    pub fn redo_count(&self) -> usize {
+        self.steps.len() - self.position
+    }
+
+    /// Writes a form to the archive, see `write_form`, recording it as a step. The steps that
+    /// could be redone are dropped.
+    pub fn write_form(&mut self, form: &dyn FormTrait) -> io::Result<()> {
+        let _lock = ArchiveLock::exclusive(&self.file_path)?;
+        let form_id = form.form_id();
+        let step = JournalStep { target: JournalTarget::Form(form_id), before: self.read_form_state(form_id)?, after: Some(get_form_record_bytes(form)) };
+        self.run(PendingRecord::Edit(step), |file_path| write_form(file_path, form))
+    }
+
+    /// Deletes a form from the archive, see `delete_form`, recording it as a step.
+    pub fn delete_form(&mut self, form_id: FormID) -> io::Result<()> {
+        let _lock = ArchiveLock::exclusive(&self.file_path)?;
+        let Some(before) = self.read_form_state(form_id)? else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "Form not found in archive."));
+        };
+        let step = JournalStep { target: JournalTarget::Form(form_id), before: Some(before), after: None };
+        self.run(PendingRecord::Edit(step), |file_path| delete_form(file_path, form_id))
+    }
+
+    /// Updates the archive information, see `write_archive_info`, recording it as a step.
+    pub fn write_archive_info(&mut self, archive: &Archive) -> io::Result<()> {
+        let _lock = ArchiveLock::exclusive(&self.file_path)?;
+        let before = get_archive_info_bytes(&read_archive_info(&self.file_path)?);
+        let step = JournalStep { target: JournalTarget::ArchiveInfo, before: Some(before), after: Some(get_archive_info_bytes(archive)) };
+        self.run(PendingRecord::Edit(step), |file_path| write_archive_info(file_path, archive))
+    }
+
+    /// Undoes up to `count` steps, most recent first, returning how many were undone.
+    pub fn undo(&mut self, count: usize) -> io::Result<usize> {
+        let _lock = ArchiveLock::exclusive(&self.file_path)?;
+        for undone in 0..count {
+            if self.position == 0 {
+                return Ok(undone);
+            }
+            let step = &self.steps[self.position - 1];
+            let (target, state) = (step.target, step.before.clone());
+            self.run(PendingRecord::Undo, |file_path| apply_state(file_path, target, state.as_deref()))?;
+        }
+        Ok(count)
+    }
+
+    /// Redoes up to `count` undone steps, oldest first, returning how many were redone.
+    pub fn redo(&mut self, count: usize) -> io::Result<usize> {
+        let _lock = ArchiveLock::exclusive(&self.file_path)?;
+        for redone in 0..count {
+            if self.position == self.steps.len() {
+                return Ok(redone);
+            }
+            let step = &self.steps[self.position];
+            let (target, state) = (step.target, step.after.clone());
+            self.run(PendingRecord::Redo, |file_path| apply_state(file_path, target, state.as_deref()))?;
+        }
+        Ok(count)
+    }
+
+    /// Keeps the archive as it is and clears the journal, dropping the undo and redo history.
+    pub fn checkpoint(&mut self) -> io::Result<()> {
+        let _lock = ArchiveLock::exclusive(&self.file_path)?;
+        self.journal.set_len(JOURNAL_MAGIC.len() as u64)?;
+        self.journal.seek(SeekFrom::End(0))?;
+        self.journal.sync_data()?;
+        self.steps.clear();
+        self.position = 0;
+        Ok(())
+    }
+
+    /// Returns the sidecar path an archive's journal is kept in.
+    pub fn get_journal_path(file_path: &str) -> String {
+        format!("{}.journal", file_path)
+    }
+
+    /// Records an operation, applies it and commits it, or aborts it if applying failed.
+    fn run(&mut self, pending: PendingRecord, apply: impl FnOnce(&str) -> io::Result<()>) -> io::Result<()> {
+        let payload = match &pending {
+            PendingRecord::Edit(step) => step.to_bytes(),
+            PendingRecord::Undo | PendingRecord::Redo => Vec::new(),
+        };
+        self.append_record(pending.record_kind(), &payload)?;
+        if let Err(e) = apply(&self.file_path) {
+            self.append_record(RECORD_ABORT, &[])?;
+            return Err(e);
+        }
+        self.append_record(RECORD_COMMIT, &[])?;
+        self.commit(pending);
+        Ok(())
+    }
+
+    /// Applies an operation found uncommitted on open again, then commits or aborts it.
+    fn finish(&mut self, pending: PendingRecord) -> io::Result<()> {
+        let (target, state) = match &pending {
+            PendingRecord::Edit(step) => (step.target, step.after.clone()),
+            PendingRecord::Undo => match self.position.checked_sub(1).and_then(|position| self.steps.get(position)) {
+                Some(step) => (step.target, step.before.clone()),
+                None => return self.append_record(RECORD_ABORT, &[]),
+            },
+            PendingRecord::Redo => match self.steps.get(self.position) {
+                Some(step) => (step.target, step.after.clone()),
+                None => return self.append_record(RECORD_ABORT, &[]),
+            },
+        };
+        if let Err(e) = apply_state(&self.file_path, target, state.as_deref()) {
+            self.append_record(RECORD_ABORT, &[])?;
+            return Err(e);
+        }
+        self.append_record(RECORD_COMMIT, &[])?;
+        self.commit(pending);
+        Ok(())
+    }
+
+    /// Moves the history past a committed operation.
+    fn commit(&mut self, pending: PendingRecord) {
+        match pending {
+            PendingRecord::Edit(step) => {
+                self.steps.truncate(self.position);
+                self.steps.push(step);
+                self.position += 1;
+            },
+            PendingRecord::Undo => self.position = self.position.saturating_sub(1),
+            PendingRecord::Redo => self.position = (self.position + 1).min(self.steps.len()),
+        }
+    }
+
+    /// Appends a record and flushes it to disk, so it outlives a crash of the edit it precedes.
+    fn append_record(&mut self, kind: u8, payload: &[u8]) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(RECORD_HEADER_BYTE_COUNT + payload.len());
+        bytes.push(kind);
+        bytes.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(payload);
+        self.journal.write_all(&bytes)?;
+        self.journal.sync_data()
+    }
+
+    fn read_form_state(&self, form_id: FormID) -> io::Result<Option<Vec<u8>>> {
+        if !get_form_exists(&self.file_path, form_id)? {
+            return Ok(None);
+        }
+        Ok(Some(get_form_record_bytes(read_form(&self.file_path, form_id)?.as_ref())))
+    }
+}
+
+impl PendingRecord {
+    fn record_kind(&self) -> u8 {
+        match self {
+            Self::Edit(_) => RECORD_EDIT,
+            Self::Undo => RECORD_UNDO,
+            Self::Redo => RECORD_REDO,
+        }
+    }
+}
+
+impl JournalStep {
+    /// Serializes the step: its target kind and FormID, then the before and after states, each a
+    /// presence flag followed by its length and bytes.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self.target {
+            JournalTarget::Form(form_id) => {
+                bytes.push(0);
+                bytes.extend_from_slice(&form_id.to_bytes());
+            },
+            JournalTarget::ArchiveInfo => {
+                bytes.push(1);
+                bytes.extend_from_slice(&FormID::from(0u16).to_bytes());
+            },
+        }
+        for state in [&self.before, &self.after] {
+            match state {
+                Some(state) => {
+                    bytes.push(1);
+                    bytes.extend_from_slice(&(state.len() as u32).to_be_bytes());
+                    bytes.extend_from_slice(state);
+                },
+                None => bytes.push(0),
+            }
+        }
+        bytes
+    }
+
+    fn read_from_byte_buffer(bytes: &[u8]) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Journal step is truncated.");
+        let header = bytes.get(..1 + FormID::BYTE_COUNT).ok_or_else(invalid)?;
+        let target = match header[0] {
+            0 => JournalTarget::Form(FormID::from([header[1], header[2]])),
+            1 => JournalTarget::ArchiveInfo,
+            kind => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown journal target kind {}.", kind))),
+        };
+
+        let mut offset = header.len();
+        let mut states = [None, None];
+        for state in &mut states {
+            match *bytes.get(offset).ok_or_else(invalid)? {
+                0 => offset += 1,
+                _ => {
+                    let length_bytes = bytes.get(offset + 1..offset + 5).ok_or_else(invalid)?;
+                    let length = u32::from_be_bytes([length_bytes[0], length_bytes[1], length_bytes[2], length_bytes[3]]) as usize;
+                    *state = Some(bytes.get(offset + 5..offset + 5 + length).ok_or_else(invalid)?.to_vec());
+                    offset += 5 + length;
+                },
+            }
+        }
+        let [before, after] = states;
+        Ok(Self { target, before, after })
+    }
+}
+
+/// Returns the record at `offset`, or `None` at the end of the journal or of what was fully written.
+fn read_record(bytes: &[u8], offset: usize) -> Option<(u8, &[u8])> {
+    let header = bytes.get(offset..offset + RECORD_HEADER_BYTE_COUNT)?;
+    let length = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    let payload_start = offset + RECORD_HEADER_BYTE_COUNT;
+    Some((header[0], bytes.get(payload_start..payload_start + length)?))
+}
+
+/// Puts a step's target in the given state.
+fn apply_state(file_path: &str, target: JournalTarget, state: Option<&[u8]>) -> io::Result<()> {
+    match (target, state) {
+        (JournalTarget::Form(_), Some(record)) => write_form(file_path, read_form_record_current(record)?.as_ref()),
+        (JournalTarget::Form(form_id), None) => match get_form_exists(file_path, form_id)? {
+            true => delete_form(file_path, form_id),
+            false => Ok(()),
+        },
+        (JournalTarget::ArchiveInfo, Some(bytes)) => write_archive_info(file_path, &read_archive_info_bytes(bytes)?),
+        (JournalTarget::ArchiveInfo, None) => Ok(()),
+    }
+}
+
+/// Serializes the archive info a journal step restores: its ID, version and description.
+fn get_archive_info_bytes(archive: &Archive) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&archive.archive_id.to_bytes());
+    bytes.extend_from_slice(&archive.version.to_bytes());
+    bytes.extend_from_slice(&archive.description.to_bytes());
+    bytes
+}
+
+fn read_archive_info_bytes(bytes: &[u8]) -> io::Result<Archive> {
+    let id_and_version = ArchiveID::BYTE_COUNT + Version::BYTE_COUNT;
+    if bytes.len() < id_and_version {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for archive info"));
+    }
+    let archive_id = ArchiveID::from([bytes[0]]);
+    let version = Version::from([bytes[1], bytes[2]]);
+    let (description, _) = StrLrg::read_from_byte_buffer(&bytes[id_and_version..])?;
+    Ok(Archive::new(archive_id, version, description))
+}
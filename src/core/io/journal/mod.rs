@@ -0,0 +1,5 @@
+// -- Modules for journaling archive edits --
+
+// Write-ahead journal with undo and redo
+pub mod io_journal;
+pub use io_journal::*;
@@ -10,4 +10,24 @@ pub use write::*;
 
 // Archive Utilities
 pub mod util;
-pub use util::*;
\ No newline at end of file
+pub use util::*;
+
+// Archive References
+pub mod refs;
+pub use refs::*;
+
+// Archive Extension Blocks
+pub mod ext;
+pub use ext::*;
+
+// Archive FormID Allocation
+pub mod alloc;
+pub use alloc::*;
+
+// Archive Form Schemas
+pub mod schema;
+pub use schema::*;
+
+// Archive Edit Journal
+pub mod journal;
+pub use journal::*;
@@ -1,63 +1,106 @@
+use std::collections::BTreeMap;
 use std::fs::File;
-use std::io::{self, Seek, SeekFrom};
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
 
-use crate::core::io::{read_block_bytestarts, read_block_header, binary_search_for_index_item};
+use crate::core::io::{get_ext_archive_metadata, get_ext_form_names, get_ext_record_lengths, get_index_data_length, read_archive_layout_versions, read_block_bytestarts, read_block_ext, read_block_header, read_form_record, read_form_record_from_buffer, binary_search_for_index_item, ArchiveLock};
 use crate::core::structs::*;
 
-use super::{read_block_index, read_form};
+use super::read_block_index;
 
-/// Reads the archive information from a file, including header and bytestart data.
+/// Reads the archive information from a file, including header, bytestart data and the archive metadata.
 pub fn read_archive_info(file_path: &str) -> io::Result<Archive> {
+    let _lock = ArchiveLock::shared(file_path)?;
     let mut file = File::open(file_path)?;  
     let header = read_block_header(&mut file)?; 
     let bytestarts = read_block_bytestarts(&mut file)?; 
 
+    // The metadata block is in the extension area past the index
+    file.seek(SeekFrom::Start(bytestarts.bytestart_index as u64 + header.form_count as u64 * 7))?;
+    let ext = read_block_ext(&mut file)?;
+
     // Populate the Archive structure with the read data
     let mut archive_out = Archive::new(header.archive_id, header.version, header.description);
     archive_out.form_count = header.form_count;
     archive_out.bytestart_index = bytestarts.bytestart_index;
     archive_out.bytestart_data = bytestarts.bytestart_data;
+    archive_out.metadata = get_ext_archive_metadata(&ext)?;
+    archive_out.record_lengths = get_ext_record_lengths(&ext)?;
 
     Ok(archive_out)
 }
 
+/// Which forms `read_lite_archive_page` lists: those of one type, or all, skipping `offset` of them
+/// and listing at most `limit`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LiteArchiveQuery {
+    pub form_type: Option<FormType>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
 /// Reads a lightweight version of the archive, including only the header and index data.
 pub fn read_lite_archive(file_path: &str) -> io::Result<LiteArchive> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    read_lite_archive_page(file_path, &LiteArchiveQuery::default())
+}
+
+/// Reads a lightweight listing of some of the archive's forms, in index order.
+///
+/// The archive is opened once and the listed records are visited in a single pass over the data
+/// block, reading their names, or taking them from the name index, and their metadata sections.
+/// `match_count` of the result counts every form the query's type matches, for paging.
+pub fn read_lite_archive_page(file_path: &str, query: &LiteArchiveQuery) -> io::Result<LiteArchive> {
+    let _lock = ArchiveLock::shared(file_path)?;
     let mut file = File::open(file_path)?; 
 
     let header = read_block_header(&mut file)?; 
     let bytestarts = read_block_bytestarts(&mut file)?; 
+    let mut archive_info = Archive::new(header.archive_id, header.version, header.description.clone());
+    archive_info.form_count = header.form_count;
+    archive_info.bytestart_index = bytestarts.bytestart_index;
+    archive_info.bytestart_data = bytestarts.bytestart_data;
+
+    // Move to the index section in the file, the extension area follows it
+    file.seek(SeekFrom::Start(bytestarts.bytestart_index as u64))?;
+    let index = read_block_index(&mut file, header.form_count)?; 
+    let ext = read_block_ext(&mut file)?;
+    let metadata = get_ext_archive_metadata(&ext)?;
+    archive_info.record_lengths = get_ext_record_lengths(&ext)?;
+    let layout_versions = read_archive_layout_versions(file_path)?;
 
     // Populate the LiteArchive structure with header info
+    let matching: Vec<usize> = (0..index.indexes.len())
+        .filter(|position| query.form_type.is_none_or(|form_type| index.indexes[*position].form_type == form_type))
+        .collect();
     let mut archive_out = LiteArchive {
         archive_id: header.archive_id,
         version: header.version,
         description: header.description,
         form_count: header.form_count,
+        match_count: matching.len() as u16,
+        metadata,
         archive_items: Vec::new(),
     };
 
-    // Move to the index section in the file
-    file.seek(SeekFrom::Start(bytestarts.bytestart_index as u64))?;
-    let index = read_block_index(&mut file, header.form_count)?; 
-
-    // Iterate through index items to populate LiteArchive
-    for index_item in index.indexes {
-        let form_id = index_item.form_id;
-        let form_type = index_item.form_type;
+    // Names come from the name index when the archive has one, otherwise from the start of each record
+    let form_names = get_ext_form_names(&ext)?;
+    for &index_position in matching.iter().skip(query.offset).take(query.limit.unwrap_or(usize::MAX)) {
+        let index_item = &index.indexes[index_position];
+        let data_length = get_index_data_length(&archive_info, &index, index_position)?;
+        let record_start = (archive_info.bytestart_data + index_item.data_start_offset) as u64;
 
-        // Attempt to read the form by its ID
-        let index_form = read_form(file_path, form_id);
-        let form_name = match index_form {
-            Ok(form) => form.form_name(),
-            Err(_) => StrSml::from("Form not found"),
+        let form_name = match form_names.as_ref().and_then(|form_names| form_names.get(&index_item.form_id)) {
+            Some(form_name) => form_name.clone(),
+            None => read_record_name(&mut file, record_start)?,
         };
+        let metadata = read_record_metadata(&mut file, file_path, &layout_versions, index_item.form_type, record_start, data_length)?;
 
-        // Create and add a LiteArchiveItem for each form in the archive
+        // Create and add a LiteArchiveItem for each listed form
         let new_lite_form = LiteArchiveItem {
-            form_id,
+            form_id: index_item.form_id,
             form_name,
-            form_type,
+            form_type: index_item.form_type,
+            metadata,
         };
         archive_out.archive_items.push(new_lite_form);
     }
@@ -65,8 +108,51 @@ pub fn read_lite_archive(file_path: &str) -> io::Result<LiteArchive> {
     Ok(archive_out)
 }
 
+/// Reads the name at the start of a form record.
+fn read_record_name(file: &mut File, record_start: u64) -> io::Result<StrSml> {
+    file.seek(SeekFrom::Start(record_start + (FormID::BYTE_COUNT + FormType::BYTE_COUNT) as u64))?;
+    StrSml::read_from_bytes(file)
+}
+
+/// Reads the metadata section of a form record.
+///
+/// The record is read once. Forms stored in their type's current layout are measured in it with the
+/// type's schema, so they are not decoded. Other forms are decoded, older layouts through their
+/// migration and unknown types through the archive's schemas.
+fn read_record_metadata(file: &mut File, file_path: &str, layout_versions: &BTreeMap<FormType, u16>, form_type: FormType, record_start: u64, data_length: u32) -> io::Result<FormMetadata> {
+    file.seek(SeekFrom::Start(record_start))?;
+    let mut record = vec![0u8; data_length as usize];
+    file.read_exact(&mut record)?;
+
+    let current_schema = with_form_type_registration(form_type, |registration| {
+        (registration.layout_version == layout_versions.get(&form_type).copied().unwrap_or(FORM_LAYOUT_VERSION_INITIAL)).then(|| registration.schema.clone()).flatten()
+    });
+    if let Some(schema) = current_schema.flatten() {
+        let form_length = schema.read_form_byte_count(&mut Cursor::new(&record))?;
+        if form_length > data_length as u64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Form is longer than its data record."));
+        }
+        if form_length == data_length as u64 {
+            return Ok(FormMetadata::default());
+        }
+        let metadata_bytes = &record[form_length as usize..];
+        let (metadata, consumed) = FormMetadata::read_from_byte_buffer(metadata_bytes)?;
+        if consumed != metadata_bytes.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Form has trailing bytes after its metadata."));
+        }
+        return Ok(metadata);
+    }
+
+    if let Some(form) = read_form_record_from_buffer(&record, layout_versions) {
+        return Ok(form?.form_metadata());
+    }
+    file.seek(SeekFrom::Start(record_start))?;
+    Ok(read_form_record(file, file_path, layout_versions, data_length)?.form_metadata())
+}
+
 /// Checks if a form with a specific `form_id` exists within the archive.
 pub fn get_form_exists(file_path: &str, form_id: FormID) -> io::Result<bool> {
+    let _lock = ArchiveLock::shared(file_path)?;
     let archive = read_archive_info(file_path)?;  
     if archive.form_count == 0 {
         return Ok(false); 
@@ -81,3 +167,15 @@ pub fn get_form_exists(file_path: &str, form_id: FormID) -> io::Result<bool> {
         None => Ok(false),    // Form not found
     }
 }
+
+/// Reads the FormIDs listed in the archive's index, in ascending order, without reading the forms.
+pub fn read_form_ids(file_path: &str) -> io::Result<Vec<FormID>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    let mut file = File::open(file_path)?;
+    let header = read_block_header(&mut file)?;
+    let bytestarts = read_block_bytestarts(&mut file)?;
+
+    file.seek(SeekFrom::Start(bytestarts.bytestart_index as u64))?;
+    let index = read_block_index(&mut file, header.form_count)?;
+    Ok(index.indexes.iter().map(|item| item.form_id).collect())
+}
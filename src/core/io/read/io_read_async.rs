@@ -0,0 +1,272 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+
+use crate::core::io::{read_lite_archive_page, ArchiveReader, LiteArchiveQuery};
+use crate::core::structs::*;
+
+/// How urgently a read request is served. Queued requests are served highest priority first, and
+/// in submission order within a priority.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ReadPriority {
+    Background,
+    #[default]
+    Normal,
+    High,
+    Immediate,
+}
+
+/// Serves a queued read, or fails it with `None` when the reader closed before serving it.
+type ReadJob = Box<dyn FnOnce(Option<&ArchiveReader>) + Send>;
+
+struct QueuedRead {
+    priority: ReadPriority,
+    sequence: u64,
+    job: ReadJob,
+}
+
+impl PartialEq for QueuedRead {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueuedRead {}
+
+impl PartialOrd for QueuedRead {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueuedRead {
+    /// The greatest read is served first: the highest priority, then the earliest submitted.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority.cmp(&other.priority).then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+#[derive(Default)]
+struct ReadQueue {
+    reads: BinaryHeap<QueuedRead>,
+    next_sequence: u64,
+    is_closed: bool,
+}
+
+struct ReadShared {
+    reader: ArchiveReader,
+    queue: Mutex<ReadQueue>,
+    available: Condvar,
+}
+
+/// An archive reader serving requests on its own worker threads, so callers never block on disk
+/// reads or decoding.
+///
+/// Each request returns a `ReadRequest`, a future resolving to the result that works with any
+/// executor and can also be polled with `try_take` from a game loop. Dropping the reader fails the
+/// requests it has not served yet.
+pub struct AsyncArchiveReader {
+    shared: Arc<ReadShared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+#[allow(unused)]
+impl AsyncArchiveReader {
+    /// Opens an archive, serving requests on `worker_count` threads, or one per available core
+    /// when 0.
+    pub fn open(file_path: &str, worker_count: usize) -> io::Result<Self> {
+        Ok(Self::new(ArchiveReader::open(file_path)?, worker_count))
+    }
+
+    /// Serves requests from an open reader, with its cache if it has one.
+    pub fn new(reader: ArchiveReader, worker_count: usize) -> Self {
+        let worker_count = match worker_count {
+            0 => thread::available_parallelism().map(|count| count.get()).unwrap_or(1),
+            count => count,
+        };
+        let shared = Arc::new(ReadShared { reader, queue: Mutex::new(ReadQueue::default()), available: Condvar::new() });
+        let workers = (0..worker_count)
+            .map(|_| {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || serve_reads(&shared))
+            })
+            .collect();
+        Self { shared, workers }
+    }
+
+    pub fn reader(&self) -> &ArchiveReader {
+        &self.shared.reader
+    }
+
+    /// Number of requests waiting for a worker.
+    pub fn pending_count(&self) -> usize {
+        lock(&self.shared.queue).reads.len()
+    }
+
+    pub fn read_form(&self, form_id: FormID, priority: ReadPriority) -> ReadRequest<Box<dyn FormTrait>> {
+        self.submit(priority, move |reader| reader.read_form(form_id))
+    }
+
+    /// Reads forms in the order requested, as one request.
+    pub fn read_forms(&self, form_ids: Vec<FormID>, priority: ReadPriority) -> ReadRequest<Vec<Box<dyn FormTrait>>> {
+        self.submit(priority, move |reader| reader.read_forms(&form_ids))
+    }
+
+    /// Reads a page of the lite archive listing, see `read_lite_archive_page`.
+    pub fn read_lite_archive(&self, query: LiteArchiveQuery, priority: ReadPriority) -> ReadRequest<LiteArchive> {
+        self.submit(priority, move |reader| read_lite_archive_page(reader.file_path(), &query))
+    }
+
+    fn submit<T: Send + 'static>(&self, priority: ReadPriority, read: impl FnOnce(&ArchiveReader) -> io::Result<T> + Send + 'static) -> ReadRequest<T> {
+        let request = ReadRequest { state: Arc::new(Mutex::new(RequestState { result: None, waker: None, is_cancelled: false })) };
+        let state = Arc::clone(&request.state);
+        let job: ReadJob = Box::new(move |reader| {
+            if lock(&state).is_cancelled {
+                return;
+            }
+            let result = match reader {
+                Some(reader) => read(reader),
+                None => Err(io::Error::new(io::ErrorKind::Interrupted, "The archive reader was closed before serving the request.")),
+            };
+            complete_request(&state, result);
+        });
+
+        let mut queue = lock(&self.shared.queue);
+        if queue.is_closed {
+            drop(queue);
+            job(None);
+            return request;
+        }
+        let sequence = queue.next_sequence;
+        queue.next_sequence += 1;
+        queue.reads.push(QueuedRead { priority, sequence, job });
+        self.shared.available.notify_one();
+        request
+    }
+}
+
+impl Drop for AsyncArchiveReader {
+    fn drop(&mut self) {
+        lock(&self.shared.queue).is_closed = true;
+        self.shared.available.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+
+        // Fail the requests no worker took
+        let unserved = std::mem::take(&mut lock(&self.shared.queue).reads);
+        for read in unserved {
+            (read.job)(None);
+        }
+    }
+}
+
+fn serve_reads(shared: &ReadShared) {
+    loop {
+        let read = {
+            let mut queue = lock(&shared.queue);
+            loop {
+                if queue.is_closed {
+                    return;
+                }
+                if let Some(read) = queue.reads.pop() {
+                    break read;
+                }
+                queue = match shared.available.wait(queue) {
+                    Ok(queue) => queue,
+                    Err(poisoned) => poisoned.into_inner(),
+                };
+            }
+        };
+        (read.job)(Some(&shared.reader));
+    }
+}
+
+struct RequestState<T> {
+    result: Option<io::Result<T>>,
+    waker: Option<Waker>,
+    is_cancelled: bool,
+}
+
+/// A pending read of an `AsyncArchiveReader`, resolving to its result.
+///
+/// Cancelling or dropping a request that is still queued keeps it from being read. A request
+/// being read when cancelled finishes, its result is discarded.
+pub struct ReadRequest<T> {
+    state: Arc<Mutex<RequestState<T>>>,
+}
+
+#[allow(unused)]
+impl<T> ReadRequest<T> {
+    /// Cancels the request, which then resolves to an `Interrupted` error. Returns false if it had
+    /// already resolved.
+    pub fn cancel(&self) -> bool {
+        let mut state = lock(&self.state);
+        if state.result.is_some() || state.is_cancelled {
+            return false;
+        }
+        state.is_cancelled = true;
+        state.result = Some(Err(io::Error::new(io::ErrorKind::Interrupted, "The read request was cancelled.")));
+        let waker = state.waker.take();
+        drop(state);
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        true
+    }
+
+    pub fn is_ready(&self) -> bool {
+        lock(&self.state).result.is_some()
+    }
+
+    /// Takes the result if the request has resolved, without waiting.
+    pub fn try_take(&mut self) -> Option<io::Result<T>> {
+        lock(&self.state).result.take()
+    }
+}
+
+impl<T> Future for ReadRequest<T> {
+    type Output = io::Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = lock(&self.state);
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl<T> Drop for ReadRequest<T> {
+    fn drop(&mut self) {
+        lock(&self.state).is_cancelled = true;
+    }
+}
+
+fn complete_request<T>(state: &Mutex<RequestState<T>>, result: io::Result<T>) {
+    let mut state = lock(state);
+    if state.is_cancelled {
+        return;
+    }
+    state.result = Some(result);
+    let waker = state.waker.take();
+    drop(state);
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
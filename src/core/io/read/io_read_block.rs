@@ -1,7 +1,7 @@
 use std::{fs::File, io::{self, Read, Seek, SeekFrom}};
 
-use crate::core::io::{IOStructByteStarts, IOStructHeader, IOStructIndex, IOStructIndexItem};
-use crate::core::structs::types::*;
+use crate::core::io::{IOStructByteStarts, IOStructExt, IOStructExtBlock, IOStructHeader, IOStructIndex, IOStructIndexItem, EXT_AREA_MAGIC};
+use crate::core::structs::{types::*, Archive};
 
 pub fn read_block_header(file: &mut File) -> io::Result<IOStructHeader> {
     // Read ArchiveID
@@ -55,28 +55,14 @@ pub fn read_block_index(file: &mut File, form_count: u16) -> io::Result<IOStruct
         indexes: Vec::new(),
     };
 
-    // Iterate over the form_count
-    for _ in 0..form_count {
-        // Read FormID
-        let mut form_id_buf = [0u8; FormID::BYTE_COUNT];
-        file.read_exact(&mut form_id_buf)?;
-        let form_id = FormID::from(form_id_buf);
-
-        // Read FormType
-        let mut form_type_buf = [0u8; 1]; // 1 byte for FormType
-        file.read_exact(&mut form_type_buf)?;
-        let form_type = FormType::from(form_type_buf[0]);
-
-        // Read Data Start Offset
-        let mut data_start_offset_buf = [0u8; 4]; // 4 bytes for u32
-        file.read_exact(&mut data_start_offset_buf)?;
-        let data_start_offset = u32::from_be_bytes(data_start_offset_buf);
-
-        // Create a new IOStructIndexItem and add it to the index
+    // Read the whole block at once, then split it into its items
+    let mut bytes = vec![0u8; form_count as usize * 7];
+    file.read_exact(&mut bytes)?;
+    for item in bytes.chunks_exact(7) {
         index.indexes.push(IOStructIndexItem {
-            form_id,
-            form_type,
-            data_start_offset,
+            form_id: FormID::from([item[0], item[1]]),
+            form_type: FormType::from(item[2]),
+            data_start_offset: u32::from_be_bytes([item[3], item[4], item[5], item[6]]),
         });
     }
 
@@ -84,6 +70,44 @@ pub fn read_block_index(file: &mut File, form_count: u16) -> io::Result<IOStruct
     Ok(index)
 }
 
+/// Reads the extension area from the current position (the end of the index) to the end of the file.
+pub fn read_block_ext(file: &mut File) -> io::Result<IOStructExt> {
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+
+    // Archives without extension blocks end at the index, older writers could leave stale bytes there instead
+    if bytes.len() < 6 || bytes[0..4] != EXT_AREA_MAGIC {
+        return Ok(IOStructExt::default());
+    }
+
+    // Read the block count
+    let block_count = u16::from_be_bytes([bytes[4], bytes[5]]);
+    let mut offset = 6;
+
+    let mut ext = IOStructExt::default();
+    for _ in 0..block_count {
+        // Read the block tag and length
+        if bytes.len() < offset + 8 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for extension block header"));
+        }
+        let tag: [u8; 4] = bytes[offset..offset + 4].try_into().unwrap();
+        let length = u32::from_be_bytes(bytes[offset + 4..offset + 8].try_into().unwrap()) as usize;
+        offset += 8;
+
+        // Read the block data
+        if bytes.len() < offset + length {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for extension block data"));
+        }
+        ext.blocks.push(IOStructExtBlock {
+            tag,
+            data: bytes[offset..offset + length].to_vec(),
+        });
+        offset += length;
+    }
+
+    Ok(ext)
+}
+
 pub fn binary_search_for_index_item(
     file: &mut File, 
     target_form_id: FormID, 
@@ -220,7 +244,7 @@ pub fn binary_search_for_index_item_and_position(
             let data_start_offset = u32::from_be_bytes(data_start_offset_buf);
 
             // Calculate the position in the index
-            let index_position = (mid - left) / item_size as u64;
+            let index_position = (mid - start) / item_size as u64;
 
             return Ok(Some((index_position, IOStructIndexItem {
                 form_id,
@@ -308,3 +332,43 @@ pub fn binary_search_for_index_item_inmem(
         }))
     }
 }
+
+/// Returns the stored byte length of the form at an index position, from where the next form's data
+/// starts. Forms are not parsed, so those stored in an older layout or with metadata are measured correctly.
+pub fn read_form_data_length(file: &mut File, archive: &Archive, index_position: u64, data_start_offset: u32) -> io::Result<u32> {
+    if let Some(record_lengths) = &archive.record_lengths {
+        return get_stored_record_length(record_lengths, index_position as usize);
+    }
+    let data_end_offset = if index_position + 1 >= archive.form_count as u64 {
+        archive.bytestart_index - archive.bytestart_data
+    } else {
+        // Skip the next index item's FormID and FormType to its data start offset
+        file.seek(SeekFrom::Start(archive.bytestart_index as u64 + (index_position + 1) * 7 + 3))?;
+        let mut data_end_offset_buf = [0u8; 4];
+        file.read_exact(&mut data_end_offset_buf)?;
+        u32::from_be_bytes(data_end_offset_buf)
+    };
+
+    data_end_offset.checked_sub(data_start_offset)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Form data offsets are out of order."))
+}
+
+/// Returns the stored byte length of the form at a position of an index read into memory.
+pub fn get_index_data_length(archive: &Archive, index: &IOStructIndex, index_position: usize) -> io::Result<u32> {
+    if let Some(record_lengths) = &archive.record_lengths {
+        return get_stored_record_length(record_lengths, index_position);
+    }
+    let data_start_offset = index.indexes[index_position].data_start_offset;
+    let data_end_offset = match index.indexes.get(index_position + 1) {
+        Some(next_index_item) => next_index_item.data_start_offset,
+        None => archive.bytestart_index - archive.bytestart_data,
+    };
+
+    data_end_offset.checked_sub(data_start_offset)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Form data offsets are out of order."))
+}
+
+fn get_stored_record_length(record_lengths: &[u32], index_position: usize) -> io::Result<u32> {
+    record_lengths.get(index_position).copied()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Record length block is shorter than the index."))
+}
@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::core::structs::*;
+
+/// Counters of a `FormCache`, since it was created or its statistics were reset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FormCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,      // Forms dropped to stay within the byte budget
+    pub invalidations: u64,  // Forms dropped because their archive was written
+    pub entry_count: usize,
+    pub bytes_used: usize,
+}
+
+struct FormCacheEntry {
+    form: Arc<dyn FormTrait>,
+    byte_count: usize,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct FormCacheState {
+    byte_budget: usize,
+    entries: BTreeMap<GlobalID, FormCacheEntry>,
+    recency: BTreeMap<u64, GlobalID>,  // Entries by last use, least recent first
+    tick: u64,
+    stats: FormCacheStats,
+}
+
+/// A cache of decoded forms keyed by `GlobalID`, evicting the least recently used forms to stay
+/// within a byte budget.
+///
+/// Forms are measured with `get_byte_count()`. The cache can be shared by the readers of several
+/// archives, see `ArchiveReader::with_cache`.
+pub struct FormCache {
+    state: Mutex<FormCacheState>,
+}
+
+#[allow(unused)]
+impl FormCache {
+    pub fn new(byte_budget: usize) -> Self {
+        Self { state: Mutex::new(FormCacheState { byte_budget, ..Default::default() }) }
+    }
+
+    pub fn byte_budget(&self) -> usize {
+        self.lock().byte_budget
+    }
+
+    /// Changes the byte budget, evicting forms until the cache fits in it.
+    pub fn set_byte_budget(&self, byte_budget: usize) {
+        let mut state = self.lock();
+        state.byte_budget = byte_budget;
+        state.evict_to_budget();
+    }
+
+    /// Returns a cached form, marking it as the most recently used.
+    pub fn get(&self, global_id: GlobalID) -> Option<Arc<dyn FormTrait>> {
+        let mut state = self.lock();
+        state.tick += 1;
+        let tick = state.tick;
+        let Some(entry) = state.entries.get_mut(&global_id) else {
+            state.stats.misses += 1;
+            return None;
+        };
+        let previous_use = std::mem::replace(&mut entry.last_used, tick);
+        let form = Arc::clone(&entry.form);
+        state.recency.remove(&previous_use);
+        state.recency.insert(tick, global_id);
+        state.stats.hits += 1;
+        Some(form)
+    }
+
+    /// Caches a form, replacing any form cached under the same GlobalID. Forms larger than the
+    /// whole budget are not cached.
+    pub fn insert(&self, global_id: GlobalID, form: Arc<dyn FormTrait>) {
+        let mut state = self.lock();
+        state.remove(global_id);
+        let byte_count = form.get_byte_count();
+        if byte_count > state.byte_budget {
+            return;
+        }
+
+        state.tick += 1;
+        let tick = state.tick;
+        state.entries.insert(global_id, FormCacheEntry { form, byte_count, last_used: tick });
+        state.recency.insert(tick, global_id);
+        state.stats.bytes_used += byte_count;
+        state.stats.entry_count += 1;
+        state.evict_to_budget();
+    }
+
+    /// Drops a cached form, returning whether it was cached.
+    pub fn invalidate(&self, global_id: GlobalID) -> bool {
+        let mut state = self.lock();
+        let removed = state.remove(global_id);
+        if removed {
+            state.stats.invalidations += 1;
+        }
+        removed
+    }
+
+    /// Drops every cached form of an archive, returning how many were cached.
+    pub fn invalidate_archive(&self, archive_id: ArchiveID) -> usize {
+        let mut state = self.lock();
+        let first = GlobalID::from((archive_id, FormID::from(u16::MIN)));
+        let last = GlobalID::from((archive_id, FormID::from(u16::MAX)));
+        let global_ids: Vec<GlobalID> = state.entries.range(first..=last).map(|(global_id, _)| *global_id).collect();
+        for global_id in &global_ids {
+            state.remove(*global_id);
+        }
+        state.stats.invalidations += global_ids.len() as u64;
+        global_ids.len()
+    }
+
+    /// Drops every cached form, keeping the statistics.
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.recency.clear();
+        state.stats.entry_count = 0;
+        state.stats.bytes_used = 0;
+    }
+
+    pub fn stats(&self) -> FormCacheStats {
+        self.lock().stats
+    }
+
+    /// Resets the hit, miss, eviction and invalidation counters.
+    pub fn reset_stats(&self) {
+        let mut state = self.lock();
+        state.stats = FormCacheStats { entry_count: state.stats.entry_count, bytes_used: state.stats.bytes_used, ..Default::default() };
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FormCacheState> {
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+impl FormCacheState {
+    fn remove(&mut self, global_id: GlobalID) -> bool {
+        let Some(entry) = self.entries.remove(&global_id) else {
+            return false;
+        };
+        self.recency.remove(&entry.last_used);
+        self.stats.bytes_used -= entry.byte_count;
+        self.stats.entry_count -= 1;
+        true
+    }
+
+    fn evict_to_budget(&mut self) {
+        while self.stats.bytes_used > self.byte_budget {
+            let Some(&global_id) = self.recency.values().next() else {
+                break;
+            };
+            self.remove(global_id);
+            self.stats.evictions += 1;
+        }
+    }
+}
@@ -1,9 +1,11 @@
-use std::{fs::File, io::{self, Seek}};
+use std::collections::BTreeMap;
+use std::{fs::File, io::{self, Read, Seek}};
 
-use crate::core::io::{binary_search_for_index_item, binary_search_for_index_item_inmem, read_archive_info, read_block_index};
+use crate::core::io::{binary_search_for_index_item_and_position, binary_search_for_index_item_inmem, get_index_data_length, read_archive_info, read_archive_layout_versions, read_block_index, read_form_data_length, read_form_with_schemas, ArchiveLock};
 use crate::core::structs::{forms::*, types::*};
 
 pub fn read_form(file_path: &str, form_id: FormID) -> io::Result<Box<dyn FormTrait>> {
+    let _lock = ArchiveLock::shared(file_path)?;
 
     let archive = read_archive_info(file_path)?;
     if archive.form_count == 0 {
@@ -11,15 +13,17 @@ pub fn read_form(file_path: &str, form_id: FormID) -> io::Result<Box<dyn FormTra
     }
 
     let mut file = File::open(file_path)?;
+    let layout_versions = read_archive_layout_versions(file_path)?;
     
     file.seek(std::io::SeekFrom::Start(archive.bytestart_index as u64))?;
 
-    match binary_search_for_index_item(&mut file, form_id, archive.form_count)? {
-        Some(index_item) => {
+    match binary_search_for_index_item_and_position(&mut file, form_id, archive.form_count)? {
+        Some((index_position, index_item)) => {
+            let data_length = read_form_data_length(&mut file, &archive, index_position, index_item.data_start_offset)?;
             let form_bytepos = index_item.data_start_offset + archive.bytestart_data;
             file.seek(std::io::SeekFrom::Start(form_bytepos as u64))?;
 
-            let read_form = FormBase::read_from_bytes(&mut file)?;
+            let read_form = read_form_record(&mut file, file_path, &layout_versions, data_length)?;
             Ok(read_form)
         }
         None => {
@@ -30,12 +34,14 @@ pub fn read_form(file_path: &str, form_id: FormID) -> io::Result<Box<dyn FormTra
 
 #[allow(unused)]
 pub fn read_forms(file_path: &str, form_ids: Vec<FormID>) -> io::Result<Vec<Box<dyn FormTrait>>> {
+    let _lock = ArchiveLock::shared(file_path)?;
     let archive = read_archive_info(file_path)?;
     if archive.form_count == 0 {
         return Err(io::Error::new(io::ErrorKind::NotFound, "Form not found in archive."));
     }
 
     const SMALL_DATASET_THRESHOLD: usize = 6000;
+    let layout_versions = read_archive_layout_versions(file_path)?;
 
     if form_ids.len() < SMALL_DATASET_THRESHOLD {
         // Data is small - use bin search in io
@@ -45,10 +51,11 @@ pub fn read_forms(file_path: &str, form_ids: Vec<FormID>) -> io::Result<Vec<Box<
         for form_id in form_ids {
             file.seek(std::io::SeekFrom::Start(archive.bytestart_index as u64))?;
 
-            match binary_search_for_index_item(&mut file, form_id, archive.form_count)? {
-                Some(index_item) => {
+            match binary_search_for_index_item_and_position(&mut file, form_id, archive.form_count)? {
+                Some((index_position, index_item)) => {
+                    let data_length = read_form_data_length(&mut file, &archive, index_position, index_item.data_start_offset)?;
                     let form_bytepos = index_item.data_start_offset + archive.bytestart_data;
-                    form_byte_starts.push(form_bytepos);
+                    form_byte_starts.push((form_bytepos, data_length));
                 }
                 None => {
                     return Err(io::Error::new(io::ErrorKind::NotFound, "Form not found in archive."));
@@ -57,9 +64,9 @@ pub fn read_forms(file_path: &str, form_ids: Vec<FormID>) -> io::Result<Vec<Box<
         }
 
         let mut forms = Vec::new();
-        for form_bytepos in form_byte_starts {
+        for (form_bytepos, data_length) in form_byte_starts {
             file.seek(std::io::SeekFrom::Start(form_bytepos as u64))?;
-            let read_form = FormBase::read_from_bytes(&mut file)?;
+            let read_form = read_form_record(&mut file, file_path, &layout_versions, data_length)?;
             forms.push(read_form);
         }
 
@@ -78,9 +85,12 @@ pub fn read_forms(file_path: &str, form_ids: Vec<FormID>) -> io::Result<Vec<Box<
         match binary_search_for_index_item_inmem(form_ids, &index)? {
             Some(form_index_items) => {
                 for form_index_item in form_index_items.indexes {
+                    let index_position = index.indexes.binary_search_by_key(&form_index_item.form_id, |index_item| index_item.form_id)
+                        .map_err(|_| io::Error::new(io::ErrorKind::NotFound, "Form not found in archive."))?;
+                    let data_length = get_index_data_length(&archive, &index, index_position)?;
                     let form_bytepos = form_index_item.data_start_offset + archive.bytestart_data;
                     file.seek(std::io::SeekFrom::Start(form_bytepos as u64))?;
-                    let read_form = FormBase::read_from_bytes(&mut file)?;
+                    let read_form = read_form_record(&mut file, file_path, &layout_versions, data_length)?;
                     forms.push(read_form);
                 }
             }
@@ -94,3 +104,89 @@ pub fn read_forms(file_path: &str, form_ids: Vec<FormID>) -> io::Result<Vec<Box<
 
     
 }
+
+/// Reads the metadata of a form, empty if it has none.
+pub fn read_form_metadata(file_path: &str, form_id: FormID) -> io::Result<FormMetadata> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    Ok(read_form(file_path, form_id)?.form_metadata())
+}
+
+/// Reads every form in the archive, in index order.
+pub fn read_forms_all(file_path: &str) -> io::Result<Vec<Box<dyn FormTrait>>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    let archive = read_archive_info(file_path)?;
+    if archive.form_count == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut file = File::open(file_path)?;
+    file.seek(std::io::SeekFrom::Start(archive.bytestart_index as u64))?;
+    let index = read_block_index(&mut file, archive.form_count)?;
+
+    let layout_versions = read_archive_layout_versions(file_path)?;
+    let mut forms = Vec::with_capacity(index.indexes.len());
+    for (index_position, index_item) in index.indexes.iter().enumerate() {
+        let data_length = get_index_data_length(&archive, &index, index_position)?;
+        let form_bytepos = index_item.data_start_offset + archive.bytestart_data;
+        file.seek(std::io::SeekFrom::Start(form_bytepos as u64))?;
+        forms.push(read_form_record(&mut file, file_path, &layout_versions, data_length)?);
+    }
+
+    Ok(forms)
+}
+
+/// Reads the form data record at the file's position: the form, then its metadata section if the
+/// record runs past the form's bytes.
+pub fn read_form_record(file: &mut File, file_path: &str, layout_versions: &BTreeMap<FormType, u16>, data_length: u32) -> io::Result<Box<dyn FormTrait>> {
+    let record_start = file.stream_position()?;
+    let mut form = read_form_with_schemas(file, file_path, layout_versions)?;
+
+    let form_length = file.stream_position()? - record_start;
+    if form_length < data_length as u64 {
+        let mut metadata_bytes = vec![0u8; (data_length as u64 - form_length) as usize];
+        file.read_exact(&mut metadata_bytes)?;
+        let (metadata, consumed) = FormMetadata::read_from_byte_buffer(&metadata_bytes)?;
+        if consumed != metadata_bytes.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Form {} has trailing bytes after its metadata.", form.form_id().to_string())));
+        }
+        form.set_form_metadata(metadata);
+    }
+
+    Ok(form)
+}
+
+/// Decodes a form data record from its bytes, like `read_form_record`.
+///
+/// Returns `None` when the record has to be read from the file instead: its type is not registered
+/// or is stored in an older layout.
+pub(crate) fn read_form_record_from_buffer(record: &[u8], layout_versions: &BTreeMap<FormType, u16>) -> Option<io::Result<Box<dyn FormTrait>>> {
+    let form_type = FormType::from(*record.get(FormID::BYTE_COUNT)?);
+    let (layout_version, read_from_byte_buffer) = with_form_type_registration(form_type, |registration| (registration.layout_version, registration.read_from_byte_buffer))?;
+    if layout_versions.get(&form_type).copied().unwrap_or(FORM_LAYOUT_VERSION_INITIAL) != layout_version {
+        return None;
+    }
+
+    Some(read_form_record_bytes(record, read_from_byte_buffer))
+}
+
+/// Decodes a form data record stored in the current layout of its type, such as one built with
+/// `get_form_record_bytes`.
+pub(crate) fn read_form_record_current(record: &[u8]) -> io::Result<Box<dyn FormTrait>> {
+    let form_type = FormType::from(*record.get(FormID::BYTE_COUNT).ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for FormType"))?);
+    match get_form_type_readers(form_type) {
+        Some((_, read_from_byte_buffer)) => read_form_record_bytes(record, read_from_byte_buffer),
+        None => Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown FormType {}", form_type.to_u8()))),
+    }
+}
+
+fn read_form_record_bytes(record: &[u8], read_from_byte_buffer: FormReadFromByteBuffer) -> io::Result<Box<dyn FormTrait>> {
+    let (mut form, form_length) = read_from_byte_buffer(record)?;
+    if form_length < record.len() {
+        let (metadata, consumed) = FormMetadata::read_from_byte_buffer(&record[form_length..])?;
+        if form_length + consumed != record.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Form {} has trailing bytes after its metadata.", form.form_id().to_string())));
+        }
+        form.set_form_metadata(metadata);
+    }
+    Ok(form)
+}
@@ -0,0 +1,162 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::rc::Rc;
+
+use crate::core::io::{read_archive_info, read_archive_layout_versions, read_form_record, ArchiveLock};
+use crate::core::structs::*;
+
+/// Byte size of an index item: FormID, form type and data start offset.
+const INDEX_ITEM_BYTE_COUNT: u64 = 7;
+
+/// The open archive shared by an iterator and the entries it yields, read-locked until all are
+/// dropped.
+struct FormSource {
+    _lock: ArchiveLock,
+    file: RefCell<File>,
+    file_path: String,
+    archive: Archive,
+    layout_versions: BTreeMap<FormType, u16>,
+}
+
+/// A form's header as listed by `FormIter`, read without decoding the form.
+pub struct FormEntry {
+    pub form_id: FormID,
+    pub form_type: FormType,
+    pub form_name: StrSml,
+    pub data_start_offset: u32,
+    pub data_length: u32,
+    source: Rc<FormSource>,
+}
+
+#[allow(unused)]
+impl FormEntry {
+    /// Decodes the form, with its metadata, from the archive.
+    pub fn read(&self) -> io::Result<Box<dyn FormTrait>> {
+        let mut file = self.source.file.borrow_mut();
+        file.seek(SeekFrom::Start(self.source.archive.bytestart_data as u64 + self.data_start_offset as u64))?;
+        read_form_record(&mut file, &self.source.file_path, &self.source.layout_versions, self.data_length)
+    }
+}
+
+/// Streams the forms of an archive in index order, reading one index item per step.
+///
+/// Unless forms were appended out of order, see `ArchiveWriteMode::Append`, they are stored in index
+/// order, so the data block is read front to back as well. `iter_forms_in_data_order` reads appended
+/// archives front to back instead. Memory use does not grow with the archive; the archive must not be
+/// written to while it is iterated.
+///
+/// Every index item yields one entry, an error when it cannot be read, so the iterator's length is
+/// the archive's form count.
+pub struct FormIter {
+    source: Rc<FormSource>,
+    position: u16,
+    data_order: Option<Vec<u16>>,
+}
+
+#[allow(unused)]
+impl FormIter {
+    /// Turns the iterator into one that decodes every form.
+    pub fn forms(self) -> impl Iterator<Item = io::Result<Box<dyn FormTrait>>> {
+        self.map(|entry| entry?.read())
+    }
+
+    fn read_entry(&self, position: u16) -> io::Result<FormEntry> {
+        let archive = &self.source.archive;
+        let mut file = self.source.file.borrow_mut();
+
+        // The item and the next item's data offset, which ends this form's record
+        let item_count = if position + 1 < archive.form_count { 2 } else { 1 };
+        let mut items = vec![0u8; (INDEX_ITEM_BYTE_COUNT * item_count) as usize];
+        file.seek(SeekFrom::Start(archive.bytestart_index as u64 + position as u64 * INDEX_ITEM_BYTE_COUNT))?;
+        file.read_exact(&mut items)?;
+
+        let data_start_offset = u32::from_be_bytes(items[3..7].try_into().unwrap());
+        let data_length = match &archive.record_lengths {
+            Some(record_lengths) => *record_lengths.get(position as usize).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "Record length block is shorter than the index.")
+            })?,
+            None => {
+                let data_end_offset = match item_count {
+                    2 => u32::from_be_bytes(items[10..14].try_into().unwrap()),
+                    _ => archive.bytestart_index - archive.bytestart_data,
+                };
+                data_end_offset.checked_sub(data_start_offset).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, format!("Index item {} has a data offset past the next item's.", position))
+                })?
+            },
+        };
+
+        // The name follows the FormID and type at the start of the record
+        file.seek(SeekFrom::Start(archive.bytestart_data as u64 + data_start_offset as u64 + (FormID::BYTE_COUNT + FormType::BYTE_COUNT) as u64))?;
+        let form_name = StrSml::read_from_bytes(&mut file)?;
+
+        Ok(FormEntry {
+            form_id: FormID::from([items[0], items[1]]),
+            form_type: FormType::from(items[2]),
+            form_name,
+            data_start_offset,
+            data_length,
+            source: Rc::clone(&self.source),
+        })
+    }
+}
+
+impl Iterator for FormIter {
+    type Item = io::Result<FormEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.source.archive.form_count {
+            return None;
+        }
+        let index_position = match &self.data_order {
+            Some(data_order) => data_order[self.position as usize],
+            None => self.position,
+        };
+        self.position += 1;
+        Some(self.read_entry(index_position))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.source.archive.form_count - self.position) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for FormIter {}
+
+/// Opens an archive for streaming its forms, see `FormIter`.
+pub fn iter_forms(file_path: &str) -> io::Result<FormIter> {
+    Ok(FormIter { source: Rc::new(open_form_source(file_path)?), position: 0, data_order: None })
+}
+
+/// Opens an archive for streaming its forms in the order of their records in the data block.
+///
+/// The index is read once to sort its items by data offset, so appended archives are read front to
+/// back; memory use grows by two bytes per form.
+pub fn iter_forms_in_data_order(file_path: &str) -> io::Result<FormIter> {
+    let source = open_form_source(file_path)?;
+    let mut index = vec![0u8; (INDEX_ITEM_BYTE_COUNT * source.archive.form_count as u64) as usize];
+    {
+        let mut file = source.file.borrow_mut();
+        file.seek(SeekFrom::Start(source.archive.bytestart_index as u64))?;
+        file.read_exact(&mut index)?;
+    }
+    let mut data_order: Vec<u16> = (0..source.archive.form_count).collect();
+    data_order.sort_by_key(|position| {
+        let item = &index[*position as usize * INDEX_ITEM_BYTE_COUNT as usize..];
+        u32::from_be_bytes(item[3..7].try_into().unwrap())
+    });
+    Ok(FormIter { source: Rc::new(source), position: 0, data_order: Some(data_order) })
+}
+
+fn open_form_source(file_path: &str) -> io::Result<FormSource> {
+    Ok(FormSource {
+        _lock: ArchiveLock::shared(file_path)?,
+        file: RefCell::new(File::open(file_path)?),
+        file_path: file_path.to_string(),
+        archive: read_archive_info(file_path)?,
+        layout_versions: read_archive_layout_versions(file_path)?,
+    })
+}
@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Seek, SeekFrom};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread;
+
+use crate::core::io::{delete_form, write_form, FormCache, get_index_data_length, read_archive_info, read_archive_layout_versions, read_block_index, read_form_record, read_form_record_from_buffer, lock_archive, ArchiveReaderLock, IOStructIndex};
+use crate::core::structs::*;
+
+/// A read-only archive that threads can share.
+///
+/// The header, index and layout versions are read once on open. Forms are read with positional
+/// reads, so no thread moves a cursor another relies on. The archive stays read-locked while the
+/// reader is open, so it can only be written through the reader's own `write_form` and
+/// `delete_form`. Other writes wait for the reader, or fail at once on the thread that opened it.
+pub struct ArchiveReader {
+    lock: Option<ArchiveReaderLock>,
+    file: File,
+    file_path: String,
+    archive: Archive,
+    index: IOStructIndex,
+    layout_versions: BTreeMap<FormType, u16>,
+    cache: Option<Arc<FormCache>>,
+}
+
+#[allow(unused)]
+impl ArchiveReader {
+    pub fn open(file_path: &str) -> io::Result<Self> {
+        let lock = Some(lock_archive(file_path)?);
+        let archive = read_archive_info(file_path)?;
+        let mut file = File::open(file_path)?;
+        file.seek(SeekFrom::Start(archive.bytestart_index as u64))?;
+        let index = read_block_index(&mut file, archive.form_count)?;
+        let layout_versions = read_archive_layout_versions(file_path)?;
+
+        Ok(Self { lock, file, file_path: file_path.to_string(), archive, index, layout_versions, cache: None })
+    }
+
+    /// Caches the forms `read_form_cached` decodes. The cache can be shared with readers of other
+    /// archives, forms are keyed by their GlobalID.
+    pub fn with_cache(mut self, cache: Arc<FormCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn cache(&self) -> Option<&Arc<FormCache>> {
+        self.cache.as_ref()
+    }
+
+    pub fn archive(&self) -> &Archive {
+        &self.archive
+    }
+
+    pub fn file_path(&self) -> &str {
+        &self.file_path
+    }
+
+    /// The FormIDs in the archive, in ascending order.
+    pub fn form_ids(&self) -> Vec<FormID> {
+        self.index.indexes.iter().map(|item| item.form_id).collect()
+    }
+
+    pub fn contains(&self, form_id: FormID) -> bool {
+        self.get_index_position(form_id).is_ok()
+    }
+
+    /// Reads a form's data record as stored: the form's bytes followed by its metadata section.
+    pub fn read_form_bytes(&self, form_id: FormID) -> io::Result<Vec<u8>> {
+        let index_position = self.get_index_position(form_id)?;
+        self.read_record(index_position)
+    }
+
+    pub fn read_form(&self, form_id: FormID) -> io::Result<Box<dyn FormTrait>> {
+        let index_position = self.get_index_position(form_id)?;
+        self.read_form_at(index_position, &mut None)
+    }
+
+    /// Reads a form through the reader's cache, decoding and caching it on a miss. Without a cache
+    /// this reads the form like `read_form`.
+    pub fn read_form_cached(&self, form_id: FormID) -> io::Result<Arc<dyn FormTrait>> {
+        let Some(cache) = &self.cache else {
+            return self.read_form(form_id).map(Arc::from);
+        };
+        let global_id = GlobalID::from((self.archive.archive_id, form_id));
+        if let Some(form) = cache.get(global_id) {
+            return Ok(form);
+        }
+
+        let form: Arc<dyn FormTrait> = Arc::from(self.read_form(form_id)?);
+        cache.insert(global_id, Arc::clone(&form));
+        Ok(form)
+    }
+
+    /// Writes a form to the archive, see `write_form`, and reopens it. The archive's cached forms
+    /// are dropped.
+    pub fn write_form(&mut self, form: &dyn FormTrait) -> io::Result<()> {
+        self.lock = None;
+        let result = write_form(&self.file_path, form);
+        self.reload()?;
+        result
+    }
+
+    /// Deletes a form from the archive, see `delete_form`, and reopens it. The archive's cached forms
+    /// are dropped.
+    pub fn delete_form(&mut self, form_id: FormID) -> io::Result<()> {
+        self.lock = None;
+        let result = delete_form(&self.file_path, form_id);
+        self.reload()?;
+        result
+    }
+
+    /// Reads forms in the order requested, on the calling thread.
+    pub fn read_forms(&self, form_ids: &[FormID]) -> io::Result<Vec<Box<dyn FormTrait>>> {
+        let mut fallback_file = None;
+        form_ids
+            .iter()
+            .map(|form_id| self.read_form_at(self.get_index_position(*form_id)?, &mut fallback_file))
+            .collect()
+    }
+
+    /// Reads forms in the order requested, decoding them on `thread_count` threads of the read pool,
+    /// or on all of them when 0. The calling thread decodes forms too.
+    ///
+    /// Fails with the error of the first form in request order that could not be read.
+    pub fn read_forms_parallel(self: &Arc<Self>, form_ids: &[FormID], thread_count: usize) -> io::Result<Vec<Box<dyn FormTrait>>> {
+        let read_pool = get_read_pool();
+        let thread_count = match thread_count {
+            0 => read_pool.thread_count,
+            count => count.min(read_pool.thread_count),
+        }
+        .min(form_ids.len());
+        if thread_count <= 1 {
+            return self.read_forms(form_ids);
+        }
+
+        // Pool threads and the calling thread take the next requested form until all are read or one fails
+        let batch = Arc::new(ParallelRead { form_ids: form_ids.to_vec(), next_request: AtomicUsize::new(0), failed: AtomicBool::new(false) });
+        let (result_sender, result_receiver) = mpsc::channel();
+        for _ in 1..thread_count {
+            let reader = Arc::clone(self);
+            let batch = Arc::clone(&batch);
+            let result_sender = result_sender.clone();
+            read_pool.submit(Box::new(move || {
+                let results = batch.read_forms(&reader);
+                // The reader must be released before the caller sees the last results
+                drop(reader);
+                let _ = result_sender.send(results);
+            }));
+        }
+        drop(result_sender);
+        let mut worker_results = vec![batch.read_forms(self)];
+        worker_results.extend(result_receiver.iter());
+
+        // Put the forms back in request order
+        let mut forms: Vec<Option<io::Result<Box<dyn FormTrait>>>> = (0..form_ids.len()).map(|_| None).collect();
+        for (request_position, result) in worker_results.into_iter().flatten() {
+            forms[request_position] = Some(result);
+        }
+
+        // Requests are taken in order, so only those after a failed one can be unread
+        forms.into_iter().map_while(|form| form).collect()
+    }
+
+    /// Rereads the archive after it was written, even partly, keeping the cache without the
+    /// archive's forms.
+    fn reload(&mut self) -> io::Result<()> {
+        if let Some(cache) = &self.cache {
+            cache.invalidate_archive(self.archive.archive_id);
+        }
+        let reopened = Self::open(&self.file_path)?;
+        *self = Self { cache: self.cache.take(), ..reopened };
+        Ok(())
+    }
+
+    fn get_index_position(&self, form_id: FormID) -> io::Result<usize> {
+        self.index
+            .indexes
+            .binary_search_by_key(&form_id, |item| item.form_id)
+            .map_err(|_| io::Error::new(io::ErrorKind::NotFound, "Form not found in archive."))
+    }
+
+    fn read_record(&self, index_position: usize) -> io::Result<Vec<u8>> {
+        let data_length = get_index_data_length(&self.archive, &self.index, index_position)?;
+        let mut record = vec![0u8; data_length as usize];
+        read_exact_at(&self.file, &mut record, self.get_record_start(index_position))?;
+        Ok(record)
+    }
+
+    fn get_record_start(&self, index_position: usize) -> u64 {
+        (self.archive.bytestart_data + self.index.indexes[index_position].data_start_offset) as u64
+    }
+
+    /// Decodes the record from its bytes. Records that need the file, older layouts and types only
+    /// the archive's schemas describe, are read through `fallback_file`, opened on first use.
+    fn read_form_at(&self, index_position: usize, fallback_file: &mut Option<File>) -> io::Result<Box<dyn FormTrait>> {
+        let record = self.read_record(index_position)?;
+        if let Some(form) = read_form_record_from_buffer(&record, &self.layout_versions) {
+            return form;
+        }
+
+        let file = match fallback_file {
+            Some(file) => file,
+            None => fallback_file.insert(File::open(&self.file_path)?),
+        };
+        file.seek(SeekFrom::Start(self.get_record_start(index_position)))?;
+        read_form_record(file, &self.file_path, &self.layout_versions, record.len() as u32)
+    }
+}
+
+/// Reads forms in the order requested, decoding them on `thread_count` threads of the read pool, or
+/// on all of them when 0.
+pub fn read_forms_parallel(file_path: &str, form_ids: Vec<FormID>, thread_count: usize) -> io::Result<Vec<Box<dyn FormTrait>>> {
+    Arc::new(ArchiveReader::open(file_path)?).read_forms_parallel(&form_ids, thread_count)
+}
+
+/// A batch of forms read by `read_forms_parallel`, shared by the threads reading it.
+struct ParallelRead {
+    form_ids: Vec<FormID>,
+    next_request: AtomicUsize,
+    failed: AtomicBool,
+}
+
+impl ParallelRead {
+    /// Reads the next requested forms until none are left or one failed, returning them with their
+    /// request positions.
+    fn read_forms(&self, reader: &ArchiveReader) -> Vec<(usize, io::Result<Box<dyn FormTrait>>)> {
+        let mut fallback_file = None;
+        let mut results = Vec::new();
+        while !self.failed.load(Ordering::Relaxed) {
+            let request_position = self.next_request.fetch_add(1, Ordering::Relaxed);
+            let Some(form_id) = self.form_ids.get(request_position) else {
+                break;
+            };
+            let result = reader.get_index_position(*form_id).and_then(|index_position| reader.read_form_at(index_position, &mut fallback_file));
+            if result.is_err() {
+                self.failed.store(true, Ordering::Relaxed);
+            }
+            results.push((request_position, result));
+        }
+        results
+    }
+}
+
+type PoolJob = Box<dyn FnOnce() + Send>;
+
+/// The threads `read_forms_parallel` decodes forms on, one per available core, started on first use
+/// and kept for the whole process.
+struct ReadPool {
+    sender: mpsc::Sender<PoolJob>,
+    thread_count: usize,
+}
+
+impl ReadPool {
+    fn submit(&self, job: PoolJob) {
+        // The pool threads never stop, so the channel stays open
+        let _ = self.sender.send(job);
+    }
+}
+
+fn get_read_pool() -> &'static ReadPool {
+    static READ_POOL: OnceLock<ReadPool> = OnceLock::new();
+    READ_POOL.get_or_init(|| {
+        let thread_count = thread::available_parallelism().map(|count| count.get()).unwrap_or(1);
+        let (sender, receiver) = mpsc::channel::<PoolJob>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..thread_count {
+            let receiver = Arc::clone(&receiver);
+            thread::spawn(move || loop {
+                let job = match receiver.lock() {
+                    Ok(receiver) => receiver.recv(),
+                    Err(poisoned) => poisoned.into_inner().recv(),
+                };
+                match job {
+                    Ok(job) => job(),
+                    Err(_) => return,
+                }
+            });
+        }
+        ReadPool { sender, thread_count }
+    })
+}
+
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+
+    // `seek_read` moves the file's cursor, which the reader never relies on
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset) {
+            Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for form record")),
+            Ok(read) => {
+                buf = &mut buf[read..];
+                offset += read as u64;
+            },
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
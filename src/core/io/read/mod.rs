@@ -1,7 +1,15 @@
 pub mod io_read_archive;
 pub mod io_read_block;
 pub mod io_read_form;
+pub mod io_read_iter;
+pub mod io_read_shared;
+pub mod io_read_cache;
+pub mod io_read_async;
 
 pub use io_read_block::*;
 pub use io_read_form::*;
-pub use io_read_archive::*;
\ No newline at end of file
+pub use io_read_archive::*;
+pub use io_read_iter::*;
+pub use io_read_shared::*;
+pub use io_read_cache::*;
+pub use io_read_async::*;
\ No newline at end of file
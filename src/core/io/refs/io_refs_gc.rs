@@ -0,0 +1,106 @@
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::io;
+
+use serde_json::{json, Value};
+
+use crate::core::io::{delete_forms, ArchiveTransaction, ReferenceIndex, ArchiveLock, ArchiveLockMode};
+use crate::core::structs::*;
+
+/// Outcome of a reachability analysis over an archive set.
+#[derive(Debug, Clone, Default)]
+pub struct GarbageReport {
+    /// Forms reachable from the roots, roots included.
+    pub reachable: Vec<GlobalID>,
+    /// Forms nothing reachable references.
+    pub unreachable: Vec<GlobalID>,
+    /// Number of unreachable forms removed, 0 when only reporting.
+    pub removed: usize,
+}
+
+#[allow(unused)]
+impl GarbageReport {
+    /// Returns the report as JSON, with `GlobalID`s as strings.
+    pub fn to_dict(&self) -> Value {
+        json!({
+            "reachable": self.reachable.iter().map(|form| form.to_string()).collect::<Vec<String>>(),
+            "unreachable": self.unreachable.iter().map(|form| form.to_string()).collect::<Vec<String>>(),
+            "removed": self.removed,
+        })
+    }
+}
+
+/// Finds every form in `archive_paths` that cannot be reached from `roots` by following references.
+///
+/// Every root must be a form in the set, so a mistyped root cannot mark everything unreachable.
+pub fn find_unreachable_forms(archive_paths: &[&str], roots: &[GlobalID]) -> io::Result<GarbageReport> {
+    let _locks = ArchiveLock::acquire_all(archive_paths, ArchiveLockMode::Shared)?;
+    let index = ReferenceIndex::build(archive_paths)?;
+    find_unreachable_in_index(&index, roots)
+}
+
+/// Finds every form unreachable from `roots` and removes them, rewriting each touched archive once.
+/// All archive edits are rolled back if any step fails.
+pub fn collect_garbage(archive_paths: &[&str], roots: &[GlobalID]) -> io::Result<GarbageReport> {
+    let _locks = ArchiveLock::acquire_all(archive_paths, ArchiveLockMode::Exclusive)?;
+    let index = ReferenceIndex::build(archive_paths)?;
+    let mut report = find_unreachable_in_index(&index, roots)?;
+    if report.unreachable.is_empty() {
+        return Ok(report);
+    }
+
+    // Group the unreachable forms by the archive holding them
+    let mut removals: Vec<(&str, Vec<FormID>)> = Vec::new();
+    for tracked in index.get_archives() {
+        let form_ids: Vec<FormID> = report.unreachable.iter()
+            .filter(|form| ArchiveID::from(*form) == tracked.archive_id)
+            .map(FormID::from)
+            .collect();
+        if !form_ids.is_empty() {
+            removals.push((tracked.file_path.as_str(), form_ids));
+        }
+    }
+
+    let touched_paths: Vec<&str> = removals.iter().map(|(path, _)| *path).collect();
+    report.removed = ArchiveTransaction::run(&touched_paths, || {
+        let mut removed = 0;
+        for (path, form_ids) in &removals {
+            removed += delete_forms(path, form_ids)?;
+        }
+        Ok(removed)
+    })?;
+
+    Ok(report)
+}
+
+/// Walks the references of an index from `roots`, splitting its forms into reachable and unreachable.
+fn find_unreachable_in_index(index: &ReferenceIndex, roots: &[GlobalID]) -> io::Result<GarbageReport> {
+    let forms = index.get_forms();
+    let known: HashSet<GlobalID> = forms.iter().copied().collect();
+
+    if roots.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "At least one root form is required."));
+    }
+    for root in roots {
+        if !known.contains(root) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("Root form {} not found in the archive set.", root)));
+        }
+    }
+
+    // Breadth-first walk, references to forms outside the set are skipped
+    let mut reachable: BTreeSet<GlobalID> = roots.iter().copied().collect();
+    let mut queue: VecDeque<GlobalID> = roots.iter().copied().collect();
+    while let Some(form) = queue.pop_front() {
+        for reference in index.get_references(form) {
+            if known.contains(&reference) && reachable.insert(reference) {
+                queue.push_back(reference);
+            }
+        }
+    }
+
+    let unreachable = forms.into_iter().filter(|form| !reachable.contains(form)).collect();
+    Ok(GarbageReport {
+        reachable: reachable.into_iter().collect(),
+        unreachable,
+        removed: 0,
+    })
+}
@@ -0,0 +1,256 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+
+use crate::core::io::{delete_form, read_archive_generation, read_archive_info, read_form, read_forms_all, write_form, ArchiveTransaction, DeleteMode, DeleteReport};
+use crate::core::structs::*;
+
+/// An archive tracked by a `ReferenceIndex`, with the content generation it was last scanned at.
+#[derive(Debug, Clone)]
+pub struct ReferenceIndexArchive {
+    pub file_path: String,
+    pub archive_id: ArchiveID,
+    pub generation: u64,
+}
+
+/// Reverse-reference index over a set of archives, answering "which forms reference this `GlobalID`?".
+///
+/// Its `write_form` and `delete_form` keep it current as they edit. Forms written any other way, by
+/// this process or another, are picked up by `refresh`, which rescans the archives whose content
+/// generation changed, see `read_archive_generation`.
+#[derive(Debug, Default)]
+pub struct ReferenceIndex {
+    archives: Vec<ReferenceIndexArchive>,
+    referrers: HashMap<GlobalID, BTreeSet<GlobalID>>,
+    references: HashMap<GlobalID, Vec<GlobalID>>,
+}
+
+#[allow(unused)]
+impl ReferenceIndex {
+    /// Creates an empty index that tracks no archives.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds an index over every form in the given archives.
+    pub fn build(file_paths: &[&str]) -> io::Result<Self> {
+        let mut index = Self::new();
+        for file_path in file_paths {
+            index.add_archive(file_path)?;
+        }
+        Ok(index)
+    }
+
+    /// Adds an archive to the index, rescanning it if it is already tracked.
+    ///
+    /// Fails with `AlreadyExists` if another tracked archive has the same ArchiveID.
+    pub fn add_archive(&mut self, file_path: &str) -> io::Result<()> {
+        let archive = read_archive_info(file_path)?;
+        let generation = read_archive_generation(file_path)?;
+        if let Some(tracked) = self.archives.iter().find(|tracked| tracked.file_path != file_path && tracked.archive_id == archive.archive_id) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("Archive {} is already tracked as {}.", archive.archive_id.to_string(), tracked.file_path),
+            ));
+        }
+
+        // Drop anything previously indexed for this path
+        if let Some(position) = self.archives.iter().position(|tracked| tracked.file_path == file_path) {
+            let stale = self.archives.remove(position);
+            self.remove_archive_forms(stale.archive_id);
+        }
+
+        for form in read_forms_all(file_path)? {
+            self.update_form(archive.archive_id, form.as_ref());
+        }
+
+        self.archives.push(ReferenceIndexArchive {
+            file_path: file_path.to_string(),
+            archive_id: archive.archive_id,
+            generation,
+        });
+
+        Ok(())
+    }
+
+    /// Rescans any tracked archive whose forms or ArchiveID changed since it was last indexed.
+    pub fn refresh(&mut self) -> io::Result<()> {
+        let mut changed_paths = Vec::new();
+        for tracked in &self.archives {
+            let generation = read_archive_generation(&tracked.file_path)?;
+            if generation != tracked.generation || read_archive_info(&tracked.file_path)?.archive_id != tracked.archive_id {
+                changed_paths.push(tracked.file_path.clone());
+            }
+        }
+
+        for file_path in changed_paths {
+            self.add_archive(&file_path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the archives tracked by this index.
+    pub fn get_archives(&self) -> &[ReferenceIndexArchive] {
+        &self.archives
+    }
+
+    /// Returns every indexed form, sorted by `GlobalID`.
+    pub fn get_forms(&self) -> Vec<GlobalID> {
+        let mut forms: Vec<GlobalID> = self.references.keys().copied().collect();
+        forms.sort();
+        forms
+    }
+
+    /// Returns every form that references `target`, sorted by `GlobalID`.
+    pub fn get_referrers(&self, target: GlobalID) -> Vec<GlobalID> {
+        match self.referrers.get(&target) {
+            Some(referrers) => referrers.iter().copied().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns every `GlobalID` referenced by `referrer`.
+    pub fn get_references(&self, referrer: GlobalID) -> Vec<GlobalID> {
+        self.references.get(&referrer).cloned().unwrap_or_default()
+    }
+
+    /// Returns whether any indexed form references `target`.
+    pub fn is_referenced(&self, target: GlobalID) -> bool {
+        self.referrers.get(&target).is_some_and(|referrers| !referrers.is_empty())
+    }
+
+    /// Records the references of a form, replacing whatever was indexed for it before.
+    pub fn update_form(&mut self, archive_id: ArchiveID, form: &dyn FormTrait) {
+        let referrer = GlobalID::from((archive_id, form.form_id()));
+        self.remove_form(referrer);
+
+        let mut references = form.get_references();
+        references.retain(|reference| !reference.is_null());
+        references.sort();
+        references.dedup();
+
+        for target in &references {
+            self.referrers.entry(*target).or_default().insert(referrer);
+        }
+        self.references.insert(referrer, references);
+    }
+
+    /// Forgets the references of a form.
+    pub fn remove_form(&mut self, referrer: GlobalID) {
+        if let Some(old_references) = self.references.remove(&referrer) {
+            for target in old_references {
+                if let Some(referrers) = self.referrers.get_mut(&target) {
+                    referrers.remove(&referrer);
+                    if referrers.is_empty() {
+                        self.referrers.remove(&target);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Writes a form to an archive and updates the index to match.
+    pub fn write_form(&mut self, file_path: &str, form: &dyn FormTrait) -> io::Result<()> {
+        write_form(file_path, form)?;
+        let archive_id = self.get_tracked_archive_id(file_path)?;
+        self.update_form(archive_id, form);
+        self.mark_scanned(file_path)
+    }
+
+    /// Deletes a form from an archive and updates the index to match.
+    pub fn delete_form(&mut self, file_path: &str, form_id: FormID) -> io::Result<()> {
+        delete_form(file_path, form_id)?;
+        let archive_id = self.get_tracked_archive_id(file_path)?;
+        self.remove_form(GlobalID::from((archive_id, form_id)));
+        self.mark_scanned(file_path)
+    }
+
+    /// Deletes a form after checking which tracked forms reference it.
+    ///
+    /// `Restrict` refuses and reports the referrers, `Cascade` strips the references from every
+    /// referrer before deleting. Every touched archive is restored if any step fails.
+    pub fn delete_form_checked(&mut self, file_path: &str, form_id: FormID, mode: DeleteMode) -> io::Result<DeleteReport> {
+        let archive_id = self.get_tracked_archive_id(file_path)?;
+        let target = GlobalID::from((archive_id, form_id));
+        let referrers: Vec<GlobalID> = self.get_referrers(target)
+            .into_iter()
+            .filter(|referrer| *referrer != target)
+            .collect();
+
+        if mode == DeleteMode::Restrict && !referrers.is_empty() {
+            return Ok(DeleteReport { deleted: false, referrers });
+        }
+        if mode != DeleteMode::Cascade || referrers.is_empty() {
+            self.delete_form(file_path, form_id)?;
+            return Ok(DeleteReport { deleted: true, referrers });
+        }
+
+        // Every referrer must live in a tracked archive for the cascade to edit it
+        let mut referrer_paths = Vec::with_capacity(referrers.len());
+        for referrer in &referrers {
+            let referrer_archive_id = ArchiveID::from(referrer);
+            match self.archives.iter().find(|tracked| tracked.archive_id == referrer_archive_id) {
+                Some(tracked) => referrer_paths.push(tracked.file_path.clone()),
+                None => return Err(io::Error::new(io::ErrorKind::NotFound, format!("Archive {} is not tracked.", referrer_archive_id.to_string()))),
+            }
+        }
+
+        let mut touched_paths: Vec<&str> = referrer_paths.iter().map(|path| path.as_str()).collect();
+        touched_paths.push(file_path);
+
+        let transaction = ArchiveTransaction::begin(&touched_paths)?;
+        let result = self.cascade_delete(file_path, form_id, target, &referrers, &referrer_paths);
+        match result {
+            Ok(()) => transaction.commit()?,
+            Err(e) => {
+                transaction.rollback()?;
+                // The in-memory index may be half updated, rescan what was restored
+                for path in &touched_paths {
+                    self.add_archive(path)?;
+                }
+                return Err(e);
+            }
+        }
+
+        Ok(DeleteReport { deleted: true, referrers })
+    }
+
+    /// Strips `target` from every referrer, then deletes it.
+    fn cascade_delete(&mut self, file_path: &str, form_id: FormID, target: GlobalID, referrers: &[GlobalID], referrer_paths: &[String]) -> io::Result<()> {
+        for (referrer, referrer_path) in referrers.iter().zip(referrer_paths) {
+            let mut form = read_form(referrer_path, FormID::from(referrer))?;
+            form.remove_references(target);
+            self.write_form(referrer_path, form.as_ref())?;
+        }
+        self.delete_form(file_path, form_id)
+    }
+
+    /// Returns the archive ID of a tracked path, adding the archive to the index if it is not tracked yet.
+    fn get_tracked_archive_id(&mut self, file_path: &str) -> io::Result<ArchiveID> {
+        if let Some(tracked) = self.archives.iter().find(|tracked| tracked.file_path == file_path) {
+            return Ok(tracked.archive_id);
+        }
+        self.add_archive(file_path)?;
+        Ok(read_archive_info(file_path)?.archive_id)
+    }
+
+    /// Stores the current generation of a tracked archive so `refresh` does not rescan our own edits.
+    fn mark_scanned(&mut self, file_path: &str) -> io::Result<()> {
+        let generation = read_archive_generation(file_path)?;
+        if let Some(tracked) = self.archives.iter_mut().find(|tracked| tracked.file_path == file_path) {
+            tracked.generation = generation;
+        }
+        Ok(())
+    }
+
+    /// Forgets every form belonging to an archive.
+    fn remove_archive_forms(&mut self, archive_id: ArchiveID) {
+        let referrers: Vec<GlobalID> = self.references.keys()
+            .filter(|referrer| ArchiveID::from(*referrer) == archive_id)
+            .copied()
+            .collect();
+        for referrer in referrers {
+            self.remove_form(referrer);
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io;
+
+use serde_json::{json, Value};
+
+use crate::core::io::{delete_form, read_archive_info, read_form, read_form_ids, write_archive_info, write_form, ArchiveTransaction, ReferenceIndex, ArchiveLock, ArchiveLockMode};
+use crate::core::structs::*;
+
+/// Outcome of a renumbering or archive remap.
+#[derive(Debug, Clone, Default)]
+pub struct RemapReport {
+    /// Every form that changed ID, as (old, new) pairs sorted by the old ID.
+    pub mappings: Vec<(GlobalID, GlobalID)>,
+    /// Forms whose references were rewritten, by their ID after the remap.
+    pub updated_forms: Vec<GlobalID>,
+}
+
+#[allow(unused)]
+impl RemapReport {
+    /// Returns the report as JSON, with `GlobalID`s as strings.
+    pub fn to_dict(&self) -> Value {
+        json!({
+            "mappings": self.mappings.iter()
+                .map(|(old, new)| json!({ "old": old.to_string(), "new": new.to_string() }))
+                .collect::<Vec<Value>>(),
+            "updated_forms": self.updated_forms.iter()
+                .map(|form| form.to_string())
+                .collect::<Vec<String>>(),
+        })
+    }
+}
+
+/// Gives forms in `file_path` new FormIDs and rewrites every reference to them across `archive_paths`.
+///
+/// Each pair is (old, new). A new FormID may only be in use if that form is itself being moved.
+/// All archive edits are rolled back if any step fails.
+pub fn renumber_forms(archive_paths: &[&str], file_path: &str, renumbering: &[(FormID, FormID)]) -> io::Result<RemapReport> {
+    let _locks = ArchiveLock::acquire_all(&[archive_paths, &[file_path]].concat(), ArchiveLockMode::Exclusive)?;
+    let paths = get_archive_set(archive_paths, file_path);
+    let archive_id = read_archive_info(file_path)?.archive_id;
+    let existing: HashSet<FormID> = read_form_ids(file_path)?.into_iter().collect();
+
+    // Validate the renumbering against the forms in the archive
+    let mut moved = HashSet::new();
+    let mut taken = HashSet::new();
+    for (old_id, new_id) in renumbering {
+        if !existing.contains(old_id) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("Form {} not found.", old_id.to_string())));
+        }
+        if new_id.to_u16() == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "FormID 00000 is reserved for null references."));
+        }
+        if !moved.insert(*old_id) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Form {} is renumbered twice.", old_id.to_string())));
+        }
+        if !taken.insert(*new_id) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("FormID {} is assigned twice.", new_id.to_string())));
+        }
+    }
+    for new_id in &taken {
+        if existing.contains(new_id) && !moved.contains(new_id) {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("FormID {} is already in use.", new_id.to_string())));
+        }
+    }
+
+    let mapping: HashMap<GlobalID, GlobalID> = renumbering.iter()
+        .filter(|(old_id, new_id)| old_id != new_id)
+        .map(|(old_id, new_id)| (GlobalID::from((archive_id, *old_id)), GlobalID::from((archive_id, *new_id))))
+        .collect();
+    if mapping.is_empty() {
+        return Ok(RemapReport::default());
+    }
+
+    let index = ReferenceIndex::build(&paths)?;
+    let referrers: Vec<GlobalID> = collect_referrers(&index, mapping.keys())
+        .into_iter()
+        .filter(|referrer| !mapping.contains_key(referrer))
+        .collect();
+    let referrer_paths = get_referrer_paths(&index, &referrers)?;
+
+    let mut touched_paths: Vec<&str> = referrer_paths.iter().map(|path| path.as_str()).collect();
+    touched_paths.push(file_path);
+
+    let remap = |reference: GlobalID| mapping.get(&reference).copied();
+    ArchiveTransaction::run(&touched_paths, || {
+        // Take every moved form out first so swapped IDs never collide
+        let mut forms = Vec::with_capacity(mapping.len());
+        for old_id in mapping.keys() {
+            forms.push(read_form(file_path, FormID::from(old_id))?);
+            delete_form(file_path, FormID::from(old_id))?;
+        }
+        for mut form in forms {
+            let new_id = mapping[&GlobalID::from((archive_id, form.form_id()))];
+            form.set_form_id(FormID::from(&new_id));
+            form.remap_references(&remap);
+            write_form(file_path, form.as_ref())?;
+        }
+
+        rewrite_referrers(&referrers, &referrer_paths, &remap)
+    })?;
+
+    let mut mappings: Vec<(GlobalID, GlobalID)> = mapping.into_iter().collect();
+    mappings.sort();
+    Ok(RemapReport { mappings, updated_forms: referrers })
+}
+
+/// Moves the archive at `file_path` to a new ArchiveID and rewrites every reference into it across `archive_paths`.
+///
+/// Fails if another archive in the set already uses the new ID. All archive edits are rolled back if any step fails.
+pub fn remap_archive_id(archive_paths: &[&str], file_path: &str, new_archive_id: ArchiveID) -> io::Result<RemapReport> {
+    let _locks = ArchiveLock::acquire_all(&[archive_paths, &[file_path]].concat(), ArchiveLockMode::Exclusive)?;
+    let paths = get_archive_set(archive_paths, file_path);
+    let archive = read_archive_info(file_path)?;
+    let old_archive_id = archive.archive_id;
+    if old_archive_id == new_archive_id {
+        return Ok(RemapReport::default());
+    }
+
+    for path in &paths {
+        if *path != file_path && read_archive_info(path)?.archive_id == new_archive_id {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("Archive {} is already used by {}.", new_archive_id.to_string(), path)));
+        }
+    }
+
+    let mappings: Vec<(GlobalID, GlobalID)> = read_form_ids(file_path)?.into_iter()
+        .map(|form_id| (GlobalID::from((old_archive_id, form_id)), GlobalID::from((new_archive_id, form_id))))
+        .collect();
+
+    let index = ReferenceIndex::build(&paths)?;
+    let referrers = collect_referrers(&index, mappings.iter().map(|(old, _)| old));
+    let referrer_paths = get_referrer_paths(&index, &referrers)?;
+
+    let mut touched_paths: Vec<&str> = referrer_paths.iter().map(|path| path.as_str()).collect();
+    touched_paths.push(file_path);
+
+    // Null references (FormID 0) stay null, even when moving archive 0
+    let remap = |reference: GlobalID| {
+        if ArchiveID::from(&reference) == old_archive_id && FormID::from(&reference) != FormID::from(0) {
+            Some(GlobalID::from((new_archive_id, FormID::from(&reference))))
+        } else {
+            None
+        }
+    };
+    ArchiveTransaction::run(&touched_paths, || {
+        rewrite_referrers(&referrers, &referrer_paths, &remap)?;
+
+        let mut new_archive = Archive::new(new_archive_id, archive.version, archive.description.clone());
+        new_archive.form_count = archive.form_count;
+        write_archive_info(file_path, &new_archive)
+    })?;
+
+    // Report the referrers by their IDs after the move
+    let updated_forms = referrers.into_iter()
+        .map(|referrer| remap(referrer).unwrap_or(referrer))
+        .collect();
+    Ok(RemapReport { mappings, updated_forms })
+}
+
+/// Returns the archive set with `file_path` included.
+fn get_archive_set<'a>(archive_paths: &[&'a str], file_path: &'a str) -> Vec<&'a str> {
+    let mut paths: Vec<&str> = archive_paths.to_vec();
+    if !paths.contains(&file_path) {
+        paths.push(file_path);
+    }
+    paths
+}
+
+/// Returns every form referencing any of `targets`, sorted and without duplicates.
+fn collect_referrers<'a>(index: &ReferenceIndex, targets: impl Iterator<Item = &'a GlobalID>) -> Vec<GlobalID> {
+    let mut referrers = BTreeSet::new();
+    for target in targets {
+        referrers.extend(index.get_referrers(*target));
+    }
+    referrers.into_iter().collect()
+}
+
+/// Looks up the archive path of each referrer.
+fn get_referrer_paths(index: &ReferenceIndex, referrers: &[GlobalID]) -> io::Result<Vec<String>> {
+    let mut referrer_paths = Vec::with_capacity(referrers.len());
+    for referrer in referrers {
+        let referrer_archive_id = ArchiveID::from(referrer);
+        match index.get_archives().iter().find(|tracked| tracked.archive_id == referrer_archive_id) {
+            Some(tracked) => referrer_paths.push(tracked.file_path.clone()),
+            None => return Err(io::Error::new(io::ErrorKind::NotFound, format!("Archive {} is not tracked.", referrer_archive_id.to_string()))),
+        }
+    }
+    Ok(referrer_paths)
+}
+
+/// Rewrites the references of every referrer through `remap`.
+fn rewrite_referrers(referrers: &[GlobalID], referrer_paths: &[String], remap: &dyn Fn(GlobalID) -> Option<GlobalID>) -> io::Result<()> {
+    for (referrer, referrer_path) in referrers.iter().zip(referrer_paths) {
+        let mut form = read_form(referrer_path, FormID::from(referrer))?;
+        form.remap_references(remap);
+        write_form(referrer_path, form.as_ref())?;
+    }
+    Ok(())
+}
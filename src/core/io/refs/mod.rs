@@ -0,0 +1,13 @@
+// -- Modules for cross-form reference tracking --
+
+// Reverse-reference index
+pub mod io_refs_index;
+pub use io_refs_index::*;
+
+// Renumbering and ArchiveID remapping
+pub mod io_refs_remap;
+pub use io_refs_remap::*;
+
+// Reachability and removal of unreferenced forms
+pub mod io_refs_gc;
+pub use io_refs_gc::*;
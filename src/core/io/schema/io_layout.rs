@@ -0,0 +1,197 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::core::io::{get_form_record_bytes, read_form_record, rewrite_archive, seek_ext_block, update_form_blocks, write_ext_block, ArchiveBlocks, IOStructExt, IOStructIndex, ArchiveLock};
+use crate::core::structs::*;
+
+/// Extension block tag holding the layout version of each form type in the archive.
+///
+/// Types without an entry are stored in `FORM_LAYOUT_VERSION_INITIAL`.
+pub const EXT_TAG_LAYOUT_VERSIONS: [u8; 4] = *b"LVER";
+
+/// The result of upgrading the forms of one type to its current layout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutUpgrade {
+    pub form_type: FormType,
+    pub from_version: u16,
+    pub to_version: u16,
+    pub form_count: usize,
+}
+
+/// Reads the layout versions recorded in an archive, keyed by form type.
+///
+/// Called on every form read, so only the layout block is read from the extension area.
+pub fn read_archive_layout_versions(file_path: &str) -> io::Result<BTreeMap<FormType, u16>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    let mut file = File::open(file_path)?;
+    let Some(length) = seek_ext_block(&mut file, EXT_TAG_LAYOUT_VERSIONS)? else {
+        return Ok(BTreeMap::new());
+    };
+    let mut bytes = vec![0u8; length as usize];
+    file.read_exact(&mut bytes)?;
+    read_layout_block(&bytes)
+}
+
+/// Returns the layout version the archive stores forms of a type in.
+pub fn get_archive_layout_version(file_path: &str, form_type: FormType) -> io::Result<u16> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    let layout_versions = read_archive_layout_versions(file_path)?;
+    Ok(get_layout_version(&layout_versions, form_type))
+}
+
+/// Records the layout version the archive stores forms of a type in.
+///
+/// Used to declare the layout of forms written before versions were recorded, it does not touch the
+/// forms themselves.
+pub fn set_archive_layout_version(file_path: &str, form_type: FormType, layout_version: u16) -> io::Result<()> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut layout_versions = read_archive_layout_versions(file_path)?;
+    layout_versions.insert(form_type, layout_version);
+    write_layout_block(file_path, &layout_versions)
+}
+
+/// Makes sure forms of a type can be written in the type's current layout, given the extension area
+/// and index of the archive they go to.
+///
+/// Records the current version in the extension area when the index holds no forms of the type yet.
+/// Fails if its forms are stored in another layout, the archive must be upgraded first.
+pub(crate) fn ensure_ext_layout_version(ext: &mut IOStructExt, index: &IOStructIndex, form_type: FormType) -> io::Result<()> {
+    let Some(layout_version) = with_form_type_registration(form_type, |registration| registration.layout_version) else {
+        return Ok(());
+    };
+    let mut layout_versions = match ext.get_block(EXT_TAG_LAYOUT_VERSIONS) {
+        Some(bytes) => read_layout_block(bytes)?,
+        None => BTreeMap::new(),
+    };
+    let stored_version = get_layout_version(&layout_versions, form_type);
+    if stored_version == layout_version {
+        return Ok(());
+    }
+    if index.indexes.iter().any(|index_item| index_item.form_type == form_type) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Forms of type {} are stored in layout version {}, upgrade the archive before writing version {}.", form_type, stored_version, layout_version),
+        ));
+    }
+    layout_versions.insert(form_type, layout_version);
+    ext.set_block(EXT_TAG_LAYOUT_VERSIONS, get_layout_bytes(&layout_versions));
+    Ok(())
+}
+
+/// Reads the form at the file's position, with the type's migration if the archive stores it in an
+/// older layout.
+pub fn read_form_with_layout(file: &mut File, layout_versions: &BTreeMap<FormType, u16>) -> io::Result<Box<dyn FormTrait>> {
+    let checkpoint = file.stream_position()?;
+    let mut form_header = [0u8; FormID::BYTE_COUNT + FormType::BYTE_COUNT];
+    file.read_exact(&mut form_header)?;
+    file.seek(SeekFrom::Start(checkpoint))?;
+
+    let form_type = FormType::from(form_header[FormID::BYTE_COUNT]);
+    let stored_version = get_layout_version(layout_versions, form_type);
+    let migration = with_form_type_registration(form_type, |registration| {
+        (registration.layout_version != stored_version).then(|| registration.get_migration(stored_version).map(|migration| migration.read_from_bytes))
+    });
+    match migration.flatten() {
+        Some(Some(read_from_bytes)) => read_from_bytes(file),
+        Some(None) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Forms of type {} are stored in layout version {}, which this library cannot read.", form_type, stored_version),
+        )),
+        None => FormBase::read_from_bytes(file),
+    }
+}
+
+/// Rewrites every form stored in an older layout in its type's current layout.
+///
+/// The upgraded forms and the new layout versions are written together in a single rewrite of the
+/// archive, so a failed upgrade leaves the archive as it was. Fails before changing anything if a type
+/// has no migration from its stored version.
+pub fn upgrade_archive(file_path: &str) -> io::Result<Vec<LayoutUpgrade>> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut blocks = ArchiveBlocks::read(file_path)?;
+    let mut layout_versions = match blocks.ext.get_block(EXT_TAG_LAYOUT_VERSIONS) {
+        Some(bytes) => read_layout_block(bytes)?,
+        None => BTreeMap::new(),
+    };
+    let mut upgrades = Vec::new();
+    for (&form_type, &stored_version) in &layout_versions {
+        let Some(registration) = get_form_type_registration(form_type) else {
+            continue;
+        };
+        if registration.layout_version == stored_version {
+            continue;
+        }
+        if registration.get_migration(stored_version).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Forms of type {} are stored in layout version {}, which this library cannot read.", registration.name, stored_version),
+            ));
+        }
+        upgrades.push(LayoutUpgrade { form_type, from_version: stored_version, to_version: registration.layout_version, form_count: 0 });
+    }
+    if upgrades.is_empty() {
+        return Ok(upgrades);
+    }
+
+    // Read every form to upgrade in its stored layout
+    let mut file = File::open(file_path)?;
+    let mut forms = Vec::new();
+    for (index_item, &data_length) in blocks.index.indexes.iter().zip(&blocks.record_lengths) {
+        let Some(upgrade) = upgrades.iter_mut().find(|upgrade| upgrade.form_type == index_item.form_type) else {
+            continue;
+        };
+        file.seek(SeekFrom::Start((index_item.data_start_offset + blocks.archive_info.bytestart_data) as u64))?;
+        forms.push(read_form_record(&mut file, file_path, &layout_versions, data_length)?);
+        upgrade.form_count += 1;
+    }
+    drop(file);
+
+    // Record the new layout versions, then update the indexes for the upgraded forms in memory
+    for upgrade in &upgrades {
+        layout_versions.insert(upgrade.form_type, upgrade.to_version);
+    }
+    blocks.ext.set_block(EXT_TAG_LAYOUT_VERSIONS, get_layout_bytes(&layout_versions));
+    let mut form_records = Vec::with_capacity(forms.len());
+    for form in &forms {
+        update_form_blocks(&mut blocks, form.as_ref())?;
+        form_records.push((form.form_id(), form.form_type(), get_form_record_bytes(form.as_ref())));
+    }
+
+    let records: Vec<(FormID, FormType, &[u8])> = form_records.iter().map(|(form_id, form_type, record)| (*form_id, *form_type, record.as_slice())).collect();
+    rewrite_archive(file_path, blocks, &HashSet::new(), &records)?;
+    Ok(upgrades)
+}
+
+fn get_layout_version(layout_versions: &BTreeMap<FormType, u16>, form_type: FormType) -> u16 {
+    layout_versions.get(&form_type).copied().unwrap_or(FORM_LAYOUT_VERSION_INITIAL)
+}
+
+fn read_layout_block(bytes: &[u8]) -> io::Result<BTreeMap<FormType, u16>> {
+    if bytes.len() < 2 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for layout version count"));
+    }
+    let entry_count = u16::from_be_bytes([bytes[0], bytes[1]]) as usize;
+    if bytes.len() < 2 + entry_count * 3 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for layout versions"));
+    }
+
+    let mut layout_versions = BTreeMap::new();
+    for entry in bytes[2..2 + entry_count * 3].chunks_exact(3) {
+        layout_versions.insert(FormType::from(entry[0]), u16::from_be_bytes([entry[1], entry[2]]));
+    }
+    Ok(layout_versions)
+}
+
+fn write_layout_block(file_path: &str, layout_versions: &BTreeMap<FormType, u16>) -> io::Result<()> {
+    write_ext_block(file_path, EXT_TAG_LAYOUT_VERSIONS, get_layout_bytes(layout_versions))
+}
+
+fn get_layout_bytes(layout_versions: &BTreeMap<FormType, u16>) -> Vec<u8> {
+    let mut bytes = (layout_versions.len() as u16).to_be_bytes().to_vec();
+    for (form_type, layout_version) in layout_versions {
+        bytes.push(form_type.to_u8());
+        bytes.extend_from_slice(&layout_version.to_be_bytes());
+    }
+    bytes
+}
@@ -0,0 +1,212 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{self, Seek, SeekFrom};
+
+use crate::core::io::{read_archive_info, read_block_index, read_ext_block, read_ext_blocks, read_form_with_layout, remove_ext_block, write_ext_block, write_ext_blocks, IOStructExt, IOStructIndex, ArchiveLock};
+use crate::core::structs::*;
+
+/// Extension block tag holding form schemas: those of the archive's `FormDynamic` types, and of
+/// every type it holds once made self-describing with `embed_archive_schemas`.
+pub const EXT_TAG_FORM_SCHEMAS: [u8; 4] = *b"SCHM";
+
+/// Reads the form schemas stored in an archive.
+pub fn read_archive_schemas(file_path: &str) -> io::Result<Vec<FormSchema>> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    match read_ext_block(file_path, EXT_TAG_FORM_SCHEMAS)? {
+        Some(bytes) => read_schema_block(&bytes),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Stores a form schema in the archive and registers it.
+///
+/// Fails if the archive already stores a different schema for the type, or the type byte or name
+/// is registered to another type or schema.
+pub fn write_archive_schema(file_path: &str, schema: &FormSchema) -> io::Result<()> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut ext = read_ext_blocks(file_path)?;
+    if add_ext_schema(&mut ext, schema)? {
+        write_ext_blocks(file_path, &ext)?;
+    }
+    Ok(())
+}
+
+/// Removes the schema of a form type from the archive, returning whether it existed.
+///
+/// Fails while forms of the type are still in the archive, since they could no longer be read.
+/// The schema stays registered for the running process.
+pub fn remove_archive_schema(file_path: &str, form_type: FormType) -> io::Result<bool> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut schemas = read_archive_schemas(file_path)?;
+    let schema_count = schemas.len();
+    schemas.retain(|schema| schema.form_type != form_type);
+    if schemas.len() == schema_count {
+        return Ok(false);
+    }
+    if get_form_type_count(file_path, form_type)? > 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Forms of type {} exist in the archive, remove them before their schema.", form_type)));
+    }
+
+    if schemas.is_empty() {
+        remove_ext_block(file_path, EXT_TAG_FORM_SCHEMAS)?;
+    } else {
+        write_archive_schemas(file_path, &schemas)?;
+    }
+    Ok(true)
+}
+
+/// Registers the form schemas stored in an archive, returning how many were newly registered.
+///
+/// Schemas of types registered with a compiled struct are skipped, those conflicting with a
+/// different registered schema fail.
+pub fn load_archive_schemas(file_path: &str) -> io::Result<usize> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    register_schemas(read_archive_schemas(file_path)?)
+}
+
+/// Reads the form at the file's position, loading the archive's form schemas once if its type is unknown.
+///
+/// `layout_versions` are the archive's, see `read_archive_layout_versions`.
+pub fn read_form_with_schemas(file: &mut File, file_path: &str, layout_versions: &BTreeMap<FormType, u16>) -> io::Result<Box<dyn FormTrait>> {
+    let checkpoint = file.stream_position()?;
+    match read_form_with_layout(file, layout_versions) {
+        Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+            if load_archive_schemas(file_path)? == 0 {
+                return Err(error);
+            }
+            file.seek(SeekFrom::Start(checkpoint))?;
+            read_form_with_layout(file, layout_versions)
+        }
+        result => result,
+    }
+}
+
+/// Makes sure the extension area of an archive stores the schema of a form about to be written to it.
+///
+/// Dynamic types always need their schema in the archive. Other types with a known schema are added
+/// once the archive is self-describing, i.e. already has a schema block.
+pub(crate) fn ensure_ext_schema(ext: &mut IOStructExt, form_type: FormType) -> io::Result<()> {
+    let Some((Some(schema), is_dynamic)) = with_form_type_registration(form_type, |registration| (registration.schema.clone(), registration.is_dynamic)) else {
+        return Ok(());
+    };
+    if !is_dynamic && ext.get_block(EXT_TAG_FORM_SCHEMAS).is_none() {
+        return Ok(());
+    }
+    add_ext_schema(ext, &schema)?;
+    Ok(())
+}
+
+/// Makes the archive self-describing by storing the schema of every form type it holds, so tools can
+/// decode its forms without the library's compiled types.
+///
+/// Returns the types in the archive that have no known schema and stay undescribed. Later writes
+/// keep the schema block complete.
+pub fn embed_archive_schemas(file_path: &str) -> io::Result<Vec<FormType>> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut undescribed = Vec::new();
+    for form_type in get_archive_form_types(file_path)? {
+        match get_form_type_schema(form_type) {
+            Some(schema) => write_archive_schema(file_path, &schema)?,
+            None => undescribed.push(form_type),
+        }
+    }
+
+    // Mark the archive self-describing even when it holds no forms yet
+    if read_ext_block(file_path, EXT_TAG_FORM_SCHEMAS)?.is_none() {
+        write_archive_schemas(file_path, &[])?;
+    }
+    Ok(undescribed)
+}
+
+/// Stores a form schema in an extension area and registers it, returning whether the area changed.
+fn add_ext_schema(ext: &mut IOStructExt, schema: &FormSchema) -> io::Result<bool> {
+    let mut schemas = match ext.get_block(EXT_TAG_FORM_SCHEMAS) {
+        Some(bytes) => read_schema_block(bytes)?,
+        None => Vec::new(),
+    };
+    for existing in &schemas {
+        if existing == schema {
+            register_form_schema(schema.clone())?;
+            return Ok(false);
+        }
+        if existing.form_type == schema.form_type {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("The archive already has a different schema for FormType byte {}.", schema.form_type.to_u8())));
+        }
+        if existing.name == schema.name {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("Schema name {} is already used by FormType byte {}.", existing.name.to_string(), existing.form_type.to_u8())));
+        }
+    }
+
+    register_form_schema(schema.clone())?;
+    schemas.push(schema.clone());
+    schemas.sort_by_key(|schema| schema.form_type);
+    ext.set_block(EXT_TAG_FORM_SCHEMAS, get_schema_bytes(&schemas));
+    Ok(true)
+}
+
+/// Registers each schema, skipping types that have a compiled struct.
+fn register_schemas(schemas: Vec<FormSchema>) -> io::Result<usize> {
+    let mut registered = 0;
+    for schema in schemas {
+        if let Some(existing) = get_form_type_registration(schema.form_type) {
+            if !existing.is_dynamic {
+                continue;
+            }
+        }
+        if register_form_schema(schema)? {
+            registered += 1;
+        }
+    }
+    Ok(registered)
+}
+
+/// Returns how many forms of a type the archive holds, from its index.
+pub(crate) fn get_form_type_count(file_path: &str, form_type: FormType) -> io::Result<usize> {
+    let index = read_archive_index(file_path)?;
+    Ok(index.indexes.iter().filter(|index_item| index_item.form_type == form_type).count())
+}
+
+/// Returns every form type the archive holds, from its index.
+fn get_archive_form_types(file_path: &str) -> io::Result<BTreeSet<FormType>> {
+    let index = read_archive_index(file_path)?;
+    Ok(index.indexes.iter().map(|index_item| index_item.form_type).collect())
+}
+
+pub(crate) fn read_archive_index(file_path: &str) -> io::Result<IOStructIndex> {
+    let archive = read_archive_info(file_path)?;
+    if archive.form_count == 0 {
+        return Ok(IOStructIndex { indexes: Vec::new() });
+    }
+    let mut file = File::open(file_path)?;
+    file.seek(SeekFrom::Start(archive.bytestart_index as u64))?;
+    read_block_index(&mut file, archive.form_count)
+}
+
+fn read_schema_block(bytes: &[u8]) -> io::Result<Vec<FormSchema>> {
+    if bytes.len() < 2 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for form schema count"));
+    }
+    let schema_count = u16::from_be_bytes([bytes[0], bytes[1]]);
+    let mut offset = 2;
+
+    let mut schemas = Vec::with_capacity(schema_count as usize);
+    for _ in 0..schema_count {
+        let (schema, consumed) = FormSchema::read_from_byte_buffer(&bytes[offset..])?;
+        schemas.push(schema);
+        offset += consumed;
+    }
+
+    Ok(schemas)
+}
+
+fn write_archive_schemas(file_path: &str, schemas: &[FormSchema]) -> io::Result<()> {
+    write_ext_block(file_path, EXT_TAG_FORM_SCHEMAS, get_schema_bytes(schemas))
+}
+
+fn get_schema_bytes(schemas: &[FormSchema]) -> Vec<u8> {
+    let mut bytes = (schemas.len() as u16).to_be_bytes().to_vec();
+    for schema in schemas {
+        bytes.extend_from_slice(&schema.to_bytes());
+    }
+    bytes
+}
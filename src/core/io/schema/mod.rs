@@ -0,0 +1,9 @@
+// -- Modules for the form schemas and layout versions stored in the archive --
+
+// Form schema storage and loading
+pub mod io_schema;
+pub use io_schema::*;
+
+// Form layout versions and upgrades
+pub mod io_layout;
+pub use io_layout::*;
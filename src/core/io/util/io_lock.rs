@@ -0,0 +1,257 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io;
+use std::marker::PhantomData;
+use std::path::{self, Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+/// How often a lock that is held elsewhere is retried while waiting for it.
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How IO functions wait for an archive that another process or thread holds a conflicting lock on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveLockWait {
+    /// Fail at once.
+    NoWait,
+    /// Retry until the duration has passed, then fail.
+    Timeout(Duration),
+    /// Wait until the lock is released.
+    Forever,
+}
+
+impl ArchiveLockWait {
+    pub const DEFAULT: Self = Self::Timeout(Duration::from_secs(10));
+}
+
+impl Default for ArchiveLockWait {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+static ARCHIVE_LOCK_WAIT: RwLock<ArchiveLockWait> = RwLock::new(ArchiveLockWait::DEFAULT);
+
+/// Sets how every IO function of the process waits for a locked archive.
+pub fn set_archive_lock_wait(wait: ArchiveLockWait) {
+    match ARCHIVE_LOCK_WAIT.write() {
+        Ok(mut current) => *current = wait,
+        Err(poisoned) => *poisoned.into_inner() = wait,
+    }
+}
+
+pub fn get_archive_lock_wait() -> ArchiveLockWait {
+    match ARCHIVE_LOCK_WAIT.read() {
+        Ok(current) => *current,
+        Err(poisoned) => *poisoned.into_inner(),
+    }
+}
+
+/// Readers share an archive, a writer has it to itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArchiveLockMode {
+    Shared,
+    Exclusive,
+}
+
+/// The error of an IO function that could not lock its archive in time, wrapped in an `io::Error`
+/// of kind `WouldBlock`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveLockedError {
+    pub file_path: String,
+    pub mode: ArchiveLockMode,
+}
+
+impl ArchiveLockedError {
+    /// Returns the lock error an `io::Error` carries, if it is one.
+    pub fn from_io_error(error: &io::Error) -> Option<&Self> {
+        error.get_ref()?.downcast_ref()
+    }
+}
+
+impl fmt::Display for ArchiveLockedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mode {
+            ArchiveLockMode::Shared => write!(f, "Archive {} is locked, another process or thread is writing it.", self.file_path),
+            ArchiveLockMode::Exclusive => write!(f, "Archive {} is locked, another process or thread is reading or writing it.", self.file_path),
+        }
+    }
+}
+
+impl Error for ArchiveLockedError {}
+
+/// A lock the current thread holds, shared by the `ArchiveLock`s taking it.
+struct HeldLock {
+    file: Option<File>,
+    mode: ArchiveLockMode,
+    count: usize,
+}
+
+thread_local! {
+    static HELD_LOCKS: RefCell<HashMap<PathBuf, HeldLock>> = RefCell::new(HashMap::new());
+}
+
+/// The read locks of open `ArchiveReaderLock`s, with the threads that took them. Kept for the whole
+/// process, as a reader can be dropped on another thread than the one that opened it.
+static READER_LOCKS: Mutex<Vec<(PathBuf, ThreadId)>> = Mutex::new(Vec::new());
+
+/// An advisory lock on an archive, held by the current thread until dropped.
+///
+/// Every IO function locks the archives it touches: shared to read, exclusive to write. The lock is
+/// taken on a `.lock` sidecar file, so it survives edits that replace the archive file, and binds
+/// other processes and threads locking the same archive. A thread holding a lock can take it again,
+/// so IO functions calling each other don't wait on themselves, but it cannot write an archive
+/// while it is only reading it.
+///
+/// Archives that don't exist or sit where no lock file can be created, such as read-only install
+/// directories, are read without a lock.
+pub struct ArchiveLock {
+    lock_path: PathBuf,
+    _thread_bound: PhantomData<*const ()>,
+}
+
+#[allow(unused)]
+impl ArchiveLock {
+    pub fn shared(file_path: &str) -> io::Result<Self> {
+        Self::acquire(file_path, ArchiveLockMode::Shared)
+    }
+
+    pub fn exclusive(file_path: &str) -> io::Result<Self> {
+        Self::acquire(file_path, ArchiveLockMode::Exclusive)
+    }
+
+    /// Locks several archives, in the same order in every process so two calls locking the same
+    /// archives cannot each hold what the other waits for.
+    pub fn acquire_all(file_paths: &[&str], mode: ArchiveLockMode) -> io::Result<Vec<Self>> {
+        let mut lock_paths = file_paths.iter().map(|file_path| Ok((get_lock_path(file_path)?, *file_path))).collect::<io::Result<Vec<_>>>()?;
+        lock_paths.sort();
+        lock_paths.dedup_by(|(a, _), (b, _)| a == b);
+        lock_paths.into_iter().map(|(_, file_path)| Self::acquire(file_path, mode)).collect()
+    }
+
+    pub fn acquire(file_path: &str, mode: ArchiveLockMode) -> io::Result<Self> {
+        let lock_path = get_lock_path(file_path)?;
+        HELD_LOCKS.with_borrow_mut(|held_locks| {
+            if let Some(held) = held_locks.get_mut(&lock_path) {
+                if held.mode == ArchiveLockMode::Shared && mode == ArchiveLockMode::Exclusive {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Archive {} is being read on this thread, it cannot be written until the read is done.", file_path)));
+                }
+                held.count += 1;
+                return Ok(());
+            }
+            if mode == ArchiveLockMode::Exclusive && is_reader_locked_on_thread(&lock_path) {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Archive {} is open in a reader on this thread, it cannot be written until the reader is closed.", file_path)));
+            }
+            let file = lock_archive_file(file_path, &lock_path, mode)?;
+            held_locks.insert(lock_path.clone(), HeldLock { file, mode, count: 1 });
+            Ok(())
+        })?;
+        Ok(Self { lock_path, _thread_bound: PhantomData })
+    }
+}
+
+impl Drop for ArchiveLock {
+    fn drop(&mut self) {
+        let _ = HELD_LOCKS.try_with(|held_locks| {
+            let mut held_locks = held_locks.borrow_mut();
+            let Some(held) = held_locks.get_mut(&self.lock_path) else {
+                return;
+            };
+            held.count -= 1;
+            if held.count == 0 {
+                if let Some(file) = held_locks.remove(&self.lock_path).and_then(|held| held.file) {
+                    let _ = file.unlock();
+                }
+            }
+        });
+    }
+}
+
+/// A lock held by a reader that keeps an archive open across calls, released on whichever thread
+/// drops it. The thread that took it cannot write the archive until then, see `ArchiveLock`.
+pub(crate) struct ArchiveReaderLock {
+    _file: Option<File>,
+    lock_path: PathBuf,
+    thread_id: ThreadId,
+}
+
+impl Drop for ArchiveReaderLock {
+    fn drop(&mut self) {
+        let mut reader_locks = READER_LOCKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(position) = reader_locks.iter().position(|(lock_path, thread_id)| *lock_path == self.lock_path && *thread_id == self.thread_id) {
+            reader_locks.swap_remove(position);
+        }
+    }
+}
+
+/// Read-locks an archive for a reader that keeps it open across calls.
+///
+/// The archive is locked without a file when it is read without a lock, see `ArchiveLock`.
+pub(crate) fn lock_archive(file_path: &str) -> io::Result<ArchiveReaderLock> {
+    let lock_path = get_lock_path(file_path)?;
+    let held_mode = HELD_LOCKS.with_borrow(|held_locks| held_locks.get(&lock_path).map(|held| held.mode));
+    if held_mode == Some(ArchiveLockMode::Exclusive) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Archive {} is locked by this thread.", file_path)));
+    }
+    let file = lock_archive_file(file_path, &lock_path, ArchiveLockMode::Shared)?;
+
+    let thread_id = thread::current().id();
+    READER_LOCKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).push((lock_path.clone(), thread_id));
+    Ok(ArchiveReaderLock { _file: file, lock_path, thread_id })
+}
+
+/// Returns whether a reader opened on the current thread holds the lock.
+fn is_reader_locked_on_thread(lock_path: &Path) -> bool {
+    let thread_id = thread::current().id();
+    READER_LOCKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).iter().any(|(reader_path, reader_thread)| reader_path == lock_path && *reader_thread == thread_id)
+}
+
+/// Locks the sidecar of an archive. Only writers create the sidecar: without one no writer has been
+/// active, so a reader goes without a lock.
+fn lock_archive_file(file_path: &str, lock_path: &Path, mode: ArchiveLockMode) -> io::Result<Option<File>> {
+    if mode == ArchiveLockMode::Shared && !Path::new(file_path).exists() {
+        return Ok(None);
+    }
+    let is_writer = mode == ArchiveLockMode::Exclusive;
+    let file = match OpenOptions::new().read(true).write(true).create(is_writer).truncate(false).open(lock_path) {
+        Ok(file) => file,
+        Err(e) if !is_writer && matches!(e.kind(), io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let wait = get_archive_lock_wait();
+    if wait == ArchiveLockWait::Forever {
+        match mode {
+            ArchiveLockMode::Shared => file.lock_shared()?,
+            ArchiveLockMode::Exclusive => file.lock()?,
+        }
+        return Ok(Some(file));
+    }
+
+    let started = Instant::now();
+    loop {
+        let result = match mode {
+            ArchiveLockMode::Shared => file.try_lock_shared(),
+            ArchiveLockMode::Exclusive => file.try_lock(),
+        };
+        match result {
+            Ok(()) => return Ok(Some(file)),
+            Err(TryLockError::Error(e)) => return Err(e),
+            Err(TryLockError::WouldBlock) => {},
+        }
+        if matches!(wait, ArchiveLockWait::Timeout(timeout) if started.elapsed() < timeout) {
+            thread::sleep(LOCK_RETRY_INTERVAL);
+            continue;
+        }
+        return Err(io::Error::new(io::ErrorKind::WouldBlock, ArchiveLockedError { file_path: file_path.to_string(), mode }));
+    }
+}
+
+/// Returns the sidecar path an archive is locked through.
+fn get_lock_path(file_path: &str) -> io::Result<PathBuf> {
+    path::absolute(format!("{}.lock", file_path))
+}
@@ -28,4 +28,31 @@ pub struct IOStructIndexItem {
     pub form_id: FormID,
     pub form_type: FormType,
     pub data_start_offset: u32,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IOStructExt {
+    pub blocks: Vec<IOStructExtBlock>,
+}
+
+#[allow(unused)]
+impl IOStructExt {
+    /// Returns the data of the block with the given tag, if the area has one.
+    pub fn get_block(&self, tag: [u8; 4]) -> Option<&[u8]> {
+        self.blocks.iter().find(|block| block.tag == tag).map(|block| block.data.as_slice())
+    }
+
+    /// Replaces the data of the block with the given tag, adding the block if the area has none.
+    pub fn set_block(&mut self, tag: [u8; 4], data: Vec<u8>) {
+        match self.blocks.iter_mut().find(|block| block.tag == tag) {
+            Some(block) => block.data = data,
+            None => self.blocks.push(IOStructExtBlock { tag, data }),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IOStructExtBlock {
+    pub tag: [u8; 4],
+    pub data: Vec<u8>,
+}
@@ -0,0 +1,93 @@
+use std::fs;
+use std::io;
+
+use crate::core::io::{ArchiveLock, ArchiveLockMode};
+
+/// Backs up a set of archives before a multi-step edit so the edit can be undone as a whole.
+///
+/// Each archive is copied to a `.bak` sidecar on `begin`. `commit` removes the sidecars,
+/// `rollback` copies them back over the archives. The archives stay write-locked until the
+/// transaction is finished or dropped.
+pub struct ArchiveTransaction {
+    file_paths: Vec<String>,
+    finished: bool,
+    _locks: Vec<ArchiveLock>,
+}
+
+#[allow(unused)]
+impl ArchiveTransaction {
+    /// Locks and backs up every archive in `file_paths`.
+    pub fn begin(file_paths: &[&str]) -> io::Result<Self> {
+        let mut transaction = Self {
+            file_paths: Vec::new(),
+            finished: false,
+            _locks: ArchiveLock::acquire_all(file_paths, ArchiveLockMode::Exclusive)?,
+        };
+
+        for file_path in file_paths {
+            if transaction.file_paths.iter().any(|path| path == file_path) {
+                continue;
+            }
+            if let Err(e) = fs::copy(file_path, Self::get_backup_path(file_path)) {
+                transaction.rollback()?;
+                return Err(e);
+            }
+            transaction.file_paths.push(file_path.to_string());
+        }
+
+        Ok(transaction)
+    }
+
+    /// Keeps the edits and removes the backups.
+    pub fn commit(mut self) -> io::Result<()> {
+        self.finished = true;
+        for file_path in &self.file_paths {
+            fs::remove_file(Self::get_backup_path(file_path))?;
+        }
+        Ok(())
+    }
+
+    /// Restores every archive from its backup and removes the backups.
+    pub fn rollback(mut self) -> io::Result<()> {
+        self.finished = true;
+        for file_path in &self.file_paths {
+            let backup_path = Self::get_backup_path(file_path);
+            fs::copy(&backup_path, file_path)?;
+            fs::remove_file(&backup_path)?;
+        }
+        Ok(())
+    }
+
+    /// Runs `edit`, committing if it succeeds and rolling back if it fails.
+    pub fn run<T>(file_paths: &[&str], edit: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+        let transaction = Self::begin(file_paths)?;
+        match edit() {
+            Ok(result) => {
+                transaction.commit()?;
+                Ok(result)
+            }
+            Err(e) => {
+                transaction.rollback()?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns the sidecar path an archive is backed up to.
+    fn get_backup_path(file_path: &str) -> String {
+        format!("{}.bak", file_path)
+    }
+}
+
+/// Rolls back a transaction dropped without `commit` or `rollback`.
+impl Drop for ArchiveTransaction {
+    fn drop(&mut self) {
+        if !self.finished {
+            for file_path in &self.file_paths {
+                let backup_path = Self::get_backup_path(file_path);
+                let _ = fs::copy(&backup_path, file_path);
+                let _ = fs::remove_file(&backup_path);
+            }
+        }
+    }
+}
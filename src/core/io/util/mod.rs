@@ -6,4 +6,12 @@ pub use io_struct::*;
 
 // Utilities for IO operations
 pub mod io_utils;
-pub use io_utils::*;
\ No newline at end of file
+pub use io_utils::*;
+
+// Backup and rollback of multi-archive edits
+pub mod io_transaction;
+pub use io_transaction::*;
+
+// Advisory locking of archives across processes and threads
+pub mod io_lock;
+pub use io_lock::*;
@@ -0,0 +1,353 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::sync::RwLock;
+
+use crate::core::io::{bump_ext_generation, get_ext_form_history, get_ext_record_lengths, get_index_data_length, read_archive_info, read_block_bytestarts, read_block_ext, read_block_header, read_block_index, remove_from_name_index, remove_from_type_index, remove_from_world_part_grid, remove_from_world_part_bounds, read_ext_blocks, seek_ext_block, set_ext_form_history, set_ext_record_lengths, write_block_bytestart, write_block_ext, write_block_header, write_block_index, IOStructExt, IOStructIndex, IOStructIndexItem, ArchiveLock, EXT_TAG_FORM_HISTORY, EXT_TAG_RECORD_LENGTHS};
+use crate::core::structs::*;
+
+/// How `write_form`, `insert_form` and `delete_form` change the data block of an archive. Archives
+/// keeping form history are always written in append mode, see `set_form_history_depth`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArchiveWriteMode {
+    /// Keep the records in index order, shifting the records after the written one and writing the index
+    /// and extension area in place. Archives left fragmented by appending are compacted by the edit.
+    #[default]
+    Rewrite,
+    /// Append the new record to the archive and repoint its index item, leaving the old record, index
+    /// and extension area as dead space until `compact_archive`. An edit costs the size of the form, the
+    /// index and the extension area instead of the size of the archive.
+    Append,
+}
+
+static ARCHIVE_WRITE_MODE: RwLock<ArchiveWriteMode> = RwLock::new(ArchiveWriteMode::Rewrite);
+
+/// Sets how every form write and delete of the process changes an archive.
+pub fn set_archive_write_mode(mode: ArchiveWriteMode) {
+    match ARCHIVE_WRITE_MODE.write() {
+        Ok(mut current) => *current = mode,
+        Err(poisoned) => *poisoned.into_inner() = mode,
+    }
+}
+
+pub fn get_archive_write_mode() -> ArchiveWriteMode {
+    match ARCHIVE_WRITE_MODE.read() {
+        Ok(current) => *current,
+        Err(poisoned) => *poisoned.into_inner(),
+    }
+}
+
+/// Returns the write mode of an archive from its extension area: append mode when it is the process's
+/// mode or the archive keeps form history.
+pub(crate) fn get_archive_write_mode_of(ext: &IOStructExt) -> ArchiveWriteMode {
+    if ext.get_block(EXT_TAG_FORM_HISTORY).is_some() {
+        return ArchiveWriteMode::Append;
+    }
+    get_archive_write_mode()
+}
+
+/// Returns the bytes of the data block taken by old versions of forms, deleted forms and replaced
+/// indexes and extension areas, not counting the revisions kept as form history.
+pub fn read_archive_dead_space(file_path: &str) -> io::Result<u32> {
+    let _lock = ArchiveLock::shared(file_path)?;
+    let archive = read_archive_info(file_path)?;
+    let Some(record_lengths) = &archive.record_lengths else {
+        return Ok(0);
+    };
+    let history_length = get_ext_form_history(&read_ext_blocks(file_path)?)?.map_or(0, |history| history.get_data_length());
+    let data_length = archive.bytestart_index - archive.bytestart_data;
+    Ok(data_length.saturating_sub(record_lengths.iter().sum::<u32>() + history_length))
+}
+
+/// Rewrites the data block with its records back in index order, returning the bytes of dead space
+/// reclaimed. Revisions kept as form history follow the current records. Archives without dead space
+/// are left as they are.
+pub fn compact_archive(file_path: &str) -> io::Result<u32> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    Ok(compact_data_block(file_path, &HashSet::new())?.map_or(0, |(_, reclaimed)| reclaimed))
+}
+
+/// Writes a form's record at the end of the archive and points its index item at it, or removes the
+/// index item when `record` is `None`, from blocks read with `ArchiveBlocks::read`.
+///
+/// The new index and extension area follow the record, and only then is the header pointed at them, so
+/// an interrupted write leaves the archive as it was. The old index and extension area become dead space
+/// at the end of the data block. When the archive keeps form history, the replaced record is kept as
+/// the form's newest revision.
+pub(crate) fn append_form_record(file_path: &str, blocks: ArchiveBlocks, form_id: FormID, record: Option<(FormType, &[u8])>) -> io::Result<()> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let ArchiveBlocks { mut archive_info, mut index, mut record_lengths, mut ext } = blocks;
+    let index_position = index.indexes.binary_search_by_key(&form_id, |item| item.form_id);
+    let mut history = get_ext_form_history(&ext)?;
+    if let (Some(history), Ok(index_position)) = (&mut history, index_position) {
+        history.push(form_id, index.indexes[index_position].data_start_offset, record_lengths[index_position]);
+    }
+
+    let mut file = File::options().read(true).write(true).open(file_path)?;
+    let file_end = file.seek(SeekFrom::End(0))? as u32;
+    archive_info.bytestart_index = file_end;
+    match (record, index_position) {
+        (Some((form_type, record)), index_position) => {
+            // The new record goes after everything the current header points at
+            file.write_all(record)?;
+            archive_info.bytestart_index += record.len() as u32;
+
+            let index_item = IOStructIndexItem { form_id, form_type, data_start_offset: file_end - archive_info.bytestart_data };
+            match index_position {
+                Ok(index_position) => {
+                    index.indexes[index_position] = index_item;
+                    record_lengths[index_position] = record.len() as u32;
+                },
+                Err(index_position) => {
+                    index.indexes.insert(index_position, index_item);
+                    record_lengths.insert(index_position, record.len() as u32);
+                },
+            }
+        },
+        (None, Ok(index_position)) => {
+            index.indexes.remove(index_position);
+            record_lengths.remove(index_position);
+            remove_from_name_index(&mut ext, &[form_id])?;
+            remove_from_type_index(&mut ext, &[form_id])?;
+            remove_from_world_part_grid(&mut ext, &[form_id])?;
+            remove_from_world_part_bounds(&mut ext, &[form_id])?;
+            bump_ext_generation(&mut ext)?;
+        },
+        (None, Err(_)) => return Err(io::Error::new(io::ErrorKind::NotFound, "Form not found in archive.")),
+    }
+    archive_info.form_count = index.indexes.len() as u16;
+    if let Some(history) = &history {
+        set_ext_form_history(&mut ext, history);
+    }
+    set_ext_record_lengths_if_needed(&mut ext, &index, &record_lengths, archive_info.bytestart_index - archive_info.bytestart_data);
+
+    write_block_index(&mut file, &index)?;
+    write_block_ext(&mut file, &ext)?;
+
+    // Point the header at the new index last
+    file.seek(SeekFrom::Start(0))?;
+    write_block_header(&mut file, &archive_info)?;
+    write_block_bytestart(&mut file, archive_info.bytestart_index, archive_info.bytestart_data)?;
+
+    Ok(())
+}
+
+/// Rewrites the archive without the given forms and with its records in index order, returning how
+/// many forms were removed and how many data bytes were freed.
+///
+/// Returns `None` without touching the archive when there is nothing to remove and the records are
+/// already in order.
+pub(crate) fn compact_data_block(file_path: &str, removed_ids: &HashSet<FormID>) -> io::Result<Option<(usize, u32)>> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut file = File::open(file_path)?;
+    if removed_ids.is_empty() && seek_ext_block(&mut file, EXT_TAG_RECORD_LENGTHS)?.is_none() {
+        return Ok(None);
+    }
+    drop(file);
+
+    // Nothing to do when every byte of the data block is still in use
+    let blocks = ArchiveBlocks::read(file_path)?;
+    let history_length = get_ext_form_history(&blocks.ext)?.map_or(0, |history| history.get_data_length());
+    let used_length = blocks.record_lengths.iter().sum::<u32>() + history_length;
+    if used_length == blocks.get_data_length() && !blocks.index.indexes.iter().any(|item| removed_ids.contains(&item.form_id)) {
+        return Ok(None);
+    }
+    rewrite_archive(file_path, blocks, removed_ids, &[]).map(Some)
+}
+
+/// Everything of an archive but its records: the header, index and extension area, read into memory
+/// so a write can edit them and store them back in one pass.
+pub(crate) struct ArchiveBlocks {
+    /// The header and byte starts, with the stored record lengths when the records are out of order.
+    pub archive_info: Archive,
+    pub index: IOStructIndex,
+    /// The byte length of each record, in index order.
+    pub record_lengths: Vec<u32>,
+    pub ext: IOStructExt,
+}
+
+#[allow(unused)]
+impl ArchiveBlocks {
+    /// Reads the header, index and extension area of an archive.
+    pub fn read(file_path: &str) -> io::Result<Self> {
+        let mut file = File::open(file_path)?;
+        let header = read_block_header(&mut file)?;
+        let bytestarts = read_block_bytestarts(&mut file)?;
+        file.seek(SeekFrom::Start(bytestarts.bytestart_index as u64))?;
+        let index = read_block_index(&mut file, header.form_count)?;
+        let ext = read_block_ext(&mut file)?;
+
+        let mut archive_info = Archive::new(header.archive_id, header.version, header.description);
+        archive_info.form_count = header.form_count;
+        archive_info.bytestart_index = bytestarts.bytestart_index;
+        archive_info.bytestart_data = bytestarts.bytestart_data;
+        archive_info.record_lengths = get_ext_record_lengths(&ext)?;
+        let record_lengths = (0..index.indexes.len())
+            .map(|index_position| get_index_data_length(&archive_info, &index, index_position))
+            .collect::<io::Result<Vec<u32>>>()?;
+        Ok(Self { archive_info, index, record_lengths, ext })
+    }
+
+    /// Returns the index position of a form, or the position it would be inserted at.
+    pub fn find_form(&self, form_id: FormID) -> Result<usize, usize> {
+        self.index.indexes.binary_search_by_key(&form_id, |index_item| index_item.form_id)
+    }
+
+    /// Returns the byte length of the data block.
+    pub fn get_data_length(&self) -> u32 {
+        self.archive_info.bytestart_index - self.archive_info.bytestart_data
+    }
+}
+
+/// Where a record of a rewritten archive comes from.
+enum RecordSource<'a> {
+    /// A record of the original data block, at its data start offset and with its length.
+    Archive(u32, u32),
+    /// A newly written record.
+    New(&'a [u8]),
+}
+
+/// Writes an archive anew from its blocks, read with `ArchiveBlocks::read` and edited in memory:
+/// without the removed forms, with each of `records` replacing or added as its form's record, and with
+/// the records in index order. Revisions kept as form history follow the current records.
+///
+/// Returns how many forms were removed and how many bytes the data block shrank by.
+pub(crate) fn rewrite_archive(file_path: &str, blocks: ArchiveBlocks, removed_ids: &HashSet<FormID>, records: &[(FormID, FormType, &[u8])]) -> io::Result<(usize, u32)> {
+    let data_length = blocks.get_data_length();
+    let ArchiveBlocks { mut archive_info, index, record_lengths, mut ext } = blocks;
+    let removed_form_ids: Vec<FormID> = removed_ids.iter().copied().collect();
+    remove_from_name_index(&mut ext, &removed_form_ids)?;
+    remove_from_type_index(&mut ext, &removed_form_ids)?;
+    remove_from_world_part_grid(&mut ext, &removed_form_ids)?;
+    remove_from_world_part_bounds(&mut ext, &removed_form_ids)?;
+
+    // Lay the kept records out one after another, the new records in their FormIDs' places
+    let new_records: HashMap<FormID, (FormType, &[u8])> = records.iter().map(|&(form_id, form_type, record)| (form_id, (form_type, record))).collect();
+    let mut items = Vec::with_capacity(index.indexes.len() + records.len());
+    for (item, record_length) in index.indexes.iter().zip(&record_lengths) {
+        if removed_ids.contains(&item.form_id) {
+            continue;
+        }
+        match new_records.get(&item.form_id) {
+            Some(&(form_type, record)) => items.push((item.form_id, form_type, RecordSource::New(record))),
+            None => items.push((item.form_id, item.form_type, RecordSource::Archive(item.data_start_offset, *record_length))),
+        }
+    }
+    let removed_count = index.indexes.len() - items.len();
+    if removed_count > 0 {
+        bump_ext_generation(&mut ext)?;
+    }
+    for &(form_id, form_type, record) in records {
+        if let Err(position) = items.binary_search_by_key(&form_id, |(item_id, _, _)| *item_id) {
+            items.insert(position, (form_id, form_type, RecordSource::New(record)));
+        }
+    }
+
+    let mut new_index = IOStructIndex { indexes: Vec::with_capacity(items.len()) };
+    let mut new_record_lengths = Vec::with_capacity(items.len());
+    let mut new_data_length = 0u32;
+    for (form_id, form_type, source) in &items {
+        let record_length = match source {
+            RecordSource::Archive(_, record_length) => *record_length,
+            RecordSource::New(record) => record.len() as u32,
+        };
+        new_index.indexes.push(IOStructIndexItem { form_id: *form_id, form_type: *form_type, data_start_offset: new_data_length });
+        new_record_lengths.push(record_length);
+        new_data_length += record_length;
+    }
+    let mut sources: Vec<RecordSource> = items.into_iter().map(|(_, _, source)| source).collect();
+
+    // Kept revisions follow the current records
+    if let Some(mut history) = get_ext_form_history(&ext)? {
+        for revision in history.revisions.values_mut().flatten() {
+            sources.push(RecordSource::Archive(revision.data_start_offset, revision.data_length));
+            revision.data_start_offset = new_data_length;
+            new_data_length += revision.data_length;
+        }
+        set_ext_form_history(&mut ext, &history);
+    }
+    set_ext_record_lengths_if_needed(&mut ext, &new_index, &new_record_lengths, new_data_length);
+
+    let bytestart_data_old = archive_info.bytestart_data;
+    archive_info.form_count = new_index.indexes.len() as u16;
+    archive_info.bytestart_data = (archive_info.get_header_byte_count() + 8) as u32;
+    archive_info.bytestart_index = archive_info.bytestart_data + new_data_length;
+
+    replace_archive(file_path, |file, temp_file| {
+        write_block_header(temp_file, &archive_info)?;
+        write_block_bytestart(temp_file, archive_info.bytestart_index, archive_info.bytestart_data)?;
+
+        // Records that followed each other in the original are copied together
+        let mut pending: Option<(u32, u32)> = None;
+        for source in sources {
+            match source {
+                RecordSource::Archive(data_start_offset, record_length) => match &mut pending {
+                    Some((start, length)) if *start + *length == data_start_offset => *length += record_length,
+                    _ => {
+                        if let Some((start, length)) = pending.replace((data_start_offset, record_length)) {
+                            copy_data(file, temp_file, (bytestart_data_old + start) as u64, length as u64)?;
+                        }
+                    },
+                },
+                RecordSource::New(record) => {
+                    if let Some((start, length)) = pending.take() {
+                        copy_data(file, temp_file, (bytestart_data_old + start) as u64, length as u64)?;
+                    }
+                    temp_file.write_all(record)?;
+                },
+            }
+        }
+        if let Some((start, length)) = pending {
+            copy_data(file, temp_file, (bytestart_data_old + start) as u64, length as u64)?;
+        }
+
+        write_block_index(temp_file, &new_index)?;
+        write_block_ext(temp_file, &ext)
+    })?;
+
+    Ok((removed_count, data_length.saturating_sub(new_data_length)))
+}
+
+/// Writes a new version of an archive next to it and renames it over the archive, so a failed or
+/// interrupted write leaves the archive as it was. `write` gets the archive to copy from and the new file.
+pub(crate) fn replace_archive<T>(file_path: &str, write: impl FnOnce(&mut File, &mut File) -> io::Result<T>) -> io::Result<T> {
+    let mut file = File::open(file_path)?;
+    let temp_path = format!("{}.tmp", file_path);
+    let mut temp_file = File::create(&temp_path)?;
+    let result = write(&mut file, &mut temp_file);
+    drop(temp_file);
+    drop(file);
+
+    match result {
+        Ok(value) => {
+            fs::rename(&temp_path, file_path)?;
+            Ok(value)
+        },
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path);
+            Err(e)
+        },
+    }
+}
+
+/// Copies bytes of an archive, from a byte offset on, to the end of another file.
+pub(crate) fn copy_data(file: &mut File, temp_file: &mut File, start: u64, length: u64) -> io::Result<()> {
+    file.seek(SeekFrom::Start(start))?;
+    let copied = io::copy(&mut file.take(length), temp_file)?;
+    if copied != length {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Archive ends before the bytes to copy."));
+    }
+    Ok(())
+}
+
+/// Stores the record lengths in an extension area unless the records run one after another in index
+/// order up to the end of the data block, when they can be told from the index alone.
+fn set_ext_record_lengths_if_needed(ext: &mut IOStructExt, index: &IOStructIndex, record_lengths: &[u32], data_length: u32) {
+    let mut record_end = 0;
+    let in_order = index.indexes.iter().zip(record_lengths).all(|(item, record_length)| {
+        let follows = item.data_start_offset == record_end;
+        record_end = item.data_start_offset + record_length;
+        follows
+    }) && record_end == data_length;
+    set_ext_record_lengths(ext, if in_order { None } else { Some(record_lengths) });
+}
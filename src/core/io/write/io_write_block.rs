@@ -3,7 +3,7 @@ use std::io;
 use std::io::Write;
 
 use crate::core::structs::Archive;
-use crate::core::io::IOStructIndex;
+use crate::core::io::{IOStructExt, IOStructIndex, EXT_AREA_MAGIC};
 
 /// Write the header block
 pub fn write_block_header(file: &mut File, archive: &Archive) -> std::io::Result<()> {
@@ -25,13 +25,34 @@ pub fn write_block_bytestart(file: &mut File, bytestart_index: u32, bytestart_da
 
 // Write the index block
 pub fn write_block_index(file: &mut File, index_block: &IOStructIndex) -> io::Result<()> {
+    // Collect the items first, the index is written with one call
+    let mut bytes = Vec::with_capacity(index_block.indexes.len() * 7);
     for index in &index_block.indexes {
-        file.write_all(&index.form_id.to_bytes())?;
-        file.write_all(&[index.form_type.to_byte()])?;
+        bytes.extend_from_slice(&index.form_id.to_bytes());
+        bytes.push(index.form_type.to_byte());
         let data_start_offset_u32 = index.data_start_offset as u32;
-        file.write_all(&data_start_offset_u32.to_be_bytes())?;
+        bytes.extend_from_slice(&data_start_offset_u32.to_be_bytes());
     }
+    file.write_all(&bytes)?;
 
     Ok(())
 }
 
+
+// Write the extension area, nothing is written when there are no blocks
+pub fn write_block_ext(file: &mut File, ext: &IOStructExt) -> io::Result<()> {
+    if ext.blocks.is_empty() {
+        return Ok(());
+    }
+
+    let mut bytes = EXT_AREA_MAGIC.to_vec();
+    bytes.extend_from_slice(&(ext.blocks.len() as u16).to_be_bytes());
+    for block in &ext.blocks {
+        bytes.extend_from_slice(&block.tag);
+        bytes.extend_from_slice(&(block.data.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&block.data);
+    }
+    file.write_all(&bytes)?;
+
+    Ok(())
+}
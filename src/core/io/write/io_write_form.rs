@@ -1,18 +1,66 @@
-use std::{fs::{remove_file, File, OpenOptions}, io::{Read, Seek, SeekFrom, Write}};
+use std::{collections::HashSet, fs::{remove_file, File, OpenOptions}, io::{Read, Seek, SeekFrom, Write}};
 use crate::core::io::*;
 use crate::core::structs::*;
 
 /// Writes a form to the archive file.
 pub fn write_form(file_path: &str, form: &dyn FormTrait) -> std::io::Result<()> {
-    let form_exists = get_form_exists(file_path, form.form_id())?;
-    if form_exists {
-        // Check if its the last form
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut blocks = ArchiveBlocks::read(file_path)?;
+    update_form_blocks(&mut blocks, form)?;
+    write_form_record(file_path, blocks, form)
+}
+
+/// Replaces the metadata of a form in the archive, leaving its fields as they are.
+pub fn write_form_metadata(file_path: &str, form_id: FormID, metadata: FormMetadata) -> std::io::Result<()> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut form = read_form(file_path, form_id)?;
+    form.set_form_metadata(metadata);
+    write_form(file_path, form.as_ref())
+}
+
+/// Writes a new form to the archive file, failing instead of overwriting if its FormID is taken.
+pub fn insert_form(file_path: &str, form: &dyn FormTrait) -> std::io::Result<()> {
+    let _lock = ArchiveLock::exclusive(file_path)?;
+    let mut blocks = ArchiveBlocks::read(file_path)?;
+    if blocks.find_form(form.form_id()).is_ok() {
+        return Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, format!("Form {} already exists in archive.", form.form_id().to_string())));
+    }
+    update_form_blocks(&mut blocks, form)?;
+    write_form_record(file_path, blocks, form)
+}
+
+/// Checks a form can be written to the archive and makes the changes to its extension area the write
+/// needs, in memory, so the area is written once along with the form.
+pub(crate) fn update_form_blocks(blocks: &mut ArchiveBlocks, form: &dyn FormTrait) -> std::io::Result<()> {
+    check_form_name_unique(&blocks.ext, form)?;
+    ensure_ext_layout_version(&mut blocks.ext, &blocks.index, form.form_type())?;
+    ensure_ext_schema(&mut blocks.ext, form.form_type())?;
+    update_name_index(&mut blocks.ext, form)?;
+    update_type_index(&mut blocks.ext, form.form_id(), form.form_type())?;
+    update_world_part_grid(&mut blocks.ext, form)?;
+    update_world_part_bounds(&mut blocks.ext, form)?;
+    bump_ext_generation(&mut blocks.ext)
+}
+
+/// Writes a form's record and the edited blocks to the archive, in the archive's write mode.
+fn write_form_record(file_path: &str, blocks: ArchiveBlocks, form: &dyn FormTrait) -> std::io::Result<()> {
+    if get_archive_write_mode_of(&blocks.ext) == ArchiveWriteMode::Append {
+        return append_form_record(file_path, blocks, form.form_id(), Some((form.form_type(), &get_form_record_bytes(form))));
+    }
+    if blocks.archive_info.record_lengths.is_some() {
+        // Records left out of order by appending are compacted along with the write
+        rewrite_archive(file_path, blocks, &HashSet::new(), &[(form.form_id(), form.form_type(), &get_form_record_bytes(form))])?;
+        return Ok(());
+    }
+
+    if blocks.find_form(form.form_id()).is_ok() {
         write_form_existing(file_path, form)?;
     } else {
         write_form_new(file_path, form)?;
     }
 
-    Ok(())
+    // The form writers leave the extension area behind the moved index, it goes back in place after it
+    write_ext_blocks(file_path, &blocks.ext)
 }
 
 /// Writes a form to the archive file if it is a new form.
@@ -42,43 +90,49 @@ fn write_form_existing(file_path: &str, form: &dyn FormTrait) -> std::io::Result
 
     if last_form_id == form.form_id() {
         // Overwrite the last form
-        write_form_existing_last(&mut file, form, &mut archive_info, last_form_data_start_offset)?;
+        write_form_existing_last(file_path, &mut file, form, &mut archive_info, last_form_data_start_offset)?;
     } else {
         // Overwrite a form inside the archive
-        write_form_existing_inner(&mut file, form, &mut archive_info)?;
+        write_form_existing_inner(file_path, &mut file, form, &mut archive_info)?;
     }
 
     Ok(())
 }
 
+/// Returns the bytes of a form's data record: the form, then its metadata section when it has metadata.
+pub(crate) fn get_form_record_bytes(form: &dyn FormTrait) -> Vec<u8> {
+    let mut bytes = form.to_bytes();
+    let metadata = form.form_metadata();
+    if !metadata.is_empty() {
+        bytes.extend_from_slice(&metadata.to_bytes());
+    }
+    bytes
+}
+
 /// Writes a form to the archive file if it is an existing form.
-fn write_form_existing_inner(file: &mut File, form: &dyn FormTrait, archive_info: &mut Archive) -> std::io::Result<()> {
+fn write_form_existing_inner(file_path: &str, file: &mut File, form: &dyn FormTrait, archive_info: &mut Archive) -> std::io::Result<()> {
 
     // Find the offset of the form to overwrite
     file.seek(SeekFrom::Start(archive_info.bytestart_index as u64))?;
     let old_form_index_item = binary_search_for_index_item_and_position(file, form.form_id(), archive_info.form_count)?;
     
-    if old_form_index_item.is_none() {
-        return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "Form not found in archive."));
-    } else {
-        let (index_position, old_form_index_item) = old_form_index_item.unwrap();
+    if let Some((index_position, old_form_index_item)) = old_form_index_item {
 
 
         // Read the old form data
         file.seek(SeekFrom::Start(archive_info.bytestart_data as u64 + old_form_index_item.data_start_offset as u64))?;
-        let old_form = FormBase::read_from_bytes(file)?;
-        let old_form_length = old_form.get_byte_count() as u32;
+        let old_form_length = read_form_data_length(file, archive_info, index_position, old_form_index_item.data_start_offset)?;
 
 
         // New form data
-        let new_form_bytes = form.to_bytes();
+        let new_form_bytes = get_form_record_bytes(form);
         let new_form_length = new_form_bytes.len() as u32;
 
         // Calculate the difference in length
         let form_length_diff = new_form_length as i32 - old_form_length as i32;
 
         // Read the index to a temp file
-        let temp_index_path = "temp_index.tmp";
+        let temp_index_path = &format!("{}.index.tmp", file_path);
         let mut temp_index_file = OpenOptions::new()
             .read(true)
             .write(true)
@@ -106,6 +160,9 @@ fn write_form_existing_inner(file: &mut File, form: &dyn FormTrait, archive_info
             if i as u64 > index_position {
                 // Apply the offset change to the data start offset
                 data_start_offset = (data_start_offset as i32 + form_length_diff) as u32;
+            } else if i as u64 == index_position {
+                // The form may be replaced by one of another type
+                form_type_buf = [form.form_type().to_u8()];
             }
 
             // Write the index item to the temp file
@@ -122,7 +179,7 @@ fn write_form_existing_inner(file: &mut File, form: &dyn FormTrait, archive_info
         ))?;
         
         // Open the temporary file to write the remaining data
-        let temp_data_path = "temp_data.tmp";
+        let temp_data_path = &format!("{}.data.tmp", file_path);
         let mut temp_data_file = OpenOptions::new()
             .read(true)
             .write(true)
@@ -134,21 +191,8 @@ fn write_form_existing_inner(file: &mut File, form: &dyn FormTrait, archive_info
         // Mark the current position (right after the old form)
         let current_position = file.stream_position()?;
 
-        // Seek to the end of the file to calculate the total length
-        file.seek(SeekFrom::End(0))?;
-        let end_of_file = file.stream_position()?;
-
-        // Calculate the length from the current position to the end of the file
-        let length_to_end = end_of_file - current_position;
-
-        // Calculate the length of the index block (each index item is 7 bytes)
-        let index_block_length = archive_info.form_count as u64 * 7;
-
         // Calculate the number of bytes to read (data block only)
-        let bytes_to_read = length_to_end - index_block_length;
-
-        // Seek back to the position right after the old form
-        file.seek(SeekFrom::Start(current_position))?;
+        let bytes_to_read = archive_info.bytestart_index as u64 - current_position;
 
         // Buffer to read in chunks
         let mut buffer = [0u8; 8192]; // 8KB buffer for reading and writing in chunks
@@ -189,9 +233,8 @@ fn write_form_existing_inner(file: &mut File, form: &dyn FormTrait, archive_info
             file.write_all(&buffer[..bytes_read])?;
         }
 
-        // Trim the file to the new length
+        // Update the archive info
         archive_info.bytestart_index = (archive_info.bytestart_index as i32 + form_length_diff) as u32;
-        file.set_len(archive_info.bytestart_index as u64 + archive_info.form_count as u64 * 7)?;
 
         // Write the new header to the archive
         file.seek(SeekFrom::Start(0))?; // Seek to start of file
@@ -203,28 +246,29 @@ fn write_form_existing_inner(file: &mut File, form: &dyn FormTrait, archive_info
         drop(temp_index_file);
         remove_file(temp_data_path)?;
         remove_file(temp_index_path)?;
+    } else {
+        return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "Form not found in archive."));
     }
 
     Ok(())
 }
 
 /// Writes a form to the archive file if it is the last form.
-fn write_form_existing_last(file: &mut File, form: &dyn FormTrait, archive_info: &mut Archive, old_data_start_offset: u32) -> std::io::Result<()> {
+fn write_form_existing_last(file_path: &str, file: &mut File, form: &dyn FormTrait, archive_info: &mut Archive, old_data_start_offset: u32) -> std::io::Result<()> {
 
     // Read old form data
     file.seek(SeekFrom::Start(archive_info.bytestart_data as u64 + old_data_start_offset as u64))?;
-    let old_form = FormBase::read_from_bytes(file)?;
-    let old_form_length = old_form.get_byte_count() as u32;
+    let old_form_length = read_form_data_length(file, archive_info, archive_info.form_count as u64 - 1, old_data_start_offset)?;
 
     // New form data
-    let new_form_bytes = form.to_bytes();
+    let new_form_bytes = get_form_record_bytes(form);
     let new_form_length = new_form_bytes.len() as u32;
 
     // Calculate the difference in length
     let form_length_diff = new_form_length as i32 - old_form_length as i32;
 
     // Read the index to a temp file
-    let temp_index_path = "temp_index.tmp";
+    let temp_index_path = &format!("{}.index.tmp", file_path);
     let mut temp_index_file = OpenOptions::new()
         .read(true)
         .write(true)
@@ -235,15 +279,9 @@ fn write_form_existing_last(file: &mut File, form: &dyn FormTrait, archive_info:
     // Seek to the start of the index
     file.seek(SeekFrom::Start(archive_info.bytestart_index as u64))?;
 
-    // Read the index to the temp file till the end of the file in chunks
+    // Read the index to the temp file in chunks, the extension area after it is written anew
     let mut buffer = [0u8; 8192]; // 8KB buffer for reading and writing in chunks
-    loop {
-        let bytes_read = file.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
-        }
-        temp_index_file.write_all(&buffer[..bytes_read])?;
-    }
+    copy_index_block(file, &mut temp_index_file, archive_info.form_count, &mut buffer)?;
 
     // Write the new form data to the archive
     file.seek(SeekFrom::Start(archive_info.bytestart_data as u64 + old_data_start_offset as u64))?;
@@ -262,8 +300,9 @@ fn write_form_existing_last(file: &mut File, form: &dyn FormTrait, archive_info:
     // Update the archive info
     archive_info.bytestart_index = (archive_info.bytestart_index as i32 + form_length_diff) as u32;
 
-    // Trim the file to the new length
-    file.set_len(archive_info.bytestart_index as u64 + archive_info.form_count as u64 * 7)?;
+    // The form may be replaced by one of another type, update its index item
+    file.seek(SeekFrom::Start(archive_info.bytestart_index as u64 + (archive_info.form_count as u64 - 1) * 7 + FormID::BYTE_COUNT as u64))?;
+    file.write_all(&[form.form_type().to_u8()])?;
 
     // Write the new header to the archive
     file.seek(SeekFrom::Start(0))?;
@@ -294,7 +333,7 @@ fn write_form_new(file_path: &str, form: &dyn FormTrait) -> std::io::Result<()>
     archive_info.bytestart_data = byte_starts.bytestart_data;
 
     // Convert the form to bytes
-    let form_bytes = form.to_bytes();
+    let form_bytes = get_form_record_bytes(form);
 
     // if the form count is 0, send to write_form_new_first
     if header.form_count == 0 {
@@ -307,14 +346,14 @@ fn write_form_new(file_path: &str, form: &dyn FormTrait) -> std::io::Result<()>
         let first_form_id = get_index_form_id_first(&mut file)?;
         // If the form id is less than the first form id, write the form to the beginning
         if form.form_id() < first_form_id {
-            write_form_new_start(&mut file, &form_bytes, form, &mut archive_info)?;
+            write_form_new_start(file_path, &mut file, &form_bytes, form, &mut archive_info)?;
         } else {
             // Get the last form in the index's form id
             file.seek(SeekFrom::Start(archive_info.bytestart_index as u64))?;
             let last_form_id = get_index_form_id_last(&mut file, archive_info.form_count)?;
             // If the form id is greater than the last form id, write the form to the end
             if form.form_id() > last_form_id {
-                write_form_new_end(&mut file, &form_bytes, &mut archive_info)?;
+                write_form_new_end(file_path, &mut file, &form_bytes, &mut archive_info)?;
             } else {
                 // Write the form in the middle of the archive
 
@@ -325,7 +364,7 @@ fn write_form_new(file_path: &str, form: &dyn FormTrait) -> std::io::Result<()>
                 // Get the form insert position and item
                 if let Some((position, index_item)) = form_insert_position {
                     // Write the form to the archive
-                    write_form_new_mid(&mut file, &form_bytes, position, index_item, &mut archive_info)?;
+                    write_form_new_mid(file_path, &mut file, &form_bytes, position, index_item, &mut archive_info)?;
                 } else {
                     return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "Form not found in archive."));
                 }
@@ -337,7 +376,7 @@ fn write_form_new(file_path: &str, form: &dyn FormTrait) -> std::io::Result<()>
 }
 
 /// Writes a new form to the middle of the archive.
-fn write_form_new_mid(file: &mut File, form_bytes: &Vec<u8>, form_insert_position: u64, prev_form_item: IOStructIndexItem, archive_info: &mut Archive) -> std::io::Result<()> {
+fn write_form_new_mid(file_path: &str, file: &mut File, form_bytes: &[u8], form_insert_position: u64, prev_form_item: IOStructIndexItem, archive_info: &mut Archive) -> std::io::Result<()> {
 
     // Seek to the start of file
     file.seek(SeekFrom::Start(0))?;
@@ -349,7 +388,7 @@ fn write_form_new_mid(file: &mut File, form_bytes: &Vec<u8>, form_insert_positio
     let _byte_starts = read_block_bytestarts(file)?;
 
     // Read the index to a temp file
-    let temp_index_path = "temp_index.tmp";
+    let temp_index_path = &format!("{}.index.tmp", file_path);
     let mut temp_index_file = OpenOptions::new()
         .read(true)
         .write(true)
@@ -411,7 +450,7 @@ fn write_form_new_mid(file: &mut File, form_bytes: &Vec<u8>, form_insert_positio
     // Seek to the start of the data changes
     file.seek(SeekFrom::Start(archive_info.bytestart_data as u64 + new_form_data_start_offset as u64))?;
     // Open the temporary file to write the remaining data
-    let temp_data_path = "temp_data.tmp";
+    let temp_data_path = &format!("{}.data.tmp", file_path);
     let mut temp_data_file = OpenOptions::new()
         .read(true)
         .write(true)
@@ -482,7 +521,7 @@ fn write_form_new_mid(file: &mut File, form_bytes: &Vec<u8>, form_insert_positio
 }
 
 /// Writes a new form to the end of the archive.
-fn write_form_new_end(file: &mut File, form_bytes: &Vec<u8>, archive_info: &mut Archive) -> std::io::Result<()> {
+fn write_form_new_end(file_path: &str, file: &mut File, form_bytes: &[u8], archive_info: &mut Archive) -> std::io::Result<()> {
     // Read the ID of the last form in the archive
     let form_count_u64 = archive_info.form_count as u64;
 
@@ -490,12 +529,12 @@ fn write_form_new_end(file: &mut File, form_bytes: &Vec<u8>, archive_info: &mut
     let index_offset = form_count_u64
         .checked_mul(7) // Multiply form_count by 7
         .and_then(|res| res.checked_sub(7)) // Subtract 7 from the result
-        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Other, "Overflow in index calculation"))?;
+        .ok_or_else(|| std::io::Error::other("Overflow in index calculation"))?;
 
     // Calculate the seek position by adding the offset to bytestart_index
     let seek_position = (archive_info.bytestart_index as u64)
         .checked_add(index_offset)
-        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Other, "Overflow in seek position calculation"))?;
+        .ok_or_else(|| std::io::Error::other("Overflow in seek position calculation"))?;
 
     // Seek to the calculated position
     file.seek(SeekFrom::Start(seek_position))?;
@@ -516,12 +555,11 @@ fn write_form_new_end(file: &mut File, form_bytes: &Vec<u8>, archive_info: &mut
 
     // Read the last form from the archive
     file.seek(SeekFrom::Start(last_form_offset as u64 + archive_info.bytestart_data as u64 ))?;
-    let last_form = FormBase::read_from_bytes(file)?;
-    let last_form_length = last_form.get_byte_count() as u32;
+    let last_form_length = read_form_data_length(file, archive_info, archive_info.form_count as u64 - 1, last_form_offset)?;
 
     // Write the old index to a temp file
     file.seek(SeekFrom::Start(archive_info.bytestart_index as u64))?;
-    let temp_file_path = "temp_file.tmp"; // Temporary file path
+    let temp_file_path = &format!("{}.index.tmp", file_path); // Temporary file path
     let mut temp_file = OpenOptions::new()
         .read(true)
         .write(true)
@@ -530,19 +568,13 @@ fn write_form_new_end(file: &mut File, form_bytes: &Vec<u8>, archive_info: &mut
         .open(temp_file_path)?;
 
     let mut buffer = [0u8; 8192]; // 8KB buffer for reading and writing in chunks
-    loop {
-        let bytes_read = file.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
-        }
-        temp_file.write_all(&buffer[..bytes_read])?;
-    }   
+    copy_index_block(file, &mut temp_file, archive_info.form_count, &mut buffer)?;
 
     // Write the new form to the archive
     file.seek(SeekFrom::Start(archive_info.bytestart_index as u64))?;
     file.write_all(form_bytes)?;
 
-    let checkpoint = file.seek(SeekFrom::Current(0))?;
+    let checkpoint = file.stream_position()?;
 
     // Write the old index back to the archive
     temp_file.seek(SeekFrom::Start(0))?;
@@ -590,15 +622,15 @@ fn write_form_new_end(file: &mut File, form_bytes: &Vec<u8>, archive_info: &mut
 }
 
 /// Writes a new form to the start of the archive.
-fn write_form_new_start(file: &mut File, form_bytes: &Vec<u8>, form: &dyn FormTrait, archive_info: &mut Archive) -> std::io::Result<()> {
+fn write_form_new_start(file_path: &str, file: &mut File, form_bytes: &[u8], form: &dyn FormTrait, archive_info: &mut Archive) -> std::io::Result<()> {
 
     let form_bytes_len = form_bytes.len() as u32;
     let data_block_length = archive_info.bytestart_index - archive_info.bytestart_data;
     file.seek(SeekFrom::Start(archive_info.bytestart_data as u64))?;
 
     // Read the data block to a temporary file
-    let temp_data_path = "temp_data.tmp";
-    let mut temp_data_file = File::options().read(true).write(true).create(true).open(temp_data_path)?;
+    let temp_data_path = &format!("{}.data.tmp", file_path);
+    let mut temp_data_file = File::options().read(true).write(true).create(true).truncate(true).open(temp_data_path)?;
 
     let mut buffer = [0u8; 8192]; // 8KB buffer for reading and writing in chunks
     let mut bytes_left = data_block_length;
@@ -615,8 +647,8 @@ fn write_form_new_start(file: &mut File, form_bytes: &Vec<u8>, form: &dyn FormTr
     }
 
     // Read the index block to a temporary file
-    let temp_index_path = "temp_index.tmp";
-    let mut temp_index_file = File::options().read(true).write(true).create(true).open(temp_index_path)?;
+    let temp_index_path = &format!("{}.index.tmp", file_path);
+    let mut temp_index_file = File::options().read(true).write(true).create(true).truncate(true).open(temp_index_path)?;
 
     // Seek to the start of the index block
     file.seek(SeekFrom::Start(archive_info.bytestart_index as u64))?;
@@ -700,11 +732,11 @@ fn write_form_new_start(file: &mut File, form_bytes: &Vec<u8>, form: &dyn FormTr
 }
 
 /// Writes a new form to the archive file if it is the first form.
-fn write_form_new_first(file: &mut File, form_bytes: &Vec<u8>, form: &dyn FormTrait, archive_info: &mut Archive) -> std::io::Result<()> {
+fn write_form_new_first(file: &mut File, form_bytes: &[u8], form: &dyn FormTrait, archive_info: &mut Archive) -> std::io::Result<()> {
 
     archive_info.form_count += 1;
     let form_bytes_len = form_bytes.len() as u32;
-    archive_info.bytestart_index = archive_info.bytestart_index + form_bytes_len;
+    archive_info.bytestart_index += form_bytes_len;
 
     file.seek(SeekFrom::Start(0))?;    
     write_block_header(file, archive_info)?;
@@ -725,5 +757,18 @@ fn write_form_new_first(file: &mut File, form_bytes: &Vec<u8>, form: &dyn FormTr
     Ok(())
 }
 
-
- 
\ No newline at end of file
+/// Copies the index block at the file's position to a temp file in chunks, leaving out the extension
+/// area that follows it.
+fn copy_index_block(file: &mut File, temp_file: &mut File, form_count: u16, buffer: &mut [u8]) -> std::io::Result<()> {
+    let mut bytes_left = form_count as usize * 7;
+    while bytes_left > 0 {
+        let to_read = buffer.len().min(bytes_left);
+        let bytes_read = file.read(&mut buffer[..to_read])?;
+        if bytes_read == 0 {
+            break; // End of file reached before expected, break loop
+        }
+        temp_file.write_all(&buffer[..bytes_read])?;
+        bytes_left -= bytes_read;
+    }
+    Ok(())
+}
@@ -15,3 +15,7 @@ pub use io_write_form::*;
 // Modules for deleting form data
 pub mod io_delete_form;
 pub use io_delete_form::*;
+
+// Modules for appending form versions, and compacting and rewriting archives
+pub mod io_write_append;
+pub use io_write_append::*;
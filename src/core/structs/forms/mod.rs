@@ -7,6 +7,21 @@
 pub mod struc_form;
 pub use struc_form::*;
 #[allow(unused)]
+pub mod struc_form_metadata;
+pub use struc_form_metadata::*;
+#[allow(unused)]
+pub mod struc_form_registry;
+pub use struc_form_registry::*;
+#[allow(unused)]
+pub mod struc_form_field;
+pub use struc_form_field::*;
+#[allow(unused)]
+pub mod struc_form_schema;
+pub use struc_form_schema::*;
+#[allow(unused)]
+pub mod struc_form_dynamic;
+pub use struc_form_dynamic::*;
+#[allow(unused)]
 pub mod struc_form_string;
 pub use struc_form_string::*;
 #[allow(unused)]
@@ -7,24 +7,43 @@ use serde_json::{json, Value};
 use crate::core::structs::{forms::*, types::*};
 
 /// Trait that all form types must implement.
-pub trait FormTrait: fmt::Display + fmt::Debug {
+pub trait FormTrait: fmt::Display + fmt::Debug + Send + Sync {
     fn form_id(&self) -> FormID;
     fn form_type(&self) -> FormType;
     fn form_name(&self) -> StrSml;
     fn to_dict(&self) -> Value;
     fn to_bytes(&self) -> Vec<u8>;
     fn get_byte_count(&self) -> usize;
+    fn get_references(&self) -> Vec<GlobalID>;
+    fn remove_references(&mut self, target: GlobalID) -> usize;
+    fn remap_references(&mut self, remap: &dyn Fn(GlobalID) -> Option<GlobalID>) -> usize;
+    fn set_form_id(&mut self, form_id: FormID);
+    fn form_metadata(&self) -> FormMetadata;
+    fn set_form_metadata(&mut self, metadata: FormMetadata);
 }
 
 /// Base struct for all forms
+///
+/// `metadata` is not part of the form's bytes, the archive stores it after them in the form's data record.
 #[derive(PartialEq, Eq, Clone)]
 pub struct FormBase {
     pub form_id: FormID,
     pub form_type: FormType,
     pub form_name: StrSml,
+    pub metadata: FormMetadata,
 }
 
 impl FormBase {
+    /// Constructor for `FormBase`, without metadata.
+    pub fn new(form_id: FormID, form_type: FormType, form_name: StrSml) -> Self {
+        Self {
+            form_id,
+            form_type,
+            form_name,
+            metadata: FormMetadata::default(),
+        }
+    }
+
     pub const BYTE_COUNT_FORM_ID: usize = FormID::BYTE_COUNT;
     pub const BYTE_COUNT_FORM_TYPE: usize = FormType::BYTE_COUNT;
 
@@ -59,89 +78,47 @@ impl FormBase {
         // Rewind file position before handling form-specific deserialization.
         file.seek(std::io::SeekFrom::Start(checkpoint))?;
 
-        // Handle deserialization based on the form type.
-        match form_type {
-            FormType::STRING => {
-                let form_string = FormString::read_from_bytes(file)?;
-                Ok(Box::new(form_string))
-            }
-            FormType::WORLD => {
-                let form_world = FormWorld::read_from_bytes(file)?;
-                Ok(Box::new(form_world))
-            }
-            FormType::REFGROUP => {
-                let form_refgroup = FormRefGroup::read_from_bytes(file)?;
-                Ok(Box::new(form_refgroup))
-            }
-            FormType::WORLDPART => {
-                let form_worldpart = FormWorldPart::read_from_bytes(file)?;
-                Ok(Box::new(form_worldpart))
-            }
-            FormType::WEATHER => {
-                let form_weather = FormWeather::read_from_bytes(file)?;
-                Ok(Box::new(form_weather))
-            }
+        // Handle deserialization with the reader registered for the form type.
+        match get_form_type_readers(form_type) {
+            Some((read_from_bytes, _)) => read_from_bytes(file),
+            None => Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown FormType {}", form_type.to_u8()))),
         }
     }
 
     /// Read `FormBase` from a byte buffer and return a boxed `FormTrait`
+    /// The returned count is the number of bytes the form used.
     pub fn read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Box<dyn FormTrait>, usize)> {
-        let mut offset = 0;
+        let offset = FormID::BYTE_COUNT;
 
-        // Read FormID from the byte buffer.
-        if bytes.len() < offset + FormID::BYTE_COUNT {
+        // Check the buffer holds a FormID.
+        if bytes.len() < offset {
             return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for FormID"));
         }
-        let form_id_array: [u8; FormID::BYTE_COUNT] = bytes[offset..offset + FormID::BYTE_COUNT].try_into().unwrap();
-        let form_id = FormID::from(form_id_array);
-        offset += FormID::BYTE_COUNT;
 
         // Read FormType from the byte buffer.
         if bytes.len() < offset + FormType::BYTE_COUNT {
             return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for FormType"));
         }
+        let form_type = FormType::from(bytes[offset]);
 
-        let byte_value = bytes[offset] as u8;
-        let form_type = FormType::from(byte_value);
-        offset += FormType::BYTE_COUNT;
-
-        // Handle deserialization based on the form type.
-        match form_type {
-            FormType::STRING => {
-                let (form_string, consumed) = FormString::read_from_byte_buffer(bytes)?;
-                offset += consumed;
-                Ok((Box::new(form_string), offset))
-            }
-            FormType::WORLD => {
-                let (form_world, consumed) = FormWorld::read_from_byte_buffer(bytes)?;
-                offset += consumed;
-                Ok((Box::new(form_world), offset))
-            }
-            FormType::REFGROUP => {
-                let (form_refgroup, consumed) = FormRefGroup::read_from_byte_buffer(bytes)?;
-                offset += consumed;
-                Ok((Box::new(form_refgroup), offset))
-            }
-            FormType::WORLDPART => {
-                let (form_worldpart, consumed) = FormWorldPart::read_from_byte_buffer(bytes)?;
-                offset += consumed;
-                Ok((Box::new(form_worldpart), offset))
-            }
-            FormType::WEATHER => {
-                let (form_weather, consumed) = FormWeather::read_from_byte_buffer(bytes)?;
-                offset += consumed;
-                Ok((Box::new(form_weather), offset))
-            }
+        // Handle deserialization with the reader registered for the form type, it reads from the form's start.
+        match get_form_type_readers(form_type) {
+            Some((_, read_from_byte_buffer)) => read_from_byte_buffer(bytes),
+            None => Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown FormType {}", form_type.to_u8()))),
         }
     }
 
-    /// Convert `FormBase` to a JSON dictionary.
+    /// Convert `FormBase` to a JSON dictionary, with `metadata` when it is set.
     fn to_dict(&self) -> Value {
-        json!({
+        let mut dict = json!({
             "form_id": self.form_id.to_string(),
             "form_type": self.form_type.to_string(),
             "form_name": self.form_name.to_string(),
-        })
+        });
+        if !self.metadata.is_empty() {
+            dict["metadata"] = self.metadata.to_dict();
+        }
+        dict
     }
 }
 
@@ -170,6 +147,30 @@ impl FormTrait for FormBase {
     fn to_dict(&self) -> Value {
         self.to_dict()
     }
+
+    fn get_references(&self) -> Vec<GlobalID> {
+        Vec::new()
+    }
+
+    fn remove_references(&mut self, _target: GlobalID) -> usize {
+        0
+    }
+
+    fn remap_references(&mut self, _remap: &dyn Fn(GlobalID) -> Option<GlobalID>) -> usize {
+        0
+    }
+
+    fn set_form_id(&mut self, form_id: FormID) {
+        self.form_id = form_id;
+    }
+
+    fn form_metadata(&self) -> FormMetadata {
+        self.metadata.clone()
+    }
+
+    fn set_form_metadata(&mut self, metadata: FormMetadata) {
+        self.metadata = metadata;
+    }
 }
 
 /// Display implementation for `FormBase`
@@ -179,7 +180,7 @@ impl fmt::Display for FormBase {
             f,
             "FormBase {{ \nform_id: {}, \nform_type: {}, \nform_name: {} \n}}",
             self.form_id.to_string(),
-            self.form_type.to_string(),
+            self.form_type,
             self.form_name.to_string()
         )
     }
@@ -192,7 +193,7 @@ impl fmt::Debug for FormBase {
             f,
             "FormBase {{ \nform_id: {}, \nform_type: {}, \nform_name: {}, \nbyte_count: {} \n}}",
             self.form_id.to_string(),
-            self.form_type.to_string(),
+            self.form_type,
             self.form_name,
             self.get_byte_count()
         )
@@ -0,0 +1,794 @@
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::sync::Arc;
+
+use serde_json::{Map, Value};
+
+use crate::core::structs::{forms::*, types::*};
+
+/// A value of a `FormDynamic` field.
+#[derive(Debug, PartialEq, Clone)]
+pub enum DynamicValue {
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    I32(i32),
+    F32(f32),
+    StrSml(StrSml),
+    StrLrg(StrLrg),
+    FormID(FormID),
+    GlobalID(GlobalID),
+    Vec2Int(Vec2Int),
+    Vec3Int(Vec3Int),
+    Vec2Float(Vec2Float),
+    Vec3Float(Vec3Float),
+    SmlColor(SmlColor),
+    LrgColor(LrgColor),
+    LangCode(LangCode),
+    EntID(EntID),
+    EntInstance(EntInstance),
+    ArchiveID(ArchiveID),
+    Version(Version),
+    FormType(FormType),
+    Array(Vec<DynamicValue>),
+}
+
+/// Runs `$scalar` on the inner value of every non-array variant, or `$array` on the items of an array.
+macro_rules! match_dynamic_value {
+    ($value:expr, |$inner:ident| $scalar:expr, |$items:ident| $array:expr) => {
+        match $value {
+            DynamicValue::U8($inner) => $scalar,
+            DynamicValue::U16($inner) => $scalar,
+            DynamicValue::U32($inner) => $scalar,
+            DynamicValue::I32($inner) => $scalar,
+            DynamicValue::F32($inner) => $scalar,
+            DynamicValue::StrSml($inner) => $scalar,
+            DynamicValue::StrLrg($inner) => $scalar,
+            DynamicValue::FormID($inner) => $scalar,
+            DynamicValue::GlobalID($inner) => $scalar,
+            DynamicValue::Vec2Int($inner) => $scalar,
+            DynamicValue::Vec3Int($inner) => $scalar,
+            DynamicValue::Vec2Float($inner) => $scalar,
+            DynamicValue::Vec3Float($inner) => $scalar,
+            DynamicValue::SmlColor($inner) => $scalar,
+            DynamicValue::LrgColor($inner) => $scalar,
+            DynamicValue::LangCode($inner) => $scalar,
+            DynamicValue::EntID($inner) => $scalar,
+            DynamicValue::EntInstance($inner) => $scalar,
+            DynamicValue::ArchiveID($inner) => $scalar,
+            DynamicValue::Version($inner) => $scalar,
+            DynamicValue::FormType($inner) => $scalar,
+            DynamicValue::Array($items) => $array,
+        }
+    };
+}
+
+/// Reads a value of each non-array field type with its `FormField` reader, `$read` being
+/// `field_read_from_bytes` or `field_read_from_byte_buffer`.
+macro_rules! read_dynamic_scalar {
+    ($field_type:expr, $source:expr, $read:ident, $wrap_result:expr) => {
+        match $field_type {
+            SchemaFieldType::U8 => $wrap_result(<u8 as FormField>::$read($source), DynamicValue::U8),
+            SchemaFieldType::U16 => $wrap_result(<u16 as FormField>::$read($source), DynamicValue::U16),
+            SchemaFieldType::U32 => $wrap_result(<u32 as FormField>::$read($source), DynamicValue::U32),
+            SchemaFieldType::I32 => $wrap_result(<i32 as FormField>::$read($source), DynamicValue::I32),
+            SchemaFieldType::F32 => $wrap_result(<f32 as FormField>::$read($source), DynamicValue::F32),
+            SchemaFieldType::StrSml => $wrap_result(<StrSml as FormField>::$read($source), DynamicValue::StrSml),
+            SchemaFieldType::StrLrg => $wrap_result(<StrLrg as FormField>::$read($source), DynamicValue::StrLrg),
+            SchemaFieldType::FormID => $wrap_result(<FormID as FormField>::$read($source), DynamicValue::FormID),
+            SchemaFieldType::GlobalID => $wrap_result(<GlobalID as FormField>::$read($source), DynamicValue::GlobalID),
+            SchemaFieldType::Vec2Int => $wrap_result(<Vec2Int as FormField>::$read($source), DynamicValue::Vec2Int),
+            SchemaFieldType::Vec3Int => $wrap_result(<Vec3Int as FormField>::$read($source), DynamicValue::Vec3Int),
+            SchemaFieldType::Vec2Float => $wrap_result(<Vec2Float as FormField>::$read($source), DynamicValue::Vec2Float),
+            SchemaFieldType::Vec3Float => $wrap_result(<Vec3Float as FormField>::$read($source), DynamicValue::Vec3Float),
+            SchemaFieldType::SmlColor => $wrap_result(<SmlColor as FormField>::$read($source), DynamicValue::SmlColor),
+            SchemaFieldType::LrgColor => $wrap_result(<LrgColor as FormField>::$read($source), DynamicValue::LrgColor),
+            SchemaFieldType::EntID => $wrap_result(<EntID as FormField>::$read($source), DynamicValue::EntID),
+            SchemaFieldType::EntInstance => $wrap_result(<EntInstance as FormField>::$read($source), DynamicValue::EntInstance),
+            SchemaFieldType::ArchiveID => $wrap_result(<ArchiveID as FormField>::$read($source), DynamicValue::ArchiveID),
+            SchemaFieldType::Version => $wrap_result(<Version as FormField>::$read($source), DynamicValue::Version),
+            SchemaFieldType::FormType => $wrap_result(<FormType as FormField>::$read($source), DynamicValue::FormType),
+            SchemaFieldType::LangCode | SchemaFieldType::Array(..) => unreachable!("language codes and arrays are checked before reading"),
+        }
+    };
+}
+
+/// Length of an array field's prefix in bytes, 0 for arrays without one.
+fn array_prefix_byte_count(length: &SchemaArrayLength) -> usize {
+    match length {
+        SchemaArrayLength::U8 => 1,
+        SchemaArrayLength::U16 => 2,
+        SchemaArrayLength::U32 => 4,
+        SchemaArrayLength::Fixed(_) | SchemaArrayLength::CountOf(_) => 0,
+    }
+}
+
+/// Largest item count an array field can store.
+fn array_max_count(length: &SchemaArrayLength) -> usize {
+    match length {
+        SchemaArrayLength::U8 => u8::MAX as usize,
+        SchemaArrayLength::U16 => u16::MAX as usize,
+        SchemaArrayLength::U32 | SchemaArrayLength::CountOf(_) => u32::MAX as usize,
+        SchemaArrayLength::Fixed(fixed) => *fixed as usize,
+    }
+}
+
+/// Returns whether items can be dropped from arrays of this length, which have their own prefix.
+fn array_is_prefixed(length: &SchemaArrayLength) -> bool {
+    array_prefix_byte_count(length) > 0
+}
+
+/// Returns whether values of the field type hold a reference to another form.
+fn is_reference_type(field_type: &SchemaFieldType) -> bool {
+    matches!(field_type, SchemaFieldType::GlobalID | SchemaFieldType::EntID | SchemaFieldType::EntInstance)
+}
+
+/// Converts a language byte to a `LangCode`, failing instead of panicking on unknown bytes.
+fn lang_code_from_byte(byte: u8) -> io::Result<DynamicValue> {
+    match byte {
+        1..=4 => Ok(DynamicValue::LangCode(LangCode::from(byte))),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown language code {}", byte))),
+    }
+}
+
+#[allow(unused)]
+impl DynamicValue {
+    /// Returns whether the value has the shape of a field type, including array lengths.
+    ///
+    /// Arrays sharing another field's length are checked against it by `FormDynamic::validate`.
+    pub fn matches(&self, field_type: &SchemaFieldType) -> bool {
+        match (self, field_type) {
+            (DynamicValue::Array(items), SchemaFieldType::Array(length, element)) => {
+                let count_fits = match length {
+                    SchemaArrayLength::Fixed(fixed) => items.len() == *fixed as usize,
+                    _ => items.len() <= array_max_count(length),
+                };
+                count_fits && items.iter().all(|item| item.matches(element))
+            }
+            (DynamicValue::U8(_), SchemaFieldType::U8)
+            | (DynamicValue::U16(_), SchemaFieldType::U16)
+            | (DynamicValue::U32(_), SchemaFieldType::U32)
+            | (DynamicValue::I32(_), SchemaFieldType::I32)
+            | (DynamicValue::F32(_), SchemaFieldType::F32)
+            | (DynamicValue::StrSml(_), SchemaFieldType::StrSml)
+            | (DynamicValue::StrLrg(_), SchemaFieldType::StrLrg)
+            | (DynamicValue::FormID(_), SchemaFieldType::FormID)
+            | (DynamicValue::GlobalID(_), SchemaFieldType::GlobalID)
+            | (DynamicValue::Vec2Int(_), SchemaFieldType::Vec2Int)
+            | (DynamicValue::Vec3Int(_), SchemaFieldType::Vec3Int)
+            | (DynamicValue::Vec2Float(_), SchemaFieldType::Vec2Float)
+            | (DynamicValue::Vec3Float(_), SchemaFieldType::Vec3Float)
+            | (DynamicValue::SmlColor(_), SchemaFieldType::SmlColor)
+            | (DynamicValue::LrgColor(_), SchemaFieldType::LrgColor)
+            | (DynamicValue::LangCode(_), SchemaFieldType::LangCode)
+            | (DynamicValue::EntID(_), SchemaFieldType::EntID)
+            | (DynamicValue::EntInstance(_), SchemaFieldType::EntInstance)
+            | (DynamicValue::ArchiveID(_), SchemaFieldType::ArchiveID)
+            | (DynamicValue::Version(_), SchemaFieldType::Version)
+            | (DynamicValue::FormType(_), SchemaFieldType::FormType) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the number of bytes the value serializes to as a field of `field_type`.
+    pub fn get_byte_count(&self, field_type: &SchemaFieldType) -> usize {
+        match (self, field_type) {
+            (DynamicValue::Array(items), SchemaFieldType::Array(length, element)) => {
+                array_prefix_byte_count(length) + items.iter().map(|item| item.get_byte_count(element)).sum::<usize>()
+            }
+            (value, _) => match_dynamic_value!(value, |inner| inner.field_byte_count(), |_items| 0),
+        }
+    }
+
+    /// Appends the value to `bytes` as a field of `field_type`, which it must match.
+    pub fn write_bytes(&self, field_type: &SchemaFieldType, bytes: &mut Vec<u8>) {
+        match (self, field_type) {
+            (DynamicValue::Array(items), SchemaFieldType::Array(length, element)) => {
+                match length {
+                    SchemaArrayLength::U8 => bytes.push(items.len() as u8),
+                    SchemaArrayLength::U16 => bytes.extend_from_slice(&(items.len() as u16).to_be_bytes()),
+                    SchemaArrayLength::U32 => bytes.extend_from_slice(&(items.len() as u32).to_be_bytes()),
+                    SchemaArrayLength::Fixed(_) | SchemaArrayLength::CountOf(_) => {}
+                }
+                for item in items {
+                    item.write_bytes(element, bytes);
+                }
+            }
+            (value, _) => match_dynamic_value!(value, |inner| inner.field_to_bytes(bytes), |_items| {}),
+        }
+    }
+
+    /// Reads a value of `field_type` from a file.
+    ///
+    /// Arrays sharing another field's length are read by `FormDynamic`, which knows that length.
+    pub fn read_from_bytes(field_type: &SchemaFieldType, file: &mut File) -> io::Result<Self> {
+        if let SchemaFieldType::Array(length, element) = field_type {
+            let count = match length {
+                SchemaArrayLength::U8 => u8::field_read_from_bytes(file)? as usize,
+                SchemaArrayLength::U16 => u16::field_read_from_bytes(file)? as usize,
+                SchemaArrayLength::U32 => u32::field_read_from_bytes(file)? as usize,
+                SchemaArrayLength::Fixed(fixed) => *fixed as usize,
+                SchemaArrayLength::CountOf(field_name) => return Err(shared_length_error(field_name)),
+            };
+            return Self::read_items_from_bytes(element, count, file);
+        }
+        if *field_type == SchemaFieldType::LangCode {
+            return lang_code_from_byte(u8::field_read_from_bytes(file)?);
+        }
+
+        read_dynamic_scalar!(field_type, &mut *file, field_read_from_bytes, |result: io::Result<_>, wrap: fn(_) -> DynamicValue| result.map(wrap))
+    }
+
+    /// Reads a value of `field_type` from the start of a byte buffer, returning the bytes consumed.
+    ///
+    /// Arrays sharing another field's length are read by `FormDynamic`, which knows that length.
+    pub fn read_from_byte_buffer(field_type: &SchemaFieldType, bytes: &[u8]) -> io::Result<(Self, usize)> {
+        if let SchemaFieldType::Array(length, element) = field_type {
+            let (count, offset) = match length {
+                SchemaArrayLength::U8 => u8::field_read_from_byte_buffer(bytes).map(|(count, consumed)| (count as usize, consumed))?,
+                SchemaArrayLength::U16 => u16::field_read_from_byte_buffer(bytes).map(|(count, consumed)| (count as usize, consumed))?,
+                SchemaArrayLength::U32 => u32::field_read_from_byte_buffer(bytes).map(|(count, consumed)| (count as usize, consumed))?,
+                SchemaArrayLength::Fixed(fixed) => (*fixed as usize, 0),
+                SchemaArrayLength::CountOf(field_name) => return Err(shared_length_error(field_name)),
+            };
+            let (items, consumed) = Self::read_items_from_byte_buffer(element, count, &bytes[offset..])?;
+            return Ok((items, offset + consumed));
+        }
+        if *field_type == SchemaFieldType::LangCode {
+            let (byte, consumed) = u8::field_read_from_byte_buffer(bytes)?;
+            return Ok((lang_code_from_byte(byte)?, consumed));
+        }
+
+        read_dynamic_scalar!(field_type, bytes, field_read_from_byte_buffer, |result: io::Result<(_, usize)>, wrap: fn(_) -> DynamicValue| result.map(|(value, consumed)| (wrap(value), consumed)))
+    }
+
+    /// Reads `count` array items of type `element` from a file.
+    fn read_items_from_bytes(element: &SchemaFieldType, count: usize, file: &mut File) -> io::Result<Self> {
+        let mut items = Vec::new();
+        for _ in 0..count {
+            items.push(Self::read_from_bytes(element, file)?);
+        }
+        Ok(DynamicValue::Array(items))
+    }
+
+    /// Reads `count` array items of type `element` from the start of a byte buffer, returning the bytes consumed.
+    fn read_items_from_byte_buffer(element: &SchemaFieldType, count: usize, bytes: &[u8]) -> io::Result<(Self, usize)> {
+        let mut items = Vec::new();
+        let mut offset = 0;
+        for _ in 0..count {
+            let (item, consumed) = Self::read_from_byte_buffer(element, &bytes[offset..])?;
+            items.push(item);
+            offset += consumed;
+        }
+        Ok((DynamicValue::Array(items), offset))
+    }
+
+    /// Converts the value to its JSON representation in `to_dict`.
+    pub fn to_dict(&self) -> Value {
+        match_dynamic_value!(self, |inner| inner.field_to_dict(), |items| Value::Array(items.iter().map(DynamicValue::to_dict).collect()))
+    }
+
+    /// Reads a value of `field_type` from its JSON representation, the shape `to_dict` writes.
+    pub fn from_dict(field_type: &SchemaFieldType, dict: &Value) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidInput, format!("{} is not a valid {}.", dict, field_type));
+        let number = |max: u64| dict.as_u64().filter(|number| *number <= max).ok_or_else(invalid);
+        let text = || dict.as_str().ok_or_else(invalid);
+        let component = |key: &str| dict.get(key).ok_or_else(invalid);
+        let int_component = |key: &str| component(key)?.as_i64().and_then(|number| i32::try_from(number).ok()).ok_or_else(invalid);
+        let float_component = |key: &str| component(key)?.as_f64().map(|number| number as f32).ok_or_else(invalid);
+        let nested = |field_type: &SchemaFieldType, key: &str| Self::from_dict(field_type, component(key)?);
+
+        Ok(match field_type {
+            SchemaFieldType::U8 => DynamicValue::U8(number(u8::MAX as u64)? as u8),
+            SchemaFieldType::U16 => DynamicValue::U16(number(u16::MAX as u64)? as u16),
+            SchemaFieldType::U32 => DynamicValue::U32(number(u32::MAX as u64)? as u32),
+            SchemaFieldType::I32 => DynamicValue::I32(dict.as_i64().and_then(|number| i32::try_from(number).ok()).ok_or_else(invalid)?),
+            SchemaFieldType::F32 => DynamicValue::F32(dict.as_f64().ok_or_else(invalid)? as f32),
+            SchemaFieldType::StrSml => DynamicValue::StrSml(read_str_sml(text()?)?),
+            SchemaFieldType::StrLrg => {
+                let value = text()?;
+                if value.encode_utf16().count() > u16::MAX as usize || value.chars().any(|character| character.len_utf16() > 1) {
+                    return Err(invalid());
+                }
+                DynamicValue::StrLrg(StrLrg::from(value))
+            }
+            SchemaFieldType::FormID => {
+                let value = text()?;
+                match value.parse::<u16>() {
+                    Ok(_) if value.len() == 5 && value.bytes().all(|byte| byte.is_ascii_digit()) => DynamicValue::FormID(FormID::from(value)),
+                    _ => return Err(invalid()),
+                }
+            }
+            SchemaFieldType::GlobalID => {
+                let value = text()?;
+                if !is_id_string(value, &[3, 5]) {
+                    return Err(invalid());
+                }
+                DynamicValue::GlobalID(GlobalID::from(value))
+            }
+            SchemaFieldType::EntID => {
+                let value = text()?;
+                if !is_id_string(value, &[3, 5, 5]) {
+                    return Err(invalid());
+                }
+                DynamicValue::EntID(EntID::from(value))
+            }
+            SchemaFieldType::EntInstance => {
+                let (DynamicValue::EntID(entity_id), DynamicValue::Vec3Float(position), DynamicValue::Vec3Float(rotation), DynamicValue::F32(scale)) = (
+                    nested(&SchemaFieldType::EntID, "entity_id")?,
+                    nested(&SchemaFieldType::Vec3Float, "position")?,
+                    nested(&SchemaFieldType::Vec3Float, "rotation")?,
+                    nested(&SchemaFieldType::F32, "scale")?,
+                ) else {
+                    unreachable!("from_dict returns the variant of the type it is given")
+                };
+                DynamicValue::EntInstance(EntInstance { entity_id, position, rotation, scale })
+            }
+            SchemaFieldType::ArchiveID => {
+                let value = text()?;
+                if !is_id_string(value, &[3]) {
+                    return Err(invalid());
+                }
+                DynamicValue::ArchiveID(ArchiveID::from(value))
+            }
+            SchemaFieldType::Version => {
+                let (major, minor) = text()?.split_once('.').ok_or_else(invalid)?;
+                match (major.parse::<u8>(), minor.parse::<u8>()) {
+                    (Ok(major), Ok(minor)) => DynamicValue::Version(Version::from((major, minor))),
+                    _ => return Err(invalid()),
+                }
+            }
+            SchemaFieldType::FormType => {
+                let value = text()?;
+                let unknown = value.strip_prefix("UNKNOWN(").and_then(|byte| byte.strip_suffix(')')).and_then(|byte| byte.parse::<u8>().ok());
+                match (get_form_type_by_name(value), unknown) {
+                    (Some(form_type), _) => DynamicValue::FormType(form_type),
+                    (None, Some(byte)) => DynamicValue::FormType(FormType::from(byte)),
+                    (None, None) => return Err(invalid()),
+                }
+            }
+            SchemaFieldType::Vec2Int => DynamicValue::Vec2Int(Vec2Int::from((int_component("x")?, int_component("y")?))),
+            SchemaFieldType::Vec3Int => DynamicValue::Vec3Int(Vec3Int::from((int_component("x")?, int_component("y")?, int_component("z")?))),
+            SchemaFieldType::Vec2Float => DynamicValue::Vec2Float(Vec2Float::from((float_component("x")?, float_component("y")?))),
+            SchemaFieldType::Vec3Float => DynamicValue::Vec3Float(Vec3Float::from((float_component("x")?, float_component("y")?, float_component("z")?))),
+            SchemaFieldType::SmlColor => {
+                let [r, g, b, a] = parse_rgba(text()?, u8::MAX as u64).ok_or_else(invalid)?;
+                DynamicValue::SmlColor(SmlColor::from((r as u8, g as u8, b as u8, a as u8)))
+            }
+            SchemaFieldType::LrgColor => {
+                let [r, g, b, a] = parse_rgba(text()?, u16::MAX as u64).ok_or_else(invalid)?;
+                DynamicValue::LrgColor(LrgColor::from((r as u16, g as u16, b as u16, a as u16)))
+            }
+            SchemaFieldType::LangCode => match text()? {
+                code @ ("EN" | "FR" | "ES" | "DE") => DynamicValue::LangCode(LangCode::from(code)),
+                _ => return Err(invalid()),
+            },
+            SchemaFieldType::Array(_, element) => {
+                let items = dict.as_array().ok_or_else(invalid)?;
+                let items = items.iter().map(|item| Self::from_dict(element, item)).collect::<io::Result<Vec<_>>>()?;
+                let value = DynamicValue::Array(items);
+                if !value.matches(field_type) {
+                    return Err(invalid());
+                }
+                value
+            }
+        })
+    }
+
+    /// Returns the form a `GlobalID`, `EntID` or `EntInstance` value references.
+    pub fn get_reference(&self) -> Option<GlobalID> {
+        match self {
+            DynamicValue::GlobalID(global_id) => Some(*global_id),
+            DynamicValue::EntID(ent_id) => Some(ent_id.global_id()),
+            DynamicValue::EntInstance(instance) => Some(instance.entity_id.global_id()),
+            _ => None,
+        }
+    }
+
+    /// Points a `GlobalID`, `EntID` or `EntInstance` value at another form, keeping an entity's reference ID.
+    fn set_reference(&mut self, reference: GlobalID) {
+        match self {
+            DynamicValue::GlobalID(global_id) => *global_id = reference,
+            DynamicValue::EntID(ent_id) => *ent_id = EntID::from((reference, ent_id.reference_id())),
+            DynamicValue::EntInstance(instance) => instance.entity_id = EntID::from((reference, instance.entity_id.reference_id())),
+            _ => {}
+        }
+    }
+
+    /// Collects every non-null reference in the value.
+    fn collect_references(&self, references: &mut Vec<GlobalID>) {
+        match self {
+            DynamicValue::Array(items) => items.iter().for_each(|item| item.collect_references(references)),
+            value => references.extend(value.get_reference().filter(|reference| !reference.is_null())),
+        }
+    }
+
+    /// Removes `target` from the value: nulled in single values and unprefixed arrays, dropped from prefixed arrays.
+    fn remove_references(&mut self, field_type: &SchemaFieldType, target: GlobalID) -> usize {
+        match (self, field_type) {
+            (DynamicValue::Array(items), SchemaFieldType::Array(length, element)) => {
+                let mut removed = 0;
+                if array_is_prefixed(length) && is_reference_type(element) {
+                    let count = items.len();
+                    items.retain(|item| item.get_reference() != Some(target));
+                    removed += count - items.len();
+                }
+                removed + items.iter_mut().map(|item| item.remove_references(element, target)).sum::<usize>()
+            }
+            (value, _) if value.get_reference() == Some(target) => {
+                value.set_reference(GlobalID::NULL);
+                1
+            }
+            _ => 0,
+        }
+    }
+
+    /// Replaces every reference the remap returns a new `GlobalID` for.
+    fn remap_references(&mut self, remap: &dyn Fn(GlobalID) -> Option<GlobalID>) -> usize {
+        match self {
+            DynamicValue::Array(items) => items.iter_mut().map(|item| item.remap_references(remap)).sum(),
+            value => match value.get_reference().filter(|reference| !reference.is_null()).and_then(remap) {
+                Some(new_reference) => {
+                    value.set_reference(new_reference);
+                    1
+                }
+                None => 0,
+            },
+        }
+    }
+}
+
+/// Returns whether `value` is all digits, split into parts of the given lengths that each fit their ID type.
+fn is_id_string(value: &str, part_lengths: &[usize]) -> bool {
+    if value.len() != part_lengths.iter().sum::<usize>() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+        return false;
+    }
+    let mut offset = 0;
+    part_lengths.iter().all(|part_length| {
+        let part = &value[offset..offset + part_length];
+        offset += part_length;
+        match part_length {
+            3 => part.parse::<u8>().is_ok(),
+            _ => part.parse::<u16>().is_ok(),
+        }
+    })
+}
+
+fn shared_length_error(field_name: &StrSml) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("Array sharing the length of {} can only be read as a form field.", field_name.to_string()))
+}
+
+/// Parses `RGBA(r, g, b, a)` with every component at most `max`.
+fn parse_rgba(text: &str, max: u64) -> Option<[u64; 4]> {
+    let inner = text.strip_prefix("RGBA(")?.strip_suffix(')')?;
+    let components = inner.split(',').map(|component| component.trim().parse::<u64>().ok().filter(|value| *value <= max)).collect::<Option<Vec<u64>>>()?;
+    components.try_into().ok()
+}
+
+/// A form of a type described by a `FormSchema` instead of a compiled struct.
+///
+/// Its schema comes from the form type registry, where `register_form_schema` or loading an
+/// archive's schemas puts it, so any reader can decode, `to_dict` and validate the form.
+/// Compiled types registered with their schema can be read as `FormDynamic` too.
+#[derive(PartialEq, Clone)]
+pub struct FormDynamic {
+    pub base: FormBase,
+    pub schema: Arc<FormSchema>,
+    pub values: Vec<DynamicValue>,
+}
+
+#[allow(unused)]
+impl FormDynamic {
+    /// Creates a form of a schema-described type, failing if the values don't match its schema.
+    pub fn new(form_id: FormID, form_type: FormType, form_name: StrSml, values: Vec<DynamicValue>) -> io::Result<Self> {
+        let form = Self {
+            base: FormBase::new(form_id, form_type, form_name),
+            schema: get_form_schema(form_type)?,
+            values,
+        };
+        form.validate()?;
+        Ok(form)
+    }
+
+    /// Checks the form has one value matching each field of its schema, with shared array lengths equal.
+    pub fn validate(&self) -> io::Result<()> {
+        if self.values.len() != self.schema.fields.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} forms have {} fields, got {} values.", self.schema.name.to_string(), self.schema.fields.len(), self.values.len())));
+        }
+        for (field, value) in self.schema.fields.iter().zip(&self.values) {
+            if !value.matches(&field.field_type) {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Field {} of {} must be a {}.", field.name.to_string(), self.schema.name.to_string(), field.field_type)));
+            }
+            if let (SchemaFieldType::Array(SchemaArrayLength::CountOf(count_field), _), DynamicValue::Array(items)) = (&field.field_type, value) {
+                if items.len() != self.get_shared_count(count_field)? {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Field {} of {} must have as many items as {}.", field.name.to_string(), self.schema.name.to_string(), count_field.to_string())));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the value of a field by name.
+    pub fn get_value(&self, name: &str) -> Option<&DynamicValue> {
+        self.schema.get_field_index(name).map(|index| &self.values[index])
+    }
+
+    /// Sets the value of a field by name, failing and leaving the form unchanged if the field
+    /// doesn't exist or the form would no longer match its schema.
+    pub fn set_value(&mut self, name: &str, value: DynamicValue) -> io::Result<()> {
+        let Some(index) = self.schema.get_field_index(name) else {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} forms have no field {}.", self.schema.name.to_string(), name)));
+        };
+        let previous = std::mem::replace(&mut self.values[index], value);
+        if let Err(error) = self.validate() {
+            self.values[index] = previous;
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    pub fn get_byte_count(&self) -> usize {
+        self.base.get_byte_count()
+            + self.schema.fields.iter().zip(&self.values).map(|(field, value)| value.get_byte_count(&field.field_type)).sum::<usize>()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.base.to_bytes();
+        bytes.reserve(self.get_byte_count() - bytes.len());
+        for (field, value) in self.schema.fields.iter().zip(&self.values) {
+            value.write_bytes(&field.field_type, &mut bytes);
+        }
+        bytes
+    }
+
+    /// Converts the form to JSON, keyed by the schema's field names.
+    pub fn to_dict(&self) -> Value {
+        let mut dict = Map::new();
+        dict.insert("form_id".to_string(), Value::from(self.base.form_id.to_string()));
+        dict.insert("form_type".to_string(), Value::from(self.schema.name.to_string()));
+        dict.insert("form_name".to_string(), Value::from(self.base.form_name.to_string()));
+        for (field, value) in self.schema.fields.iter().zip(&self.values) {
+            dict.insert(field.name.to_string(), value.to_dict());
+        }
+        if !self.base.metadata.is_empty() {
+            dict.insert("metadata".to_string(), self.base.metadata.to_dict());
+        }
+        Value::Object(dict)
+    }
+
+    /// Reads a form from JSON in the shape `to_dict` writes, its `form_type` being a registered schema's name.
+    pub fn from_dict(dict: &Value) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid dynamic form: {}", message));
+
+        let form_type_name = dict.get("form_type").and_then(Value::as_str).ok_or_else(|| invalid("form_type must be a schema name"))?;
+        let form_type = get_form_type_by_name(form_type_name).ok_or_else(|| invalid(&format!("no form type is registered as {}", form_type_name)))?;
+        let schema = get_form_schema(form_type)?;
+
+        let form_id = DynamicValue::from_dict(&SchemaFieldType::FormID, dict.get("form_id").unwrap_or(&Value::Null))?;
+        let DynamicValue::FormID(form_id) = form_id else { unreachable!() };
+        let form_name = read_str_sml(dict.get("form_name").and_then(Value::as_str).ok_or_else(|| invalid("form_name must be a string"))?)?;
+
+        let mut values = Vec::with_capacity(schema.fields.len());
+        for field in &schema.fields {
+            let name = field.name.to_string();
+            let field_dict = dict.get(&name).ok_or_else(|| invalid(&format!("missing field {}", name)))?;
+            values.push(DynamicValue::from_dict(&field.field_type, field_dict)?);
+        }
+
+        let mut base = FormBase::new(form_id, form_type, form_name);
+        if let Some(metadata) = dict.get("metadata") {
+            base.metadata = FormMetadata::from_dict(metadata)?;
+        }
+
+        let form = Self { base, schema, values };
+        form.validate()?;
+        Ok(form)
+    }
+
+    /// Reads a form from a file, decoding its fields with the registered schema of its type.
+    pub fn read_from_bytes(file: &mut File) -> io::Result<Self> {
+        let base = Self::read_base_from_bytes(file)?;
+        let schema = get_form_schema(base.form_type)?;
+        Self::read_fields_from_bytes(base, schema, file)
+    }
+
+    /// Reads a form from a file with the given schema, whatever its type is registered as.
+    ///
+    /// Lets tools decode any form from the schemas an archive embeds, without registering them.
+    pub fn read_from_bytes_with_schema(file: &mut File, schema: Arc<FormSchema>) -> io::Result<Self> {
+        let base = Self::read_base_from_bytes(file)?;
+        Self::read_fields_from_bytes(base, schema, file)
+    }
+
+    /// Reads a form from the start of a byte buffer, returning the bytes consumed.
+    pub fn read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        let (base, offset) = Self::read_base_from_byte_buffer(bytes)?;
+        let schema = get_form_schema(base.form_type)?;
+        Self::read_fields_from_byte_buffer(base, schema, bytes, offset)
+    }
+
+    /// Reads a form from the start of a byte buffer with the given schema, returning the bytes consumed.
+    pub fn read_from_byte_buffer_with_schema(bytes: &[u8], schema: Arc<FormSchema>) -> io::Result<(Self, usize)> {
+        let (base, offset) = Self::read_base_from_byte_buffer(bytes)?;
+        Self::read_fields_from_byte_buffer(base, schema, bytes, offset)
+    }
+
+    fn read_base_from_bytes(file: &mut File) -> io::Result<FormBase> {
+        let form_id = FormID::field_read_from_bytes(file)?;
+        let form_type = FormType::field_read_from_bytes(file)?;
+        let form_name = StrSml::read_from_bytes(file)?;
+        Ok(FormBase::new(form_id, form_type, form_name))
+    }
+
+    fn read_base_from_byte_buffer(bytes: &[u8]) -> io::Result<(FormBase, usize)> {
+        let (form_id, mut offset) = FormID::field_read_from_byte_buffer(bytes)?;
+        let (form_type, consumed) = FormType::field_read_from_byte_buffer(&bytes[offset..])?;
+        offset += consumed;
+        let (form_name, consumed) = StrSml::read_from_byte_buffer(&bytes[offset..])?;
+        offset += consumed;
+        Ok((FormBase::new(form_id, form_type, form_name), offset))
+    }
+
+    fn read_fields_from_bytes(base: FormBase, schema: Arc<FormSchema>, file: &mut File) -> io::Result<Self> {
+        let mut form = Self { base, schema: schema.clone(), values: Vec::with_capacity(schema.fields.len()) };
+        for field in &schema.fields {
+            let value = match &field.field_type {
+                SchemaFieldType::Array(SchemaArrayLength::CountOf(count_field), element) => {
+                    DynamicValue::read_items_from_bytes(element, form.get_shared_count(count_field)?, file)?
+                }
+                field_type => DynamicValue::read_from_bytes(field_type, file)?,
+            };
+            form.values.push(value);
+        }
+        Ok(form)
+    }
+
+    fn read_fields_from_byte_buffer(base: FormBase, schema: Arc<FormSchema>, bytes: &[u8], mut offset: usize) -> io::Result<(Self, usize)> {
+        let mut form = Self { base, schema: schema.clone(), values: Vec::with_capacity(schema.fields.len()) };
+        for field in &schema.fields {
+            let (value, consumed) = match &field.field_type {
+                SchemaFieldType::Array(SchemaArrayLength::CountOf(count_field), element) => {
+                    DynamicValue::read_items_from_byte_buffer(element, form.get_shared_count(count_field)?, &bytes[offset..])?
+                }
+                field_type => DynamicValue::read_from_byte_buffer(field_type, &bytes[offset..])?,
+            };
+            form.values.push(value);
+            offset += consumed;
+        }
+        Ok((form, offset))
+    }
+
+    /// Returns the item count of the array field another field shares its length with.
+    fn get_shared_count(&self, count_field: &StrSml) -> io::Result<usize> {
+        let index = self.schema.fields.iter().position(|field| field.name == *count_field);
+        match index.and_then(|index| self.values.get(index)) {
+            Some(DynamicValue::Array(items)) => Ok(items.len()),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} has no earlier array field {} to share the length of.", self.schema.name.to_string(), count_field.to_string()))),
+        }
+    }
+
+    /// Returns every reference held by `GlobalID`, `EntID` and `EntInstance` values, including those in arrays.
+    pub fn get_references(&self) -> Vec<GlobalID> {
+        let mut references = Vec::new();
+        for value in &self.values {
+            value.collect_references(&mut references);
+        }
+        references
+    }
+
+    /// Removes `target` from the form. Items of prefixed arrays are dropped, along with the matching
+    /// items of every field sharing the array's length; other references are nulled.
+    pub fn remove_references(&mut self, target: GlobalID) -> usize {
+        let schema = self.schema.clone();
+        let mut removed = 0;
+
+        for (index, field) in schema.fields.iter().enumerate() {
+            let SchemaFieldType::Array(length, element) = &field.field_type else { continue };
+            let DynamicValue::Array(items) = &self.values[index] else { continue };
+            if !array_is_prefixed(length) || !is_reference_type(element) {
+                continue;
+            }
+
+            let keep: Vec<bool> = items.iter().map(|item| item.get_reference() != Some(target)).collect();
+            if keep.iter().all(|keep| *keep) {
+                continue;
+            }
+            removed += keep.iter().filter(|keep| !**keep).count();
+
+            // Fields sharing the length, directly or through another sharing field, lose the same items
+            let mut sharing = vec![field.name.clone()];
+            for (shared_index, shared_field) in schema.fields.iter().enumerate() {
+                let is_sharing = match &shared_field.field_type {
+                    SchemaFieldType::Array(SchemaArrayLength::CountOf(count_field), _) => sharing.contains(count_field),
+                    _ => false,
+                };
+                if shared_index == index || is_sharing {
+                    if let DynamicValue::Array(shared_items) = &mut self.values[shared_index] {
+                        let mut keep_iter = keep.iter();
+                        shared_items.retain(|_| *keep_iter.next().unwrap_or(&true));
+                    }
+                    if is_sharing {
+                        sharing.push(shared_field.name.clone());
+                    }
+                }
+            }
+        }
+
+        removed + schema.fields.iter().zip(&mut self.values).map(|(field, value)| value.remove_references(&field.field_type, target)).sum::<usize>()
+    }
+
+    pub fn remap_references(&mut self, remap: &dyn Fn(GlobalID) -> Option<GlobalID>) -> usize {
+        self.values.iter_mut().map(|value| value.remap_references(remap)).sum()
+    }
+}
+
+/// Returns the registered schema of a form type, failing if the type has none.
+fn get_form_schema(form_type: FormType) -> io::Result<Arc<FormSchema>> {
+    match get_form_type_schema(form_type) {
+        Some(schema) => Ok(schema),
+        None => Err(io::Error::new(io::ErrorKind::InvalidData, format!("No form schema is registered for FormType {}", form_type.to_u8()))),
+    }
+}
+
+impl FormTrait for FormDynamic {
+    fn form_id(&self) -> FormID {
+        self.base.form_id
+    }
+
+    fn form_type(&self) -> FormType {
+        self.base.form_type
+    }
+
+    fn form_name(&self) -> StrSml {
+        self.base.form_name.clone()
+    }
+
+    fn to_dict(&self) -> Value {
+        self.to_dict()
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn get_byte_count(&self) -> usize {
+        self.get_byte_count()
+    }
+
+    fn get_references(&self) -> Vec<GlobalID> {
+        self.get_references()
+    }
+
+    fn remove_references(&mut self, target: GlobalID) -> usize {
+        self.remove_references(target)
+    }
+
+    fn remap_references(&mut self, remap: &dyn Fn(GlobalID) -> Option<GlobalID>) -> usize {
+        self.remap_references(remap)
+    }
+
+    fn set_form_id(&mut self, form_id: FormID) {
+        self.base.form_id = form_id;
+    }
+
+    fn form_metadata(&self) -> FormMetadata {
+        self.base.metadata.clone()
+    }
+
+    fn set_form_metadata(&mut self, metadata: FormMetadata) {
+        self.base.metadata = metadata;
+    }
+}
+
+impl fmt::Display for FormDynamic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FormDynamic {}", self.to_dict())
+    }
+}
+
+impl fmt::Debug for FormDynamic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
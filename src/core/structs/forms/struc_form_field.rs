@@ -0,0 +1,243 @@
+use std::fs::File;
+use std::io::{self, Read};
+
+use serde_json::Value;
+
+use crate::core::structs::forms::SchemaFieldType;
+use crate::core::structs::types::*;
+
+/// Derives the serialization and `FormTrait` impl of a form struct, see `smn_archive_derive`.
+pub use smn_archive_derive::Form;
+
+/// Re-exports used by the code `#[derive(Form)]` generates.
+#[doc(hidden)]
+pub mod derive_support {
+    pub use serde_json::{Map, Value};
+}
+
+/// A value that can be stored as a field of a `#[derive(Form)]` struct.
+pub trait FormField: Sized {
+    /// Returns the number of bytes the field serializes to.
+    fn field_byte_count(&self) -> usize;
+
+    /// Appends the serialized field to `bytes`.
+    fn field_to_bytes(&self, bytes: &mut Vec<u8>);
+
+    /// Reads the field from a file.
+    fn field_read_from_bytes(file: &mut File) -> io::Result<Self>;
+
+    /// Reads the field from the start of a byte buffer, returning the bytes consumed.
+    fn field_read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)>;
+
+    /// Converts the field to its JSON representation in `to_dict`.
+    fn field_to_dict(&self) -> Value;
+
+    /// Returns the type describing the field in a `FormSchema`.
+    fn schema_type() -> SchemaFieldType;
+}
+
+/// Takes the first `N` bytes of a buffer, failing if it is shorter.
+fn read_field_array<const N: usize>(bytes: &[u8], type_name: &str) -> io::Result<[u8; N]> {
+    match bytes.get(..N) {
+        Some(field_bytes) => Ok(field_bytes.try_into().unwrap()),
+        None => Err(io::Error::new(io::ErrorKind::UnexpectedEof, format!("Not enough bytes for {}", type_name))),
+    }
+}
+
+/// Implements `FormField` for a big-endian number, stored as a JSON number.
+macro_rules! impl_form_field_number {
+    ($type:ty, $schema:ident) => {
+        impl FormField for $type {
+            fn field_byte_count(&self) -> usize {
+                std::mem::size_of::<$type>()
+            }
+
+            fn field_to_bytes(&self, bytes: &mut Vec<u8>) {
+                bytes.extend_from_slice(&self.to_be_bytes());
+            }
+
+            fn field_read_from_bytes(file: &mut File) -> io::Result<Self> {
+                let mut buffer = [0u8; std::mem::size_of::<$type>()];
+                file.read_exact(&mut buffer)?;
+                Ok(<$type>::from_be_bytes(buffer))
+            }
+
+            fn field_read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)> {
+                let buffer = read_field_array::<{ std::mem::size_of::<$type>() }>(bytes, stringify!($type))?;
+                Ok((<$type>::from_be_bytes(buffer), std::mem::size_of::<$type>()))
+            }
+
+            fn field_to_dict(&self) -> Value {
+                Value::from(*self)
+            }
+
+            fn schema_type() -> SchemaFieldType {
+                SchemaFieldType::$schema
+            }
+        }
+    };
+}
+
+/// Implements `FormField` for a type with a fixed `BYTE_COUNT`, built from its byte array by `$from`.
+macro_rules! impl_form_field_fixed {
+    ($type:ident, $from:expr, |$value:ident| $dict:expr) => {
+        impl FormField for $type {
+            fn field_byte_count(&self) -> usize {
+                <$type>::BYTE_COUNT
+            }
+
+            fn field_to_bytes(&self, bytes: &mut Vec<u8>) {
+                bytes.extend_from_slice(&self.to_bytes());
+            }
+
+            fn field_read_from_bytes(file: &mut File) -> io::Result<Self> {
+                let mut buffer = [0u8; <$type>::BYTE_COUNT];
+                file.read_exact(&mut buffer)?;
+                Ok($from(buffer))
+            }
+
+            fn field_read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)> {
+                let buffer = read_field_array::<{ <$type>::BYTE_COUNT }>(bytes, stringify!($type))?;
+                Ok(($from(buffer), <$type>::BYTE_COUNT))
+            }
+
+            fn field_to_dict(&self) -> Value {
+                let $value = self;
+                $dict
+            }
+
+            fn schema_type() -> SchemaFieldType {
+                SchemaFieldType::$type
+            }
+        }
+    };
+}
+
+impl_form_field_number!(u8, U8);
+impl_form_field_number!(u16, U16);
+impl_form_field_number!(u32, U32);
+impl_form_field_number!(i32, I32);
+impl_form_field_number!(f32, F32);
+
+impl_form_field_fixed!(FormID, FormID::from, |form_id| Value::from(form_id.to_string()));
+impl_form_field_fixed!(ArchiveID, ArchiveID::from, |archive_id| Value::from(archive_id.to_string()));
+impl_form_field_fixed!(GlobalID, GlobalID::from, |global_id| Value::from(global_id.to_string()));
+impl_form_field_fixed!(EntID, EntID::from, |ent_id| Value::from(ent_id.to_string()));
+impl_form_field_fixed!(Version, Version::from, |version| Value::from(version.to_string()));
+impl_form_field_fixed!(SmlColor, SmlColor::read_from_byte_buffer, |color| Value::from(color.to_string()));
+impl_form_field_fixed!(LrgColor, LrgColor::from_bytes, |color| Value::from(color.to_string()));
+impl_form_field_fixed!(Vec2Int, Vec2Int::from, |vector| vector.to_dict());
+impl_form_field_fixed!(Vec3Int, Vec3Int::from, |vector| vector.to_dict());
+impl_form_field_fixed!(Vec2Float, Vec2Float::from, |vector| vector.to_dict());
+impl_form_field_fixed!(Vec3Float, Vec3Float::from, |vector| vector.to_dict());
+impl_form_field_fixed!(EntInstance, |buffer: [u8; EntInstance::BYTE_COUNT]| EntInstance::from_bytes(&buffer), |instance| instance.to_dict());
+
+impl FormField for FormType {
+    fn field_byte_count(&self) -> usize {
+        FormType::BYTE_COUNT
+    }
+
+    fn field_to_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.push(self.to_byte());
+    }
+
+    fn field_read_from_bytes(file: &mut File) -> io::Result<Self> {
+        let mut buffer = [0u8; FormType::BYTE_COUNT];
+        file.read_exact(&mut buffer)?;
+        Ok(FormType::from(buffer[0]))
+    }
+
+    fn field_read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        let buffer = read_field_array::<{ FormType::BYTE_COUNT }>(bytes, "FormType")?;
+        Ok((FormType::from(buffer[0]), FormType::BYTE_COUNT))
+    }
+
+    fn field_to_dict(&self) -> Value {
+        Value::from(self.to_string())
+    }
+
+    fn schema_type() -> SchemaFieldType {
+        SchemaFieldType::FormType
+    }
+}
+
+impl FormField for LangCode {
+    fn field_byte_count(&self) -> usize {
+        LangCode::BYTE_COUNT
+    }
+
+    fn field_to_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.push(self.to_byte());
+    }
+
+    fn field_read_from_bytes(file: &mut File) -> io::Result<Self> {
+        let mut buffer = [0u8; LangCode::BYTE_COUNT];
+        file.read_exact(&mut buffer)?;
+        Ok(LangCode::from(buffer[0]))
+    }
+
+    fn field_read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        let buffer = read_field_array::<{ LangCode::BYTE_COUNT }>(bytes, "LangCode")?;
+        Ok((LangCode::from(buffer[0]), LangCode::BYTE_COUNT))
+    }
+
+    fn field_to_dict(&self) -> Value {
+        Value::from(self.to_string())
+    }
+
+    fn schema_type() -> SchemaFieldType {
+        SchemaFieldType::LangCode
+    }
+}
+
+impl FormField for StrSml {
+    fn field_byte_count(&self) -> usize {
+        self.get_byte_count()
+    }
+
+    fn field_to_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_bytes());
+    }
+
+    fn field_read_from_bytes(file: &mut File) -> io::Result<Self> {
+        StrSml::read_from_bytes(file)
+    }
+
+    fn field_read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        StrSml::read_from_byte_buffer(bytes)
+    }
+
+    fn field_to_dict(&self) -> Value {
+        Value::from(self.to_string())
+    }
+
+    fn schema_type() -> SchemaFieldType {
+        SchemaFieldType::StrSml
+    }
+}
+
+impl FormField for StrLrg {
+    fn field_byte_count(&self) -> usize {
+        self.get_byte_count()
+    }
+
+    fn field_to_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_bytes());
+    }
+
+    fn field_read_from_bytes(file: &mut File) -> io::Result<Self> {
+        StrLrg::read_from_bytes(file)
+    }
+
+    fn field_read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        StrLrg::read_from_byte_buffer(bytes)
+    }
+
+    fn field_to_dict(&self) -> Value {
+        Value::from(self.to_string())
+    }
+
+    fn schema_type() -> SchemaFieldType {
+        SchemaFieldType::StrLrg
+    }
+}
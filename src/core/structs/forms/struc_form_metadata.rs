@@ -0,0 +1,235 @@
+use std::fmt;
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Map, Value};
+
+use crate::core::structs::types::*;
+
+/// Editor metadata of a form: flags, last-modified time, author and revision counter.
+///
+/// It is stored as a section after the form's bytes in its data record, and only when not empty,
+/// so forms without metadata keep their original record. The section is a `u8` entry count, then
+/// per entry a `u8` key, a `u16` length and the data. Entries with unknown keys are kept as they are.
+#[derive(PartialEq, Eq, Clone)]
+pub struct FormMetadata {
+    pub flags: u16,
+    pub modified: u64,           // Unix time in seconds, 0 when never set
+    pub author: StrSml,          // Author or tool that last modified the form
+    pub revision: u32,
+    pub extra: Vec<(u8, Vec<u8>)>,  // Entries of keys this library doesn't know, by key
+}
+
+#[allow(unused)]
+impl FormMetadata {
+    /// The form is kept in the archive but should be skipped at runtime.
+    pub const FLAG_DISABLED: u16 = 1 << 0;
+    /// The form is marked deleted and waiting to be removed.
+    pub const FLAG_DELETED: u16 = 1 << 1;
+    /// The form is only used by the editor.
+    pub const FLAG_EDITOR_ONLY: u16 = 1 << 2;
+    /// The form's text is localized through string forms.
+    pub const FLAG_LOCALIZED: u16 = 1 << 3;
+
+    pub const KEY_FLAGS: u8 = 1;
+    pub const KEY_MODIFIED: u8 = 2;
+    pub const KEY_AUTHOR: u8 = 3;
+    pub const KEY_REVISION: u8 = 4;
+
+    const FLAG_NAMES: [(u16, &'static str); 4] = [
+        (Self::FLAG_DISABLED, "DISABLED"),
+        (Self::FLAG_DELETED, "DELETED"),
+        (Self::FLAG_EDITOR_ONLY, "EDITOR_ONLY"),
+        (Self::FLAG_LOCALIZED, "LOCALIZED"),
+    ];
+
+    /// Returns whether no metadata is set, in which case none is stored.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn has_flag(&self, flag: u16) -> bool {
+        self.flags & flag == flag
+    }
+
+    pub fn set_flag(&mut self, flag: u16, enabled: bool) {
+        if enabled {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+    }
+
+    /// Records a modification: sets the modified time to now and the author, and bumps the revision.
+    pub fn touch(&mut self, author: &str) {
+        self.modified = SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+        self.author = StrSml::from(author);
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    /// Converts the metadata into its stored section, writing only the entries that are set.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut entries: Vec<(u8, Vec<u8>)> = Vec::new();
+        if self.flags != 0 {
+            entries.push((Self::KEY_FLAGS, self.flags.to_be_bytes().to_vec()));
+        }
+        if self.modified != 0 {
+            entries.push((Self::KEY_MODIFIED, self.modified.to_be_bytes().to_vec()));
+        }
+        if !self.author.to_string().is_empty() {
+            entries.push((Self::KEY_AUTHOR, self.author.to_bytes()));
+        }
+        if self.revision != 0 {
+            entries.push((Self::KEY_REVISION, self.revision.to_be_bytes().to_vec()));
+        }
+        entries.extend(self.extra.iter().cloned());
+
+        let mut bytes = vec![entries.len() as u8];
+        for (key, data) in entries {
+            bytes.push(key);
+            bytes.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            bytes.extend_from_slice(&data);
+        }
+        bytes
+    }
+
+    /// Reads the metadata from its stored section, returning the bytes consumed.
+    pub fn read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        if bytes.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for form metadata entry count"));
+        }
+        let entry_count = bytes[0];
+        let mut offset = 1;
+
+        let mut metadata = Self::default();
+        for _ in 0..entry_count {
+            if bytes.len() < offset + 3 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for form metadata entry"));
+            }
+            let key = bytes[offset];
+            let length = u16::from_be_bytes([bytes[offset + 1], bytes[offset + 2]]) as usize;
+            offset += 3;
+            if bytes.len() < offset + length {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for form metadata entry data"));
+            }
+            let data = &bytes[offset..offset + length];
+            offset += length;
+
+            match key {
+                Self::KEY_FLAGS => metadata.flags = u16::from_be_bytes(read_entry_array(data, "flags")?),
+                Self::KEY_MODIFIED => metadata.modified = u64::from_be_bytes(read_entry_array(data, "modified")?),
+                Self::KEY_AUTHOR => {
+                    let (author, consumed) = StrSml::read_from_byte_buffer(data)?;
+                    if consumed != data.len() {
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, "Form metadata author has trailing bytes"));
+                    }
+                    metadata.author = author;
+                }
+                Self::KEY_REVISION => metadata.revision = u32::from_be_bytes(read_entry_array(data, "revision")?),
+                _ => metadata.extra.push((key, data.to_vec())),
+            }
+        }
+
+        Ok((metadata, offset))
+    }
+
+    /// Converts the metadata into a JSON dictionary, flags listed by name.
+    pub fn to_dict(&self) -> Value {
+        let mut flags = Vec::new();
+        for bit in 0..u16::BITS {
+            let flag = 1u16 << bit;
+            if self.flags & flag == 0 {
+                continue;
+            }
+            match Self::FLAG_NAMES.iter().find(|(named_flag, _)| *named_flag == flag) {
+                Some((_, name)) => flags.push(Value::from(*name)),
+                None => flags.push(Value::from(format!("BIT{}", bit))),
+            }
+        }
+
+        let mut dict = Map::new();
+        dict.insert("flags".to_string(), Value::Array(flags));
+        dict.insert("modified".to_string(), Value::from(self.modified));
+        dict.insert("author".to_string(), Value::from(self.author.to_string()));
+        dict.insert("revision".to_string(), Value::from(self.revision));
+        if !self.extra.is_empty() {
+            dict.insert("extra".to_string(), Value::Array(self.extra.iter().map(|(key, data)| json!({"key": key, "bytes": data})).collect()));
+        }
+        Value::Object(dict)
+    }
+
+    /// Reads the metadata from a JSON dictionary as written by `to_dict`, missing keys left unset.
+    pub fn from_dict(dict: &Value) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid form metadata: {}", message));
+        let dict = dict.as_object().ok_or_else(|| invalid("expected an object"))?;
+
+        let mut metadata = Self::default();
+        if let Some(flags) = dict.get("flags") {
+            for flag in flags.as_array().ok_or_else(|| invalid("flags must be an array"))? {
+                let name = flag.as_str().ok_or_else(|| invalid("flags must be names"))?;
+                metadata.flags |= match Self::FLAG_NAMES.iter().find(|(_, flag_name)| *flag_name == name) {
+                    Some((named_flag, _)) => *named_flag,
+                    None => match name.strip_prefix("BIT").and_then(|bit| bit.parse::<u32>().ok()).filter(|bit| *bit < u16::BITS) {
+                        Some(bit) => 1 << bit,
+                        None => return Err(invalid(&format!("unknown flag {}", name))),
+                    },
+                };
+            }
+        }
+        if let Some(modified) = dict.get("modified") {
+            metadata.modified = modified.as_u64().ok_or_else(|| invalid("modified must be a unix time"))?;
+        }
+        if let Some(author) = dict.get("author") {
+            let author = author.as_str().ok_or_else(|| invalid("author must be a string"))?;
+            if author.len() > u8::MAX as usize || !author.is_ascii() {
+                return Err(invalid("author must be an ASCII string of up to 255 characters"));
+            }
+            metadata.author = StrSml::from(author);
+        }
+        if let Some(revision) = dict.get("revision") {
+            metadata.revision = revision.as_u64().and_then(|revision| u32::try_from(revision).ok()).ok_or_else(|| invalid("revision must be a u32"))?;
+        }
+        if let Some(extra) = dict.get("extra") {
+            for entry in extra.as_array().ok_or_else(|| invalid("extra must be an array"))? {
+                let key = entry.get("key").and_then(Value::as_u64).and_then(|key| u8::try_from(key).ok()).ok_or_else(|| invalid("extra keys must be bytes"))?;
+                if (Self::KEY_FLAGS..=Self::KEY_REVISION).contains(&key) {
+                    return Err(invalid(&format!("extra key {} is a known key", key)));
+                }
+                let data = entry.get("bytes").and_then(Value::as_array).ok_or_else(|| invalid("extra bytes must be an array"))?;
+                let data = data.iter().map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok())).collect::<Option<Vec<u8>>>().ok_or_else(|| invalid("extra bytes must be bytes"))?;
+                metadata.extra.push((key, data));
+            }
+        }
+        Ok(metadata)
+    }
+}
+
+impl Default for FormMetadata {
+    fn default() -> Self {
+        Self {
+            flags: 0,
+            modified: 0,
+            author: StrSml::from(""),
+            revision: 0,
+            extra: Vec::new(),
+        }
+    }
+}
+
+fn read_entry_array<const N: usize>(data: &[u8], name: &str) -> io::Result<[u8; N]> {
+    data.try_into().map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("Form metadata {} must be {} bytes", name, N)))
+}
+
+/// Display implementation for `FormMetadata`
+impl fmt::Display for FormMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FormMetadata {}", self.to_dict())
+    }
+}
+
+/// Debug implementation for `FormMetadata`
+impl fmt::Debug for FormMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
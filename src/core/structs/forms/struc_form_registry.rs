@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::core::structs::{forms::*, types::*};
+
+/// Reads a form of a registered type from a file positioned at the form's start.
+pub type FormReadFromBytes = fn(&mut File) -> io::Result<Box<dyn FormTrait>>;
+
+/// Reads a form of a registered type from a buffer starting at the form, returning the bytes consumed.
+pub type FormReadFromByteBuffer = fn(&[u8]) -> io::Result<(Box<dyn FormTrait>, usize)>;
+
+/// Layout version of every form type until its layout changes, and of forms in archives that
+/// record no version for their type.
+pub const FORM_LAYOUT_VERSION_INITIAL: u16 = 1;
+
+/// A versioned deserializer, reading a form stored in an older layout into the current one.
+#[derive(Debug, Clone)]
+pub struct FormMigration {
+    pub from_version: u16,
+    pub read_from_bytes: FormReadFromBytes,
+}
+
+/// A form type known to the library: its type byte, name, deserializers and, when known, the
+/// `FormSchema` describing its layout.
+///
+/// Dynamic types have no compiled struct, their forms are decoded as `FormDynamic` with the schema.
+/// `layout_version` is the version of the current layout, `migrations` read the older ones.
+#[derive(Debug, Clone)]
+pub struct FormTypeRegistration {
+    pub form_type: FormType,
+    pub name: String,
+    pub read_from_bytes: FormReadFromBytes,
+    pub read_from_byte_buffer: FormReadFromByteBuffer,
+    pub schema: Option<Arc<FormSchema>>,
+    pub is_dynamic: bool,
+    pub layout_version: u16,
+    pub migrations: Vec<FormMigration>,
+}
+
+impl FormTypeRegistration {
+    pub fn new(form_type: FormType, name: &str, read_from_bytes: FormReadFromBytes, read_from_byte_buffer: FormReadFromByteBuffer) -> Self {
+        Self {
+            form_type,
+            name: name.to_string(),
+            read_from_bytes,
+            read_from_byte_buffer,
+            schema: None,
+            is_dynamic: false,
+            layout_version: FORM_LAYOUT_VERSION_INITIAL,
+            migrations: Vec::new(),
+        }
+    }
+
+    /// Sets the version of the type's current layout.
+    pub fn with_layout_version(mut self, layout_version: u16) -> Self {
+        self.layout_version = layout_version;
+        self
+    }
+
+    /// Adds a deserializer reading forms stored in an older layout version.
+    pub fn with_migration(mut self, from_version: u16, read_from_bytes: FormReadFromBytes) -> Self {
+        self.migrations.retain(|migration| migration.from_version != from_version);
+        self.migrations.push(FormMigration { from_version, read_from_bytes });
+        self
+    }
+
+    /// Returns the deserializer for forms stored in a layout version, if the type can read it.
+    pub fn get_migration(&self, from_version: u16) -> Option<&FormMigration> {
+        self.migrations.iter().find(|migration| migration.from_version == from_version)
+    }
+
+    /// Describes the type's layout with the fields of a `#[derive(Form)]` struct's `schema_fields()`.
+    pub fn with_schema_fields(mut self, fields: Vec<FormSchemaField>) -> Self {
+        self.schema = Some(Arc::new(FormSchema::new(self.form_type, &self.name, fields)));
+        self
+    }
+
+    /// Creates the registration of a schema-described type, read as `FormDynamic`.
+    pub fn dynamic(schema: FormSchema) -> Self {
+        Self {
+            form_type: schema.form_type,
+            name: schema.name.to_string(),
+            read_from_bytes: |file| Ok(Box::new(FormDynamic::read_from_bytes(file)?)),
+            read_from_byte_buffer: |bytes| FormDynamic::read_from_byte_buffer(bytes).map(|(form, consumed)| (Box::new(form) as Box<dyn FormTrait>, consumed)),
+            schema: Some(Arc::new(schema)),
+            is_dynamic: true,
+            layout_version: FORM_LAYOUT_VERSION_INITIAL,
+            migrations: Vec::new(),
+        }
+    }
+}
+
+/// Every registered form type, keyed by type byte. Starts out with the built-in types.
+static FORM_TYPE_REGISTRY: OnceLock<RwLock<BTreeMap<u8, FormTypeRegistration>>> = OnceLock::new();
+
+fn get_registry() -> &'static RwLock<BTreeMap<u8, FormTypeRegistration>> {
+    FORM_TYPE_REGISTRY.get_or_init(|| {
+        let builtins = [
+            FormTypeRegistration::new(
+                FormType::STRING,
+                "STRING",
+                |file| Ok(Box::new(FormString::read_from_bytes(file)?)),
+                |bytes| FormString::read_from_byte_buffer(bytes).map(|(form, consumed)| (Box::new(form) as Box<dyn FormTrait>, consumed)),
+            )
+            .with_schema_fields(FormString::schema_fields()),
+            FormTypeRegistration::new(
+                FormType::WORLD,
+                "WORLD",
+                |file| Ok(Box::new(FormWorld::read_from_bytes(file)?)),
+                |bytes| FormWorld::read_from_byte_buffer(bytes).map(|(form, consumed)| (Box::new(form) as Box<dyn FormTrait>, consumed)),
+            )
+            .with_schema_fields(FormWorld::schema_fields())
+            .with_migration(FormWorld::LAYOUT_VERSION_LEGACY, |file| Ok(Box::new(FormWorld::read_from_bytes_legacy(file)?))),
+            FormTypeRegistration::new(
+                FormType::REFGROUP,
+                "REFGROUP",
+                |file| Ok(Box::new(FormRefGroup::read_from_bytes(file)?)),
+                |bytes| FormRefGroup::read_from_byte_buffer(bytes).map(|(form, consumed)| (Box::new(form) as Box<dyn FormTrait>, consumed)),
+            )
+            .with_schema_fields(FormRefGroup::schema_fields()),
+            FormTypeRegistration::new(
+                FormType::WORLDPART,
+                "WORLDPART",
+                |file| Ok(Box::new(FormWorldPart::read_from_bytes(file)?)),
+                |bytes| FormWorldPart::read_from_byte_buffer(bytes).map(|(form, consumed)| (Box::new(form) as Box<dyn FormTrait>, consumed)),
+            )
+            .with_schema_fields(FormWorldPart::schema_fields()),
+            FormTypeRegistration::new(
+                FormType::WEATHER,
+                "WEATHER",
+                |file| Ok(Box::new(FormWeather::read_from_bytes(file)?)),
+                |bytes| FormWeather::read_from_byte_buffer(bytes).map(|(form, consumed)| (Box::new(form) as Box<dyn FormTrait>, consumed)),
+            )
+            .with_schema_fields(FormWeather::schema_fields()),
+        ];
+
+        RwLock::new(builtins.into_iter().map(|registration| (registration.form_type.to_u8(), registration)).collect())
+    })
+}
+
+/// Registers a form type so archives containing it can be read.
+///
+/// Fails if the type byte or the name is already registered.
+pub fn register_form_type(registration: FormTypeRegistration) -> io::Result<()> {
+    let mut registry = match get_registry().write() {
+        Ok(registry) => registry,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    if let Some(existing) = registry.get(&registration.form_type.to_u8()) {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("FormType byte {} is already registered as {}.", registration.form_type.to_u8(), existing.name)));
+    }
+    if registry.values().any(|existing| existing.name == registration.name) {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("FormType name {} is already registered.", registration.name)));
+    }
+
+    registry.insert(registration.form_type.to_u8(), registration);
+    Ok(())
+}
+
+/// Registers a schema-described form type, returning whether it was newly registered.
+///
+/// Registering a schema the type is already registered with does nothing, compiled or not. Fails if
+/// the schema is invalid, or its type byte or name is registered to another type or schema.
+pub fn register_form_schema(schema: FormSchema) -> io::Result<bool> {
+    schema.validate()?;
+    if let Some(existing) = get_form_type_registration(schema.form_type) {
+        return match existing.schema {
+            Some(existing_schema) if *existing_schema == schema => Ok(false),
+            Some(_) => Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("FormType byte {} is already registered with a different {} schema.", schema.form_type.to_u8(), existing.name))),
+            None => Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("FormType byte {} is already registered as {}.", schema.form_type.to_u8(), existing.name))),
+        };
+    }
+    register_form_type(FormTypeRegistration::dynamic(schema))?;
+    Ok(true)
+}
+
+/// Returns the registration of a form type, if it is registered.
+pub fn get_form_type_registration(form_type: FormType) -> Option<FormTypeRegistration> {
+    let registry = match get_registry().read() {
+        Ok(registry) => registry,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    registry.get(&form_type.to_u8()).cloned()
+}
+
+/// Calls `f` with the registration of a form type while the registry is read, without cloning the
+/// registration. `f` must not register form types.
+pub fn with_form_type_registration<T>(form_type: FormType, f: impl FnOnce(&FormTypeRegistration) -> T) -> Option<T> {
+    let registry = match get_registry().read() {
+        Ok(registry) => registry,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    registry.get(&form_type.to_u8()).map(f)
+}
+
+/// Returns the registered name of a form type.
+pub fn get_form_type_name(form_type: FormType) -> Option<String> {
+    with_form_type_registration(form_type, |registration| registration.name.clone())
+}
+
+/// Returns the deserializers of a form type's current layout.
+pub fn get_form_type_readers(form_type: FormType) -> Option<(FormReadFromBytes, FormReadFromByteBuffer)> {
+    with_form_type_registration(form_type, |registration| (registration.read_from_bytes, registration.read_from_byte_buffer))
+}
+
+/// Returns the schema describing a form type's layout, if it has one.
+pub fn get_form_type_schema(form_type: FormType) -> Option<Arc<FormSchema>> {
+    with_form_type_registration(form_type, |registration| registration.schema.clone()).flatten()
+}
+
+/// Returns the form type registered under a name.
+pub fn get_form_type_by_name(name: &str) -> Option<FormType> {
+    let registry = match get_registry().read() {
+        Ok(registry) => registry,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    registry.values().find(|registration| registration.name == name).map(|registration| registration.form_type)
+}
+
+/// Returns every registered form type, ordered by type byte.
+pub fn get_registered_form_types() -> Vec<FormTypeRegistration> {
+    let registry = match get_registry().read() {
+        Ok(registry) => registry,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    registry.values().cloned().collect()
+}
@@ -0,0 +1,480 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use serde_json::{json, Value};
+
+use crate::core::structs::types::*;
+
+/// Deepest nesting of array field types a schema may declare.
+pub const SCHEMA_MAX_ARRAY_DEPTH: usize = 8;
+
+/// How the length of an array field is stored.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SchemaArrayLength {
+    /// Length prefix of one byte.
+    U8,
+    /// Length prefix of two bytes.
+    U16,
+    /// Length prefix of four bytes.
+    U32,
+    /// Exactly this many items, without a prefix.
+    Fixed(u16),
+    /// As many items as the earlier array field of this name, without a prefix. Only valid on form fields, not array items.
+    CountOf(StrSml),
+}
+
+/// Type of a field in a `FormSchema`, drawn from the archive value types.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SchemaFieldType {
+    U8,
+    U16,
+    U32,
+    I32,
+    F32,
+    StrSml,
+    StrLrg,
+    FormID,
+    GlobalID,
+    Vec2Int,
+    Vec3Int,
+    Vec2Float,
+    Vec3Float,
+    SmlColor,
+    LrgColor,
+    LangCode,
+    EntID,
+    EntInstance,
+    ArchiveID,
+    Version,
+    FormType,
+    Array(SchemaArrayLength, Box<SchemaFieldType>),
+}
+
+/// Type byte and name of every non-array field type.
+const SCHEMA_SCALAR_TYPES: [(SchemaFieldType, u8, &str); 21] = [
+    (SchemaFieldType::U8, 1, "u8"),
+    (SchemaFieldType::U16, 2, "u16"),
+    (SchemaFieldType::U32, 3, "u32"),
+    (SchemaFieldType::I32, 4, "i32"),
+    (SchemaFieldType::F32, 5, "f32"),
+    (SchemaFieldType::StrSml, 6, "StrSml"),
+    (SchemaFieldType::StrLrg, 7, "StrLrg"),
+    (SchemaFieldType::FormID, 8, "FormID"),
+    (SchemaFieldType::GlobalID, 9, "GlobalID"),
+    (SchemaFieldType::Vec2Int, 10, "Vec2Int"),
+    (SchemaFieldType::Vec3Int, 11, "Vec3Int"),
+    (SchemaFieldType::Vec2Float, 12, "Vec2Float"),
+    (SchemaFieldType::Vec3Float, 13, "Vec3Float"),
+    (SchemaFieldType::SmlColor, 14, "SmlColor"),
+    (SchemaFieldType::LrgColor, 15, "LrgColor"),
+    (SchemaFieldType::LangCode, 16, "LangCode"),
+    (SchemaFieldType::EntID, 17, "EntID"),
+    (SchemaFieldType::EntInstance, 18, "EntInstance"),
+    (SchemaFieldType::ArchiveID, 19, "ArchiveID"),
+    (SchemaFieldType::Version, 20, "Version"),
+    (SchemaFieldType::FormType, 21, "FormType"),
+];
+
+/// Type byte of an array field type, followed by the length kind and the element type.
+const SCHEMA_ARRAY_TYPE_BYTE: u8 = 0x80;
+
+#[allow(unused)]
+impl SchemaFieldType {
+    /// Parses a field type name such as `StrSml`, `GlobalID[u8]`, `f32[4][u16]` or `Vec3Int[world_parts]`.
+    ///
+    /// Array suffixes are `[u8]`, `[u16]` and `[u32]` for a length prefix, a number for a fixed length,
+    /// or the name of an earlier array field whose length is shared.
+    pub fn parse(name: &str) -> io::Result<Self> {
+        Self::parse_nested(name.trim(), 0)
+    }
+
+    fn parse_nested(name: &str, depth: usize) -> io::Result<Self> {
+        if depth > SCHEMA_MAX_ARRAY_DEPTH {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Field type nests arrays deeper than {}.", SCHEMA_MAX_ARRAY_DEPTH)));
+        }
+
+        let Some(element_name) = name.strip_suffix(']') else {
+            return match SCHEMA_SCALAR_TYPES.iter().find(|(_, _, scalar_name)| *scalar_name == name) {
+                Some((field_type, _, _)) => Ok(field_type.clone()),
+                None => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown field type {}.", name))),
+            };
+        };
+
+        let Some((element_name, length_name)) = element_name.rsplit_once('[') else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unbalanced brackets in field type {}.", name)));
+        };
+        let length = match length_name {
+            "u8" => SchemaArrayLength::U8,
+            "u16" => SchemaArrayLength::U16,
+            "u32" => SchemaArrayLength::U32,
+            _ if length_name.bytes().all(|byte| byte.is_ascii_digit()) => match length_name.parse::<u16>() {
+                Ok(fixed) => SchemaArrayLength::Fixed(fixed),
+                Err(_) => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid array length {} in field type {}.", length_name, name))),
+            },
+            _ => SchemaArrayLength::CountOf(read_str_sml(length_name)?),
+        };
+
+        Ok(SchemaFieldType::Array(length, Box::new(Self::parse_nested(element_name, depth + 1)?)))
+    }
+
+    pub fn get_byte_count(&self) -> usize {
+        match self {
+            SchemaFieldType::Array(SchemaArrayLength::Fixed(_), element) => 4 + element.get_byte_count(),
+            SchemaFieldType::Array(SchemaArrayLength::CountOf(field_name), element) => 2 + field_name.get_byte_count() + element.get_byte_count(),
+            SchemaFieldType::Array(_, element) => 2 + element.get_byte_count(),
+            _ => 1,
+        }
+    }
+
+    /// Appends the field type's encoding to `bytes`.
+    pub fn write_bytes(&self, bytes: &mut Vec<u8>) {
+        match self {
+            SchemaFieldType::Array(length, element) => {
+                bytes.push(SCHEMA_ARRAY_TYPE_BYTE);
+                match length {
+                    SchemaArrayLength::U8 => bytes.push(1),
+                    SchemaArrayLength::U16 => bytes.push(2),
+                    SchemaArrayLength::U32 => bytes.push(4),
+                    SchemaArrayLength::Fixed(fixed) => {
+                        bytes.push(0);
+                        bytes.extend_from_slice(&fixed.to_be_bytes());
+                    }
+                    SchemaArrayLength::CountOf(field_name) => {
+                        bytes.push(3);
+                        bytes.extend_from_slice(&field_name.to_bytes());
+                    }
+                }
+                element.write_bytes(bytes);
+            }
+            scalar => bytes.push(SCHEMA_SCALAR_TYPES.iter().find(|(field_type, _, _)| field_type == scalar).map(|(_, type_byte, _)| *type_byte).unwrap()),
+        }
+    }
+
+    /// Returns the number of bytes every value of the type serializes to, `None` when it varies, as
+    /// for strings and arrays with a length prefix.
+    pub fn get_fixed_value_byte_count(&self) -> Option<usize> {
+        match self {
+            SchemaFieldType::U8 => Some(1),
+            SchemaFieldType::U16 => Some(2),
+            SchemaFieldType::U32 | SchemaFieldType::I32 | SchemaFieldType::F32 => Some(4),
+            SchemaFieldType::StrSml | SchemaFieldType::StrLrg => None,
+            SchemaFieldType::FormID => Some(FormID::BYTE_COUNT),
+            SchemaFieldType::GlobalID => Some(GlobalID::BYTE_COUNT),
+            SchemaFieldType::Vec2Int => Some(Vec2Int::BYTE_COUNT),
+            SchemaFieldType::Vec3Int => Some(Vec3Int::BYTE_COUNT),
+            SchemaFieldType::Vec2Float => Some(Vec2Float::BYTE_COUNT),
+            SchemaFieldType::Vec3Float => Some(Vec3Float::BYTE_COUNT),
+            SchemaFieldType::SmlColor => Some(SmlColor::BYTE_COUNT),
+            SchemaFieldType::LrgColor => Some(LrgColor::BYTE_COUNT),
+            SchemaFieldType::LangCode => Some(LangCode::BYTE_COUNT),
+            SchemaFieldType::EntID => Some(EntID::BYTE_COUNT),
+            SchemaFieldType::EntInstance => Some(EntInstance::BYTE_COUNT),
+            SchemaFieldType::ArchiveID => Some(ArchiveID::BYTE_COUNT),
+            SchemaFieldType::Version => Some(Version::BYTE_COUNT),
+            SchemaFieldType::FormType => Some(FormType::BYTE_COUNT),
+            SchemaFieldType::Array(SchemaArrayLength::Fixed(fixed), element) => element.get_fixed_value_byte_count().map(|byte_count| *fixed as usize * byte_count),
+            SchemaFieldType::Array(..) => None,
+        }
+    }
+
+    /// Moves a reader past a value of the type, reading only the lengths of strings and arrays.
+    /// Returns the item count of an array, 0 for other types.
+    ///
+    /// Arrays sharing another field's length are skipped by `FormSchema`, which knows that length.
+    pub fn skip_value<R: Read + Seek>(&self, reader: &mut R) -> io::Result<usize> {
+        let SchemaFieldType::Array(length, element) = self else {
+            let byte_count = match (self, self.get_fixed_value_byte_count()) {
+                (_, Some(byte_count)) => byte_count,
+                (SchemaFieldType::StrSml, None) => read_length::<1>(reader)?,
+                (_, None) => read_length::<2>(reader)? * 2,
+            };
+            reader.seek(SeekFrom::Current(byte_count as i64))?;
+            return Ok(0);
+        };
+
+        let count = match length {
+            SchemaArrayLength::U8 => read_length::<1>(reader)?,
+            SchemaArrayLength::U16 => read_length::<2>(reader)?,
+            SchemaArrayLength::U32 => read_length::<4>(reader)?,
+            SchemaArrayLength::Fixed(fixed) => *fixed as usize,
+            SchemaArrayLength::CountOf(field_name) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Array sharing the length of {} can only be skipped as a form field.", field_name.to_string())));
+            }
+        };
+        element.skip_items(count, reader)?;
+        Ok(count)
+    }
+
+    /// Moves a reader past `count` array items of the type.
+    fn skip_items<R: Read + Seek>(&self, count: usize, reader: &mut R) -> io::Result<()> {
+        if let Some(byte_count) = self.get_fixed_value_byte_count() {
+            reader.seek(SeekFrom::Current((count * byte_count) as i64))?;
+            return Ok(());
+        }
+        for _ in 0..count {
+            self.skip_value(reader)?;
+        }
+        Ok(())
+    }
+
+    /// Returns whether the type is or contains an array sharing another field's length.
+    pub fn has_count_of(&self) -> bool {
+        match self {
+            SchemaFieldType::Array(SchemaArrayLength::CountOf(_), _) => true,
+            SchemaFieldType::Array(_, element) => element.has_count_of(),
+            _ => false,
+        }
+    }
+
+    /// Reads a field type from the start of a byte buffer, returning the bytes consumed.
+    pub fn read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        Self::read_nested(bytes, 0)
+    }
+
+    fn read_nested(bytes: &[u8], depth: usize) -> io::Result<(Self, usize)> {
+        if depth > SCHEMA_MAX_ARRAY_DEPTH {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Field type nests arrays deeper than {}.", SCHEMA_MAX_ARRAY_DEPTH)));
+        }
+
+        let Some(&type_byte) = bytes.first() else {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for field type"));
+        };
+        if type_byte != SCHEMA_ARRAY_TYPE_BYTE {
+            return match SCHEMA_SCALAR_TYPES.iter().find(|(_, scalar_byte, _)| *scalar_byte == type_byte) {
+                Some((field_type, _, _)) => Ok((field_type.clone(), 1)),
+                None => Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown field type byte {}", type_byte))),
+            };
+        }
+
+        let (length, offset) = match bytes.get(1) {
+            Some(1) => (SchemaArrayLength::U8, 2),
+            Some(2) => (SchemaArrayLength::U16, 2),
+            Some(4) => (SchemaArrayLength::U32, 2),
+            Some(0) => match bytes.get(2..4) {
+                Some(fixed) => (SchemaArrayLength::Fixed(u16::from_be_bytes([fixed[0], fixed[1]])), 4),
+                None => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for fixed array length")),
+            },
+            Some(3) => {
+                let (field_name, consumed) = StrSml::read_from_byte_buffer(&bytes[2..])?;
+                (SchemaArrayLength::CountOf(field_name), 2 + consumed)
+            }
+            Some(length_byte) => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown array length kind {}", length_byte))),
+            None => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for array length kind")),
+        };
+
+        let (element, consumed) = Self::read_nested(&bytes[offset..], depth + 1)?;
+        Ok((SchemaFieldType::Array(length, Box::new(element)), offset + consumed))
+    }
+}
+
+impl fmt::Display for SchemaFieldType {
+    /// Formats the field type as the name `parse` accepts.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaFieldType::Array(length, element) => match length {
+                SchemaArrayLength::U8 => write!(f, "{}[u8]", element),
+                SchemaArrayLength::U16 => write!(f, "{}[u16]", element),
+                SchemaArrayLength::U32 => write!(f, "{}[u32]", element),
+                SchemaArrayLength::Fixed(fixed) => write!(f, "{}[{}]", element, fixed),
+                SchemaArrayLength::CountOf(field_name) => write!(f, "{}[{}]", element, field_name),
+            },
+            scalar => write!(f, "{}", SCHEMA_SCALAR_TYPES.iter().find(|(field_type, _, _)| field_type == scalar).map(|(_, _, name)| *name).unwrap()),
+        }
+    }
+}
+
+/// A named field of a `FormSchema`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FormSchemaField {
+    pub name: StrSml,
+    pub field_type: SchemaFieldType,
+}
+
+#[allow(unused)]
+impl FormSchemaField {
+    pub fn new(name: &str, field_type: SchemaFieldType) -> Self {
+        Self { name: StrSml::from(name), field_type }
+    }
+}
+
+/// Layout of a form type that has no compiled struct, read and written as a `FormDynamic`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FormSchema {
+    pub form_type: FormType,
+    pub name: StrSml,
+    pub fields: Vec<FormSchemaField>,
+}
+
+#[allow(unused)]
+impl FormSchema {
+    pub fn new(form_type: FormType, name: &str, fields: Vec<FormSchemaField>) -> Self {
+        Self { form_type, name: StrSml::from(name), fields }
+    }
+
+    /// Checks the schema has a name and uniquely named fields that don't shadow the `FormBase` keys,
+    /// and that every shared array length names an earlier array field.
+    pub fn validate(&self) -> io::Result<()> {
+        if self.name.to_string().is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "A form schema needs a name."));
+        }
+        if self.fields.len() > u16::MAX as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Form schema {} has more than {} fields.", self.name.to_string(), u16::MAX)));
+        }
+
+        let mut names = BTreeSet::from(["form_id".to_string(), "form_type".to_string(), "form_name".to_string(), "metadata".to_string()]);
+        for field in &self.fields {
+            let name = field.name.to_string();
+            if name.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Form schema {} has a field without a name.", self.name.to_string())));
+            }
+            if !names.insert(name.clone()) {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Form schema {} declares field {} twice or shadows a form key.", self.name.to_string(), name)));
+            }
+        }
+
+        for (index, field) in self.fields.iter().enumerate() {
+            let element = match &field.field_type {
+                SchemaFieldType::Array(SchemaArrayLength::CountOf(count_field), element) => {
+                    let shares_array = self.fields[..index].iter().any(|earlier| earlier.name == *count_field && matches!(earlier.field_type, SchemaFieldType::Array(..)));
+                    if !shares_array {
+                        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Field {} of {} shares the length of {}, which is not an earlier array field.", field.name.to_string(), self.name.to_string(), count_field.to_string())));
+                    }
+                    element
+                }
+                field_type => field_type,
+            };
+            if element.has_count_of() {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Field {} of {} shares an array length inside an array.", field.name.to_string(), self.name.to_string())));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of bytes a form of the schema's type takes, reading from a reader positioned at
+    /// the form's start only the lengths of its strings and arrays. The reader is left at the form's end.
+    ///
+    /// Used to find what follows a form in its record, such as its metadata, without decoding it.
+    pub fn read_form_byte_count<R: Read + Seek>(&self, reader: &mut R) -> io::Result<u64> {
+        let form_start = reader.stream_position()?;
+        reader.seek(SeekFrom::Current((FormID::BYTE_COUNT + FormType::BYTE_COUNT) as i64))?;
+        SchemaFieldType::StrSml.skip_value(reader)?;
+
+        let mut counts = Vec::with_capacity(self.fields.len());
+        for field in &self.fields {
+            let count = match &field.field_type {
+                SchemaFieldType::Array(SchemaArrayLength::CountOf(count_field), element) => {
+                    let Some(count) = self.fields.iter().position(|earlier| earlier.name == *count_field).and_then(|index| counts.get(index).copied()) else {
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} has no earlier array field {} to share the length of.", self.name.to_string(), count_field.to_string())));
+                    };
+                    element.skip_items(count, reader)?;
+                    count
+                }
+                field_type => field_type.skip_value(reader)?,
+            };
+            counts.push(count);
+        }
+        Ok(reader.stream_position()? - form_start)
+    }
+
+    /// Returns the index of the field with the given name.
+    pub fn get_field_index(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field.name.to_string() == name)
+    }
+
+    pub fn get_byte_count(&self) -> usize {
+        FormType::BYTE_COUNT
+            + self.name.get_byte_count()
+            + 2
+            + self.fields.iter().map(|field| field.name.get_byte_count() + field.field_type.get_byte_count()).sum::<usize>()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.get_byte_count());
+        bytes.push(self.form_type.to_byte());
+        bytes.extend_from_slice(&self.name.to_bytes());
+        bytes.extend_from_slice(&(self.fields.len() as u16).to_be_bytes());
+        for field in &self.fields {
+            bytes.extend_from_slice(&field.name.to_bytes());
+            field.field_type.write_bytes(&mut bytes);
+        }
+        bytes
+    }
+
+    pub fn read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        let Some(&form_type_byte) = bytes.first() else {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for FormSchema type"));
+        };
+        let (name, consumed) = StrSml::read_from_byte_buffer(&bytes[1..])?;
+        let mut offset = 1 + consumed;
+
+        let Some(field_count_bytes) = bytes.get(offset..offset + 2) else {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for FormSchema field count"));
+        };
+        let field_count = u16::from_be_bytes([field_count_bytes[0], field_count_bytes[1]]);
+        offset += 2;
+
+        let mut fields = Vec::with_capacity(field_count as usize);
+        for _ in 0..field_count {
+            let (field_name, consumed) = StrSml::read_from_byte_buffer(&bytes[offset..])?;
+            offset += consumed;
+            let (field_type, consumed) = SchemaFieldType::read_from_byte_buffer(&bytes[offset..])?;
+            offset += consumed;
+            fields.push(FormSchemaField { name: field_name, field_type });
+        }
+
+        Ok((Self { form_type: FormType::from(form_type_byte), name, fields }, offset))
+    }
+
+    /// Converts the schema to JSON, in the shape `from_dict` reads.
+    pub fn to_dict(&self) -> Value {
+        json!({
+            "form_type": self.form_type.to_u8(),
+            "name": self.name.to_string(),
+            "fields": self.fields.iter().map(|field| json!({
+                "name": field.name.to_string(),
+                "type": field.field_type.to_string(),
+            })).collect::<Vec<Value>>(),
+        })
+    }
+
+    /// Reads a schema from JSON such as
+    /// `{"form_type": 100, "name": "QUEST", "fields": [{"name": "giver", "type": "GlobalID"}]}`.
+    pub fn from_dict(dict: &Value) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid form schema: {}", message));
+
+        let form_type = match dict.get("form_type").and_then(Value::as_u64) {
+            Some(form_type) if form_type <= u8::MAX as u64 => FormType::from(form_type as u8),
+            _ => return Err(invalid("form_type must be a type byte")),
+        };
+        let name = dict.get("name").and_then(Value::as_str).ok_or_else(|| invalid("name must be a string"))?;
+        let field_dicts = dict.get("fields").and_then(Value::as_array).ok_or_else(|| invalid("fields must be an array"))?;
+
+        let mut fields = Vec::with_capacity(field_dicts.len());
+        for field_dict in field_dicts {
+            let field_name = field_dict.get("name").and_then(Value::as_str).ok_or_else(|| invalid("field name must be a string"))?;
+            let field_type = field_dict.get("type").and_then(Value::as_str).ok_or_else(|| invalid("field type must be a string"))?;
+            fields.push(FormSchemaField { name: read_str_sml(field_name)?, field_type: SchemaFieldType::parse(field_type)? });
+        }
+
+        let schema = Self { form_type, name: read_str_sml(name)?, fields };
+        schema.validate()?;
+        Ok(schema)
+    }
+}
+
+/// Converts a string to a `StrSml`, failing instead of panicking when it doesn't fit.
+pub(crate) fn read_str_sml(value: &str) -> io::Result<StrSml> {
+    if value.len() > u8::MAX as usize || !value.is_ascii() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} is not a StrSml, it must be ASCII and at most 255 characters.", value)));
+    }
+    Ok(StrSml::from(value))
+}
+
+/// Reads a big-endian length prefix of `N` bytes from a reader.
+fn read_length<const N: usize>(reader: &mut impl Read) -> io::Result<usize> {
+    let mut length_bytes = [0u8; N];
+    reader.read_exact(&mut length_bytes)?;
+    Ok(length_bytes.iter().fold(0usize, |length, byte| length << 8 | *byte as usize))
+}
@@ -1,16 +1,15 @@
 use std::fmt;
-use std::io;
-use std::io::Read;
 
-use serde_json::json;
-
-use crate::core::structs::{FormBase, FormTrait};
-use crate::core::structs::types::{FormID, EntInstance, StrSml, FormType};
+use crate::core::structs::{Form, FormBase, FormMetadata};
+use crate::core::structs::types::{FormID, EntID, EntInstance, StrSml, FormType, GlobalID, BoundingBox, EntityIndex, EntityQuery, Vec3Float};
 
 /// Represents a world part form, which contains a base and a list of entity instances (EntInstance).
-#[derive(PartialEq, Eq, Clone)]
+/// Its references live inside the entity IDs, so the reference methods are written by hand.
+#[derive(PartialEq, Eq, Clone, Form)]
+#[form(crate = "crate", manual_references)]
 pub struct FormWorldPart {
     pub base: FormBase,          // Base form structure (ID, type, name)
+    #[form(count = "u16")]
     pub entities: Vec<EntInstance>,    // List of entity instances in this world part
 }
 
@@ -25,6 +24,7 @@ impl FormWorldPart {
             form_id,
             form_type: FormType::WORLDPART,
             form_name,
+            metadata: FormMetadata::default(),
         };
         Self {
             base,
@@ -32,152 +32,49 @@ impl FormWorldPart {
         }
     }
 
-    /// Returns the byte count needed to serialize the form.
-    pub fn get_byte_count(&self) -> usize {
-        self.base.get_byte_count()
-            + Self::BYTE_COUNT_ENTITIES_COUNT
-            + (self.entities.len() * EntInstance::BYTE_COUNT)
+    /// Returns the bounds of the entity positions, `None` without a positioned entity.
+    pub fn get_bounds(&self) -> Option<BoundingBox> {
+        BoundingBox::from_points(self.entities.iter().map(|entity| entity.position))
     }
 
-    /// Serializes `FormWorldPart` to a byte array.
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let mut bytes = self.base.to_bytes();
-        bytes.extend_from_slice(&(self.entities.len() as u16).to_be_bytes());
-        for entity in &self.entities {
-            bytes.extend_from_slice(&entity.to_bytes());
-        }
-        bytes
+    /// Returns the positions in `entities` of the entities matching the query, checking each one.
+    /// For repeated queries, `build_entity_index` skips the entities far from the query instead.
+    pub fn query_entities(&self, query: &EntityQuery) -> Vec<usize> {
+        query.get_matches(&self.get_positions())
     }
 
-    /// Converts the form into a dictionary-like JSON object.
-    pub fn to_dict(&self) -> serde_json::Value {
-        json!({
-            "form_id": self.base.form_id.to_string(),
-            "form_type": self.base.form_type.to_string(),
-            "form_name": self.base.form_name.to_string(),
-            "entities": self.entities.iter().map(|ent_instance| ent_instance.to_dict()).collect::<Vec<_>>(),
-        })
+    /// Builds a bounding volume hierarchy over the entity positions, valid until the entities change.
+    pub fn build_entity_index(&self) -> EntityIndex {
+        EntityIndex::new(&self.get_positions())
     }
-}
-
-#[allow(unused)]
-impl FormWorldPart {
-    /// Reads `FormWorldPart` from a binary file
-    pub fn read_from_bytes(file: &mut std::fs::File) -> std::io::Result<Self> {
-        // Read the FormID
-        let mut form_id_buffer = [0u8; FormID::BYTE_COUNT];
-        file.read_exact(&mut form_id_buffer)?;
-        let form_id = FormID::from(form_id_buffer);
-
-        // Read the FormType
-        let mut form_type_buffer = [0u8; FormType::BYTE_COUNT];
-        file.read_exact(&mut form_type_buffer)?;
-        let form_type = FormType::from(form_type_buffer[0]);
-
-        // Read the FormName
-        let form_name = StrSml::read_from_bytes(file)?;
-
-        // Read the number of entities (2 bytes)
-        let mut entity_count_buffer = [0u8; 2];
-        file.read_exact(&mut entity_count_buffer)?;
-        let entity_count = u16::from_be_bytes(entity_count_buffer) as usize;
 
-        // Read the entities
-        let mut entities = Vec::with_capacity(entity_count);
-        for _ in 0..entity_count {
-            let entity = EntInstance::read_from_bytes(file)?;
-            entities.push(entity);
-        }
-
-        Ok(FormWorldPart {
-            base: FormBase {
-                form_id,
-                form_type,
-                form_name,
-            },
-            entities,
-        })
+    fn get_positions(&self) -> Vec<Vec3Float> {
+        self.entities.iter().map(|entity| entity.position).collect()
     }
 
-    /// Reads `FormWorldPart` from a byte buffer
-    pub fn read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)> {
-        let mut offset = 0;
-
-        // Read FormID
-        if bytes.len() < offset + FormID::BYTE_COUNT {
-            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for FormID"));
-        }
-        let form_id_array: [u8; FormID::BYTE_COUNT] = bytes[offset..offset + FormID::BYTE_COUNT].try_into().unwrap();
-        let form_id = FormID::from(form_id_array);
-        offset += FormID::BYTE_COUNT;
-
-        // Read FormType
-        if bytes.len() < offset + FormType::BYTE_COUNT {
-            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for FormType"));
-        }
-        let form_type = FormType::from(bytes[offset]);
-        offset += FormType::BYTE_COUNT;
-
-        // Read FormName
-        let (form_name, consumed) = StrSml::read_from_byte_buffer(&bytes[offset..])?;
-        offset += consumed;
-
-        // Read number of entities (2 bytes)
-        if bytes.len() < offset + 2 {
-            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for entity count"));
-        }
-        let entity_count = u16::from_be_bytes([bytes[offset], bytes[offset + 1]]) as usize;
-        offset += 2;
-
-        // Read the entities
-        let mut entities = Vec::with_capacity(entity_count);
-        for _ in 0..entity_count {
-            if bytes.len() < offset + EntInstance::BYTE_COUNT {
-                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for entity instance"));
-            }
-            let (entity, consumed) = EntInstance::from_byte_buffer(&bytes[offset..])?;
-            offset += consumed;
-            entities.push(entity);
-        }
-
-        Ok((
-            FormWorldPart {
-                base: FormBase {
-                    form_id,
-                    form_type,
-                    form_name,
-                },
-                entities,
-            },
-            offset,
-        ))
-    }
-}
-
-/// Implementation of the `FormTrait` for `FormWorldPart`.
-impl FormTrait for FormWorldPart {
-    fn to_bytes(&self) -> Vec<u8> {
-        self.to_bytes()
+    /// Returns the `GlobalID`s this form references through its entity instances.
+    pub fn get_references(&self) -> Vec<GlobalID> {
+        self.entities.iter().map(|entity| entity.entity_id.global_id()).collect()
     }
 
-    fn get_byte_count(&self) -> usize {
-        self.get_byte_count()
+    /// Removes every entity instance of `target`, returning how many were removed.
+    pub fn remove_references(&mut self, target: GlobalID) -> usize {
+        let count_before = self.entities.len();
+        self.entities.retain(|entity| entity.entity_id.global_id() != target);
+        count_before - self.entities.len()
     }
 
-    fn form_id(&self) -> FormID {
-        self.base.form_id
-    }
-
-    fn form_type(&self) -> FormType {
-        self.base.form_type
-    }
-
-    fn form_name(&self) -> StrSml {
-        self.base.form_name.clone()
-    }
-
-    fn to_dict(&self) -> serde_json::Value {
-        self.to_dict()
+    /// Replaces every reference `remap` returns a new `GlobalID` for, returning how many were replaced.
+    /// Only the referenced form changes, each instance keeps its reference ID.
+    pub fn remap_references(&mut self, remap: &dyn Fn(GlobalID) -> Option<GlobalID>) -> usize {
+        let mut remapped = 0;
+        for entity in self.entities.iter_mut() {
+            if let Some(new_reference) = remap(entity.entity_id.global_id()) {
+                entity.entity_id = EntID::from((new_reference, entity.entity_id.reference_id()));
+                remapped += 1;
+            }
+        }
+        remapped
     }
 }
 
@@ -188,7 +85,7 @@ impl fmt::Display for FormWorldPart {
             f,
             "FormWorldPart {{ \nform_id: {}, \nform_type: {}, \nform_name: {}, \nentities_count: {}, \nentities: {:?} }}",
             self.base.form_id.to_string(),
-            self.base.form_type.to_string(),
+            self.base.form_type,
             self.base.form_name.to_string(),
             self.entities.len(),
             self.entities
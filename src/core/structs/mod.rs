@@ -10,4 +10,9 @@ pub use types::*;
 
 // Archive Struct
 pub mod struc_archive;
-pub use struc_archive::*;
\ No newline at end of file
+pub use struc_archive::*;
+
+// Archive Metadata Struct
+#[allow(unused)]
+pub mod struc_archive_metadata;
+pub use struc_archive_metadata::*;
\ No newline at end of file
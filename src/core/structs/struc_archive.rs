@@ -1,6 +1,6 @@
 use std::fmt;
 
-use crate::core::structs::types::*;
+use crate::core::structs::{types::*, ArchiveMetadata, ArchiveMetadataValue, FormMetadata};
 
 /// Struct that represents an archive.
 pub struct Archive {
@@ -9,10 +9,12 @@ pub struct Archive {
     pub version: Version,
     pub description: StrLrg,
     pub form_count: u16,
+    pub metadata: ArchiveMetadata,
 
     // Read fields
     pub bytestart_index: u32,
     pub bytestart_data: u32,   
+    pub record_lengths: Option<Vec<u32>>,  // Stored when records are out of index order, see `EXT_TAG_RECORD_LENGTHS`
 }
 
 #[allow(unused)]
@@ -24,8 +26,10 @@ impl Archive {
             version,
             description,
             form_count: 0,
+            metadata: ArchiveMetadata::default(),
             bytestart_index: 0, 
             bytestart_data: 0,   
+            record_lengths: None,
         }
     }
 
@@ -36,8 +40,10 @@ impl Archive {
             version: Version::from(0.1),
             description: StrLrg::from("An empty archive"),
             form_count: 0,
+            metadata: ArchiveMetadata::default(),
             bytestart_index: 0,
             bytestart_data: 0,
+            record_lengths: None,
         }
     }
 
@@ -64,6 +70,21 @@ impl Archive {
         bytes.extend_from_slice(&(self.get_form_count() as u16).to_be_bytes());
         bytes
     }
+
+    /// Returns the metadata value stored under `key`.
+    pub fn get_metadata(&self, key: &str) -> Option<&ArchiveMetadataValue> {
+        self.metadata.get(key)
+    }
+
+    /// Sets a metadata value, written to the archive with `write_archive_metadata`.
+    pub fn set_metadata(&mut self, key: &str, value: ArchiveMetadataValue) -> std::io::Result<()> {
+        self.metadata.set(key, value)
+    }
+
+    /// Removes a metadata value, returning it if the key was set.
+    pub fn remove_metadata(&mut self, key: &str) -> Option<ArchiveMetadataValue> {
+        self.metadata.remove(key)
+    }
 }
 
 /// Display implementation for `Archive`.
@@ -103,6 +124,8 @@ pub struct LiteArchive {
     pub version: Version,
     pub description: StrLrg,
     pub form_count: u16,
+    pub match_count: u16,  // Forms the listing's query matched, before paging
+    pub metadata: ArchiveMetadata,
 
     pub archive_items: Vec<LiteArchiveItem>,
 }
@@ -128,9 +151,29 @@ impl LiteArchive {
         for item in &self.archive_items {
             bytes.extend_from_slice(&item.to_bytes());
         }
+
+        // Append the archive metadata, an entry count of 0 when the archive has none
+        bytes.extend_from_slice(&self.metadata.to_bytes());
         
         bytes
     }
+
+    /// Converts a paged listing to bytes. Like `to_bytes`, with the match count and the number of
+    /// listed items after the form count.
+    pub fn to_page_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.archive_id.to_bytes());
+        bytes.extend_from_slice(&self.version.to_bytes());
+        bytes.extend_from_slice(&self.description.to_bytes());
+        bytes.extend_from_slice(&self.form_count.to_be_bytes());
+        bytes.extend_from_slice(&self.match_count.to_be_bytes());
+        bytes.extend_from_slice(&(self.archive_items.len() as u16).to_be_bytes());
+        for item in &self.archive_items {
+            bytes.extend_from_slice(&item.to_bytes());
+        }
+        bytes.extend_from_slice(&self.metadata.to_bytes());
+        bytes
+    }
 }
 
 
@@ -138,11 +181,13 @@ impl fmt::Debug for LiteArchive {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Archive ID: {}, Version: {}, Description: {}, Form Count: {}, Archive Items: {:?}",
+            "Archive ID: {}, Version: {}, Description: {}, Form Count: {}, Match Count: {}, Metadata: {:?}, Archive Items: {:?}",
             self.archive_id.to_string(),
             self.version.to_string(),
             self.description.to_string(),
             self.form_count,
+            self.match_count,
+            self.metadata,
             self.archive_items
         )
     }
@@ -152,6 +197,7 @@ pub struct LiteArchiveItem {
     pub form_id: FormID,
     pub form_name: StrSml,
     pub form_type: FormType,
+    pub metadata: FormMetadata,
 }
 
 impl LiteArchiveItem {
@@ -166,6 +212,9 @@ impl LiteArchiveItem {
         
         // Convert form_type to bytes and append
         bytes.push(self.form_type.to_byte());
+
+        // Append the metadata section, an entry count of 0 when the form has none
+        bytes.extend_from_slice(&self.metadata.to_bytes());
         
         bytes
     }
@@ -176,10 +225,11 @@ impl fmt::Debug for LiteArchiveItem {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Form ID: {}, Form Name: {}, Form Type: {}",
+            "Form ID: {}, Form Name: {}, Form Type: {}, Metadata: {:?}",
             self.form_id.to_string(),
             self.form_name.to_string(),
-            self.form_type.to_string()
+            self.form_type,
+            self.metadata
         )
     }
 }
\ No newline at end of file
@@ -0,0 +1,247 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+
+use serde_json::{json, Map, Value};
+
+use crate::core::structs::types::*;
+
+/// A typed value in an archive's key/value metadata.
+#[derive(PartialEq, Clone, Debug)]
+pub enum ArchiveMetadataValue {
+    Str(StrLrg),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+    Bool(bool),
+    Bytes(Vec<u8>),
+}
+
+#[allow(unused)]
+impl ArchiveMetadataValue {
+    pub const TYPE_STR: u8 = 1;
+    pub const TYPE_U64: u8 = 2;
+    pub const TYPE_I64: u8 = 3;
+    pub const TYPE_F64: u8 = 4;
+    pub const TYPE_BOOL: u8 = 5;
+    pub const TYPE_BYTES: u8 = 6;
+
+    const TYPE_NAMES: [(u8, &'static str); 6] = [
+        (Self::TYPE_STR, "str"),
+        (Self::TYPE_U64, "u64"),
+        (Self::TYPE_I64, "i64"),
+        (Self::TYPE_F64, "f64"),
+        (Self::TYPE_BOOL, "bool"),
+        (Self::TYPE_BYTES, "bytes"),
+    ];
+
+    /// Returns the byte that identifies the value's type when stored.
+    pub fn get_type_byte(&self) -> u8 {
+        match self {
+            Self::Str(_) => Self::TYPE_STR,
+            Self::U64(_) => Self::TYPE_U64,
+            Self::I64(_) => Self::TYPE_I64,
+            Self::F64(_) => Self::TYPE_F64,
+            Self::Bool(_) => Self::TYPE_BOOL,
+            Self::Bytes(_) => Self::TYPE_BYTES,
+        }
+    }
+
+    /// Returns the name of the value's type, as accepted by `parse`.
+    pub fn get_type_name(&self) -> &'static str {
+        let type_byte = self.get_type_byte();
+        Self::TYPE_NAMES.iter().find(|(byte, _)| *byte == type_byte).map(|(_, name)| *name).unwrap()
+    }
+
+    /// Parses a value from text, given its type name (`str`, `u64`, `i64`, `f64`, `bool` or `bytes` as hex).
+    pub fn parse(type_name: &str, text: &str) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid {} metadata value: {}", type_name, text));
+        match type_name {
+            "str" => Ok(Self::Str(StrLrg::from(text))),
+            "u64" => text.parse().map(Self::U64).map_err(|_| invalid()),
+            "i64" => text.parse().map(Self::I64).map_err(|_| invalid()),
+            "f64" => text.parse().map(Self::F64).map_err(|_| invalid()),
+            "bool" => text.parse().map(Self::Bool).map_err(|_| invalid()),
+            "bytes" => {
+                if !text.len().is_multiple_of(2) {
+                    return Err(invalid());
+                }
+                (0..text.len())
+                    .step_by(2)
+                    .map(|index| u8::from_str_radix(text.get(index..index + 2).ok_or_else(invalid)?, 16).map_err(|_| invalid()))
+                    .collect::<io::Result<Vec<u8>>>()
+                    .map(Self::Bytes)
+            },
+            _ => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown metadata value type: {}", type_name))),
+        }
+    }
+
+    /// Converts the value into its type byte followed by its data.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.get_type_byte()];
+        match self {
+            Self::Str(value) => bytes.extend_from_slice(&value.to_bytes()),
+            Self::U64(value) => bytes.extend_from_slice(&value.to_be_bytes()),
+            Self::I64(value) => bytes.extend_from_slice(&value.to_be_bytes()),
+            Self::F64(value) => bytes.extend_from_slice(&value.to_be_bytes()),
+            Self::Bool(value) => bytes.push(*value as u8),
+            Self::Bytes(value) => {
+                bytes.extend_from_slice(&(value.len() as u32).to_be_bytes());
+                bytes.extend_from_slice(value);
+            },
+        }
+        bytes
+    }
+
+    pub fn read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        if bytes.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for metadata value type"));
+        }
+        let data = &bytes[1..];
+        let fixed = |length: usize| -> io::Result<&[u8]> {
+            data.get(..length).ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for metadata value"))
+        };
+
+        let (value, consumed) = match bytes[0] {
+            Self::TYPE_STR => {
+                let (value, consumed) = StrLrg::read_from_byte_buffer(data)?;
+                (Self::Str(value), consumed)
+            },
+            Self::TYPE_U64 => (Self::U64(u64::from_be_bytes(fixed(8)?.try_into().unwrap())), 8),
+            Self::TYPE_I64 => (Self::I64(i64::from_be_bytes(fixed(8)?.try_into().unwrap())), 8),
+            Self::TYPE_F64 => (Self::F64(f64::from_be_bytes(fixed(8)?.try_into().unwrap())), 8),
+            Self::TYPE_BOOL => (Self::Bool(fixed(1)?[0] != 0), 1),
+            Self::TYPE_BYTES => {
+                let length = u32::from_be_bytes(fixed(4)?.try_into().unwrap()) as usize;
+                (Self::Bytes(fixed(4 + length)?[4..].to_vec()), 4 + length)
+            },
+            type_byte => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown metadata value type: {}", type_byte))),
+        };
+        Ok((value, 1 + consumed))
+    }
+
+    pub fn to_dict(&self) -> Value {
+        match self {
+            Self::Str(value) => json!(value.to_string()),
+            Self::U64(value) => json!(value),
+            Self::I64(value) => json!(value),
+            Self::F64(value) => json!(value),
+            Self::Bool(value) => json!(value),
+            Self::Bytes(value) => json!(value),
+        }
+    }
+}
+
+impl fmt::Display for ArchiveMetadataValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bytes(value) => write!(f, "{}", value.iter().map(|byte| format!("{:02x}", byte)).collect::<String>()),
+            _ => write!(f, "{}", self.to_dict().as_str().map(str::to_string).unwrap_or_else(|| self.to_dict().to_string())),
+        }
+    }
+}
+
+/// Build metadata of an archive, as typed values keyed by name.
+///
+/// It is stored in the archive's extension area and only when not empty. Keys are ASCII names of
+/// up to 255 characters; the `KEY_` constants name the common build entries.
+#[derive(PartialEq, Clone, Default)]
+pub struct ArchiveMetadata {
+    entries: BTreeMap<String, ArchiveMetadataValue>,
+}
+
+#[allow(unused)]
+impl ArchiveMetadata {
+    pub const KEY_BUILD_NUMBER: &'static str = "build_number";
+    pub const KEY_SOURCE_COMMIT: &'static str = "source_commit";
+    pub const KEY_TOOL_VERSION: &'static str = "tool_version";
+    pub const KEY_TARGET_PLATFORM: &'static str = "target_platform";
+    pub const KEY_CREATED: &'static str = "created";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&ArchiveMetadataValue> {
+        self.entries.get(key)
+    }
+
+    /// Sets a value, replacing any value under the same key. Fails if the key is not a valid `StrSml`.
+    pub fn set(&mut self, key: &str, value: ArchiveMetadataValue) -> io::Result<()> {
+        if key.is_empty() || key.len() > u8::MAX as usize || !key.is_ascii() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid archive metadata key: {}", key)));
+        }
+        self.entries.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    /// Removes a value, returning it if the key was set.
+    pub fn remove(&mut self, key: &str) -> Option<ArchiveMetadataValue> {
+        self.entries.remove(key)
+    }
+
+    /// Iterates over the entries in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ArchiveMetadataValue)> {
+        self.entries.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// Converts the metadata into a `u16` entry count followed by each key (`StrSml`) and value.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = (self.entries.len() as u16).to_be_bytes().to_vec();
+        for (key, value) in &self.entries {
+            bytes.extend_from_slice(&StrSml::from(key.as_str()).to_bytes());
+            bytes.extend_from_slice(&value.to_bytes());
+        }
+        bytes
+    }
+
+    pub fn read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        if bytes.len() < 2 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for archive metadata count"));
+        }
+        let entry_count = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let mut offset = 2;
+
+        let mut metadata = Self::default();
+        for _ in 0..entry_count {
+            let (key, consumed) = StrSml::read_from_byte_buffer(&bytes[offset..])?;
+            if !bytes[offset + 1..offset + consumed].is_ascii() {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Archive metadata key is not ASCII"));
+            }
+            offset += consumed;
+            let (value, consumed) = ArchiveMetadataValue::read_from_byte_buffer(&bytes[offset..])?;
+            offset += consumed;
+            metadata.entries.insert(key.to_string(), value);
+        }
+
+        Ok((metadata, offset))
+    }
+
+    pub fn to_dict(&self) -> Value {
+        let mut dict = Map::new();
+        for (key, value) in &self.entries {
+            dict.insert(key.clone(), value.to_dict());
+        }
+        Value::Object(dict)
+    }
+}
+
+impl fmt::Display for ArchiveMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ArchiveMetadata {}", self.to_dict())
+    }
+}
+
+impl fmt::Debug for ArchiveMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
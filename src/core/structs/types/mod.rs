@@ -29,4 +29,8 @@ pub use types_entinstance::*;
 
 #[allow(unused)]
 pub mod types_color;
-pub use types_color::*;
\ No newline at end of file
+pub use types_color::*;
+
+#[allow(unused)]
+pub mod types_spatial;
+pub use types_spatial::*;
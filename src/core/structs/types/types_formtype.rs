@@ -1,37 +1,43 @@
+use std::fmt;
+use std::io;
+
+use crate::core::structs::forms::{get_form_type_by_name, with_form_type_registration};
+
 // ----------------------------- FormType ----------------------------- //
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub enum FormType {
-    STRING = 0,
-    WORLD = 1,
-    REFGROUP = 2,
-    WORLDPART = 3,  
-    WEATHER = 4,
-}
+/// Type byte of a form. The built-in types are associated constants, other types
+/// are registered at runtime with `register_form_type`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct FormType(u8);
 
 #[allow(unused)]
 impl FormType {
+    pub const STRING: FormType = FormType(0);
+    pub const WORLD: FormType = FormType(1);
+    pub const REFGROUP: FormType = FormType(2);
+    pub const WORLDPART: FormType = FormType(3);
+    pub const WEATHER: FormType = FormType(4);
+
     /// Number of bytes for `FormType` (1 byte).
     pub const BYTE_COUNT: usize = 1;
 
-    /// Converts `FormType` to its string representation.
-    pub fn to_string(&self) -> String {
-        match self {
-            FormType::STRING => "STRING".to_string(),
-            FormType::WORLD => "WORLD".to_string(),
-            FormType::REFGROUP => "REFGROUP".to_string(),
-            FormType::WORLDPART => "WORLDPART".to_string(),
-            FormType::WEATHER => "WEATHER".to_string(),
-        }
+    /// Creates a `FormType` from its type byte, usable in constants.
+    pub const fn new(byte: u8) -> Self {
+        FormType(byte)
     }
 
     /// Converts `FormType` to its integer representation.
     pub fn to_u8(&self) -> u8 {
-        *self as u8
+        self.0
     }
 
     /// Converts `FormType` to a byte.
     pub fn to_byte(&self) -> u8 {
-        *self as u8
+        self.0
+    }
+
+    /// Returns whether a deserializer is registered for this `FormType`.
+    pub fn is_registered(&self) -> bool {
+        with_form_type_registration(*self, |_| ()).is_some()
     }
 
     /// Returns the byte count for `FormType` (always 1).
@@ -40,30 +46,32 @@ impl FormType {
     }
 }
 
+impl fmt::Display for FormType {
+    /// Formats the registered name, or `UNKNOWN(<byte>)` when the type is not registered.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        with_form_type_registration(*self, |registration| f.pad(&registration.name)).unwrap_or_else(|| write!(f, "UNKNOWN({})", self.0))
+    }
+}
+
+impl fmt::Debug for FormType {
+    /// Formats the registered name, or `FormType(<byte>)` when the type is not registered.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        with_form_type_registration(*self, |registration| f.pad(&registration.name)).unwrap_or_else(|| write!(f, "FormType({})", self.0))
+    }
+}
+
 impl From<u8> for FormType {
-    /// Creates a `FormType` from a byte.
+    /// Creates a `FormType` from a byte. Unregistered bytes are kept and rejected when a form of that type is read.
     fn from(byte: u8) -> Self {
-        match byte {
-            0 => FormType::STRING,
-            1 => FormType::WORLD,
-            2 => FormType::REFGROUP,
-            3 => FormType::WORLDPART,
-            4 => FormType::WEATHER,
-            _ => panic!("Unknown FormType"),
-        }
+        FormType(byte)
     }
 }
 
-impl From<&str> for FormType {
-    /// Creates a `FormType` from a string.
-    fn from(s: &str) -> Self {
-        match s {
-            "STRING" => FormType::STRING,
-            "WORLD" => FormType::WORLD,
-            "REFGROUP" => FormType::REFGROUP,
-            "WORLDPART" => FormType::WORLDPART,
-            "WEATHER" => FormType::WEATHER,
-            _ => panic!("Unknown FormType"),
-        }
+impl TryFrom<&str> for FormType {
+    type Error = io::Error;
+
+    /// Creates a `FormType` from a registered name, failing if no type is registered under it.
+    fn try_from(s: &str) -> io::Result<Self> {
+        get_form_type_by_name(s).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown FormType {}", s)))
     }
 }
@@ -74,7 +74,7 @@ impl From<&GlobalID> for FormID {
 
 
 // -----------------------------  ArchiveID -----------------------------  //
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
 pub struct ArchiveID {
     value: u8,
 }
@@ -146,7 +146,7 @@ impl From<&GlobalID> for ArchiveID {
 
 
 // -----------------------------  GlobalID -----------------------------  //
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
 pub struct GlobalID {
     archive_id: ArchiveID,
     form_id: FormID,
@@ -157,6 +157,14 @@ impl GlobalID {
     /// Number of bytes for GlobalID (3 bytes: 1 for ArchiveID + 2 for FormID).
     pub const BYTE_COUNT: usize = ArchiveID::BYTE_COUNT + FormID::BYTE_COUNT;
 
+    /// The null GlobalID (`00000000`), used to clear a reference slot that cannot be removed.
+    pub const NULL: GlobalID = GlobalID { archive_id: ArchiveID { value: 0 }, form_id: FormID { value: 0 } };
+
+    /// Returns whether this is the null GlobalID.
+    pub fn is_null(&self) -> bool {
+        *self == Self::NULL
+    }
+
     /// Converts GlobalID to a byte array.
     pub fn to_bytes(&self) -> [u8; Self::BYTE_COUNT] {
         let mut bytes = [0u8; Self::BYTE_COUNT];
@@ -0,0 +1,292 @@
+use serde_json::json;
+
+use crate::core::structs::types::{Vec3Float, Vec3Int};
+
+// -----------------------------  WorldPartQuery -----------------------------  //
+/// A region of a world to find the world parts of, by their anchors.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum WorldPartQuery {
+    /// The world part whose anchor is nearest to the point, the first one listed on a tie.
+    Nearest(Vec3Int),
+    /// World parts anchored inside the box, its faces included.
+    Box { min: Vec3Int, max: Vec3Int },
+    /// World parts anchored within the radius of the center, its surface included.
+    Radius { center: Vec3Int, radius: u32 },
+}
+
+#[allow(unused)]
+impl WorldPartQuery {
+    /// Returns whether an anchor matches a box or radius query. A nearest query depends on the other
+    /// anchors, so no anchor matches it on its own.
+    pub fn contains(&self, anchor: Vec3Int) -> bool {
+        match self {
+            Self::Nearest(_) => false,
+            Self::Box { min, max } => {
+                (min.x..=max.x).contains(&anchor.x) && (min.y..=max.y).contains(&anchor.y) && (min.z..=max.z).contains(&anchor.z)
+            },
+            Self::Radius { center, radius } => get_distance_squared(*center, anchor) <= *radius as i128 * *radius as i128,
+        }
+    }
+
+    /// Returns the smallest box holding every anchor a box or radius query matches, as the inclusive
+    /// minimum and maximum of each axis. `None` for a nearest query, which has no bounds.
+    pub fn get_bounds(&self) -> Option<([i64; 3], [i64; 3])> {
+        match self {
+            Self::Nearest(_) => None,
+            Self::Box { min, max } => Some((get_coordinates(*min), get_coordinates(*max))),
+            Self::Radius { center, radius } => {
+                let center = get_coordinates(*center);
+                Some((center.map(|axis| axis - *radius as i64), center.map(|axis| axis + *radius as i64)))
+            },
+        }
+    }
+
+    /// Returns the positions of the matching anchors in the list.
+    pub fn get_matches(&self, anchors: &[Vec3Int]) -> Vec<usize> {
+        match self {
+            Self::Nearest(point) => anchors
+                .iter()
+                .enumerate()
+                .min_by_key(|(position, anchor)| (get_distance_squared(*point, **anchor), *position))
+                .map(|(position, _)| vec![position])
+                .unwrap_or_default(),
+            _ => (0..anchors.len()).filter(|position| self.contains(anchors[*position])).collect(),
+        }
+    }
+}
+
+/// Returns the squared distance between two points, exact for any coordinates.
+pub fn get_distance_squared(a: Vec3Int, b: Vec3Int) -> i128 {
+    let (dx, dy, dz) = (a.x as i128 - b.x as i128, a.y as i128 - b.y as i128, a.z as i128 - b.z as i128);
+    dx * dx + dy * dy + dz * dz
+}
+
+fn get_coordinates(point: Vec3Int) -> [i64; 3] {
+    [point.x as i64, point.y as i64, point.z as i64]
+}
+
+fn get_has_nan(point: Vec3Float) -> bool {
+    point.x.is_nan() || point.y.is_nan() || point.z.is_nan()
+}
+
+// -----------------------------  BoundingBox -----------------------------  //
+/// An axis-aligned box, its faces included.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct BoundingBox {
+    pub min: Vec3Float,
+    pub max: Vec3Float,
+}
+
+#[allow(unused)]
+impl BoundingBox {
+    /// Number of bytes for `BoundingBox`: its minimum and maximum `Vec3Float`, 24 bytes.
+    pub const BYTE_COUNT: usize = 2 * Vec3Float::BYTE_COUNT;
+
+    /// Returns the smallest box holding every point, `None` if there are none. Points with a NaN
+    /// coordinate lie nowhere and are left out.
+    pub fn from_points(points: impl IntoIterator<Item = Vec3Float>) -> Option<Self> {
+        let mut points = points.into_iter().filter(|point| !get_has_nan(*point));
+        let first = points.next()?;
+        Some(points.fold(Self { min: first, max: first }, |bounds, point| Self {
+            min: Vec3Float::from((bounds.min.x.min(point.x), bounds.min.y.min(point.y), bounds.min.z.min(point.z))),
+            max: Vec3Float::from((bounds.max.x.max(point.x), bounds.max.y.max(point.y), bounds.max.z.max(point.z))),
+        }))
+    }
+
+    /// Returns whether the point is inside the box.
+    pub fn contains(&self, point: Vec3Float) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y) && (self.min.z..=self.max.z).contains(&point.z)
+    }
+
+    /// Returns whether the two boxes share any point.
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min.x <= other.max.x && other.min.x <= self.max.x
+            && self.min.y <= other.max.y && other.min.y <= self.max.y
+            && self.min.z <= other.max.z && other.min.z <= self.max.z
+    }
+
+    /// Converts `BoundingBox` to a byte array, the minimum then the maximum.
+    pub fn to_bytes(&self) -> [u8; Self::BYTE_COUNT] {
+        let mut bytes = [0u8; Self::BYTE_COUNT];
+        bytes[..Vec3Float::BYTE_COUNT].copy_from_slice(&self.min.to_bytes());
+        bytes[Vec3Float::BYTE_COUNT..].copy_from_slice(&self.max.to_bytes());
+        bytes
+    }
+
+    pub fn to_dict(&self) -> serde_json::Value {
+        json!({
+            "min": self.min.to_dict(),
+            "max": self.max.to_dict(),
+        })
+    }
+}
+
+impl From<[u8; BoundingBox::BYTE_COUNT]> for BoundingBox {
+    /// Creates a `BoundingBox` from a byte array.
+    fn from(bytes: [u8; BoundingBox::BYTE_COUNT]) -> Self {
+        Self {
+            min: Vec3Float::from(<[u8; Vec3Float::BYTE_COUNT]>::try_from(&bytes[..Vec3Float::BYTE_COUNT]).unwrap()),
+            max: Vec3Float::from(<[u8; Vec3Float::BYTE_COUNT]>::try_from(&bytes[Vec3Float::BYTE_COUNT..]).unwrap()),
+        }
+    }
+}
+
+// -----------------------------  Plane -----------------------------  //
+/// A plane splitting space into an inner side, the one its normal points to, and an outer side.
+/// A point is on the inner side when `normal · point + distance >= 0`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Plane {
+    pub normal: Vec3Float,
+    pub distance: f32,
+}
+
+#[allow(unused)]
+impl Plane {
+    /// Returns how far the point is from the plane, in lengths of the normal, negative on its outer side.
+    pub fn get_signed_distance(&self, point: Vec3Float) -> f64 {
+        self.normal.x as f64 * point.x as f64 + self.normal.y as f64 * point.y as f64 + self.normal.z as f64 * point.z as f64 + self.distance as f64
+    }
+}
+
+// -----------------------------  EntityQuery -----------------------------  //
+/// A region of a world part to find the entity instances of, by their positions.
+#[derive(Debug, PartialEq, Clone)]
+pub enum EntityQuery {
+    /// Entities positioned inside the box, its faces included.
+    Box { min: Vec3Float, max: Vec3Float },
+    /// Entities positioned within the radius of the center, its surface included.
+    Radius { center: Vec3Float, radius: f32 },
+    /// Entities positioned on the inner side of every plane, such as the six planes of a view frustum.
+    Planes(Vec<Plane>),
+}
+
+#[allow(unused)]
+impl EntityQuery {
+    /// Returns whether a position matches the query. Positions with a NaN coordinate never match.
+    pub fn contains(&self, position: Vec3Float) -> bool {
+        match self {
+            Self::Box { min, max } => BoundingBox { min: *min, max: *max }.contains(position),
+            Self::Radius { center, radius } => {
+                let (dx, dy, dz) = (position.x as f64 - center.x as f64, position.y as f64 - center.y as f64, position.z as f64 - center.z as f64);
+                *radius >= 0.0 && dx * dx + dy * dy + dz * dz <= *radius as f64 * *radius as f64
+            },
+            Self::Planes(planes) => {
+                !get_has_nan(position) && planes.iter().all(|plane| plane.get_signed_distance(position) >= 0.0)
+            },
+        }
+    }
+
+    /// Returns whether any position inside the box could match the query. Never false for a box
+    /// holding a matching position, so boxes it is false for can be skipped whole.
+    pub fn overlaps(&self, bounds: &BoundingBox) -> bool {
+        match self {
+            Self::Box { min, max } => bounds.intersects(&BoundingBox { min: *min, max: *max }),
+            Self::Radius { center, .. } => {
+                // The point of the box nearest to the center
+                let nearest = Vec3Float::from((center.x.max(bounds.min.x).min(bounds.max.x), center.y.max(bounds.min.y).min(bounds.max.y), center.z.max(bounds.min.z).min(bounds.max.z)));
+                self.contains(nearest)
+            },
+            Self::Planes(planes) => planes.iter().all(|plane| {
+                // The corner of the box furthest along the normal, axes the normal ignores left at 0 so an
+                // infinite box does not give NaN
+                let get_corner_axis = |normal: f32, min: f32, max: f32| if normal > 0.0 { max } else if normal < 0.0 { min } else { 0.0 };
+                let corner = Vec3Float::from((
+                    get_corner_axis(plane.normal.x, bounds.min.x, bounds.max.x),
+                    get_corner_axis(plane.normal.y, bounds.min.y, bounds.max.y),
+                    get_corner_axis(plane.normal.z, bounds.min.z, bounds.max.z),
+                ));
+                plane.get_signed_distance(corner) >= 0.0
+            }),
+        }
+    }
+
+    /// Returns the positions of the matching entity positions in the list.
+    pub fn get_matches(&self, positions: &[Vec3Float]) -> Vec<usize> {
+        (0..positions.len()).filter(|position| self.contains(positions[*position])).collect()
+    }
+}
+
+// -----------------------------  EntityIndex -----------------------------  //
+/// Largest number of entity positions a leaf of an `EntityIndex` holds.
+const ENTITY_INDEX_LEAF_SIZE: usize = 8;
+
+/// A bounding volume hierarchy over the entity positions of a world part, answering an
+/// `EntityQuery` by skipping every node whose bounds the query does not overlap.
+#[derive(Debug, Clone)]
+pub struct EntityIndex {
+    nodes: Vec<EntityIndexNode>,
+    entries: Vec<(usize, Vec3Float)>,
+}
+
+/// A node of an `EntityIndex`. Leaves hold `count` entries from `start`, other nodes have their
+/// first child right after them and their second child at `second_child`.
+#[derive(Debug, Clone)]
+struct EntityIndexNode {
+    bounds: BoundingBox,
+    start: usize,
+    count: usize,
+    second_child: usize,
+}
+
+#[allow(unused)]
+impl EntityIndex {
+    /// Builds the hierarchy over entity positions, splitting each node at the median of its longest
+    /// axis. Positions with a NaN coordinate never match a query and are left out.
+    pub fn new(positions: &[Vec3Float]) -> Self {
+        let mut index = Self {
+            nodes: Vec::new(),
+            entries: positions.iter().copied().enumerate().filter(|(_, position)| !get_has_nan(*position)).collect(),
+        };
+        if !index.entries.is_empty() {
+            index.build_node(0, index.entries.len());
+        }
+        index
+    }
+
+    fn build_node(&mut self, start: usize, end: usize) -> usize {
+        let entries = &mut self.entries[start..end];
+        let bounds = BoundingBox::from_points(entries.iter().map(|(_, position)| *position)).unwrap();
+        let node = self.nodes.len();
+        self.nodes.push(EntityIndexNode { bounds, start, count: end - start, second_child: 0 });
+        if entries.len() <= ENTITY_INDEX_LEAF_SIZE {
+            return node;
+        }
+
+        let extents = [bounds.max.x - bounds.min.x, bounds.max.y - bounds.min.y, bounds.max.z - bounds.min.z];
+        let axis = (0..3).max_by(|a, b| extents[*a].total_cmp(&extents[*b])).unwrap();
+        let get_axis = |position: &Vec3Float| [position.x, position.y, position.z][axis];
+        let middle = entries.len() / 2;
+        entries.select_nth_unstable_by(middle, |(_, a), (_, b)| get_axis(a).total_cmp(&get_axis(b)));
+
+        self.nodes[node].count = 0;
+        self.build_node(start, start + middle);
+        self.nodes[node].second_child = self.build_node(start + middle, end);
+        node
+    }
+
+    /// Returns the bounds of the indexed positions, `None` if there are none.
+    pub fn get_bounds(&self) -> Option<BoundingBox> {
+        self.nodes.first().map(|node| node.bounds)
+    }
+
+    /// Returns the positions in the entity list of the entities matching the query, in list order.
+    pub fn query(&self, query: &EntityQuery) -> Vec<usize> {
+        let mut matches = Vec::new();
+        let mut stack = if self.nodes.is_empty() { Vec::new() } else { vec![0] };
+        while let Some(node) = stack.pop() {
+            let node_item = &self.nodes[node];
+            if !query.overlaps(&node_item.bounds) {
+                continue;
+            }
+            if node_item.count == 0 {
+                stack.push(node_item.second_child);
+                stack.push(node + 1);
+                continue;
+            }
+            let entries = &self.entries[node_item.start..node_item.start + node_item.count];
+            matches.extend(entries.iter().filter(|(_, position)| query.contains(*position)).map(|(entity, _)| *entity));
+        }
+        matches.sort_unstable();
+        matches
+    }
+}
@@ -3,7 +3,7 @@ use std::fs::OpenOptions;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
-pub fn formtype_add(formtype_name: &str) -> io::Result<(PathBuf, PathBuf, PathBuf)> {
+pub fn formtype_add(formtype_name: &str, formtype_byte: u8) -> io::Result<(PathBuf, PathBuf, PathBuf)> {
     // Get the current working directory
     let current_dir = match env::current_dir() {
         Ok(path) => path,
@@ -48,7 +48,7 @@ pub fn formtype_add(formtype_name: &str) -> io::Result<(PathBuf, PathBuf, PathBu
     println!("Types misc path: {}", path_types_misc.display());
 
     // Generate the form class
-    match generate_form_type_class(&path_form_template, &path_form_out_dir, &formtype_name) {
+    match generate_form_type_class(&path_form_template, &path_form_out_dir, &formtype_name, formtype_byte) {
         Ok(_) => {},
         Err(e) => {
             eprintln!("Failed to generate form class: {}", e);
@@ -69,7 +69,7 @@ pub fn formtype_add(formtype_name: &str) -> io::Result<(PathBuf, PathBuf, PathBu
     Ok((path_form_template, path_form_out_dir, path_types_misc))
 }
 
-fn generate_form_type_class(path_form_template: &Path, path_form_out_dir: &Path, formtype_name: &str) -> io::Result<()> {
+fn generate_form_type_class(path_form_template: &Path, path_form_out_dir: &Path, formtype_name: &str, formtype_byte: u8) -> io::Result<()> {
     // Read the form template file
     let form_template = match std::fs::read_to_string(path_form_template) {
         Ok(content) => content,
@@ -81,7 +81,8 @@ fn generate_form_type_class(path_form_template: &Path, path_form_out_dir: &Path,
 
     // Replace the formtype_name placeholder with the actual formtype_name
     let form_type_name_lower = formtype_name.to_lowercase();
-    let form_class = form_template.replace("FormType::*FORMTYPE*", &format!("FormType::{}", formtype_name.to_uppercase()));
+    let form_class = form_template.replace("*FORMTYPEBYTE*", &formtype_byte.to_string());
+    let form_class = form_class.replace("*FORMTYPENAME*", &formtype_name.to_uppercase());
     let form_class = form_class.replace("*FORMTYPE*", formtype_name);
     // Define the path for the new form file
     let path_form_out = path_form_out_dir.join(format!("struc_form_{}.rs", form_type_name_lower));
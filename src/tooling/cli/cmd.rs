@@ -1,11 +1,28 @@
-use std::env;
+use std::{env, fs};
 
+use crate::core::io::{read_lite_archive_page, LiteArchiveQuery, build_world_part_bounds, query_world_part_entities, read_world_part_bounds, read_world_part_bounds_all, remove_world_part_bounds, build_world_part_grid, get_world_part_grid_cell_size, query_world_parts, remove_world_part_grid, build_type_index, read_forms_of_type, read_type_index, remove_type_index, build_name_index, read_form_by_name, read_name_index, remove_name_index, read_archive_metadata, remove_archive_metadata_value, set_archive_metadata_value, allocate_form_id, allocate_form_id_in_range, collect_garbage, delete_form_checked, embed_archive_schemas, find_unreachable_forms, load_archive_schemas, read_archive_schemas, read_reserved_ranges, remap_archive_id, remove_archive_schema, renumber_forms, reserve_form_id_range, set_archive_layout_version, upgrade_archive, write_archive_schema, write_form, DeleteMode, FormIDRange, ReferenceIndex, RemapReport};
+use crate::core::structs::{ArchiveMetadataValue, get_form_type_by_name, get_form_type_registration, ArchiveID, FormDynamic, FormID, FormSchema, FormType, GlobalID, StrSml, BoundingBox, EntityQuery, Vec3Float, Vec3Int, WorldPartQuery};
 use crate::tooling::{automation, testing::*};
 
 // Command list
-const CMD_LIST: [&str; 2] = [
+const CMD_LIST: [&str; 17] = [
     "test",
     "gen",
+    "refs",
+    "delete",
+    "renumber",
+    "remaparchive",
+    "reserve",
+    "allocid",
+    "gc",
+    "schema",
+    "upgrade",
+    "meta",
+    "names",
+    "types",
+    "lite",
+    "grid",
+    "bounds",
 ];
 
 pub fn run_cmd() {
@@ -27,6 +44,51 @@ pub fn run_cmd() {
         "buildfull" => {
             cmd_buildfull(args);
         },
+        "refs" => {
+            cmd_refs(args);
+        },
+        "delete" => {
+            cmd_delete(args);
+        },
+        "renumber" => {
+            cmd_renumber(args);
+        },
+        "remaparchive" => {
+            cmd_remaparchive(args);
+        },
+        "reserve" => {
+            cmd_reserve(args);
+        },
+        "allocid" => {
+            cmd_allocid(args);
+        },
+        "gc" => {
+            cmd_gc(args);
+        },
+        "schema" => {
+            cmd_schema(args);
+        },
+        "upgrade" => {
+            cmd_upgrade(args);
+        },
+        "meta" => {
+            cmd_meta(args);
+        },
+        "names" => {
+            cmd_names(args);
+        },
+        "types" => {
+            cmd_types(args);
+        },
+        "lite" => {
+            cmd_lite(args);
+        },
+        "grid" => {
+            cmd_grid(args);
+        },
+        "bounds" => {
+            cmd_bounds(args);
+        },
         _ => {
             println!("Invalid command, available commands are:");
             for cmd in CMD_LIST.iter() {
@@ -60,6 +122,682 @@ fn cmd_buildfull(args: Vec<String>) {
     automation::build::build_full();
 }
 
+// References -----------------------------------
+fn cmd_refs(args: Vec<String>) {
+    if args.len() < 4 {
+        println!("Usage: refs [GlobalID] [Archive Path] [Archive Path...]");
+        return;
+    }
+
+    // Extract the referenced GlobalID (8 digits: 3 for ArchiveID + 5 for FormID)
+    let target_str = &args[2];
+    if target_str.len() != 8 || !target_str.chars().all(|c| c.is_ascii_digit()) {
+        println!("Invalid GlobalID: {}", target_str);
+        return;
+    }
+    let target = GlobalID::from(target_str.as_str());
+
+    // Build the index over every archive given
+    let file_paths: Vec<&str> = args[3..].iter().map(|path| path.as_str()).collect();
+    let index = match ReferenceIndex::build(&file_paths) {
+        Ok(index) => index,
+        Err(e) => {
+            println!("Error building reference index: {:?}", e);
+            return;
+        }
+    };
+
+    let referrers = index.get_referrers(target);
+    if referrers.is_empty() {
+        println!("No forms reference {}", target);
+        return;
+    }
+
+    println!("{} form(s) reference {}:", referrers.len(), target);
+    for referrer in referrers {
+        println!("-> {}", referrer);
+    }
+}
+
+fn cmd_delete(args: Vec<String>) {
+    if args.len() < 5 {
+        println!("Usage: delete [unchecked/restrict/cascade] [FormID] [Archive Path] [Referencing Archive Path...]");
+        return;
+    }
+
+    // Extract the delete mode
+    let mode = match args[2].as_str() {
+        "unchecked" => DeleteMode::Unchecked,
+        "restrict" => DeleteMode::Restrict,
+        "cascade" => DeleteMode::Cascade,
+        _ => {
+            println!("Invalid delete mode: {}", args[2]);
+            return;
+        }
+    };
+
+    // Extract the FormID to delete
+    let form_id: u16 = match args[3].parse() {
+        Ok(n) => n,
+        Err(_) => {
+            println!("Invalid FormID: {}", args[3]);
+            return;
+        }
+    };
+
+    let file_path = &args[4];
+    let archive_paths: Vec<&str> = args[4..].iter().map(|path| path.as_str()).collect();
+
+    match delete_form_checked(&archive_paths, file_path, FormID::from(form_id), mode) {
+        Ok(report) if report.deleted => {
+            println!("Deleted form {}", FormID::from(form_id).to_string());
+            let action = if mode == DeleteMode::Cascade { "updated" } else { "left dangling in" };
+            for referrer in report.referrers {
+                println!("-> {} {}", action, referrer);
+            }
+        },
+        Ok(report) => {
+            println!("Form {} is still referenced, not deleted:", FormID::from(form_id).to_string());
+            for referrer in report.referrers {
+                println!("-> {}", referrer);
+            }
+        },
+        Err(e) => {
+            println!("Error deleting form: {:?}", e);
+        }
+    }
+}
+
+fn cmd_renumber(args: Vec<String>) {
+    if args.len() < 4 {
+        println!("Usage: renumber [Old FormID:New FormID,...] [Archive Path] [Referencing Archive Path...]");
+        return;
+    }
+
+    // Extract the old:new FormID pairs
+    let mut renumbering = Vec::new();
+    for pair in args[2].split(',') {
+        let parsed = pair.split_once(':')
+            .and_then(|(old_id, new_id)| Some((old_id.parse::<u16>().ok()?, new_id.parse::<u16>().ok()?)));
+        match parsed {
+            Some((old_id, new_id)) => renumbering.push((FormID::from(old_id), FormID::from(new_id))),
+            None => {
+                println!("Invalid FormID pair: {}", pair);
+                return;
+            }
+        }
+    }
+
+    let file_path = &args[3];
+    let archive_paths: Vec<&str> = args[3..].iter().map(|path| path.as_str()).collect();
+
+    match renumber_forms(&archive_paths, file_path, &renumbering) {
+        Ok(report) => print_remap_report(&report),
+        Err(e) => println!("Error renumbering forms: {:?}", e),
+    }
+}
+
+fn cmd_remaparchive(args: Vec<String>) {
+    if args.len() < 4 {
+        println!("Usage: remaparchive [New ArchiveID] [Archive Path] [Referencing Archive Path...]");
+        return;
+    }
+
+    // Extract the new ArchiveID
+    let archive_id: u8 = match args[2].parse() {
+        Ok(n) => n,
+        Err(_) => {
+            println!("Invalid ArchiveID: {}", args[2]);
+            return;
+        }
+    };
+
+    let file_path = &args[3];
+    let archive_paths: Vec<&str> = args[3..].iter().map(|path| path.as_str()).collect();
+
+    match remap_archive_id(&archive_paths, file_path, ArchiveID::from(archive_id)) {
+        Ok(report) => print_remap_report(&report),
+        Err(e) => println!("Error remapping archive: {:?}", e),
+    }
+}
+
+fn print_remap_report(report: &RemapReport) {
+    println!("{}", serde_json::to_string_pretty(&report.to_dict()).unwrap());
+}
+
+fn cmd_gc(args: Vec<String>) {
+    if args.len() < 5 {
+        println!("Usage: gc [report/remove] [Root GlobalID,...] [Archive Path] [Archive Path...]");
+        return;
+    }
+
+    // Extract whether to remove the unreachable forms
+    let remove = match args[2].as_str() {
+        "report" => false,
+        "remove" => true,
+        _ => {
+            println!("Invalid gc mode: {}", args[2]);
+            return;
+        }
+    };
+
+    // Extract the root GlobalIDs (8 digits each: 3 for ArchiveID + 5 for FormID)
+    let mut roots = Vec::new();
+    for root_str in args[3].split(',') {
+        if root_str.len() != 8 || !root_str.chars().all(|c| c.is_ascii_digit()) {
+            println!("Invalid GlobalID: {}", root_str);
+            return;
+        }
+        roots.push(GlobalID::from(root_str));
+    }
+
+    let archive_paths: Vec<&str> = args[4..].iter().map(|path| path.as_str()).collect();
+    let result = if remove {
+        collect_garbage(&archive_paths, &roots)
+    } else {
+        find_unreachable_forms(&archive_paths, &roots)
+    };
+
+    match result {
+        Ok(report) => {
+            println!("{} reachable, {} unreachable form(s)", report.reachable.len(), report.unreachable.len());
+            for form in &report.unreachable {
+                println!("-> {}", form);
+            }
+            if remove {
+                println!("Removed {} form(s)", report.removed);
+            }
+        },
+        Err(e) => println!("Error collecting garbage: {:?}", e),
+    }
+}
+
+// FormID Allocation -----------------------------
+fn cmd_reserve(args: Vec<String>) {
+    if args.len() == 3 {
+        // List the reserved ranges
+        match read_reserved_ranges(&args[2]) {
+            Ok(ranges) if ranges.is_empty() => println!("No reserved ranges"),
+            Ok(ranges) => {
+                for range in ranges {
+                    println!("-> {}: {} - {}", range.name.to_string(), range.start.to_string(), range.end.to_string());
+                }
+            },
+            Err(e) => println!("Error reading reserved ranges: {:?}", e),
+        }
+        return;
+    }
+
+    if args.len() != 6 {
+        println!("Usage: reserve [Archive Path] [Range Name] [Start FormID] [End FormID]");
+        return;
+    }
+
+    // Extract the range name and bounds
+    let name = &args[3];
+    if name.len() > u8::MAX as usize || !name.is_ascii() {
+        println!("Invalid range name: {}", name);
+        return;
+    }
+    let (start, end): (u16, u16) = match (args[4].parse(), args[5].parse()) {
+        (Ok(start), Ok(end)) => (start, end),
+        _ => {
+            println!("Invalid FormID range: {} - {}", args[4], args[5]);
+            return;
+        }
+    };
+
+    let range = FormIDRange::new(StrSml::from(name.as_str()), FormID::from(start), FormID::from(end));
+    match reserve_form_id_range(&args[2], range) {
+        Ok(()) => println!("Reserved {} for {}", format!("{} - {}", FormID::from(start).to_string(), FormID::from(end).to_string()), name),
+        Err(e) => println!("Error reserving range: {:?}", e),
+    }
+}
+
+fn cmd_allocid(args: Vec<String>) {
+    if args.len() < 3 || args.len() > 4 {
+        println!("Usage: allocid [Archive Path] [Range Name]");
+        return;
+    }
+
+    // Allocate outside the reserved ranges unless a range is named
+    let result = match args.get(3) {
+        Some(name) => allocate_form_id_in_range(&args[2], name),
+        None => allocate_form_id(&args[2]),
+    };
+
+    match result {
+        Ok(form_id) => println!("Next free FormID: {}", form_id.to_string()),
+        Err(e) => println!("Error allocating FormID: {:?}", e),
+    }
+}
+
+// Form Schemas ----------------------------------
+fn cmd_schema(args: Vec<String>) {
+    if args.len() == 3 {
+        // List the stored schemas
+        match read_archive_schemas(&args[2]) {
+            Ok(schemas) if schemas.is_empty() => println!("No form schemas"),
+            Ok(schemas) => {
+                for schema in schemas {
+                    println!("-> {} ({})", schema.name.to_string(), schema.form_type.to_u8());
+                    for field in schema.fields {
+                        println!("   {}: {}", field.name.to_string(), field.field_type.to_string());
+                    }
+                }
+            },
+            Err(e) => println!("Error reading form schemas: {:?}", e),
+        }
+        return;
+    }
+
+    if args.len() == 4 && args[3] == "embed" {
+        // Store the schema of every form type in the archive
+        match embed_archive_schemas(&args[2]) {
+            Ok(undescribed) if undescribed.is_empty() => println!("Embedded schemas of all form types"),
+            Ok(undescribed) => {
+                let undescribed: Vec<String> = undescribed.iter().map(|form_type| form_type.to_string()).collect();
+                println!("Embedded schemas, no schema known for: {}", undescribed.join(", "));
+            },
+            Err(e) => println!("Error embedding schemas: {:?}", e),
+        }
+        return;
+    }
+
+    if args.len() != 5 {
+        println!("Usage: schema [Archive Path] [embed] | [add/remove/writeform] [Schema JSON Path/Type Byte/Form JSON Path]");
+        return;
+    }
+
+    let archive_path = &args[2];
+    match args[3].as_str() {
+        "add" => {
+            let schema = match read_json_file(&args[4]).and_then(|dict| FormSchema::from_dict(&dict)) {
+                Ok(schema) => schema,
+                Err(e) => {
+                    println!("Error reading schema: {:?}", e);
+                    return;
+                }
+            };
+            match write_archive_schema(archive_path, &schema) {
+                Ok(()) => println!("Stored schema {} as FormType {}", schema.name.to_string(), schema.form_type.to_u8()),
+                Err(e) => println!("Error storing schema: {:?}", e),
+            }
+        },
+        "remove" => {
+            let form_type: u8 = match args[4].parse() {
+                Ok(form_type) => form_type,
+                Err(_) => {
+                    println!("Invalid type byte: {}", args[4]);
+                    return;
+                }
+            };
+            match remove_archive_schema(archive_path, FormType::from(form_type)) {
+                Ok(true) => println!("Removed schema of FormType {}", form_type),
+                Ok(false) => println!("No schema stored for FormType {}", form_type),
+                Err(e) => println!("Error removing schema: {:?}", e),
+            }
+        },
+        "writeform" => {
+            // The form's type is named in its JSON, so the archive's schemas must be known first
+            let form = load_archive_schemas(archive_path)
+                .and_then(|_| read_json_file(&args[4]))
+                .and_then(|dict| FormDynamic::from_dict(&dict));
+            match form.and_then(|form| write_form(archive_path, &form).map(|_| form)) {
+                Ok(form) => println!("Wrote {}", form),
+                Err(e) => println!("Error writing form: {:?}", e),
+            }
+        },
+        _ => println!("Usage: schema [Archive Path] [embed] | [add/remove/writeform] [Schema JSON Path/Type Byte/Form JSON Path]"),
+    }
+}
+
+// Form Layouts ----------------------------------
+fn cmd_upgrade(args: Vec<String>) {
+    if args.len() != 3 && args.len() != 5 {
+        println!("Usage: upgrade [Archive Path] [Form Type Name/Byte] [Stored Layout Version]");
+        return;
+    }
+
+    let archive_path = &args[2];
+    if args.len() == 5 {
+        // Declare the layout of forms written before versions were recorded
+        let form_type = match args[3].parse::<u8>() {
+            Ok(byte) => Some(FormType::from(byte)),
+            Err(_) => get_form_type_by_name(&args[3]),
+        };
+        let Some(form_type) = form_type else {
+            println!("Unknown form type: {}", args[3]);
+            return;
+        };
+        let layout_version: u16 = match args[4].parse() {
+            Ok(layout_version) => layout_version,
+            Err(_) => {
+                println!("Invalid layout version: {}", args[4]);
+                return;
+            }
+        };
+        if let Err(e) = set_archive_layout_version(archive_path, form_type, layout_version) {
+            println!("Error setting layout version: {:?}", e);
+            return;
+        }
+    }
+
+    match upgrade_archive(archive_path) {
+        Ok(upgrades) if upgrades.is_empty() => println!("All forms are in their current layout"),
+        Ok(upgrades) => {
+            for upgrade in upgrades {
+                println!("-> {}: {} form(s) upgraded from layout {} to {}", upgrade.form_type.to_string(), upgrade.form_count, upgrade.from_version, upgrade.to_version);
+            }
+        },
+        Err(e) => println!("Error upgrading archive: {:?}", e),
+    }
+}
+
+// Archive Metadata ----------------------------------
+fn cmd_meta(args: Vec<String>) {
+    if args.len() < 3 {
+        println!("Usage: meta [Archive Path] [set/remove] [Key] [str/u64/i64/f64/bool/bytes] [Value]");
+        return;
+    }
+
+    if args.len() == 3 {
+        // List the metadata values
+        match read_archive_metadata(&args[2]) {
+            Ok(metadata) if metadata.is_empty() => println!("No archive metadata"),
+            Ok(metadata) => {
+                for (key, value) in metadata.iter() {
+                    println!("-> {} ({}): {}", key, value.get_type_name(), value);
+                }
+            },
+            Err(e) => println!("Error reading archive metadata: {:?}", e),
+        }
+        return;
+    }
+
+    let archive_path = &args[2];
+    match (args[3].as_str(), args.len()) {
+        ("set", 7) => {
+            let result = ArchiveMetadataValue::parse(&args[5], &args[6])
+                .and_then(|value| set_archive_metadata_value(archive_path, &args[4], value));
+            match result {
+                Ok(()) => println!("Set {}", args[4]),
+                Err(e) => println!("Error setting archive metadata: {:?}", e),
+            }
+        },
+        ("remove", 5) => match remove_archive_metadata_value(archive_path, &args[4]) {
+            Ok(true) => println!("Removed {}", args[4]),
+            Ok(false) => println!("No metadata value {}", args[4]),
+            Err(e) => println!("Error removing archive metadata: {:?}", e),
+        },
+        _ => println!("Usage: meta [Archive Path] [set/remove] [Key] [str/u64/i64/f64/bool/bytes] [Value]"),
+    }
+}
+
+// Form Name Index ----------------------------------
+fn cmd_names(args: Vec<String>) {
+    if args.len() < 3 || args.len() > 5 {
+        println!("Usage: names [Archive Path] [build/remove/find] [Form Name]");
+        return;
+    }
+
+    let archive_path = &args[2];
+    match (args.get(3).map(|arg| arg.as_str()), args.get(4)) {
+        (None, None) => match read_name_index(archive_path) {
+            Ok(Some(names)) => {
+                for (form_name, form_id) in names {
+                    println!("-> {}: {}", form_name, form_id.to_string());
+                }
+            },
+            Ok(None) => println!("No name index"),
+            Err(e) => println!("Error reading name index: {:?}", e),
+        },
+        (Some("build"), None) => match build_name_index(archive_path) {
+            Ok(count) => println!("Indexed {} form name(s)", count),
+            Err(e) => println!("Error building name index: {:?}", e),
+        },
+        (Some("remove"), None) => match remove_name_index(archive_path) {
+            Ok(true) => println!("Removed name index"),
+            Ok(false) => println!("No name index"),
+            Err(e) => println!("Error removing name index: {:?}", e),
+        },
+        (Some("find"), Some(form_name)) => match read_form_by_name(archive_path, form_name) {
+            Ok(form) => println!("{}", form.to_dict()),
+            Err(e) => println!("Error reading form: {:?}", e),
+        },
+        _ => println!("Usage: names [Archive Path] [build/remove/find] [Form Name]"),
+    }
+}
+
+// Form Type Index ----------------------------------
+fn cmd_types(args: Vec<String>) {
+    if args.len() < 3 || args.len() > 5 {
+        println!("Usage: types [Archive Path] [build/remove/read] [Form Type Name/Byte]");
+        return;
+    }
+
+    let archive_path = &args[2];
+    match (args.get(3).map(|arg| arg.as_str()), args.get(4)) {
+        (None, None) => match read_type_index(archive_path) {
+            Ok(Some(types)) => {
+                for (form_type, form_ids) in types {
+                    println!("-> {}: {} form(s)", form_type.to_string(), form_ids.len());
+                }
+            },
+            Ok(None) => println!("No type index"),
+            Err(e) => println!("Error reading type index: {:?}", e),
+        },
+        (Some("build"), None) => match build_type_index(archive_path) {
+            Ok(count) => println!("Indexed {} form type(s)", count),
+            Err(e) => println!("Error building type index: {:?}", e),
+        },
+        (Some("remove"), None) => match remove_type_index(archive_path) {
+            Ok(true) => println!("Removed type index"),
+            Ok(false) => println!("No type index"),
+            Err(e) => println!("Error removing type index: {:?}", e),
+        },
+        (Some("read"), Some(type_arg)) => {
+            let form_type = match type_arg.parse::<u8>() {
+                Ok(byte) => Some(FormType::from(byte)),
+                Err(_) => get_form_type_by_name(type_arg),
+            };
+            let Some(form_type) = form_type else {
+                println!("Unknown form type: {}", type_arg);
+                return;
+            };
+            match read_forms_of_type(archive_path, form_type) {
+                Ok(forms) => {
+                    for form in forms {
+                        println!("-> {}", form.to_dict());
+                    }
+                },
+                Err(e) => println!("Error reading forms: {:?}", e),
+            }
+        },
+        _ => println!("Usage: types [Archive Path] [build/remove/read] [Form Type Name/Byte]"),
+    }
+}
+
+// Lite Listing ----------------------------------
+fn cmd_lite(args: Vec<String>) {
+    if args.len() < 3 || args.len() > 6 {
+        println!("Usage: lite [Archive Path] [Form Type Name/Byte/all] [Offset] [Limit]");
+        return;
+    }
+
+    let mut query = LiteArchiveQuery::default();
+    if let Some(type_arg) = args.get(3).filter(|arg| arg.as_str() != "all") {
+        let form_type = match type_arg.parse::<u8>() {
+            Ok(byte) => Some(FormType::from(byte)),
+            Err(_) => get_form_type_by_name(type_arg),
+        };
+        let Some(form_type) = form_type else {
+            println!("Unknown form type: {}", type_arg);
+            return;
+        };
+        query.form_type = Some(form_type);
+    }
+    if let Some(offset_arg) = args.get(4) {
+        let Ok(offset) = offset_arg.parse() else {
+            println!("Invalid offset: {}", offset_arg);
+            return;
+        };
+        query.offset = offset;
+    }
+    if let Some(limit_arg) = args.get(5) {
+        let Ok(limit) = limit_arg.parse() else {
+            println!("Invalid limit: {}", limit_arg);
+            return;
+        };
+        query.limit = Some(limit);
+    }
+
+    match read_lite_archive_page(&args[2], &query) {
+        Ok(lite) => {
+            println!("Listing {} of {} matching form(s), {} in archive", lite.archive_items.len(), lite.match_count, lite.form_count);
+            for item in &lite.archive_items {
+                println!("-> {:?}", item);
+            }
+        },
+        Err(e) => println!("Error reading lite archive: {:?}", e),
+    }
+}
+
+// World Part Grid ----------------------------------
+fn cmd_grid(args: Vec<String>) {
+    if args.len() < 3 || args.len() > 7 {
+        println!("Usage: grid [Archive Path] [build/remove/near] [Cell Size/World FormID] [x,y,z] [Radius]");
+        return;
+    }
+
+    let archive_path = &args[2];
+    match (args.get(3).map(|arg| arg.as_str()), args.get(4)) {
+        (None, None) => match get_world_part_grid_cell_size(archive_path) {
+            Ok(Some(cell_size)) => println!("World part grid with {} unit cells", cell_size),
+            Ok(None) => println!("No world part grid"),
+            Err(e) => println!("Error reading world part grid: {:?}", e),
+        },
+        (Some("build"), Some(cell_size_arg)) => {
+            let Ok(cell_size) = cell_size_arg.parse::<u32>() else {
+                println!("Invalid cell size: {}", cell_size_arg);
+                return;
+            };
+            match build_world_part_grid(archive_path, cell_size) {
+                Ok(count) => println!("Indexed the parts of {} world(s)", count),
+                Err(e) => println!("Error building world part grid: {:?}", e),
+            }
+        },
+        (Some("remove"), None) => match remove_world_part_grid(archive_path) {
+            Ok(true) => println!("Removed world part grid"),
+            Ok(false) => println!("No world part grid"),
+            Err(e) => println!("Error removing world part grid: {:?}", e),
+        },
+        (Some("near"), Some(world_arg)) => {
+            let Ok(world_id) = world_arg.parse::<u16>() else {
+                println!("Invalid world FormID: {}", world_arg);
+                return;
+            };
+            let coordinates: Vec<i32> = args.get(5).map(|arg| arg.split(',').filter_map(|axis| axis.trim().parse().ok()).collect()).unwrap_or_default();
+            if coordinates.len() != 3 {
+                println!("Usage: grid [Archive Path] near [World FormID] [x,y,z] [Radius]");
+                return;
+            }
+            let point = Vec3Int::from((coordinates[0], coordinates[1], coordinates[2]));
+
+            // Without a radius, the nearest part
+            let query = match args.get(6).map(|arg| arg.parse::<u32>()) {
+                Some(Ok(radius)) => WorldPartQuery::Radius { center: point, radius },
+                Some(Err(_)) => {
+                    println!("Invalid radius: {}", args[6]);
+                    return;
+                },
+                None => WorldPartQuery::Nearest(point),
+            };
+            match query_world_parts(archive_path, FormID::from(world_id), &query) {
+                Ok(world_parts) => {
+                    for world_part in world_parts {
+                        println!("-> {}", world_part.to_string());
+                    }
+                },
+                Err(e) => println!("Error querying world parts: {:?}", e),
+            }
+        },
+        _ => println!("Usage: grid [Archive Path] [build/remove/near] [Cell Size/World FormID] [x,y,z] [Radius]"),
+    }
+}
+
+// World Part Bounds ----------------------------------
+fn cmd_bounds(args: Vec<String>) {
+    if args.len() < 3 || args.len() > 7 {
+        println!("Usage: bounds [Archive Path] [build/remove/show/pick] [World Part FormID] [x,y,z] [Radius]");
+        return;
+    }
+
+    let archive_path = &args[2];
+    let form_id = match args.get(4).map(|arg| arg.parse::<u16>()) {
+        Some(Ok(form_id)) => Some(FormID::from(form_id)),
+        Some(Err(_)) => {
+            println!("Invalid world part FormID: {}", args[4]);
+            return;
+        },
+        None => None,
+    };
+    match (args.get(3).map(|arg| arg.as_str()), form_id) {
+        (None, None) => match read_world_part_bounds_all(archive_path) {
+            Ok(Some(part_bounds)) => {
+                for (form_id, bounds) in part_bounds {
+                    println!("{} -> {}", form_id.to_string(), get_bounds_string(bounds));
+                }
+            },
+            Ok(None) => println!("No stored world part bounds"),
+            Err(e) => println!("Error reading world part bounds: {:?}", e),
+        },
+        (Some("build"), None) => match build_world_part_bounds(archive_path) {
+            Ok(count) => println!("Stored the bounds of {} world part(s)", count),
+            Err(e) => println!("Error building world part bounds: {:?}", e),
+        },
+        (Some("remove"), None) => match remove_world_part_bounds(archive_path) {
+            Ok(true) => println!("Removed world part bounds"),
+            Ok(false) => println!("No stored world part bounds"),
+            Err(e) => println!("Error removing world part bounds: {:?}", e),
+        },
+        (Some("show"), Some(form_id)) => match read_world_part_bounds(archive_path, form_id) {
+            Ok(bounds) => println!("{} -> {}", form_id.to_string(), get_bounds_string(bounds)),
+            Err(e) => println!("Error reading world part bounds: {:?}", e),
+        },
+        (Some("pick"), Some(form_id)) => {
+            let coordinates: Vec<f32> = args.get(5).map(|arg| arg.split(',').filter_map(|axis| axis.trim().parse().ok()).collect()).unwrap_or_default();
+            let radius = args.get(6).and_then(|arg| arg.parse::<f32>().ok());
+            let (3, Some(radius)) = (coordinates.len(), radius) else {
+                println!("Usage: bounds [Archive Path] pick [World Part FormID] [x,y,z] [Radius]");
+                return;
+            };
+            let query = EntityQuery::Radius { center: Vec3Float::from((coordinates[0], coordinates[1], coordinates[2])), radius };
+            match query_world_part_entities(archive_path, form_id, &query) {
+                Ok(entities) => {
+                    for entity in entities {
+                        println!("-> {}", entity.to_string());
+                    }
+                },
+                Err(e) => println!("Error querying entities: {:?}", e),
+            }
+        },
+        _ => println!("Usage: bounds [Archive Path] [build/remove/show/pick] [World Part FormID] [x,y,z] [Radius]"),
+    }
+}
+
+fn get_bounds_string(bounds: Option<BoundingBox>) -> String {
+    match bounds {
+        Some(bounds) => format!("{} to {}", bounds.min.to_string(), bounds.max.to_string()),
+        None => "no positioned entities".to_string(),
+    }
+}
+
+fn read_json_file(file_path: &str) -> std::io::Result<serde_json::Value> {
+    let text = fs::read_to_string(file_path)?;
+    serde_json::from_str(&text).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
 // Testing  --------------------------------------
 fn cmd_test(args: Vec<String>) {
     println!("Running Tests...");
@@ -86,6 +824,78 @@ fn cmd_test(args: Vec<String>) {
         "todictionary" => {
             test_todict();
         },
+        "refs" => {
+            test_refs();
+        },
+        "remap" => {
+            test_remap();
+        },
+        "alloc" => {
+            test_alloc();
+        },
+        "gc" => {
+            test_gc();
+        },
+        "registry" => {
+            test_registry();
+        },
+        "derive" => {
+            test_derive();
+        },
+        "dynamic" => {
+            test_dynamic();
+        },
+        "schema" => {
+            test_schema();
+        },
+        "upgrade" => {
+            test_upgrade();
+        },
+        "metadata" => {
+            test_metadata();
+        },
+        "archivemeta" => {
+            test_archive_metadata();
+        },
+        "names" => {
+            test_names();
+        },
+        "typeindex" => {
+            test_type_index();
+        },
+        "iter" => {
+            test_iter();
+        },
+        "lite" => {
+            test_lite();
+        },
+        "parallel" => {
+            test_parallel();
+        },
+        "cache" => {
+            test_cache();
+        },
+        "async" => {
+            test_async();
+        },
+        "lock" => {
+            test_lock();
+        },
+        "journal" => {
+            test_journal();
+        },
+        "append" => {
+            test_append();
+        },
+        "history" => {
+            test_history();
+        },
+        "worldgrid" => {
+            test_worldgrid();
+        },
+        "entities" => {
+            test_entities();
+        },
         "manyformsthreaded" => {
             // Check if we have enough arguments
             if args.len() < 6 {
@@ -163,12 +973,26 @@ fn cmn_gen(args: Vec<String>) {
 
     match gen_type.as_str() {
         "formtype" => {
-            if args.len() < 4 {
-                println!("Please provide the name of the formtype you want to generate");
+            if args.len() < 5 {
+                println!("Usage: gen formtype [Name] [Type Byte]");
                 return;
             }
             let formtype_name = &args[3];
-            let _ = automation::formtype_management::formtype_add(formtype_name);
+
+            // The type byte must not clash with a registered form type
+            let formtype_byte: u8 = match args[4].parse() {
+                Ok(n) => n,
+                Err(_) => {
+                    println!("Invalid type byte: {}", args[4]);
+                    return;
+                }
+            };
+            if let Some(registration) = get_form_type_registration(FormType::from(formtype_byte)) {
+                println!("Type byte {} is already registered as {}", formtype_byte, registration.name);
+                return;
+            }
+
+            let _ = automation::formtype_management::formtype_add(formtype_name, formtype_byte);
         },
         _ => {
             println!("Invalid generation type: {}", gen_type);
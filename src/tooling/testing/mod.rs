@@ -14,4 +14,76 @@ pub mod test_basic;
 pub use test_basic::*;
 
 pub mod test_todict;
-pub use test_todict::*;
\ No newline at end of file
+pub use test_todict::*;
+
+pub mod test_refs;
+pub use test_refs::*;
+
+pub mod test_remap;
+pub use test_remap::*;
+
+pub mod test_alloc;
+pub use test_alloc::*;
+
+pub mod test_gc;
+pub use test_gc::*;
+
+pub mod test_registry;
+pub use test_registry::*;
+
+
+pub mod test_derive;
+pub use test_derive::*;
+pub mod test_dynamic;
+pub use test_dynamic::*;
+
+pub mod test_schema;
+pub use test_schema::*;
+
+pub mod test_upgrade;
+pub use test_upgrade::*;
+
+pub mod test_metadata;
+pub use test_metadata::*;
+
+pub mod test_archive_metadata;
+pub use test_archive_metadata::*;
+
+pub mod test_names;
+pub use test_names::*;
+
+pub mod test_type_index;
+pub use test_type_index::*;
+
+pub mod test_iter;
+pub use test_iter::*;
+
+pub mod test_lite;
+pub use test_lite::*;
+
+pub mod test_parallel;
+pub use test_parallel::*;
+
+pub mod test_cache;
+pub use test_cache::*;
+
+pub mod test_async;
+pub use test_async::*;
+
+pub mod test_lock;
+pub use test_lock::*;
+
+pub mod test_journal;
+pub use test_journal::*;
+
+pub mod test_append;
+pub use test_append::*;
+
+pub mod test_history;
+pub use test_history::*;
+
+pub mod test_worldgrid;
+pub use test_worldgrid::*;
+
+pub mod test_entities;
+pub use test_entities::*;
@@ -0,0 +1,58 @@
+use std::{env, path::PathBuf};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_alloc() {
+    println!("------ TESTING FORMID ALLOCATION ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_alloc.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive = Archive::new(ArchiveID::from(1), Version::from((1, 0)), StrLrg::from("Allocation Test Archive"));
+    let _ = write_archive_skeleton(path, &archive);
+
+    // An empty archive hands out the first FormID
+    println!("First allocation: {:?}", allocate_form_id(path));
+
+    // Reserve ranges for a team and for weather forms
+    println!("Reserve TeamA: {:?}", reserve_form_id_range(path, FormIDRange::new(StrSml::from("TeamA"), FormID::from(100), FormID::from(199))));
+    println!("Reserve Weather: {:?}", reserve_form_id_range(path, FormIDRange::new(StrSml::from("Weather"), FormID::from(200), FormID::from(202))));
+    println!("Overlapping reserve: {:?}", reserve_form_id_range(path, FormIDRange::new(StrSml::from("TeamB"), FormID::from(150), FormID::from(250))));
+    println!("Reserved ranges: {:?}", read_reserved_ranges(path));
+
+    // Fill forms through the allocator and insert-only writes
+    for _ in 0..3 {
+        let form_id = match allocate_form_id(path) {
+            Ok(form_id) => form_id,
+            Err(e) => {
+                println!("Error allocating FormID: {:?}", e);
+                return;
+            }
+        };
+        let result = insert_form(path, &FormString::new(form_id, StrSml::from("StrAlloc"), vec![LangCode::EN], vec![StrLrg::from("Allocated")]));
+        println!("Inserted {}: {:?}", form_id.to_string(), result);
+    }
+    for _ in 0..4 {
+        let result = allocate_form_id_in_range(path, "Weather");
+        println!("Weather allocation: {:?}", result);
+        if let Ok(form_id) = result {
+            let _ = insert_form(path, &FormString::new(form_id, StrSml::from("StrWeather"), vec![LangCode::EN], vec![StrLrg::from("Rain")]));
+        }
+    }
+
+    // Inserting over an existing form must be refused
+    let result = insert_form(path, &FormString::new(FormID::from(1), StrSml::from("StrDupe"), vec![LangCode::EN], vec![StrLrg::from("Duplicate")]));
+    println!("Duplicate insert: {:?}", result);
+    println!("Form 1 after duplicate insert: {:?}", read_form(path, FormID::from(1)).map(|form| form.form_name()));
+
+    // The ranges must survive the form writes and deletes
+    let _ = write_form(path, &FormString::new(FormID::from(1), StrSml::from("StrAllocLonger"), vec![LangCode::EN], vec![StrLrg::from("Allocated and rewritten")]));
+    let _ = delete_form(path, FormID::from(2));
+    let _ = write_archive_info(path, &Archive::new(ArchiveID::from(1), Version::from((1, 1)), StrLrg::from("Allocation Test Archive, Renamed")));
+    println!("Reserved ranges after edits: {:?}", read_reserved_ranges(path));
+    println!("Allocation after delete: {:?}", allocate_form_id(path));
+    println!("Release TeamA: {:?}", release_form_id_range(path, "TeamA"));
+    println!("Reserved ranges after release: {:?}", read_reserved_ranges(path));
+}
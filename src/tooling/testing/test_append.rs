@@ -0,0 +1,115 @@
+use std::{env, fs, io::Write, path::PathBuf};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_append() {
+    println!("------ TESTING APPEND WRITE MODE ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_append.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let _ = write_archive_skeleton(path, &Archive::new(ArchiveID::from(1), Version::from((1, 0)), StrLrg::from("Append Test Archive")));
+    for form_id in 1..=5u16 {
+        let _ = write_form(path, &make_string(form_id, "Original"));
+    }
+    let _ = build_name_index(path);
+    let _ = build_type_index(path);
+    let offsets_before = read_offsets(path);
+    println!("Dead space: {:?}, file length: {}", read_archive_dead_space(path), get_file_length(path));
+
+    // Editing a form in the middle leaves the others where they are
+    set_archive_write_mode(ArchiveWriteMode::Append);
+    println!("Mode: {:?}", get_archive_write_mode());
+    println!("Edit 2: {:?}", write_form(path, &make_string(2, "Edited and longer")));
+    let offsets_after = read_offsets(path);
+    println!("Others unmoved: {}", offsets_before.iter().zip(&offsets_after).filter(|(before, _)| before.0 != FormID::from(2)).all(|(before, after)| before == after));
+    println!("Form 2 moved to end: {}", offsets_after[1].1 > offsets_before[4].1);
+    println!("Dead space: {:?}", read_archive_dead_space(path));
+    print_state(path);
+
+    // Every reader follows the repointed index
+    let forms = read_forms(path, vec![FormID::from(5), FormID::from(2)]).map(|forms| forms.iter().map(get_text).collect::<Vec<_>>());
+    println!("read_forms: {:?}", forms);
+    println!("Lite: {:?}", read_lite_archive(path).map(|lite| lite.archive_items.iter().map(|item| item.form_name.to_string()).collect::<Vec<_>>()));
+    println!("Iter: {:?}", iter_forms(path).map(|forms| forms.forms().map(|form| form.map(|form| get_text(&form))).collect::<Vec<_>>()));
+    println!("Iter in data order: {:?}", iter_forms_in_data_order(path).map(|forms| forms.map(|entry| entry.map(|entry| entry.form_id.to_string())).collect::<Vec<_>>()));
+    let reader = ArchiveReader::open(path).expect("Failed to open archive");
+    println!("Reader: {:?}", reader.read_forms(&[FormID::from(2), FormID::from(3)]).map(|forms| forms.iter().map(get_text).collect::<Vec<_>>()));
+    drop(reader);
+    let metadata = FormMetadata { revision: 3, ..Default::default() };
+    println!("Metadata: {:?}", write_form_metadata(path, FormID::from(3), metadata).and_then(|_| read_form_metadata(path, FormID::from(3))).map(|metadata| metadata.revision));
+
+    // An append cut off before its header is written leaves the archive as it was
+    let header_bytes = read_header_bytes(path);
+    let _ = write_form(path, &make_string(3, "Interrupted"));
+    write_header_bytes(path, &header_bytes);
+    println!("Interrupted edit 3: {:?}", read_form(path, FormID::from(3)).map(|form| get_text(&form)));
+    print_state(path);
+
+    // New forms are appended, deleted forms leave their record behind
+    println!("Insert 9: {:?}", insert_form(path, &make_string(9, "Inserted")));
+    println!("Insert 9 again: {:?}", insert_form(path, &make_string(9, "Again")).map_err(|e| e.kind()));
+    println!("Write 0: {:?}", write_form(path, &make_string(0, "First")));
+    println!("Delete 4: {:?}", delete_form(path, FormID::from(4)));
+    println!("Delete 4 again: {:?}", delete_form(path, FormID::from(4)).map_err(|e| e.kind()));
+    println!("Delete 1, 5, 8: {:?}", delete_forms(path, &[FormID::from(1), FormID::from(5), FormID::from(8)]));
+    println!("Name index: {:?} {:?}", find_form_id_by_name(path, "Str09").map(|form_id| form_id.map(|form_id| form_id.to_string())), find_form_id_by_name(path, "Str04"));
+    println!("Type index: {:?}", list_forms_of_type(path, FormType::STRING).map(|form_ids| form_ids.iter().map(|form_id| form_id.to_string()).collect::<Vec<_>>()));
+    println!("Dead space: {:?}", read_archive_dead_space(path));
+    print_state(path);
+
+    // Compaction reclaims the dead space
+    let length_before = get_file_length(path);
+    let reclaimed = compact_archive(path);
+    // The stored record lengths go as well
+    let length_block = 8 + 4 * read_offsets(path).len() as u64;
+    println!("Compact: {:?}, file shrank by it: {}", reclaimed, reclaimed.as_ref().map(|reclaimed| length_before - get_file_length(path) == *reclaimed as u64 + length_block).unwrap_or(false));
+    println!("Compact again: {:?}", compact_archive(path));
+    println!("Dead space: {:?}", read_archive_dead_space(path));
+    print_state(path);
+
+    // Rewrite mode compacts a fragmented archive as it edits it
+    println!("Append edit 3: {:?}", write_form(path, &make_string(3, "Appended")));
+    println!("Dead space: {:?}", read_archive_dead_space(path));
+    set_archive_write_mode(ArchiveWriteMode::Rewrite);
+    println!("Rewrite edit 2: {:?}", write_form(path, &make_string(2, "Rewritten")));
+    println!("Dead space: {:?}", read_archive_dead_space(path));
+    print_state(path);
+}
+
+fn make_string(form_id: u16, text: &str) -> FormString {
+    FormString::new(FormID::from(form_id), StrSml::from(format!("Str{:02}", form_id)), vec![LangCode::EN], vec![StrLrg::from(text)])
+}
+
+fn get_text(form: &Box<dyn FormTrait>) -> String {
+    format!("{}={}", form.form_id().to_string(), form.to_dict()["strings"][0])
+}
+
+fn read_offsets(path: &str) -> Vec<(FormID, u32)> {
+    read_archive_index(path)
+        .map(|index| index.indexes.iter().map(|item| (item.form_id, item.data_start_offset)).collect())
+        .unwrap_or_default()
+}
+
+fn get_file_length(path: &str) -> u64 {
+    fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0)
+}
+
+/// Returns the header and byte starts of an archive, the only bytes an append writes in place.
+fn read_header_bytes(path: &str) -> Vec<u8> {
+    let header_length = read_archive_info(path).map(|archive| archive.bytestart_data as usize).unwrap_or(0);
+    fs::read(path).map(|bytes| bytes[..header_length].to_vec()).unwrap_or_default()
+}
+
+fn write_header_bytes(path: &str, header_bytes: &[u8]) {
+    if let Ok(mut file) = fs::OpenOptions::new().write(true).open(path) {
+        let _ = file.write_all(header_bytes);
+    }
+}
+
+fn print_state(path: &str) {
+    let forms: Vec<String> = read_forms_all(path).map(|forms| forms.iter().map(get_text).collect()).unwrap_or_default();
+    println!("  Forms: {:?}", forms);
+}
@@ -0,0 +1,59 @@
+use std::{env, path::PathBuf};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_archive_metadata() {
+    println!("------ TESTING ARCHIVE METADATA ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_archive_metadata.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    // Build metadata is given with the skeleton
+    let archive_id = ArchiveID::from(1);
+    let mut archive = Archive::new(archive_id, Version::from((1, 0)), StrLrg::from("Archive Metadata Test Archive"));
+    let _ = archive.set_metadata(ArchiveMetadata::KEY_BUILD_NUMBER, ArchiveMetadataValue::U64(1042));
+    let _ = archive.set_metadata(ArchiveMetadata::KEY_SOURCE_COMMIT, ArchiveMetadataValue::Str(StrLrg::from("9f2c41e")));
+    let _ = archive.set_metadata(ArchiveMetadata::KEY_TOOL_VERSION, ArchiveMetadataValue::Str(StrLrg::from("smn_archive 0.4")));
+    let _ = archive.set_metadata(ArchiveMetadata::KEY_TARGET_PLATFORM, ArchiveMetadataValue::Str(StrLrg::from("linux-x86_64")));
+    let _ = archive.set_metadata(ArchiveMetadata::KEY_CREATED, ArchiveMetadataValue::I64(1_700_000_000));
+    let _ = archive.set_metadata("compression_ratio", ArchiveMetadataValue::F64(0.75));
+    let _ = archive.set_metadata("debug", ArchiveMetadataValue::Bool(false));
+    let _ = archive.set_metadata("checksum", ArchiveMetadataValue::Bytes(vec![0xde, 0xad, 0xbe, 0xef]));
+    println!("Invalid key: {:?}", archive.set_metadata("", ArchiveMetadataValue::Bool(true)));
+    println!("Byte round trip: {:?}", ArchiveMetadata::read_from_byte_buffer(&archive.metadata.to_bytes()).map(|(metadata, consumed)| (metadata == archive.metadata, consumed == archive.metadata.to_bytes().len())));
+    println!("Write skeleton: {:?}", write_archive_skeleton(path, &archive));
+    println!("Read info: {:?}", read_archive_info(path).map(|info| info.metadata));
+
+    // Edits to the archive keep the metadata
+    let _ = write_form(path, &FormString::new(FormID::from(200), StrSml::from("StrWrldName"), vec![LangCode::EN], vec![StrLrg::from("Amonal")]));
+    let _ = write_form(path, &FormRefGroup::new(FormID::from(10), StrSml::from("CollWrldList"), vec![GlobalID::from((archive_id, FormID::from(50)))]));
+    let _ = write_archive_info(path, &Archive::new(archive_id, Version::from((1, 1)), StrLrg::from("Archive Metadata Test Archive, Renamed")));
+    let _ = delete_form(path, FormID::from(10));
+    println!("Read after edits: {:?}", read_archive_info(path).map(|info| (info.description.to_string(), info.form_count, info.metadata == archive.metadata)));
+
+    // Single values
+    println!("Set value: {:?}", set_archive_metadata_value(path, ArchiveMetadata::KEY_BUILD_NUMBER, ArchiveMetadataValue::U64(1043)));
+    println!("Parse value: {:?}", ArchiveMetadataValue::parse("bytes", "0a0B").and_then(|value| set_archive_metadata_value(path, "signature", value)));
+    println!("Parse invalid: {:?}", ArchiveMetadataValue::parse("u64", "-3"));
+    println!("Parse unknown type: {:?}", ArchiveMetadataValue::parse("date", "2024-01-01"));
+    println!("Remove value: {:?}", remove_archive_metadata_value(path, "debug"));
+    println!("Remove missing: {:?}", remove_archive_metadata_value(path, "debug"));
+    match read_archive_info(path) {
+        Ok(info) => {
+            for (key, value) in info.metadata.iter() {
+                println!("-> {} ({}): {}", key, value.get_type_name(), value);
+            }
+            println!("Build number: {:?}", info.get_metadata(ArchiveMetadata::KEY_BUILD_NUMBER));
+        },
+        Err(e) => println!("Error reading archive info: {:?}", e),
+    }
+    println!("Read lite: {:?}", read_lite_archive(path));
+
+    // Clearing the metadata removes its block
+    println!("Clear: {:?}", write_archive_metadata(path, &ArchiveMetadata::default()));
+    println!("Read cleared: {:?}", read_archive_info(path).map(|info| info.metadata.is_empty()));
+    println!("Block exists: {:?}", read_ext_block(path, EXT_TAG_ARCHIVE_METADATA).map(|block| block.is_some()));
+    println!("Read form: {:?}", read_form(path, FormID::from(200)).map(|form| form.form_name().to_string()));
+}
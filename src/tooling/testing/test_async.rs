@@ -0,0 +1,122 @@
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::{env, path::PathBuf};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+/// Wakes the thread blocked on a future.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Records the order requests complete in.
+struct OrderWaker {
+    label: &'static str,
+    order: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Wake for OrderWaker {
+    fn wake(self: Arc<Self>) {
+        self.order.lock().unwrap().push(self.label);
+    }
+}
+
+/// Runs a future to completion on the calling thread, the way a game's executor would poll it.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        thread::park();
+    }
+}
+
+pub fn test_async() {
+    println!("------ TESTING ASYNC READS ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_async.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive_id = ArchiveID::from(1);
+    let archive = Archive::new(archive_id, Version::from((1, 0)), StrLrg::from("Async Test Archive"));
+    let _ = write_archive_skeleton(path, &archive);
+    for form_id in 1..=400u16 {
+        let form_name = StrSml::from(format!("Part{:03}", form_id));
+        let _ = write_form(path, &FormWorldPart::new(FormID::from(form_id), form_name, Vec::new()));
+    }
+
+    // Futures resolve on any executor
+    let reader = AsyncArchiveReader::open(path, 2).expect("Failed to open archive");
+    println!("Read form: {:?}", block_on(reader.read_form(FormID::from(42), ReadPriority::Normal)).map(|form| form.form_name().to_string()));
+    println!("Read forms: {:?}", block_on(reader.read_forms(vec![FormID::from(3), FormID::from(1), FormID::from(2)], ReadPriority::High)).map(|forms| forms.iter().map(|form| form.form_id().to_string()).collect::<Vec<_>>()));
+    let query = LiteArchiveQuery { form_type: Some(FormType::WORLDPART), offset: 10, limit: Some(3) };
+    println!("Read lite: {:?}", block_on(reader.read_lite_archive(query, ReadPriority::Background)).map(|lite| (lite.match_count, lite.archive_items.iter().map(|item| item.form_name.to_string()).collect::<Vec<_>>())));
+    println!("Missing form: {:?}", block_on(reader.read_form(FormID::from(900), ReadPriority::Normal)).map(|form| form.form_id()));
+
+    // A game loop polls without waiting
+    let mut request = reader.read_form(FormID::from(7), ReadPriority::Immediate);
+    let result = loop {
+        if let Some(result) = request.try_take() {
+            break result;
+        }
+        thread::yield_now();
+    };
+    println!("Polled from loop: {:?}", result.map(|form| form.form_name().to_string()));
+
+    // With one worker busy on a long read, queued requests are served by priority
+    let single = AsyncArchiveReader::open(path, 1).expect("Failed to open archive");
+    let all_ids: Vec<FormID> = (1..=400u16).map(FormID::from).collect();
+    let blocker = single.read_forms(all_ids.clone(), ReadPriority::Normal);
+    while single.pending_count() > 0 {
+        thread::yield_now();
+    }
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let mut requests = vec![
+        ("background", single.read_forms(all_ids[..50].to_vec(), ReadPriority::Background)),
+        ("normal", single.read_forms(all_ids[50..100].to_vec(), ReadPriority::Normal)),
+        ("immediate", single.read_forms(all_ids[100..150].to_vec(), ReadPriority::Immediate)),
+        ("high", single.read_forms(all_ids[150..200].to_vec(), ReadPriority::High)),
+    ];
+    let cancelled = single.read_forms(all_ids[200..250].to_vec(), ReadPriority::Immediate);
+    println!("Cancel queued: {}", cancelled.cancel());
+    println!("Cancel again: {}", cancelled.cancel());
+    for (label, request) in requests.iter_mut() {
+        let waker = Waker::from(Arc::new(OrderWaker { label, order: Arc::clone(&order) }));
+        if Pin::new(request).poll(&mut Context::from_waker(&waker)).is_ready() {
+            order.lock().unwrap().push(label);
+        }
+    }
+    println!("Blocker: {:?}", block_on(blocker).map(|forms| forms.len()));
+    while !requests.iter().all(|(_, request)| request.is_ready()) {
+        thread::yield_now();
+    }
+    for (label, request) in requests {
+        println!("{}: {:?}", label, block_on(request).map(|forms| forms.len()));
+    }
+    println!("Served in order: {:?}", order.lock().unwrap());
+    println!("Cancelled: {:?}", block_on(cancelled).map(|forms| forms.len()));
+
+    // Closing the reader fails what it has not served
+    let closing = AsyncArchiveReader::open(path, 1).expect("Failed to open archive");
+    let busy = closing.read_forms(all_ids.clone(), ReadPriority::Normal);
+    while closing.pending_count() > 0 {
+        thread::yield_now();
+    }
+    let queued = closing.read_form(FormID::from(1), ReadPriority::Background);
+    println!("Pending: {}", closing.pending_count());
+    drop(closing);
+    println!("Busy after close: {:?}", block_on(busy).map(|forms| forms.len()));
+    println!("Queued after close: {:?}", block_on(queued).map(|form| form.form_id()));
+}
@@ -0,0 +1,67 @@
+use std::{env, path::PathBuf, sync::Arc};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_cache() {
+    println!("------ TESTING FORM CACHE ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_cache.smn");
+    let path = archive_path.to_str().unwrap();
+    let other_path_buf = current_dir.join("archives").join("test_cache_other.smn");
+    let other_path = other_path_buf.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive_id = ArchiveID::from(1);
+    let _ = write_archive_skeleton(path, &Archive::new(archive_id, Version::from((1, 0)), StrLrg::from("Cache Test Archive")));
+    for form_id in 1..=6u16 {
+        let _ = write_form(path, &FormString::new(FormID::from(form_id), StrSml::from(format!("Str{:02}", form_id)), vec![LangCode::EN], vec![StrLrg::from("Text")]));
+    }
+    let _ = write_form(path, &FormString::new(FormID::from(20), StrSml::from("StrCredits"), vec![LangCode::EN], vec![StrLrg::from("Credits ".repeat(20))]));
+    let other_id = ArchiveID::from(2);
+    let _ = write_archive_skeleton(other_path, &Archive::new(other_id, Version::from((1, 0)), StrLrg::from("Cache Test Other Archive")));
+    let _ = write_form(other_path, &FormString::new(FormID::from(1), StrSml::from("StrOther"), vec![LangCode::EN], vec![StrLrg::from("Other")]));
+
+    // A budget of three strings
+    let string_size = read_form(path, FormID::from(1)).map(|form| form.get_byte_count()).unwrap_or(0);
+    let cache = Arc::new(FormCache::new(string_size * 3));
+    let mut reader = ArchiveReader::open(path).expect("Failed to open archive").with_cache(Arc::clone(&cache));
+    let other_reader = ArchiveReader::open(other_path).expect("Failed to open other archive").with_cache(Arc::clone(&cache));
+
+    for form_id in [1, 2, 1, 3, 1, 2] {
+        let _ = reader.read_form_cached(FormID::from(form_id));
+    }
+    println!("Repeated reads: {:?}", cache.stats());
+    println!("Same form shared: {:?}", reader.read_form_cached(FormID::from(1)).and_then(|first| reader.read_form_cached(FormID::from(1)).map(|second| Arc::ptr_eq(&first, &second))));
+
+    // The least recently used form makes room, keys include the archive
+    let _ = reader.read_form_cached(FormID::from(4));
+    println!("After eviction: {:?}", cache.stats());
+    println!("Cached: {:?}", [1, 2, 3, 4].map(|form_id| cache.get(GlobalID::from((archive_id, FormID::from(form_id)))).is_some()));
+    println!("Other archive: {:?}", other_reader.read_form_cached(FormID::from(1)).map(|form| form.form_name().to_string()));
+    println!("Cached after other archive: {:?}", [(archive_id, 1), (other_id, 1)].map(|(archive, form_id)| cache.get(GlobalID::from((archive, FormID::from(form_id)))).is_some()));
+    println!("Larger than budget: {:?}", reader.read_form_cached(FormID::from(20)).map(|form| (form.form_name().to_string(), cache.get(GlobalID::from((archive_id, FormID::from(20)))).is_some())));
+    println!("Missing form: {:?}", reader.read_form_cached(FormID::from(900)).map(|form| form.form_id()));
+
+    // Writing through the reader drops that archive's forms only
+    cache.reset_stats();
+    let edited = FormString::new(FormID::from(1), StrSml::from("Str01"), vec![LangCode::EN], vec![StrLrg::from("Edited")]);
+    println!("Write through reader: {:?}", reader.write_form(&edited));
+    println!("After write: {:?}", cache.stats());
+    println!("Read edited: {:?}", reader.read_form_cached(FormID::from(1)).map(|form| form.to_dict()["strings"].clone()));
+    println!("Other still cached: {:?}", cache.get(GlobalID::from((other_id, FormID::from(1)))).is_some());
+    let _ = reader.read_form_cached(FormID::from(5));
+    println!("Delete through reader: {:?}", reader.delete_form(FormID::from(5)));
+    println!("Read deleted: {:?}", reader.read_form_cached(FormID::from(5)).map(|form| form.form_id()));
+    println!("Form count: {}", reader.archive().form_count);
+
+    // Shrinking the budget evicts, clearing keeps the counters
+    cache.set_byte_budget(string_size);
+    println!("Shrunk: {:?}", cache.stats());
+    cache.clear();
+    println!("Cleared: {:?}", cache.stats());
+
+    // Without a cache forms are read each time
+    let uncached = ArchiveReader::open(path).expect("Failed to open archive");
+    println!("Uncached: {:?}", uncached.read_form_cached(FormID::from(2)).map(|form| (form.form_name().to_string(), uncached.cache().is_none())));
+}
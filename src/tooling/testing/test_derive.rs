@@ -0,0 +1,89 @@
+use std::{env, fmt, path::PathBuf};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+/// A form declared only through its fields, using every layout `#[derive(Form)]` supports.
+#[derive(Clone, Form)]
+#[form(crate = "crate")]
+struct FormPatrol {
+    base: FormBase,
+    #[form(reference)]
+    leader: GlobalID,
+    #[form(count = "u8", reference)]
+    waypoints: Vec<GlobalID>,
+    #[form(count_of = "waypoints", rename = "waypoint_positions")]
+    positions: Vec<Vec3Float>,
+    #[form(fixed = 2, reference)]
+    alarms: Vec<GlobalID>,
+    speed: f32,
+    briefing: StrLrg,
+}
+
+impl FormPatrol {
+    const FORM_TYPE: FormType = FormType::new(201);
+}
+
+impl fmt::Display for FormPatrol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FormPatrol {}", self.to_dict())
+    }
+}
+
+impl fmt::Debug for FormPatrol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+pub fn test_derive() {
+    println!("------ TESTING FORM DERIVE ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_derive.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive = Archive::new(ArchiveID::from(1), Version::from((1, 0)), StrLrg::from("Derive Test Archive"));
+    let _ = write_archive_skeleton(path, &archive);
+
+    let _ = register_form_type(FormTypeRegistration::new(
+        FormPatrol::FORM_TYPE,
+        "PATROL",
+        |file| Ok(Box::new(FormPatrol::read_from_bytes(file)?)),
+        |bytes| FormPatrol::read_from_byte_buffer(bytes).map(|(form, consumed)| (Box::new(form) as Box<dyn FormTrait>, consumed)),
+    ).with_schema_fields(FormPatrol::schema_fields()));
+
+    let patrol = FormPatrol {
+        base: FormBase::new(FormID::from(1), FormPatrol::FORM_TYPE, StrSml::from("PatrolGate")),
+        leader: GlobalID::from("00100010"),
+        waypoints: vec![GlobalID::from("00100020"), GlobalID::from("00100021"), GlobalID::from("00100020")],
+        positions: vec![Vec3Float::from((0.0, 0.0, 0.0)), Vec3Float::from((5.0, 0.0, 2.5)), Vec3Float::from((10.0, 0.0, 5.0))],
+        alarms: vec![GlobalID::from("00100020"), GlobalID::from("00100030")],
+        speed: 1.5,
+        briefing: StrLrg::from("Walk the wall twice per night"),
+    };
+
+    // Serialization
+    let bytes = patrol.to_bytes();
+    println!("Byte count: {} (serialized {})", patrol.get_byte_count(), bytes.len());
+    println!("Buffer read: {:?}", FormPatrol::read_from_byte_buffer(&bytes).map(|(form, consumed)| (form.to_bytes() == bytes, consumed)));
+    println!("Short buffer read: {:?}", FormPatrol::read_from_byte_buffer(&bytes[..bytes.len() - 3]).map(|(_, consumed)| consumed));
+
+    // Archive round trip through the registry
+    println!("Write: {:?}", write_form(path, &patrol));
+    println!("Read: {:?}", read_form(path, FormID::from(1)).map(|form| form.to_dict()));
+
+    // Generated references, counted items drop their positions, fixed slots are nulled
+    println!("References: {:?}", patrol.get_references());
+    let mut removed = patrol.clone();
+    println!("Removed 00100020: {}", removed.remove_references(GlobalID::from("00100020")));
+    println!("After removal: waypoints {:?}, positions {}, alarms {:?}", removed.waypoints, removed.positions.len(), removed.alarms);
+    let mut remapped = patrol.clone();
+    println!("Remapped 00100020: {}", remapped.remap_references(&|reference| (reference == GlobalID::from("00100020")).then(|| GlobalID::from("00200020"))));
+    println!("After remap: {:?}", remapped.get_references());
+
+    // The built-in forms use the same derive
+    let world = FormWorld::new(FormID::from(2), StrSml::from("WorldMain"), GlobalID::from("00100001"), StrSml::from("MapMain"), vec![GlobalID::from("00100003")], vec![Vec3Int::from((0, 0, 0))]);
+    println!("World byte count: {} (serialized {})", world.get_byte_count(), world.to_bytes().len());
+    println!("World dict: {}", world.to_dict());
+}
@@ -0,0 +1,81 @@
+use std::{env, path::PathBuf};
+
+use serde_json::json;
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_dynamic() {
+    println!("------ TESTING DYNAMIC FORMS ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_dynamic.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive = Archive::new(ArchiveID::from(1), Version::from((1, 0)), StrLrg::from("Dynamic Test Archive"));
+    let _ = write_archive_skeleton(path, &archive);
+
+    // A designer-authored schema, as it would come from a data file
+    let schema = FormSchema::from_dict(&json!({
+        "form_type": 120,
+        "name": "QUEST",
+        "fields": [
+            {"name": "title", "type": "StrLrg"},
+            {"name": "giver", "type": "GlobalID"},
+            {"name": "stages", "type": "StrSml[u8]"},
+            {"name": "rewards", "type": "GlobalID[u16]"},
+            {"name": "markers", "type": "Vec3Float[2]"},
+            {"name": "tint", "type": "SmlColor"},
+            {"name": "level", "type": "u8"},
+        ],
+    })).expect("Failed to read schema");
+    println!("Schema: {}", schema.to_dict());
+    println!("Schema byte round trip: {:?}", FormSchema::read_from_byte_buffer(&schema.to_bytes()).map(|(read, consumed)| (read == schema, consumed == schema.get_byte_count())));
+    println!("Invalid field type: {:?}", SchemaFieldType::parse("Vec4Float").map(|field_type| field_type.to_string()));
+    println!("Store schema: {:?}", write_archive_schema(path, &schema));
+    println!("Conflicting schema: {:?}", write_archive_schema(path, &FormSchema::new(schema.form_type, "QUEST", Vec::new())));
+
+    // Forms built from JSON against the schema
+    let quest_dict = json!({
+        "form_id": "00001",
+        "form_type": "QUEST",
+        "form_name": "QuestLighthouse",
+        "title": "The Dark Lighthouse",
+        "giver": "00100010",
+        "stages": ["Reach the coast", "Climb the tower", "Relight the lamp"],
+        "rewards": ["00100020", "00100021", "00100020"],
+        "markers": [{"x": 0.0, "y": 0.0, "z": 0.0}, {"x": 12.5, "y": 40.0, "z": -3.0}],
+        "tint": "RGBA(255, 200, 120, 255)",
+        "level": 7,
+    });
+    let quest = FormDynamic::from_dict(&quest_dict).expect("Failed to read quest");
+    println!("Dict round trip: {}", quest.to_dict() == quest_dict);
+    println!("Byte count: {} (serialized {})", quest.get_byte_count(), quest.to_bytes().len());
+    println!("Buffer read: {:?}", FormBase::read_from_byte_buffer(&quest.to_bytes()).map(|(form, consumed)| (form.to_bytes() == quest.to_bytes(), consumed)));
+
+    // Validation against the schema
+    let mut wrong_marker_count = quest_dict.clone();
+    wrong_marker_count["markers"] = json!([{"x": 0.0, "y": 0.0, "z": 0.0}]);
+    println!("Wrong fixed count: {:?}", FormDynamic::from_dict(&wrong_marker_count).map(|form| form.to_dict()));
+    let mut edited = quest.clone();
+    println!("Set level: {:?}", edited.set_value("level", DynamicValue::U8(9)));
+    println!("Set level wrong type: {:?}", edited.set_value("level", DynamicValue::U16(9)));
+    println!("Level: {:?}", edited.get_value("level"));
+    println!("New with missing values: {:?}", FormDynamic::new(FormID::from(2), schema.form_type, StrSml::from("QuestEmpty"), Vec::new()).map(|form| form.to_dict()));
+
+    // Archive round trip, the archive carries the schema
+    println!("Write: {:?}", write_form(path, &quest));
+    println!("Read: {:?}", read_form(path, FormID::from(1)).map(|form| form.to_dict() == quest_dict));
+    println!("Stored schemas: {:?}", read_archive_schemas(path).map(|schemas| schemas.iter().map(|schema| schema.name.to_string()).collect::<Vec<_>>()));
+    println!("Remove schema in use: {:?}", remove_archive_schema(path, schema.form_type));
+
+    // References, prefixed arrays drop removed items and single fields are nulled
+    println!("References: {:?}", quest.get_references());
+    let mut removed = quest.clone();
+    println!("Removed 00100020: {}", removed.remove_references(GlobalID::from("00100020")));
+    println!("Removed 00100010: {}", removed.remove_references(GlobalID::from("00100010")));
+    println!("After removal: giver {}, rewards {}", removed.to_dict()["giver"], removed.to_dict()["rewards"]);
+    let mut remapped = quest.clone();
+    println!("Remapped 00100020: {}", remapped.remap_references(&|reference| (reference == GlobalID::from("00100020")).then(|| GlobalID::from("00200020"))));
+    println!("After remap: {:?}", remapped.get_references());
+}
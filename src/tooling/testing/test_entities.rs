@@ -0,0 +1,144 @@
+use std::{env, path::PathBuf, time::Instant};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_entities() {
+    println!("------ TESTING ENTITY QUERIES ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_entities.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    // 5000 entities scattered over a cube 1000 units wide, plus ones positioned nowhere and at infinity
+    let archive_id = ArchiveID::from(1);
+    let mut seed: u32 = 12345;
+    let mut entities: Vec<EntInstance> = (0..5000)
+        .map(|reference| {
+            let mut next = || {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                (seed >> 8) as f32 / (1 << 24) as f32 * 1000.0 - 500.0
+            };
+            make_entity(archive_id, reference, Vec3Float::from((next(), next(), next())))
+        })
+        .collect();
+    entities.push(make_entity(archive_id, 5000, Vec3Float::from((f32::NAN, 0.0, 0.0))));
+    entities.push(make_entity(archive_id, 5001, Vec3Float::from((f32::INFINITY, 0.0, 0.0))));
+    let world_part = FormWorldPart::new(FormID::from(10), StrSml::from("PartMain"), entities);
+    println!("Bounds: {:?}", world_part.get_bounds().map(|bounds| bounds.to_dict().to_string()));
+
+    let queries = [
+        EntityQuery::Box { min: Vec3Float::from((-50.0, -50.0, -50.0)), max: Vec3Float::from((50.0, 50.0, 50.0)) },
+        EntityQuery::Box { min: Vec3Float::from((-1000.0, -1000.0, -1000.0)), max: Vec3Float::from((1000.0, 1000.0, 1000.0)) },
+        EntityQuery::Box { min: Vec3Float::from((1.0, 1.0, 1.0)), max: Vec3Float::from((0.0, 0.0, 0.0)) },
+        EntityQuery::Box { min: Vec3Float::from((0.0, -1.0, -1.0)), max: Vec3Float::from((f32::INFINITY, 1.0, 1.0)) },
+        EntityQuery::Radius { center: Vec3Float::from((100.0, -200.0, 300.0)), radius: 120.0 },
+        EntityQuery::Radius { center: Vec3Float::from((0.0, 0.0, 0.0)), radius: 0.0 },
+        EntityQuery::Radius { center: Vec3Float::from((0.0, 0.0, 0.0)), radius: -10.0 },
+        get_frustum(Vec3Float::from((0.0, 0.0, -500.0)), 10.0, 600.0),
+        get_frustum(Vec3Float::from((0.0, 0.0, 2000.0)), 10.0, 600.0),
+        EntityQuery::Planes(vec![Plane { normal: Vec3Float::from((0.0, 1.0, 0.0)), distance: 0.0 }]),
+        EntityQuery::Planes(Vec::new()),
+    ];
+
+    // The hierarchy finds what checking every entity finds
+    let start = Instant::now();
+    let index = world_part.build_entity_index();
+    println!("Index built in {:?}, bounds match: {}", start.elapsed(), index.get_bounds() == world_part.get_bounds());
+    let mut all_match = true;
+    for query in &queries {
+        let scanned = world_part.query_entities(query);
+        let indexed = index.query(query);
+        all_match &= scanned == indexed;
+        println!("  {} matches for {:?}", indexed.len(), query);
+    }
+    println!("Index matches scan: {}", all_match);
+    println!("Empty index: {:?}, {:?}", EntityIndex::new(&[]).get_bounds(), EntityIndex::new(&[]).query(&queries[1]));
+
+    let start = Instant::now();
+    for _ in 0..100 {
+        world_part.query_entities(&queries[0]);
+    }
+    let scan_time = start.elapsed();
+    let start = Instant::now();
+    for _ in 0..100 {
+        index.query(&queries[0]);
+    }
+    println!("100 box queries: scan {:?}, index {:?}", scan_time, start.elapsed());
+
+    // Archive queries, without then with stored bounds
+    let _ = write_archive_skeleton(path, &Archive::new(archive_id, Version::from((1, 0)), StrLrg::from("Entity Test Archive")));
+    let far_part = FormWorldPart::new(FormID::from(11), StrSml::from("PartFar"), vec![make_entity(archive_id, 1, Vec3Float::from((2000.0, 0.0, 2000.0))), make_entity(archive_id, 2, Vec3Float::from((2010.0, 5.0, 1990.0)))]);
+    let _ = write_form(path, &world_part);
+    let _ = write_form(path, &far_part);
+    let _ = write_form(path, &FormWorldPart::new(FormID::from(12), StrSml::from("PartEmpty"), Vec::new()));
+    let _ = write_form(path, &FormString::new(FormID::from(13), StrSml::from("StrName"), vec![LangCode::EN], vec![StrLrg::from("Name")]));
+
+    let far = EntityQuery::Radius { center: Vec3Float::from((2000.0, 0.0, 2000.0)), radius: 20.0 };
+    println!("Stored bounds: {:?}", read_world_part_bounds_all(path).map(|part_bounds| part_bounds.is_some()));
+    println!("Parts in frustum: {:?}", find_world_parts_in(path, &queries[7]).map(|form_ids| get_form_id_strings(&form_ids)));
+    println!("Parts near far part: {:?}", find_world_parts_in(path, &far).map(|form_ids| get_form_id_strings(&form_ids)));
+    println!("Bounds of far part: {:?}", read_world_part_bounds(path, FormID::from(11)).map(|bounds| bounds.map(|bounds| bounds.to_dict().to_string())));
+    println!("Bounds of empty part: {:?}", read_world_part_bounds(path, FormID::from(12)));
+    println!("Bounds of string: {:?}", read_world_part_bounds(path, FormID::from(13)).map_err(|e| e.kind()));
+    println!("Bounds of missing part: {:?}", read_world_part_bounds(path, FormID::from(99)).map_err(|e| e.kind()));
+    let scanned = queries.iter().map(|query| query_world_part_entities(path, FormID::from(10), query).map(|entities| entities.len()).unwrap_or_default()).collect::<Vec<_>>();
+
+    println!("Build bounds: {:?}", build_world_part_bounds(path));
+    match read_world_part_bounds_all(path) {
+        Ok(Some(part_bounds)) => {
+            for (form_id, bounds) in part_bounds {
+                println!("  {}: {:?}", form_id.to_string(), bounds.map(|bounds| bounds.to_dict().to_string()));
+            }
+        },
+        other => println!("  Stored bounds: {:?}", other),
+    }
+    println!("Parts in frustum: {:?}", find_world_parts_in(path, &queries[7]).map(|form_ids| get_form_id_strings(&form_ids)));
+    println!("Parts near far part: {:?}", find_world_parts_in(path, &far).map(|form_ids| get_form_id_strings(&form_ids)));
+    let stored = queries.iter().map(|query| query_world_part_entities(path, FormID::from(10), query).map(|entities| entities.len()).unwrap_or_default()).collect::<Vec<_>>();
+    println!("Entity queries match without stored bounds: {}", stored == scanned);
+    println!("Entities near far part: {:?}", query_world_part_entities(path, FormID::from(11), &far).map(|entities| entities.len()));
+    println!("Entities of main part near far part: {:?}", query_world_part_entities(path, FormID::from(10), &far).map(|entities| entities.len()));
+
+    // Writes keep the stored bounds current
+    let mut moved_part = far_part.clone();
+    moved_part.entities[1].position = Vec3Float::from((-2000.0, 0.0, -2000.0));
+    println!("Move entity: {:?}", write_form(path, &moved_part));
+    println!("Bounds of far part: {:?}", read_world_part_bounds(path, FormID::from(11)).map(|bounds| bounds.map(|bounds| bounds.to_dict().to_string())));
+    set_archive_write_mode(ArchiveWriteMode::Append);
+    println!("Add part in append mode: {:?}", write_form(path, &FormWorldPart::new(FormID::from(14), StrSml::from("PartNew"), vec![make_entity(archive_id, 3, Vec3Float::from((0.0, 0.0, 0.0)))])));
+    set_archive_write_mode(ArchiveWriteMode::Rewrite);
+    println!("Parts at origin: {:?}", find_world_parts_in(path, &queries[5]).map(|form_ids| get_form_id_strings(&form_ids)));
+
+    // Deleted world parts leave the stored bounds
+    println!("Delete far part: {:?}", delete_form(path, FormID::from(11)));
+    println!("Parts near far part: {:?}", find_world_parts_in(path, &far).map(|form_ids| get_form_id_strings(&form_ids)));
+    println!("Bounds of deleted part: {:?}", read_world_part_bounds(path, FormID::from(11)).map_err(|e| e.kind()));
+    println!("Stored parts: {:?}", read_world_part_bounds_all(path).map(|part_bounds| part_bounds.map(|part_bounds| get_form_id_strings(&part_bounds.into_keys().collect::<Vec<_>>()))));
+    println!("Remove bounds: {:?}, again: {:?}", remove_world_part_bounds(path), remove_world_part_bounds(path));
+    println!("Stored bounds: {:?}", read_world_part_bounds_all(path).map(|part_bounds| part_bounds.is_some()));
+}
+
+fn make_entity(archive_id: ArchiveID, reference: u16, position: Vec3Float) -> EntInstance {
+    EntInstance::from((EntID::from((GlobalID::from((archive_id, FormID::from(5000))), FormID::from(reference))), position, Vec3Float::from((0.0, 0.0, 0.0)), 1.0))
+}
+
+/// Returns the planes of a frustum looking along +z from the eye, with a 90 degree field of view.
+fn get_frustum(eye: Vec3Float, near: f32, far: f32) -> EntityQuery {
+    let plane = |normal: (f32, f32, f32), point: Vec3Float| {
+        let normal = Vec3Float::from(normal);
+        Plane { normal, distance: -(normal.x * point.x + normal.y * point.y + normal.z * point.z) }
+    };
+    EntityQuery::Planes(vec![
+        plane((0.0, 0.0, 1.0), Vec3Float::from((eye.x, eye.y, eye.z + near))),
+        plane((0.0, 0.0, -1.0), Vec3Float::from((eye.x, eye.y, eye.z + far))),
+        plane((1.0, 0.0, 1.0), eye),
+        plane((-1.0, 0.0, 1.0), eye),
+        plane((0.0, 1.0, 1.0), eye),
+        plane((0.0, -1.0, 1.0), eye),
+    ])
+}
+
+fn get_form_id_strings(form_ids: &[FormID]) -> Vec<String> {
+    form_ids.iter().map(|form_id| form_id.to_string()).collect()
+}
@@ -0,0 +1,71 @@
+use std::{env, path::PathBuf};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_gc() {
+    println!("------ TESTING GARBAGE COLLECTION ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let content_path = current_dir.join("archives").join("test_gc_content.smn");
+    let lists_path = current_dir.join("archives").join("test_gc_lists.smn");
+    let content = content_path.to_str().unwrap();
+    let lists = lists_path.to_str().unwrap();
+    println!("Archive Paths: {:?}, {:?}", content_path, lists_path);
+
+    let content_id = ArchiveID::from(1);
+    let lists_id = ArchiveID::from(2);
+    let _ = write_archive_skeleton(content, &Archive::new(content_id, Version::from((1, 0)), StrLrg::from("GC Content Archive")));
+    let _ = write_archive_skeleton(lists, &Archive::new(lists_id, Version::from((1, 0)), StrLrg::from("GC Lists Archive")));
+    let _ = reserve_form_id_range(content, FormIDRange::new(StrSml::from("Strings"), FormID::from(200), FormID::from(299)));
+
+    // World list -> beach -> world name, the forest and its name are orphaned
+    let _ = write_form(lists, &FormRefGroup::new(
+        FormID::from(1),
+        StrSml::from("CollWrldList"),
+        vec![GlobalID::from((content_id, FormID::from(50)))]
+    ));
+    let _ = write_form(content, &FormWorld::new(
+        FormID::from(50),
+        StrSml::from("WrldBeach"),
+        GlobalID::from((content_id, FormID::from(200))),
+        StrSml::from("BeachOfAmonal"),
+        vec![GlobalID::from((content_id, FormID::from(105)))],
+        vec![Vec3Int::from((1, 2, 0))]
+    ));
+    let _ = write_form(content, &FormWorld::new(
+        FormID::from(51),
+        StrSml::from("WrldForest"),
+        GlobalID::from((content_id, FormID::from(201))),
+        StrSml::from("ForestOfAmonal"),
+        vec![],
+        vec![]
+    ));
+    for (form_id, text) in [(200, "Amonal"), (201, "Old Forest"), (202, "Unused")] {
+        let _ = write_form(content, &FormString::new(
+            FormID::from(form_id),
+            StrSml::from("StrWrldName"),
+            vec![LangCode::EN],
+            vec![StrLrg::from(text)]
+        ));
+    }
+
+    let roots = [GlobalID::from((lists_id, FormID::from(1)))];
+
+    // Roots outside the set must be refused
+    let result = find_unreachable_forms(&[content, lists], &[GlobalID::from((lists_id, FormID::from(9)))]);
+    println!("Missing root result: {:?}", result);
+
+    let result = find_unreachable_forms(&[content, lists], &roots);
+    println!("Report result: {:?}", result.map(|report| report.to_dict()));
+
+    let result = collect_garbage(&[content, lists], &roots);
+    println!("Collect result: {:?}", result.map(|report| report.to_dict()));
+    println!("Content forms after collect: {:?}", read_form_ids(content));
+    println!("Beach after collect: {:?}", read_form(content, FormID::from(50)).map(|form| form.form_name()));
+    println!("World name after collect: {:?}", read_form(content, FormID::from(200)).map(|form| form.form_name()));
+    println!("Reserved ranges after collect: {:?}", read_reserved_ranges(content).map(|ranges| ranges.len()));
+
+    // A second pass has nothing left to remove
+    let result = collect_garbage(&[content, lists], &roots);
+    println!("Second collect result: {:?}", result.map(|report| report.to_dict()));
+}
@@ -0,0 +1,84 @@
+use std::{env, path::PathBuf};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_history() {
+    println!("------ TESTING FORM HISTORY ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_history.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let _ = write_archive_skeleton(path, &Archive::new(ArchiveID::from(1), Version::from((1, 0)), StrLrg::from("History Test Archive")));
+    let _ = write_form(path, &make_string(1, "First"));
+    let _ = write_form(path, &make_string(2, "Other"));
+    println!("Depth without history: {:?}", get_form_history_depth(path));
+    println!("Revisions without history: {:?}", list_form_revisions(path, FormID::from(1)).map(|revisions| revisions.len()));
+
+    // Each write keeps the version it replaces, up to the depth
+    println!("Set depth 3: {:?}, depth: {:?}", set_form_history_depth(path, 3), get_form_history_depth(path));
+    println!("Write mode: {:?}", get_archive_write_mode());
+    for text in ["Second", "Third", "Fourth", "Fifth"] {
+        let _ = write_form(path, &make_string(1, text));
+    }
+    print_revisions(path, FormID::from(1));
+    println!("Revisions of untouched form: {:?}", list_form_revisions(path, FormID::from(2)).map(|revisions| revisions.len()));
+    println!("Current: {:?}", read_form(path, FormID::from(1)).map(|form| get_text(&form)));
+    println!("Read revision 3: {:?}", read_form_revision(path, FormID::from(1), 3).map(|form| get_text(&form)));
+    println!("Read dropped revision 1: {:?}", read_form_revision(path, FormID::from(1), 1).map_err(|e| e.kind()));
+    let revisions = list_form_revisions(path, FormID::from(1)).unwrap_or_default();
+    println!("Timestamps set: {}", revisions.iter().all(|revision| revision.replaced_at > 0));
+    println!("Dead space: {:?}", read_archive_dead_space(path));
+
+    // Restoring writes the revision back and keeps the version it replaced
+    println!("Restore 2: {:?}", restore_form_revision(path, FormID::from(1), 2));
+    println!("Current: {:?}", read_form(path, FormID::from(1)).map(|form| get_text(&form)));
+    print_revisions(path, FormID::from(1));
+    println!("Restore missing: {:?}", restore_form_revision(path, FormID::from(1), 99).map_err(|e| e.kind()));
+
+    // Deleted forms keep their history and can be brought back
+    println!("Delete 2: {:?}", delete_form(path, FormID::from(2)));
+    println!("Read deleted: {:?}", read_form(path, FormID::from(2)).map_err(|e| e.kind()));
+    print_revisions(path, FormID::from(2));
+    println!("Restore deleted: {:?}", restore_form_revision(path, FormID::from(2), 1));
+    println!("Current: {:?}", read_form(path, FormID::from(2)).map(|form| get_text(&form)));
+
+    // Compaction keeps the history and drops only dead space
+    println!("Compact: {:?}", compact_archive(path).map(|reclaimed| reclaimed > 0));
+    println!("Dead space: {:?}", read_archive_dead_space(path));
+    print_revisions(path, FormID::from(1));
+    println!("Read revision 4 after compact: {:?}", read_form_revision(path, FormID::from(1), 4).map(|form| get_text(&form)));
+    println!("Forms after compact: {:?}", read_forms_all(path).map(|forms| forms.iter().map(get_text).collect::<Vec<_>>()));
+    let _ = write_form(path, &make_string(1, "Sixth"));
+    println!("Write after compact: {:?}", read_form_revision(path, FormID::from(1), 6).map(|form| get_text(&form)));
+
+    // A lower depth drops the oldest revisions, no depth drops the history
+    println!("Set depth 1: {:?}", set_form_history_depth(path, 1));
+    print_revisions(path, FormID::from(1));
+    println!("Set depth 0: {:?}, depth: {:?}", set_form_history_depth(path, 0), get_form_history_depth(path));
+    println!("Revisions: {:?}", list_form_revisions(path, FormID::from(1)).map(|revisions| revisions.len()));
+    println!("Compact: {:?}, dead space: {:?}", compact_archive(path).map(|reclaimed| reclaimed > 0), read_archive_dead_space(path));
+    println!("Forms: {:?}", read_forms_all(path).map(|forms| forms.iter().map(get_text).collect::<Vec<_>>()));
+}
+
+fn make_string(form_id: u16, text: &str) -> FormString {
+    FormString::new(FormID::from(form_id), StrSml::from(format!("Str{:02}", form_id)), vec![LangCode::EN], vec![StrLrg::from(text)])
+}
+
+fn get_text(form: &Box<dyn FormTrait>) -> String {
+    format!("{}={}", form.form_id().to_string(), form.to_dict()["strings"][0])
+}
+
+fn print_revisions(path: &str, form_id: FormID) {
+    match list_form_revisions(path, form_id) {
+        Ok(revisions) => {
+            let texts: Vec<String> = revisions
+                .iter()
+                .map(|revision| format!("{}: {}", revision.revision, read_form_revision(path, form_id, revision.revision).map(|form| get_text(&form)).unwrap_or_else(|e| format!("{:?}", e.kind()))))
+                .collect();
+            println!("  Revisions of {}: {:?}", form_id.to_string(), texts);
+        },
+        Err(e) => println!("  Revisions of {}: error {:?}", form_id.to_string(), e),
+    }
+}
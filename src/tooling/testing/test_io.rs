@@ -287,8 +287,14 @@ pub fn test_read_forms_many_threaded(form_count: u16, thread_count: usize) {
     println!("-- Started Forms Read with Multiple Threads --");
     let read_start = Instant::now();
 
-    // Use Arc to safely share the file path between threads
-    let file_path = Arc::new(file_path);
+    // Open the archive once and share the reader between threads
+    let reader = match ArchiveReader::open(&file_path) {
+        Ok(reader) => Arc::new(reader),
+        Err(e) => {
+            eprintln!("Error opening archive: {:?}", e);
+            return;
+        }
+    };
 
     // Use AtomicUsize to track the number of completed forms
     let completed_forms = Arc::new(AtomicUsize::new(0));
@@ -300,7 +306,7 @@ pub fn test_read_forms_many_threaded(form_count: u16, thread_count: usize) {
     let mut handles = Vec::new();
 
     for thread_id in 0..thread_count {
-        let file_path = Arc::clone(&file_path);
+        let reader = Arc::clone(&reader);
         let completed_forms = Arc::clone(&completed_forms);
 
         // Calculate the start and end form IDs for this thread
@@ -312,7 +318,7 @@ pub fn test_read_forms_many_threaded(form_count: u16, thread_count: usize) {
             let form_ids: Vec<FormID> = (start_form..=end_form).map(FormID::from).collect();
 
             // Call the optimized batch read function
-            match read_forms(&file_path, form_ids) {
+            match reader.read_forms(&form_ids) {
                 Ok(forms) => {
                     // Update the completed_forms counter
                     completed_forms.fetch_add(forms.len(), Ordering::SeqCst);
@@ -374,4 +380,12 @@ pub fn test_read_forms_many_threaded(form_count: u16, thread_count: usize) {
         "Successfully read {} forms across {} threads.",
         form_count, thread_count
     );
+
+    // The same forms decoded by the reader's own thread pool
+    let form_ids: Vec<FormID> = (1..=form_count).map(FormID::from).collect();
+    let parallel_start = Instant::now();
+    match read_forms_parallel(&file_path, form_ids, thread_count) {
+        Ok(forms) => println!("Parallel Read Duration: {:?} for {} forms", parallel_start.elapsed(), forms.len()),
+        Err(e) => eprintln!("Error reading forms in parallel: {:?}", e),
+    }
 }
@@ -0,0 +1,69 @@
+use std::{env, path::PathBuf};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_iter() {
+    println!("------ TESTING FORM ITERATION ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_iter.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive_id = ArchiveID::from(1);
+    let archive = Archive::new(archive_id, Version::from((1, 0)), StrLrg::from("Iteration Test Archive"));
+    let _ = write_archive_skeleton(path, &archive);
+    println!("Empty archive: {:?}", iter_forms(path).map(|forms| forms.count()));
+
+    for form_id in 1..=30u16 {
+        let form_name = StrSml::from(format!("Form{:02}", form_id));
+        let _ = match form_id % 3 {
+            0 => write_form(path, &FormString::new(FormID::from(form_id), form_name, vec![LangCode::EN], vec![StrLrg::from("Text")])),
+            1 => write_form(path, &FormRefGroup::new(FormID::from(form_id), form_name, vec![GlobalID::from((archive_id, FormID::from(1)))])),
+            _ => write_form(path, &FormWorldPart::new(FormID::from(form_id), form_name, Vec::new())),
+        };
+    }
+    let mut metadata = FormMetadata::default();
+    metadata.revision = 2;
+    let _ = write_form_metadata(path, FormID::from(15), metadata);
+
+    // Headers come from the index without decoding the forms
+    let mut forms = iter_forms(path).expect("Failed to open archive");
+    println!("Size hint: {:?}", forms.size_hint());
+    let _ = forms.nth(4);
+    println!("Length after 5: {}", forms.len());
+    let forms = iter_forms(path).expect("Failed to open archive");
+    let headers: Vec<String> = forms
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.form_type == FormType::STRING)
+        .map(|entry| format!("{} {} ({} bytes)", entry.form_id.to_string(), entry.form_name, entry.data_length))
+        .take(4)
+        .collect();
+    println!("Strings: {:?}", headers);
+
+    // Only the chosen forms are decoded
+    let decoded: Vec<String> = iter_forms(path)
+        .expect("Failed to open archive")
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.form_name.to_string().ends_with('5'))
+        .map(|entry| entry.read().map(|form| format!("{} revision {}", form.form_name(), form.form_metadata().revision)))
+        .collect::<Result<_, _>>()
+        .unwrap_or_default();
+    println!("Decoded: {:?}", decoded);
+
+    // Decoding every form matches reading them all at once
+    let streamed: Vec<serde_json::Value> = iter_forms(path)
+        .expect("Failed to open archive")
+        .forms()
+        .filter_map(|form| form.ok())
+        .map(|form| form.to_dict())
+        .collect();
+    let all: Vec<serde_json::Value> = read_forms_all(path).unwrap_or_default().iter().map(|form| form.to_dict()).collect();
+    println!("Streamed {} forms, matches read_forms_all: {}", streamed.len(), streamed == all);
+    let data_order: Vec<FormID> = iter_forms_in_data_order(path).expect("Failed to open archive").filter_map(|entry| entry.ok()).map(|entry| entry.form_id).collect();
+    println!("Data order matches index order: {}", data_order == (1..=30u16).map(FormID::from).collect::<Vec<_>>());
+
+    // Record lengths cover the data block
+    let data_length: u32 = iter_forms(path).expect("Failed to open archive").filter_map(|entry| entry.ok()).map(|entry| entry.data_length).sum();
+    println!("Data lengths add up: {:?}", read_archive_info(path).map(|info| info.bytestart_index - info.bytestart_data == data_length));
+}
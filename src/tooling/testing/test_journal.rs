@@ -0,0 +1,85 @@
+use std::{env, fs, path::PathBuf};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_journal() {
+    println!("------ TESTING ARCHIVE JOURNAL ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_journal.smn");
+    let path = archive_path.to_str().unwrap();
+    let journal_path = ArchiveJournal::get_journal_path(path);
+    println!("Archive Path: {:?}", archive_path);
+
+    let _ = fs::remove_file(&journal_path);
+    let _ = write_archive_skeleton(path, &Archive::new(ArchiveID::from(1), Version::from((1, 0)), StrLrg::from("Journal Test Archive")));
+    let _ = write_form(path, &make_string(1, "Original"));
+
+    // Each edit is a step
+    let mut journal = ArchiveJournal::open(path).expect("Failed to open journal");
+    println!("Opened: {:?}, undo: {}, redo: {}", journal.recovery(), journal.undo_count(), journal.redo_count());
+    println!("Open twice: {:?}", ArchiveJournal::open(path).err().map(|e| (e.kind(), ArchiveLockedError::from_io_error(&e).is_some())));
+    println!("Edit: {:?}", journal.write_form(&make_string(1, "Edited")));
+    println!("Add: {:?}", journal.write_form(&make_string(2, "Added")));
+    println!("Delete: {:?}", journal.delete_form(FormID::from(1)));
+    println!("Delete missing: {:?}", journal.delete_form(FormID::from(9)).map_err(|e| e.kind()));
+    println!("Rename: {:?}", journal.write_archive_info(&Archive::new(ArchiveID::from(1), Version::from((1, 1)), StrLrg::from("Renamed Archive"))));
+    println!("History: {:?}", journal.history().iter().map(|step| (step.target, step.before.is_some(), step.after.is_some())).collect::<Vec<_>>());
+    print_state(path, &journal);
+
+    // Undo and redo walk the history
+    println!("Undo 2: {:?}", journal.undo(2));
+    print_state(path, &journal);
+    println!("Undo 10: {:?}", journal.undo(10));
+    print_state(path, &journal);
+    println!("Redo 3: {:?}", journal.redo(3));
+    print_state(path, &journal);
+
+    // A new edit drops the steps that could be redone
+    println!("Branch: {:?}", journal.write_form(&make_string(3, "Branch")));
+    println!("Redo after branch: {:?}", journal.redo(1));
+    print_state(path, &journal);
+
+    // The history outlives the session
+    drop(journal);
+    let mut journal = ArchiveJournal::open(path).expect("Failed to reopen journal");
+    println!("Reopened: {:?}", journal.recovery());
+    print_state(path, &journal);
+    println!("Undo after reopen: {:?}", journal.undo(1));
+    print_state(path, &journal);
+
+    // An edit recorded but not applied before a crash is replayed on open
+    println!("Redo: {:?}", journal.redo(1));
+    drop(journal);
+    let journal_length = fs::metadata(&journal_path).map(|metadata| metadata.len()).unwrap_or(0);
+    let _ = fs::OpenOptions::new().write(true).open(&journal_path).and_then(|file| file.set_len(journal_length - 5));
+    let _ = delete_form(path, FormID::from(3));
+    let journal = ArchiveJournal::open(path).expect("Failed to reopen journal");
+    println!("After crash: {:?}", journal.recovery());
+    print_state(path, &journal);
+    drop(journal);
+
+    // A record cut off by a crash is dropped
+    let _ = fs::OpenOptions::new().append(true).open(&journal_path).and_then(|mut file| std::io::Write::write_all(&mut file, &[1, 0, 0, 1]));
+    let mut journal = ArchiveJournal::open(path).expect("Failed to reopen journal");
+    println!("After torn record: {:?}, journal length kept: {}", journal.recovery(), fs::metadata(&journal_path).map(|metadata| metadata.len()).unwrap_or(0) == journal_length);
+    print_state(path, &journal);
+
+    // A checkpoint keeps the archive and clears the history
+    println!("Checkpoint: {:?}", journal.checkpoint());
+    println!("Journal length: {}", fs::metadata(&journal_path).map(|metadata| metadata.len()).unwrap_or(0));
+    println!("Undo after checkpoint: {:?}", journal.undo(1));
+    print_state(path, &journal);
+}
+
+fn make_string(form_id: u16, text: &str) -> FormString {
+    FormString::new(FormID::from(form_id), StrSml::from(format!("Str{:02}", form_id)), vec![LangCode::EN], vec![StrLrg::from(text)])
+}
+
+fn print_state(path: &str, journal: &ArchiveJournal) {
+    let forms: Vec<String> = read_forms_all(path)
+        .map(|forms| forms.iter().map(|form| format!("{}={}", form.form_id().to_string(), form.to_dict()["strings"][0])).collect())
+        .unwrap_or_default();
+    let description = read_archive_info(path).map(|archive| archive.description.to_string()).unwrap_or_default();
+    println!("  State: {:?} {:?}, undo: {}, redo: {}", description, forms, journal.undo_count(), journal.redo_count());
+}
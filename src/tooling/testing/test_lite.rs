@@ -0,0 +1,102 @@
+use std::{env, fs, path::PathBuf};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_lite() {
+    println!("------ TESTING LITE ARCHIVE LISTING ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_lite.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive_id = ArchiveID::from(1);
+    let archive = Archive::new(archive_id, Version::from((1, 0)), StrLrg::from("Lite Test Archive"));
+    let _ = write_archive_skeleton(path, &archive);
+    println!("Empty archive: {:?}", read_lite_archive(path).map(|lite| (lite.form_count, lite.match_count, lite.archive_items.len())));
+
+    for form_id in 1..=12u16 {
+        let form_name = StrSml::from(format!("Form{:02}", form_id));
+        let _ = match form_id % 3 {
+            0 => write_form(path, &FormString::new(FormID::from(form_id), form_name, vec![LangCode::EN], vec![StrLrg::from("Text")])),
+            1 => write_form(path, &FormRefGroup::new(FormID::from(form_id), form_name, vec![GlobalID::from((archive_id, FormID::from(1)))])),
+            _ => write_form(path, &FormWorldPart::new(FormID::from(form_id), form_name, Vec::new())),
+        };
+    }
+    let mut metadata = FormMetadata::default();
+    metadata.author = StrSml::from("lister");
+    metadata.revision = 4;
+    let _ = write_form_metadata(path, FormID::from(6), metadata.clone());
+    let _ = set_archive_metadata_value(path, ArchiveMetadata::KEY_BUILD_NUMBER, ArchiveMetadataValue::U64(77));
+
+    // The full listing matches reading each form
+    let lite = read_lite_archive(path).expect("Failed to read lite archive");
+    let forms = read_forms_all(path).expect("Failed to read forms");
+    let matches_forms = lite.archive_items.len() == forms.len() && lite.archive_items.iter().zip(&forms).all(|(item, form)| {
+        item.form_id == form.form_id() && item.form_type == form.form_type() && item.form_name == form.form_name() && item.metadata == form.form_metadata()
+    });
+    println!("Full listing: {} of {}, matches forms: {}", lite.archive_items.len(), lite.match_count, matches_forms);
+    println!("Archive metadata: {:?}", lite.metadata);
+    println!("Listed metadata: {:?}", lite.archive_items.iter().find(|item| item.form_id == FormID::from(6)).map(|item| item.metadata == metadata));
+
+    // Filtering by type and paging through the matches
+    let strings = LiteArchiveQuery { form_type: Some(FormType::STRING), ..Default::default() };
+    println!("Strings: {:?}", read_lite_archive_page(path, &strings).map(|lite| lite.archive_items.iter().map(|item| item.form_name.to_string()).collect::<Vec<_>>()));
+    for offset in [0, 5, 10, 15] {
+        let page = LiteArchiveQuery { form_type: None, offset, limit: Some(5) };
+        match read_lite_archive_page(path, &page) {
+            Ok(lite) => println!("Page at {}: {} of {} {:?}", offset, lite.archive_items.len(), lite.match_count, lite.archive_items.iter().map(|item| item.form_id.to_string()).collect::<Vec<_>>()),
+            Err(e) => println!("Error reading page: {:?}", e),
+        }
+    }
+    let world_parts = LiteArchiveQuery { form_type: Some(FormType::WORLDPART), offset: 1, limit: Some(2) };
+    println!("World parts: {:?}", read_lite_archive_page(path, &world_parts).map(|lite| (lite.match_count, lite.archive_items.iter().map(|item| item.form_name.to_string()).collect::<Vec<_>>())));
+    let missing = LiteArchiveQuery { form_type: Some(FormType::WORLD), ..Default::default() };
+    println!("Worlds: {:?}", read_lite_archive_page(path, &missing).map(|lite| (lite.match_count, lite.archive_items.len())));
+
+    // Page bytes carry the match count and the listed item count
+    let page = read_lite_archive_page(path, &LiteArchiveQuery { form_type: None, offset: 0, limit: Some(3) }).expect("Failed to read page");
+    let header_length = ArchiveID::BYTE_COUNT + Version::BYTE_COUNT + page.description.get_byte_count() + 2;
+    let page_bytes = page.to_page_bytes();
+    println!("Page bytes: {} = {} + 4, counts {:?}", page_bytes.len(), page.to_bytes().len(), &page_bytes[header_length..header_length + 4]);
+
+    // Metadata is found past forms with arrays, and names come from the name index once there is one
+    let entity = EntInstance::from((EntID::from((GlobalID::from((archive_id, FormID::from(1))), FormID::from(1))), Vec3Float::from((1.0, 2.0, 3.0)), Vec3Float::from((0.0, 0.0, 0.0)), 1.0));
+    let mut world_part = FormWorldPart::new(FormID::from(13), StrSml::from("Form13"), vec![entity; 40]);
+    world_part.set_form_metadata(metadata.clone());
+    let _ = write_form(path, &world_part);
+    let mut world = FormWorld::new(
+        FormID::from(14),
+        StrSml::from("Form14"),
+        GlobalID::from((archive_id, FormID::from(3))),
+        StrSml::from("MapLite"),
+        vec![GlobalID::from((archive_id, FormID::from(13)))],
+        vec![Vec3Int::from((0, 0, 0))],
+    );
+    world.set_form_metadata(metadata.clone());
+    let _ = write_form(path, &world);
+    for name_index in [false, true] {
+        if name_index {
+            println!("Build name index: {:?}", build_name_index(path));
+        }
+        let lite = read_lite_archive(path).expect("Failed to read lite archive");
+        let forms = read_forms_all(path).expect("Failed to read forms");
+        let matches_forms = lite.archive_items.len() == forms.len() && lite.archive_items.iter().zip(&forms).all(|(item, form)| {
+            item.form_id == form.form_id() && item.form_type == form.form_type() && item.form_name == form.form_name() && item.metadata == form.form_metadata()
+        });
+        println!("Listing with name index {}: {} forms, matches forms: {}", name_index, lite.archive_items.len(), matches_forms);
+    }
+
+    // A record whose entity count runs past its end fails the listing instead of listing default metadata
+    let corrupt_path = current_dir.join("archives").join("test_lite_corrupt.smn");
+    let corrupt_path = corrupt_path.to_str().unwrap();
+    let mut bytes = fs::read(path).expect("Failed to read archive");
+    let name_bytes = b"\x06Form13";
+    if let Some(position) = bytes.windows(name_bytes.len()).position(|window| window == name_bytes) {
+        bytes[position + name_bytes.len()..position + name_bytes.len() + 2].copy_from_slice(&[0xFF, 0xFF]);
+    }
+    let _ = fs::write(corrupt_path, &bytes);
+    println!("Corrupt record listing: {:?}", read_lite_archive(corrupt_path).map(|lite| lite.archive_items.len()).map_err(|e| e.kind()));
+    let page = LiteArchiveQuery { form_type: None, offset: 0, limit: Some(12) };
+    println!("Corrupt archive page before it: {:?}", read_lite_archive_page(corrupt_path, &page).map(|lite| lite.archive_items.len()).map_err(|e| e.kind()));
+}
@@ -0,0 +1,134 @@
+use std::{env, fs, io, path::{Path, PathBuf}, sync::mpsc, thread, time::{Duration, Instant}};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_lock() {
+    println!("------ TESTING ARCHIVE LOCKS ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_lock.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let _ = write_archive_skeleton(path, &Archive::new(ArchiveID::from(1), Version::from((1, 0)), StrLrg::from("Lock Test Archive")));
+    let _ = write_form(path, &make_string(1));
+
+    // Another thread writing blocks readers
+    let (held, release) = hold_lock(path, ArchiveLockMode::Exclusive);
+    set_archive_lock_wait(ArchiveLockWait::NoWait);
+    let result = read_form(path, FormID::from(1));
+    println!("Read while written: {}", describe_error(&result));
+    if let Err(e) = &result {
+        println!("Lock error: {:?}", ArchiveLockedError::from_io_error(e).map(|locked| (locked.file_path == path, locked.mode)));
+    }
+    set_archive_lock_wait(ArchiveLockWait::Timeout(Duration::from_millis(50)));
+    let started = Instant::now();
+    let result = read_form(path, FormID::from(1));
+    println!("Read with timeout: {}, waited: {}", describe_error(&result), started.elapsed() >= Duration::from_millis(50));
+    release.send(()).expect("Failed to release lock");
+    held.join().expect("Lock thread panicked");
+
+    // Waiting forever succeeds once the writer is done
+    let (held, release) = hold_lock(path, ArchiveLockMode::Exclusive);
+    set_archive_lock_wait(ArchiveLockWait::Forever);
+    let releaser = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        release.send(()).expect("Failed to release lock");
+    });
+    println!("Read waiting forever: {:?}", read_form(path, FormID::from(1)).map(|form| form.form_name().to_string()));
+    releaser.join().expect("Release thread panicked");
+    held.join().expect("Lock thread panicked");
+
+    // Readers share an archive, writers wait for them
+    set_archive_lock_wait(ArchiveLockWait::NoWait);
+    let (held, release) = hold_lock(path, ArchiveLockMode::Shared);
+    println!("Read while read: {:?}", read_form(path, FormID::from(1)).map(|form| form.form_name().to_string()));
+    println!("Write while read: {}", describe_error(&write_form(path, &make_string(2))));
+    release.send(()).expect("Failed to release lock");
+    held.join().expect("Lock thread panicked");
+    println!("Write after read: {:?}", write_form(path, &make_string(2)));
+
+    // An open reader keeps the archive read-locked, but writes through itself
+    let mut reader = ArchiveReader::open(path).expect("Failed to open archive");
+    set_archive_lock_wait(ArchiveLockWait::DEFAULT);
+    let started = Instant::now();
+    println!("Write while reader open: {}, failed at once: {}", describe_error(&write_form(path, &make_string(3))), started.elapsed() < Duration::from_secs(1));
+    set_archive_lock_wait(ArchiveLockWait::NoWait);
+    let blocked = thread::scope(|scope| scope.spawn(|| describe_error(&write_form(path, &make_string(3)))).join().expect("Write thread panicked"));
+    println!("Write while reader open on another thread: {}", blocked);
+    println!("Write through reader: {:?}, contains: {}", reader.write_form(&make_string(3)), reader.contains(FormID::from(3)));
+    println!("Write while reader reopened: {}", describe_error(&write_form(path, &make_string(4))));
+    drop(reader);
+    println!("Write after reader closed: {:?}", write_form(path, &make_string(4)));
+
+    // A thread holding a lock takes it again, but cannot write what it is reading
+    {
+        let _lock = ArchiveLock::exclusive(path).expect("Failed to lock archive");
+        println!("Reentrant write: {:?}", write_form(path, &make_string(5)));
+        println!("Reentrant read: {:?}", read_form(path, FormID::from(5)).map(|form| form.form_name().to_string()));
+    }
+    {
+        let _lock = ArchiveLock::shared(path).expect("Failed to lock archive");
+        println!("Write while reading on thread: {}", describe_error(&write_form(path, &make_string(6))));
+    }
+    let forms = iter_forms(path).expect("Failed to iterate archive");
+    println!("Write while iterating: {}", describe_error(&write_form(path, &make_string(6))));
+    drop(forms);
+
+    // A transaction keeps its archives locked until finished
+    let transaction = ArchiveTransaction::begin(&[path]).expect("Failed to begin transaction");
+    let blocked = thread::scope(|scope| scope.spawn(|| describe_error(&read_form(path, FormID::from(1)))).join().expect("Read thread panicked"));
+    println!("Read during transaction: {}", blocked);
+    let _ = transaction.commit();
+    let unblocked = thread::scope(|scope| scope.spawn(|| read_form(path, FormID::from(1)).is_ok()).join().expect("Read thread panicked"));
+    println!("Read after transaction: {}", unblocked);
+
+    // Concurrent writers take turns instead of corrupting the archive
+    set_archive_lock_wait(ArchiveLockWait::Forever);
+    thread::scope(|scope| {
+        for writer in 0..4u16 {
+            scope.spawn(move || {
+                for form in 0..10u16 {
+                    write_form(path, &make_string(100 + writer * 10 + form)).expect("Failed to write form");
+                }
+            });
+        }
+    });
+    let form_ids = read_form_ids(path).unwrap_or_default();
+    let all_readable = read_forms(path, form_ids.clone()).map(|forms| forms.len() == form_ids.len());
+    println!("Concurrent writes: {} forms, all readable: {:?}", form_ids.len(), all_readable);
+
+    // Only writers create the sidecar, reads of an archive without one leave none behind
+    let lock_path = format!("{}.lock", path);
+    let _ = fs::remove_file(&lock_path);
+    println!("Read without sidecar: {:?}, sidecar created: {}", read_form(path, FormID::from(1)).map(|form| form.form_name().to_string()), Path::new(&lock_path).exists());
+    let _ = write_form(path, &make_string(7));
+    println!("Sidecar after write: {}", Path::new(&lock_path).exists());
+
+    set_archive_lock_wait(ArchiveLockWait::DEFAULT);
+}
+
+fn make_string(form_id: u16) -> FormString {
+    FormString::new(FormID::from(form_id), StrSml::from(format!("Str{:03}", form_id)), vec![LangCode::EN], vec![StrLrg::from("Text")])
+}
+
+/// Locks the archive on another thread until the returned sender is signalled.
+fn hold_lock(file_path: &str, mode: ArchiveLockMode) -> (thread::JoinHandle<()>, mpsc::Sender<()>) {
+    let file_path = file_path.to_string();
+    let (locked_tx, locked_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let handle = thread::spawn(move || {
+        let _lock = ArchiveLock::acquire(&file_path, mode).expect("Failed to lock archive");
+        locked_tx.send(()).expect("Failed to signal lock");
+        let _ = release_rx.recv();
+    });
+    locked_rx.recv().expect("Lock thread failed");
+    (handle, release_tx)
+}
+
+fn describe_error<T>(result: &io::Result<T>) -> String {
+    match result {
+        Ok(_) => "Ok".to_string(),
+        Err(e) => format!("{:?} locked: {}", e.kind(), ArchiveLockedError::from_io_error(e).is_some()),
+    }
+}
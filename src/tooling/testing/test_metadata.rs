@@ -0,0 +1,86 @@
+use std::{env, path::PathBuf};
+
+use serde_json::json;
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_metadata() {
+    println!("------ TESTING FORM METADATA ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_metadata.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive_id = ArchiveID::from(1);
+    let archive = Archive::new(archive_id, Version::from((1, 0)), StrLrg::from("Metadata Test Archive"));
+    let _ = write_archive_skeleton(path, &archive);
+
+    let mut metadata = FormMetadata::default();
+    metadata.set_flag(FormMetadata::FLAG_EDITOR_ONLY, true);
+    metadata.set_flag(FormMetadata::FLAG_LOCALIZED, true);
+    metadata.modified = 1_700_000_000;
+    metadata.author = StrSml::from("level_editor");
+    metadata.revision = 3;
+    metadata.extra.push((200, vec![1, 2, 3]));
+    println!("Metadata: {}", metadata);
+    println!("Byte round trip: {:?}", FormMetadata::read_from_byte_buffer(&metadata.to_bytes()).map(|(read, consumed)| (read == metadata, consumed == metadata.to_bytes().len())));
+    println!("Dict round trip: {:?}", FormMetadata::from_dict(&metadata.to_dict()).map(|read| read == metadata));
+    println!("Unknown flag name: {:?}", FormMetadata::from_dict(&json!({"flags": ["HIDDEN"]})));
+
+    // Forms with and without metadata side by side
+    let mut world = FormWorld::new(
+        FormID::from(50),
+        StrSml::from("WrldBeach"),
+        GlobalID::from((archive_id, FormID::from(200))),
+        StrSml::from("BeachOfAmonal"),
+        vec![GlobalID::from((archive_id, FormID::from(105)))],
+        vec![Vec3Int::from((1, 2, 0))]
+    );
+    world.base.metadata = metadata.clone();
+    let string = FormString::new(FormID::from(200), StrSml::from("StrWrldName"), vec![LangCode::EN], vec![StrLrg::from("Amonal")]);
+    println!("Write string: {:?}", write_form(path, &string));
+    println!("Write world: {:?}", write_form(path, &world));
+    println!("Write refgroup: {:?}", write_form(path, &FormRefGroup::new(FormID::from(10), StrSml::from("CollWrldList"), vec![GlobalID::from((archive_id, FormID::from(50)))])));
+    println!("Read world: {:?}", read_form(path, FormID::from(50)).map(|form| form.form_metadata() == metadata));
+    println!("World dict: {:?}", read_form(path, FormID::from(50)).map(|form| form.to_dict()["metadata"].clone()));
+    println!("String dict has metadata: {:?}", read_form(path, FormID::from(200)).map(|form| form.to_dict().get("metadata").is_some()));
+    println!("Read all: {:?}", read_forms_all(path).map(|forms| forms.iter().map(|form| form.form_metadata().revision).collect::<Vec<_>>()));
+
+    // Editing the form or only its metadata
+    world.world_map = StrSml::from("BeachOfAmonalNorth");
+    println!("Rewrite world: {:?}", write_form(path, &world));
+    let mut edited = metadata.clone();
+    edited.set_flag(FormMetadata::FLAG_DISABLED, true);
+    edited.revision += 1;
+    println!("Write metadata: {:?}", write_form_metadata(path, FormID::from(50), edited.clone()));
+    println!("Read metadata: {:?}", read_form_metadata(path, FormID::from(50)).map(|read| (read == edited, read.has_flag(FormMetadata::FLAG_DISABLED))));
+    println!("Read world map: {:?}", read_form(path, FormID::from(50)).map(|form| form.to_dict()["world_map"].clone()));
+    println!("Clear metadata: {:?}", write_form_metadata(path, FormID::from(50), FormMetadata::default()));
+    println!("Read cleared: {:?}", read_form_metadata(path, FormID::from(50)).map(|read| read.is_empty()));
+    println!("Neighbours intact: {:?}", read_forms_all(path).map(|forms| forms.iter().map(|form| form.form_name().to_string()).collect::<Vec<_>>()));
+
+    // The lite index lists metadata
+    let _ = write_form_metadata(path, FormID::from(200), edited.clone());
+    match read_lite_archive(path) {
+        Ok(lite) => {
+            for item in &lite.archive_items {
+                println!("-> {:?}", item);
+            }
+        },
+        Err(e) => println!("Error reading lite archive: {:?}", e),
+    }
+
+    // Dynamic forms take metadata from JSON
+    let _ = register_form_schema(FormSchema::new(FormType::from(122), "NOTEBOARD", vec![FormSchemaField::new("text", SchemaFieldType::StrLrg)]));
+    let note_dict = json!({
+        "form_id": "00300",
+        "form_type": "NOTEBOARD",
+        "form_name": "NoteHarbor",
+        "text": "Ferry leaves at dawn",
+        "metadata": {"flags": ["DISABLED"], "modified": 1_700_000_100u64, "author": "writer", "revision": 1},
+    });
+    let note = FormDynamic::from_dict(&note_dict).expect("Failed to read note");
+    println!("Write note: {:?}", write_form(path, &note));
+    println!("Read note: {:?}", read_form(path, FormID::from(300)).map(|form| form.to_dict() == note_dict));
+}
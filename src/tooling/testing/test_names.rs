@@ -0,0 +1,61 @@
+use std::{env, path::PathBuf};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_names() {
+    println!("------ TESTING NAME INDEX ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_names.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive_id = ArchiveID::from(1);
+    let archive = Archive::new(archive_id, Version::from((1, 0)), StrLrg::from("Name Index Test Archive"));
+    let _ = write_archive_skeleton(path, &archive);
+
+    let string = |form_id: u16, form_name: &str| FormString::new(FormID::from(form_id), StrSml::from(form_name), vec![LangCode::EN], vec![StrLrg::from(form_name)]);
+    for form_id in 1..=40u16 {
+        let _ = write_form(path, &string(form_id * 3, &format!("StrItem{:02}", 41 - form_id)));
+    }
+    let _ = write_form(path, &string(200, "StrItem05"));
+
+    // Without an index names are found by reading the forms, duplicates block building one
+    println!("Read without index: {:?}", read_form_by_name(path, "StrItem10").map(|form| form.form_id().to_string()));
+    println!("Build with duplicates: {:?}", build_name_index(path));
+    let _ = write_form(path, &string(200, "StrTitle"));
+    println!("Build: {:?}", build_name_index(path));
+
+    // Every name is found through the index
+    let mut all_found = true;
+    for form_id in 1..=40u16 {
+        let found = find_form_id_by_name(path, &format!("StrItem{:02}", 41 - form_id));
+        all_found &= matches!(found, Ok(Some(id)) if id == FormID::from(form_id * 3));
+    }
+    println!("All found: {}", all_found);
+    println!("Find missing: {:?}", find_form_id_by_name(path, "StrItem99"));
+    println!("Find before first: {:?}", find_form_id_by_name(path, "Aaa"));
+    println!("Read by name: {:?}", read_form_by_name(path, "StrTitle").map(|form| form.to_dict()));
+    println!("Read missing: {:?}", read_form_by_name(path, "StrMissing").map(|form| form.form_id()));
+
+    // Writes keep the index current and names unique
+    println!("Write duplicate: {:?}", write_form(path, &string(201, "StrTitle")));
+    println!("Insert duplicate: {:?}", insert_form(path, &string(201, "StrItem01")));
+    println!("Rewrite same name: {:?}", write_form(path, &string(200, "StrTitle")));
+    println!("Rename: {:?}", write_form(path, &string(200, "StrTitleMain")));
+    println!("Find old name: {:?}", find_form_id_by_name(path, "StrTitle"));
+    println!("Find new name: {:?}", find_form_id_by_name(path, "StrTitleMain"));
+    println!("Reuse old name: {:?}", insert_form(path, &string(201, "StrTitle")));
+    println!("Delete: {:?}", delete_form(path, FormID::from(201)));
+    println!("Find deleted: {:?}", find_form_id_by_name(path, "StrTitle"));
+    println!("Delete several: {:?}", delete_forms(path, &[FormID::from(3), FormID::from(6)]));
+    println!("Find deleted: {:?}", (find_form_id_by_name(path, "StrItem40"), find_form_id_by_name(path, "StrItem39")));
+    println!("Renumber: {:?}", renumber_forms(&[path], path, &[(FormID::from(200), FormID::from(300))]).map(|report| report.mappings.len()));
+    println!("Find renumbered: {:?}", find_form_id_by_name(path, "StrTitleMain"));
+
+    // The index matches the forms after all the edits
+    let indexed = read_name_index(path).map(|names| names.map(|names| names.len()));
+    println!("Index entries: {:?}, forms: {:?}", indexed, read_form_ids(path).map(|form_ids| form_ids.len()));
+    println!("Remove: {:?}", remove_name_index(path));
+    println!("Write duplicate without index: {:?}", write_form(path, &string(201, "StrTitleMain")));
+}
@@ -0,0 +1,79 @@
+use std::{env, path::PathBuf, sync::Arc, thread};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+use crate::tooling::testing::FormWorldLegacy;
+
+pub fn test_parallel() {
+    println!("------ TESTING PARALLEL READS ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_parallel.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive_id = ArchiveID::from(1);
+    let archive = Archive::new(archive_id, Version::from((1, 0)), StrLrg::from("Parallel Test Archive"));
+    let _ = write_archive_skeleton(path, &archive);
+
+    for form_id in 1..=60u16 {
+        let form_name = StrSml::from(format!("Form{:02}", form_id));
+        let _ = match form_id % 3 {
+            0 => write_form(path, &FormString::new(FormID::from(form_id), form_name, vec![LangCode::EN], vec![StrLrg::from("Text")])),
+            1 => write_form(path, &FormRefGroup::new(FormID::from(form_id), form_name, vec![GlobalID::from((archive_id, FormID::from(1)))])),
+            _ => write_form(path, &FormWorldPart::new(FormID::from(form_id), form_name, Vec::new())),
+        };
+    }
+    let mut metadata = FormMetadata::default();
+    metadata.revision = 5;
+    let _ = write_form_metadata(path, FormID::from(30), metadata);
+
+    // Forms come back in request order, duplicates included, with their metadata
+    let form_ids: Vec<FormID> = [42, 7, 30, 59, 1, 30, 18].into_iter().map(FormID::from).collect();
+    let expected = read_forms(path, form_ids.clone()).expect("Failed to read forms");
+    for thread_count in [0, 1, 3, 16] {
+        match read_forms_parallel(path, form_ids.clone(), thread_count) {
+            Ok(forms) => {
+                let in_order = forms.len() == expected.len() && forms.iter().zip(&expected).all(|(form, expected)| form.to_bytes() == expected.to_bytes() && form.form_metadata() == expected.form_metadata());
+                println!("{} thread(s): {} forms, matches read_forms: {}", thread_count, forms.len(), in_order);
+            },
+            Err(e) => println!("{} thread(s): error {:?}", thread_count, e),
+        }
+    }
+    println!("Empty request: {:?}", read_forms_parallel(path, Vec::new(), 4).map(|forms| forms.len()));
+    println!("Missing form: {:?}", read_forms_parallel(path, vec![FormID::from(3), FormID::from(900), FormID::from(4)], 4).map(|forms| forms.len()));
+
+    // The pool's threads are kept between calls and let go of the reader before each call returns
+    let reader = Arc::new(ArchiveReader::open(path).expect("Failed to open archive"));
+    let repeated = (0..20).all(|_| reader.read_forms_parallel(&form_ids, 0).is_ok_and(|forms| forms.len() == form_ids.len()));
+    println!("Repeated parallel reads: {}", repeated);
+    drop(reader);
+    let rewrite_metadata = FormMetadata { revision: 5, ..Default::default() };
+    println!("Write after parallel read: {:?}", read_forms_parallel(path, form_ids.clone(), 4).and_then(|_| write_form_metadata(path, FormID::from(30), rewrite_metadata)));
+
+    // One reader shared by several threads
+    let reader = Arc::new(ArchiveReader::open(path).expect("Failed to open archive"));
+    println!("Reader: {} forms, contains 900: {}", reader.form_ids().len(), reader.contains(FormID::from(900)));
+    let handles: Vec<_> = (0..4u16)
+        .map(|thread_id| {
+            let reader = Arc::clone(&reader);
+            thread::spawn(move || {
+                let form_ids: Vec<FormID> = (1..=15u16).map(|offset| FormID::from(thread_id * 15 + offset)).collect();
+                reader.read_forms(&form_ids).map(|forms| forms.iter().all(|form| form.form_name().to_string() == format!("Form{:02}", form.form_id().to_u16())))
+            })
+        })
+        .collect();
+    let results: Vec<_> = handles.into_iter().map(|handle| handle.join().expect("Thread failed to join")).collect();
+    println!("Shared reads: {:?}", results);
+    println!("Record bytes: {:?}", reader.read_form_bytes(FormID::from(30)).map(|bytes| bytes.len() == read_form(path, FormID::from(30)).unwrap().to_bytes().len() + FormMetadata { revision: 5, ..Default::default() }.to_bytes().len()));
+    drop(reader);
+
+    // Forms stored in an older layout are read through the file and their migration
+    let _ = write_form(path, &FormWorldLegacy {
+        base: FormBase::new(FormID::from(70), FormType::WORLD, StrSml::from("WrldBeach")),
+        world_name: StrSml::from("BeachOfAmonal"),
+        world_map: StrSml::from("MapAmonal"),
+        world_parts: Vec::new(),
+    });
+    println!("Declare legacy: {:?}", set_archive_layout_version(path, FormType::WORLD, FormWorld::LAYOUT_VERSION_LEGACY));
+    println!("Read legacy: {:?}", read_forms_parallel(path, vec![FormID::from(70), FormID::from(1)], 2).map(|forms| forms[0].to_dict()["world_map"].clone()));
+}
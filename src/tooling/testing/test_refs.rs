@@ -0,0 +1,105 @@
+use std::{env, path::PathBuf};
+
+use crate::core::io::{read_form, write_archive_skeleton, write_form, DeleteMode, ReferenceIndex};
+use crate::core::structs::*;
+
+pub fn test_refs() {
+    println!("------ TESTING REFERENCES ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_refs.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive_id = ArchiveID::from(1);
+    let archive = Archive::new(archive_id, Version::from((1, 0)), StrLrg::from("Reference Test Archive"));
+    let _ = write_archive_skeleton(path, &archive);
+
+    // World list -> worlds -> world name strings
+    let _ = write_form(path, &FormRefGroup::new(
+        FormID::from(1),
+        StrSml::from("CollWrldList"),
+        vec![GlobalID::from((archive_id, FormID::from(50))), GlobalID::from((archive_id, FormID::from(51)))]
+    ));
+    let _ = write_form(path, &FormWorld::new(
+        FormID::from(50),
+        StrSml::from("WrldBeach"),
+        GlobalID::from((archive_id, FormID::from(200))),
+        StrSml::from("BeachOfAmonal"),
+        vec![GlobalID::from((archive_id, FormID::from(105)))],
+        vec![Vec3Int::from((1, 2, 0))]
+    ));
+    let _ = write_form(path, &FormWorld::new(
+        FormID::from(51),
+        StrSml::from("WrldForest"),
+        GlobalID::from((archive_id, FormID::from(200))),
+        StrSml::from("ForestOfAmonal"),
+        vec![GlobalID::from((archive_id, FormID::from(105)))],
+        vec![Vec3Int::from((660, -600, 110))]
+    ));
+    let _ = write_form(path, &FormString::new(
+        FormID::from(200),
+        StrSml::from("StrWrldName"),
+        vec![LangCode::EN],
+        vec![StrLrg::from("Amonal")]
+    ));
+
+    let mut index = match ReferenceIndex::build(&[path]) {
+        Ok(index) => index,
+        Err(e) => {
+            println!("Error building reference index: {:?}", e);
+            return;
+        }
+    };
+
+    let world_name = GlobalID::from((archive_id, FormID::from(200)));
+    let world_part = GlobalID::from((archive_id, FormID::from(105)));
+    println!("Referrers of {}: {:?}", world_name, index.get_referrers(world_name));
+    println!("Referrers of {}: {:?}", world_part, index.get_referrers(world_part));
+
+    // Repoint the forest at a new world part through the index
+    let result = index.write_form(path, &FormWorld::new(
+        FormID::from(51),
+        StrSml::from("WrldForest"),
+        GlobalID::from((archive_id, FormID::from(200))),
+        StrSml::from("ForestOfAmonal"),
+        vec![GlobalID::from((archive_id, FormID::from(106)))],
+        vec![Vec3Int::from((660, -600, 110))]
+    ));
+    println!("Indexed write result: {:?}", result);
+    println!("Referrers of {} after edit: {:?}", world_part, index.get_referrers(world_part));
+
+    // A plain write of a form of the same length is picked up by a refresh
+    let _ = write_form(path, &FormWorld::new(
+        FormID::from(51),
+        StrSml::from("WrldForest"),
+        GlobalID::from((archive_id, FormID::from(200))),
+        StrSml::from("ForestOfAmonal"),
+        vec![GlobalID::from((archive_id, FormID::from(107)))],
+        vec![Vec3Int::from((660, -600, 110))]
+    ));
+    let new_world_part = GlobalID::from((archive_id, FormID::from(107)));
+    println!("Refresh result: {:?}", index.refresh());
+    println!("Referrers of {} after refresh: {:?}", new_world_part, index.get_referrers(new_world_part));
+
+    // A second archive with the same ArchiveID is refused
+    let duplicate_path_buf = current_dir.join("archives").join("test_refs_duplicate.smn");
+    let duplicate_path = duplicate_path_buf.to_str().unwrap();
+    let _ = write_archive_skeleton(duplicate_path, &archive);
+    println!("Add duplicate ArchiveID: {:?}", index.add_archive(duplicate_path).map_err(|e| e.kind()));
+
+    // Deleting the beach should leave the world part unreferenced
+    let result = index.delete_form(path, FormID::from(50));
+    println!("Indexed delete result: {:?}", result);
+    println!("{} referenced after delete: {}", world_part, index.is_referenced(world_part));
+    println!("Referrers of {} after delete: {:?}", world_name, index.get_referrers(world_name));
+
+    // A restricted delete of the world name must be refused while the forest uses it
+    let result = index.delete_form_checked(path, FormID::from(200), DeleteMode::Restrict);
+    println!("Restricted delete result: {:?}", result);
+
+    // A cascading delete nulls the forest's world name and removes the string
+    let result = index.delete_form_checked(path, FormID::from(200), DeleteMode::Cascade);
+    println!("Cascading delete result: {:?}", result);
+    println!("Forest after cascade: {:?}", read_form(path, FormID::from(51)));
+    println!("World name after cascade: {:?}", read_form(path, FormID::from(200)));
+}
@@ -0,0 +1,131 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::{env, fmt, path::PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+/// A form type defined outside the library's forms module, the way a downstream crate would.
+struct FormNote {
+    base: FormBase,
+    text: StrSml,
+}
+
+impl FormNote {
+    const FORM_TYPE: FormType = FormType::new(200);
+
+    fn new(form_id: FormID, form_name: StrSml, text: StrSml) -> Self {
+        Self { base: FormBase::new(form_id, Self::FORM_TYPE, form_name), text }
+    }
+
+    fn read_from_byte_buffer(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        if bytes.len() < FormID::BYTE_COUNT + FormType::BYTE_COUNT {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Not enough bytes for FormNote"));
+        }
+        let form_id = FormID::from([bytes[0], bytes[1]]);
+        let mut offset = FormID::BYTE_COUNT + FormType::BYTE_COUNT;
+        let (form_name, consumed) = StrSml::read_from_byte_buffer(&bytes[offset..])?;
+        offset += consumed;
+        let (text, consumed) = StrSml::read_from_byte_buffer(&bytes[offset..])?;
+        offset += consumed;
+        Ok((Self::new(form_id, form_name, text), offset))
+    }
+
+    fn read_from_bytes(file: &mut File) -> io::Result<Self> {
+        // Read the fixed part, then each string by its length byte
+        let mut bytes = vec![0u8; FormID::BYTE_COUNT + FormType::BYTE_COUNT + 1];
+        file.read_exact(&mut bytes)?;
+        for _ in 0..2 {
+            let length = *bytes.last().unwrap() as usize;
+            let mut rest = vec![0u8; length + 1];
+            file.read_exact(&mut rest)?;
+            bytes.extend_from_slice(&rest);
+        }
+        bytes.pop();
+        Ok(Self::read_from_byte_buffer(&bytes)?.0)
+    }
+}
+
+impl FormTrait for FormNote {
+    fn form_id(&self) -> FormID { self.base.form_id }
+    fn form_type(&self) -> FormType { self.base.form_type }
+    fn form_name(&self) -> StrSml { self.base.form_name.clone() }
+    fn to_dict(&self) -> Value {
+        json!({
+            "form_id": self.base.form_id.to_string(),
+            "form_type": self.base.form_type.to_string(),
+            "form_name": self.base.form_name.to_string(),
+            "text": self.text.to_string(),
+        })
+    }
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.base.to_bytes();
+        bytes.extend_from_slice(&self.text.to_bytes());
+        bytes
+    }
+    fn get_byte_count(&self) -> usize { self.base.get_byte_count() + self.text.get_byte_count() }
+    fn get_references(&self) -> Vec<GlobalID> { Vec::new() }
+    fn remove_references(&mut self, _target: GlobalID) -> usize { 0 }
+    fn remap_references(&mut self, _remap: &dyn Fn(GlobalID) -> Option<GlobalID>) -> usize { 0 }
+    fn set_form_id(&mut self, form_id: FormID) { self.base.form_id = form_id; }
+    fn form_metadata(&self) -> FormMetadata { self.base.metadata.clone() }
+    fn set_form_metadata(&mut self, metadata: FormMetadata) { self.base.metadata = metadata; }
+}
+
+impl fmt::Display for FormNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FormNote {{ form_id: {}, form_name: {}, text: {} }}", self.base.form_id.to_string(), self.base.form_name.to_string(), self.text.to_string())
+    }
+}
+
+impl fmt::Debug for FormNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+pub fn test_registry() {
+    println!("------ TESTING FORMTYPE REGISTRY ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_registry.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive = Archive::new(ArchiveID::from(1), Version::from((1, 0)), StrLrg::from("Registry Test Archive"));
+    let _ = write_archive_skeleton(path, &archive);
+
+    let builtins: Vec<String> = get_registered_form_types().iter().map(|registration| registration.name.clone()).collect();
+    println!("Built-in form types: {:?}", builtins);
+
+    // Appending a form works without registration, reading it back needs the deserializer
+    let _ = write_form(path, &FormString::new(FormID::from(1), StrSml::from("StrIntro"), vec![LangCode::EN], vec![StrLrg::from("Hello")]));
+    let _ = write_form(path, &FormNote::new(FormID::from(2), StrSml::from("NoteIntro"), StrSml::from("Welcome to Amonal")));
+    println!("Unregistered read: {:?}", read_form(path, FormID::from(2)));
+    println!("Unregistered type name: {}", FormNote::FORM_TYPE.to_string());
+
+    let registration = FormTypeRegistration::new(
+        FormNote::FORM_TYPE,
+        "NOTE",
+        |file| Ok(Box::new(FormNote::read_from_bytes(file)?)),
+        |bytes| FormNote::read_from_byte_buffer(bytes).map(|(form, consumed)| (Box::new(form) as Box<dyn FormTrait>, consumed)),
+    );
+    println!("Register NOTE: {:?}", register_form_type(registration.clone()));
+    println!("Register NOTE again: {:?}", register_form_type(registration));
+    println!("Register over WORLD: {:?}", register_form_type(FormTypeRegistration::new(
+        FormType::WORLD,
+        "WORLD2",
+        |file| Ok(Box::new(FormNote::read_from_bytes(file)?)),
+        |bytes| FormNote::read_from_byte_buffer(bytes).map(|(form, consumed)| (Box::new(form) as Box<dyn FormTrait>, consumed)),
+    )));
+
+    println!("Registered type name: {}", FormNote::FORM_TYPE.to_string());
+    println!("Type from name: {:?}", FormType::try_from("NOTE"));
+    println!("Type from unknown name: {:?}", FormType::try_from("NOTEBOOK"));
+    println!("Registered read: {:?}", read_form(path, FormID::from(2)).map(|form| form.to_dict()));
+    println!("Read all: {:?}", read_forms_all(path).map(|forms| forms.iter().map(|form| form.form_type().to_string()).collect::<Vec<String>>()));
+
+    let note_bytes = FormNote::new(FormID::from(3), StrSml::from("NoteBuffer"), StrSml::from("From bytes")).to_bytes();
+    println!("Buffer read: {:?}", FormBase::read_from_byte_buffer(&note_bytes).map(|(form, consumed)| (form.to_dict(), consumed, note_bytes.len())));
+}
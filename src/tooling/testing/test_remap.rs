@@ -0,0 +1,84 @@
+use std::{env, path::PathBuf};
+
+use crate::core::io::{read_archive_info, read_form, remap_archive_id, renumber_forms, write_archive_skeleton, write_form};
+use crate::core::structs::*;
+
+pub fn test_remap() {
+    println!("------ TESTING REMAP ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let content_path = current_dir.join("archives").join("test_remap_content.smn");
+    let lists_path = current_dir.join("archives").join("test_remap_lists.smn");
+    let content = content_path.to_str().unwrap();
+    let lists = lists_path.to_str().unwrap();
+    println!("Archive Paths: {:?}, {:?}", content_path, lists_path);
+
+    let content_id = ArchiveID::from(1);
+    let lists_id = ArchiveID::from(2);
+    let _ = write_archive_skeleton(content, &Archive::new(content_id, Version::from((1, 0)), StrLrg::from("Remap Content Archive")));
+    let _ = write_archive_skeleton(lists, &Archive::new(lists_id, Version::from((1, 0)), StrLrg::from("Remap Lists Archive")));
+
+    // Two worlds sharing a name string, listed from a second archive
+    let _ = write_form(content, &FormWorld::new(
+        FormID::from(50),
+        StrSml::from("WrldBeach"),
+        GlobalID::from((content_id, FormID::from(200))),
+        StrSml::from("BeachOfAmonal"),
+        vec![GlobalID::from((content_id, FormID::from(105)))],
+        vec![Vec3Int::from((1, 2, 0))]
+    ));
+    let _ = write_form(content, &FormWorld::new(
+        FormID::from(51),
+        StrSml::from("WrldForest"),
+        GlobalID::from((content_id, FormID::from(200))),
+        StrSml::from("ForestOfAmonal"),
+        vec![GlobalID::from((content_id, FormID::from(105)))],
+        vec![Vec3Int::from((660, -600, 110))]
+    ));
+    let _ = write_form(content, &FormString::new(
+        FormID::from(200),
+        StrSml::from("StrWrldName"),
+        vec![LangCode::EN],
+        vec![StrLrg::from("Amonal")]
+    ));
+    let _ = write_form(lists, &FormRefGroup::new(
+        FormID::from(1),
+        StrSml::from("CollWrldList"),
+        vec![GlobalID::from((content_id, FormID::from(50))), GlobalID::from((content_id, FormID::from(51)))]
+    ));
+
+    // Swap the two worlds and move the name string
+    let result = renumber_forms(&[content, lists], content, &[
+        (FormID::from(50), FormID::from(51)),
+        (FormID::from(51), FormID::from(50)),
+        (FormID::from(200), FormID::from(300)),
+    ]);
+    println!("Renumber result: {:?}", result.map(|report| report.to_dict()));
+    println!("Form 50 after renumber: {:?}", read_form(content, FormID::from(50)));
+    println!("Form 300 after renumber: {:?}", read_form(content, FormID::from(300)));
+    println!("World list after renumber: {:?}", read_form(lists, FormID::from(1)));
+
+    // Renumbering onto a FormID that stays in use must be refused
+    let result = renumber_forms(&[content, lists], content, &[(FormID::from(50), FormID::from(300))]);
+    println!("Colliding renumber result: {:?}", result);
+
+    // Moving onto an ArchiveID used elsewhere in the set must be refused
+    let result = remap_archive_id(&[content, lists], content, lists_id);
+    println!("Colliding remap result: {:?}", result);
+
+    // Move the content archive to a new ArchiveID
+    let result = remap_archive_id(&[content, lists], content, ArchiveID::from(7));
+    println!("Remap result: {:?}", result.map(|report| report.to_dict()));
+    println!("Content archive after remap: {:?}", read_archive_info(content).map(|archive| archive.archive_id.to_string()));
+    println!("World list after remap: {:?}", read_form(lists, FormID::from(1)));
+    println!("Form 51 after remap: {:?}", read_form(content, FormID::from(51)));
+
+    // Null references stay null when archive 0 moves
+    let zero_path = current_dir.join("archives").join("test_remap_zero.smn");
+    let zero = zero_path.to_str().unwrap();
+    let _ = write_archive_skeleton(zero, &Archive::new(ArchiveID::from(0), Version::from((1, 0)), StrLrg::from("Remap Zero Archive")));
+    let _ = write_form(zero, &FormString::new(FormID::from(5), StrSml::from("StrZero"), vec![LangCode::EN], vec![StrLrg::from("Zero")]));
+    let _ = write_form(zero, &FormRefGroup::new(FormID::from(6), StrSml::from("CollZero"), vec![GlobalID::from((ArchiveID::from(0), FormID::from(5))), GlobalID::NULL]));
+    let result = remap_archive_id(&[zero], zero, ArchiveID::from(3));
+    println!("Remap archive 0 result: {:?}", result.map(|report| report.updated_forms.iter().map(|form| form.to_string()).collect::<Vec<_>>()));
+    println!("Group after remap: {:?}", read_form(zero, FormID::from(6)).map(|form| form.get_references().iter().map(|reference| reference.to_string()).collect::<Vec<_>>()));
+}
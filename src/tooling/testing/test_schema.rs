@@ -0,0 +1,116 @@
+use std::sync::Arc;
+use std::{env, path::PathBuf};
+
+use serde_json::json;
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_schema() {
+    println!("------ TESTING EMBEDDED SCHEMAS ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_schema.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive_id = ArchiveID::from(1);
+    let archive = Archive::new(archive_id, Version::from((1, 0)), StrLrg::from("Schema Test Archive"));
+    let _ = write_archive_skeleton(path, &archive);
+
+    let world = FormWorld::new(
+        FormID::from(50),
+        StrSml::from("WrldBeach"),
+        GlobalID::from((archive_id, FormID::from(200))),
+        StrSml::from("BeachOfAmonal"),
+        vec![GlobalID::from((archive_id, FormID::from(105)))],
+        vec![Vec3Int::from((1, 2, 0))]
+    );
+    let string = FormString::new(
+        FormID::from(200),
+        StrSml::from("StrWrldName"),
+        vec![LangCode::EN, LangCode::FR],
+        vec![StrLrg::from("Amonal"), StrLrg::from("Amonal")]
+    );
+    let _ = write_form(path, &world);
+    let _ = write_form(path, &string);
+    println!("Schemas before embed: {:?}", read_archive_schemas(path).map(|schemas| schemas.len()));
+
+    // Embed, then keep the block complete on later writes
+    println!("Embed: {:?}", embed_archive_schemas(path));
+    let world_part = FormWorldPart::new(
+        FormID::from(105),
+        StrSml::from("WrldPartBeach"),
+        vec![EntInstance::from((
+            EntID::from((GlobalID::from((archive_id, FormID::from(300))), FormID::from(1))),
+            Vec3Float::from((1.0, 2.0, 3.0)),
+            Vec3Float::from((0.0, 90.0, 0.0)),
+            1.5,
+        ))]
+    );
+    let _ = write_form(path, &world_part);
+    let schemas = match read_archive_schemas(path) {
+        Ok(schemas) => schemas,
+        Err(e) => {
+            println!("Error reading schemas: {:?}", e);
+            return;
+        }
+    };
+    for schema in &schemas {
+        let fields: Vec<String> = schema.fields.iter().map(|field| format!("{}: {}", field.name.to_string(), field.field_type.to_string())).collect();
+        println!("-> {} ({}): {}", schema.name.to_string(), schema.form_type.to_u8(), fields.join(", "));
+    }
+
+    // Decode each compiled form with only the embedded schemas, as a generic viewer would
+    let compiled: Vec<Box<dyn FormTrait>> = vec![Box::new(world), Box::new(string), Box::new(world_part)];
+    for form in &compiled {
+        let Some(schema) = schemas.iter().find(|schema| schema.form_type == form.form_type()) else {
+            println!("No schema for {}", form.form_type().to_string());
+            continue;
+        };
+        let bytes = match read_form(path, form.form_id()) {
+            Ok(read) => read.to_bytes(),
+            Err(e) => {
+                println!("Error reading {}: {:?}", form.form_id().to_string(), e);
+                continue;
+            }
+        };
+        match FormDynamic::read_from_byte_buffer_with_schema(&bytes, Arc::new(schema.clone())) {
+            Ok((decoded, consumed)) => println!(
+                "Decoded {}: dict {}, bytes {}, consumed {}",
+                schema.name.to_string(),
+                decoded.to_dict() == form.to_dict(),
+                decoded.to_bytes() == bytes,
+                consumed == bytes.len()
+            ),
+            Err(e) => println!("Error decoding {}: {:?}", schema.name.to_string(), e),
+        }
+    }
+
+    // Shared lengths in a dynamic schema
+    let schema = FormSchema::from_dict(&json!({
+        "form_type": 121,
+        "name": "LOOTTABLE",
+        "fields": [
+            {"name": "items", "type": "GlobalID[u8]"},
+            {"name": "weights", "type": "f32[items]"},
+        ],
+    })).expect("Failed to read schema");
+    println!("Count of unknown field: {:?}", FormSchema::new(FormType::from(122), "BROKEN", vec![FormSchemaField::new("weights", SchemaFieldType::parse("f32[items]").unwrap())]).validate());
+    println!("Store schema: {:?}", write_archive_schema(path, &schema));
+    let loot_dict = json!({
+        "form_id": "00400",
+        "form_type": "LOOTTABLE",
+        "form_name": "LootChest",
+        "items": ["00100050", "00100200"],
+        "weights": [0.75, 0.25],
+    });
+    let loot = FormDynamic::from_dict(&loot_dict).expect("Failed to read loot table");
+    let mut wrong_count = loot_dict.clone();
+    wrong_count["weights"] = json!([1.0]);
+    println!("Wrong shared count: {:?}", FormDynamic::from_dict(&wrong_count).map(|form| form.to_dict()));
+    println!("Write: {:?}", write_form(path, &loot));
+    println!("Read: {:?}", read_form(path, FormID::from(400)).map(|form| form.to_dict() == loot_dict));
+    let mut removed = loot.clone();
+    println!("Removed 00100050: {}", removed.remove_references(GlobalID::from("00100050")));
+    println!("After removal: items {}, weights {}", removed.to_dict()["items"], removed.to_dict()["weights"]);
+}
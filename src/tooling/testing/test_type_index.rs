@@ -0,0 +1,52 @@
+use std::{env, path::PathBuf};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_type_index() {
+    println!("------ TESTING TYPE INDEX ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_types.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive_id = ArchiveID::from(1);
+    let archive = Archive::new(archive_id, Version::from((1, 0)), StrLrg::from("Type Index Test Archive"));
+    let _ = write_archive_skeleton(path, &archive);
+
+    // Interleave the types so each one's FormIDs are spread over the index
+    for form_id in 1..=12u16 {
+        let form_name = StrSml::from(format!("Form{:02}", form_id));
+        let _ = match form_id % 3 {
+            0 => write_form(path, &FormString::new(FormID::from(form_id), form_name, vec![LangCode::EN], vec![StrLrg::from("Text")])),
+            1 => write_form(path, &FormRefGroup::new(FormID::from(form_id), form_name, vec![GlobalID::from((archive_id, FormID::from(1)))])),
+            _ => write_form(path, &FormWorldPart::new(FormID::from(form_id), form_name, Vec::new())),
+        };
+    }
+
+    println!("List without index: {:?}", list_forms_of_type(path, FormType::STRING));
+    println!("Build: {:?}", build_type_index(path));
+    println!("List strings: {:?}", list_forms_of_type(path, FormType::STRING));
+    println!("List refgroups: {:?}", list_forms_of_type(path, FormType::REFGROUP));
+    println!("List weathers: {:?}", list_forms_of_type(path, FormType::WEATHER));
+    println!("Read worldparts: {:?}", read_forms_of_type(path, FormType::WORLDPART).map(|forms| forms.iter().map(|form| form.form_name().to_string()).collect::<Vec<_>>()));
+
+    // Writes and deletes keep the ranges current
+    println!("Write new: {:?}", write_form(path, &FormString::new(FormID::from(7), StrSml::from("Form07"), vec![LangCode::EN], vec![StrLrg::from("Retyped")])));
+    println!("Write between: {:?}", write_form(path, &FormString::new(FormID::from(20), StrSml::from("Form20"), vec![LangCode::EN], vec![StrLrg::from("Text")])));
+    println!("Delete: {:?}", delete_form(path, FormID::from(3)));
+    println!("Delete several: {:?}", delete_forms(path, &[FormID::from(2), FormID::from(5)]));
+    println!("List strings: {:?}", list_forms_of_type(path, FormType::STRING));
+    println!("List refgroups: {:?}", list_forms_of_type(path, FormType::REFGROUP));
+    println!("List worldparts: {:?}", list_forms_of_type(path, FormType::WORLDPART));
+
+    println!("Retype last: {:?}", write_form(path, &FormRefGroup::new(FormID::from(20), StrSml::from("Form20"), Vec::new())));
+    println!("List refgroups: {:?}", list_forms_of_type(path, FormType::REFGROUP));
+
+    // The index matches the archive's own index after the edits
+    let rebuilt = read_type_index(path);
+    let _ = build_type_index(path);
+    println!("Index current: {:?}", rebuilt.ok() == read_type_index(path).ok());
+    println!("Remove: {:?}", remove_type_index(path));
+    println!("List after remove: {:?}", list_forms_of_type(path, FormType::STRING));
+}
@@ -50,7 +50,7 @@ fn test_types_misc() {
     let form_type_string = FormType::from(0);
     println!("{:?}", form_type_string.to_string());
 
-    let form_type_world = FormType::from("WORLD");
+    let form_type_world = FormType::try_from("WORLD").expect("WORLD is a built-in form type");
     println!("{:?}", form_type_world.to_string());
 
     let form_type_refgroup = FormType::REFGROUP;
@@ -0,0 +1,88 @@
+use std::{env, fmt, path::PathBuf};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+/// A world in the legacy layout, as written before `world_name` became `world_name_id`.
+#[derive(Clone, Form)]
+#[form(crate = "crate")]
+pub(crate) struct FormWorldLegacy {
+    pub(crate) base: FormBase,
+    pub(crate) world_name: StrSml,
+    pub(crate) world_map: StrSml,
+    #[form(count = "u16", reference)]
+    pub(crate) world_parts: Vec<GlobalID>,
+}
+
+impl fmt::Display for FormWorldLegacy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FormWorldLegacy {}", self.to_dict())
+    }
+}
+
+impl fmt::Debug for FormWorldLegacy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+pub fn test_upgrade() {
+    println!("------ TESTING LAYOUT UPGRADES ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_upgrade.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive_id = ArchiveID::from(1);
+    let archive = Archive::new(archive_id, Version::from((1, 0)), StrLrg::from("Upgrade Test Archive"));
+    let _ = write_archive_skeleton(path, &archive);
+
+    // An archive written before layout versions were recorded
+    for (form_id, form_name, world_name) in [(50, "WrldBeach", "BeachOfAmonal"), (51, "WrldForest", "ForestOfAmonal")] {
+        let _ = write_form(path, &FormWorldLegacy {
+            base: FormBase::new(FormID::from(form_id), FormType::WORLD, StrSml::from(form_name)),
+            world_name: StrSml::from(world_name),
+            world_map: StrSml::from("MapAmonal"),
+            world_parts: vec![GlobalID::from((archive_id, FormID::from(105))), GlobalID::from((archive_id, FormID::from(106)))],
+        });
+    }
+    let _ = write_form(path, &FormString::new(FormID::from(200), StrSml::from("StrWrldName"), vec![LangCode::EN], vec![StrLrg::from("Amonal")]));
+    println!("Layout versions: {:?}", read_archive_layout_versions(path));
+    println!("Read undeclared: {:?}", read_form(path, FormID::from(50)).map(|form| form.to_dict()["world_name_id"].clone()));
+
+    // Declared, old forms are read through the migration
+    println!("Declare legacy: {:?}", set_archive_layout_version(path, FormType::WORLD, FormWorld::LAYOUT_VERSION_LEGACY));
+    println!("World layout: {:?}", get_archive_layout_version(path, FormType::WORLD));
+    println!("Read migrated: {:?}", read_form(path, FormID::from(50)).map(|form| form.to_dict()));
+    let world = FormWorld::new(
+        FormID::from(52),
+        StrSml::from("WrldCliffs"),
+        GlobalID::from((archive_id, FormID::from(200))),
+        StrSml::from("MapAmonal"),
+        vec![GlobalID::from((archive_id, FormID::from(107)))],
+        vec![Vec3Int::from((4, 0, 1))]
+    );
+    println!("Write before upgrade: {:?}", write_form(path, &world));
+
+    // Unknown layouts are refused before anything is rewritten
+    println!("Declare unknown: {:?}", set_archive_layout_version(path, FormType::STRING, 7));
+    println!("Read unknown: {:?}", read_form(path, FormID::from(200)).map(|form| form.to_dict()));
+    println!("Upgrade unknown: {:?}", upgrade_archive(path));
+    println!("Layout versions: {:?}", read_archive_layout_versions(path));
+    let _ = set_archive_layout_version(path, FormType::STRING, FORM_LAYOUT_VERSION_INITIAL);
+
+    // Upgrade rewrites the old forms in the current layout
+    println!("Upgrade: {:?}", upgrade_archive(path));
+    println!("Upgrade again: {:?}", upgrade_archive(path));
+    println!("Layout versions: {:?}", read_archive_layout_versions(path));
+    match read_forms_all(path) {
+        Ok(forms) => {
+            for form in forms {
+                println!("-> {} ({} bytes)", form.to_dict(), form.get_byte_count());
+            }
+        },
+        Err(e) => println!("Error reading forms: {:?}", e),
+    }
+    println!("Write after upgrade: {:?}", write_form(path, &world));
+    println!("Read written: {:?}", read_form(path, FormID::from(52)).map(|form| form.to_dict() == world.to_dict()));
+}
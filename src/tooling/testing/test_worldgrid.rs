@@ -0,0 +1,80 @@
+use std::{env, path::PathBuf};
+
+use crate::core::io::*;
+use crate::core::structs::*;
+
+pub fn test_worldgrid() {
+    println!("------ TESTING WORLD PART QUERIES ------");
+    let current_dir: PathBuf = env::current_dir().expect("Failed to get current directory");
+    let archive_path = current_dir.join("archives").join("test_worldgrid.smn");
+    let path = archive_path.to_str().unwrap();
+    println!("Archive Path: {:?}", archive_path);
+
+    let archive_id = ArchiveID::from(1);
+    let _ = write_archive_skeleton(path, &Archive::new(archive_id, Version::from((1, 0)), StrLrg::from("World Grid Test Archive")));
+
+    // A 6 by 6 world of parts 100 units apart, centered on the origin
+    let mut world_parts = Vec::new();
+    let mut anchors = Vec::new();
+    for row in 0..6 {
+        for column in 0..6 {
+            world_parts.push(GlobalID::from((archive_id, FormID::from(100 + row * 6 + column))));
+            anchors.push(Vec3Int::from((column as i32 * 100 - 250, (row % 2) as i32 * 10, row as i32 * 100 - 250)));
+        }
+    }
+    let world = FormWorld::new(FormID::from(1), StrSml::from("WrldMain"), GlobalID::NULL, StrSml::from("MapMain"), world_parts, anchors);
+    let _ = write_form(path, &world);
+
+    let queries = [
+        WorldPartQuery::Nearest(Vec3Int::from((0, 0, 0))),
+        WorldPartQuery::Nearest(Vec3Int::from((10_000, 0, 10_000))),
+        WorldPartQuery::Box { min: Vec3Int::from((-150, 0, -150)), max: Vec3Int::from((50, 0, 150)) },
+        WorldPartQuery::Box { min: Vec3Int::from((-1000, -1000, -1000)), max: Vec3Int::from((1000, 1000, 1000)) },
+        WorldPartQuery::Box { min: Vec3Int::from((1, 1, 1)), max: Vec3Int::from((0, 0, 0)) },
+        WorldPartQuery::Radius { center: Vec3Int::from((-50, 0, -50)), radius: 75 },
+        WorldPartQuery::Radius { center: Vec3Int::from((-250, 0, -250)), radius: 100 },
+        WorldPartQuery::Radius { center: Vec3Int::from((i32::MAX, i32::MIN, i32::MAX)), radius: u32::MAX },
+    ];
+
+    // Without a grid the world itself is read
+    println!("Grid: {:?}", get_world_part_grid_cell_size(path));
+    let expected: Vec<Vec<GlobalID>> = queries.iter().map(|query| world.query_parts(query)).collect();
+    for (query, expected) in queries.iter().zip(&expected) {
+        println!("{:?}: {:?}", query, expected.iter().map(|world_part| world_part.to_string()).collect::<Vec<_>>());
+    }
+    let mut uneven = world.clone();
+    uneven.world_parts.truncate(30);
+    println!("Anchors past the parts: {:?}", uneven.query_parts(&WorldPartQuery::Box { min: Vec3Int::from((-1000, -1000, -1000)), max: Vec3Int::from((1000, 1000, 1000)) }).len());
+    println!("Without grid matches world: {}", queries.iter().zip(&expected).all(|(query, expected)| query_world_parts(path, FormID::from(1), query).ok().as_ref() == Some(expected)));
+
+    // Every cell size answers the same as the world
+    println!("Build with 0 unit cells: {:?}", build_world_part_grid(path, 0).map_err(|e| e.kind()));
+    for cell_size in [1, 75, 150, 1000, u32::MAX] {
+        let built = build_world_part_grid(path, cell_size);
+        let matches = queries.iter().zip(&expected).all(|(query, expected)| query_world_parts(path, FormID::from(1), query).ok().as_ref() == Some(expected));
+        println!("{} unit cells: built {:?}, matches world: {}", cell_size, built, matches);
+    }
+
+    // Writes keep the grid current
+    let _ = build_world_part_grid(path, 150);
+    let mut moved = world.clone();
+    moved.world_part_anchors[0] = Vec3Int::from((5000, 0, 5000));
+    println!("Move part: {:?}", write_form(path, &moved));
+    let far = WorldPartQuery::Radius { center: Vec3Int::from((5000, 0, 5000)), radius: 10 };
+    println!("Near moved part: {:?}", query_world_parts(path, FormID::from(1), &far).map(|world_parts| world_parts.iter().map(|world_part| world_part.to_string()).collect::<Vec<_>>()));
+    println!("Matches moved world: {}", queries.iter().all(|query| query_world_parts(path, FormID::from(1), query).ok() == Some(moved.query_parts(query))));
+
+    let small = FormWorld::new(FormID::from(2), StrSml::from("WrldSmall"), GlobalID::NULL, StrSml::from("MapSmall"), vec![GlobalID::from((archive_id, FormID::from(200)))], vec![Vec3Int::from((7, 7, 7))]);
+    set_archive_write_mode(ArchiveWriteMode::Append);
+    println!("Add world in append mode: {:?}", write_form(path, &small));
+    set_archive_write_mode(ArchiveWriteMode::Rewrite);
+    println!("Nearest in new world: {:?}", query_world_parts(path, FormID::from(2), &WorldPartQuery::Nearest(Vec3Int::from((0, 0, 0)))).map(|world_parts| world_parts.iter().map(|world_part| world_part.to_string()).collect::<Vec<_>>()));
+    println!("Grid worlds rebuilt: {:?}", build_world_part_grid(path, 150));
+
+    // Deleted worlds leave the grid
+    println!("Delete world: {:?}", delete_form(path, FormID::from(2)));
+    println!("Query deleted world: {:?}", query_world_parts(path, FormID::from(2), &WorldPartQuery::Nearest(Vec3Int::from((0, 0, 0)))).map_err(|e| e.kind()));
+    println!("Query remaining world: {:?}", query_world_parts(path, FormID::from(1), &queries[0]).map(|world_parts| world_parts.len()));
+    println!("Remove grid: {:?}, again: {:?}", remove_world_part_grid(path), remove_world_part_grid(path));
+    println!("Grid: {:?}", get_world_part_grid_cell_size(path));
+}